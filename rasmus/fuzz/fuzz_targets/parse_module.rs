@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rasmus::binary::{module_parser::ModuleParser, parse_trait::ParseBin};
+
+// The parser must reject malformed input with an error, never panic or abort
+fuzz_target!(|data: &[u8]| {
+    let _ = ModuleParser::parse(data);
+});
@@ -1,4 +1,18 @@
-pub type Addr = usize;
+/// Handle of a store entry.
+/// A slot freed by the store can be reused by a new entry with the next generation,
+/// so a handle kept for a removed entry never refers to the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Addr {
+    pub index: usize,
+    pub generation: u32,
+}
+
+impl std::fmt::Display for Addr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.index)
+    }
+}
+
 pub type FuncAddr = Addr;
 pub type TableAddr = Addr;
 pub type MemAddr = Addr;
@@ -6,3 +20,5 @@ pub type GlobalAddr = Addr;
 pub type ElemAddr = Addr;
 pub type DataAddr = Addr;
 pub type ExternAddr = Addr;
+pub type TagAddr = Addr;
+pub type ExnAddr = Addr;
@@ -2,5 +2,6 @@ pub mod module_parser;
 pub mod parse_trait;
 pub mod parser_helpers;
 pub mod parsers;
+pub mod streaming_parser;
 pub mod syntax_error;
 mod types;
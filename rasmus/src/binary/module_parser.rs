@@ -1,372 +1,198 @@
-use nom::{
-    bytes::complete::{tag, take},
-    IResult as NomResult,
-};
+use std::io::Read;
 
-use crate::entities::types::*;
 use crate::{
     binary::{
         parse_trait::*,
-        syntax_error::{ParseResult, SyntaxError},
+        streaming_parser::{Payload, PayloadReader, StreamError, StreamingParser},
+        syntax_error::ParseResult,
     },
-    entities::module::*,
+    entities::{module::*, types::Byte},
 };
 pub struct ModuleParser;
 
-macro_rules! get_section_content {
-    ($bytes:expr) => {{
-        let (bytes, (section_id_byte, section_content)) =
-            Self::take_section($bytes).map_err(|_| SyntaxError::InvalidModuleSection)?;
-        (
-            bytes,
-            SectionId::try_from(section_id_byte)?,
-            section_content,
-        )
-    }};
-}
-
 impl ModuleParser {
-    fn take_magic(bytes: &[Byte]) -> NomResult<&[Byte], ()> {
-        tag(Module::MAGIC)(bytes).map(|(input, _)| (input, ()))
-    }
-
-    fn take_version(bytes: &[Byte]) -> NomResult<&[Byte], ()> {
-        tag(Module::VERSION)(bytes).map(|(input, _)| (input, ()))
-    }
-
-    fn take_section(bytes: &[Byte]) -> NomResult<&[Byte], (u8, &[Byte])> {
-        let (bytes, section_id_bytes) = take(1usize)(bytes)?;
-        let (bytes, size) = U32Type::parse(bytes)?;
-        let (bytes, section_content) = take(size.0)(bytes)?;
-
-        Ok((bytes, (section_id_bytes[0], section_content)))
-    }
-
-    fn parse_types_section(bytes: &[Byte]) -> ParseResult<Vec<FuncType>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed =
-            U32Type::parse(remaining_bytes).map_err(|_| SyntaxError::InvalidTypesModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut func_types: Vec<FuncType> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let func_type_parsed = Self::parse_func_type(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidTypesModuleSection)?;
-            remaining_bytes = func_type_parsed.0;
-            func_types.push(func_type_parsed.1);
-        }
-
-        Ok(func_types)
-    }
-
-    fn parse_func_type(bytes: &[Byte]) -> NomResult<&[Byte], FuncType> {
-        let (bytes, _) = tag(&[FuncType::ENCODE_BYTE_FUNC])(bytes)?;
-        let (bytes, parameters_vec_len) = U32Type::parse(bytes)?;
-
-        let mut remaining_bytes = bytes;
-
-        let mut parameters: Vec<ValType> = Vec::with_capacity(parameters_vec_len.0 as usize);
-        for _ in 0..parameters_vec_len.0 {
-            let parsed_val_type = ValType::parse(remaining_bytes)?;
-            parameters.push(parsed_val_type.1);
-            remaining_bytes = parsed_val_type.0;
-        }
-
-        let (bytes, results_vec_len) = U32Type::parse(remaining_bytes)?;
-        remaining_bytes = bytes;
-        let mut results: Vec<ValType> = Vec::with_capacity(results_vec_len.0 as usize);
-        for _ in 0..results_vec_len.0 {
-            let parsed_val_type = ValType::parse(remaining_bytes)?;
-            results.push(parsed_val_type.1);
-            remaining_bytes = parsed_val_type.0;
-        }
-
-        Ok((
-            remaining_bytes,
-            FuncType {
-                parameters,
-                results,
-            },
-        ))
-    }
-
-    fn parse_code_section(bytes: &[Byte]) -> ParseResult<Vec<CodeType>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed =
-            U32Type::parse(remaining_bytes).map_err(|_| SyntaxError::InvalidCodeModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut code_types: Vec<CodeType> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let code_type_parsed = Self::parse_code_type(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidCodeModuleSection)?;
-            remaining_bytes = code_type_parsed.0;
-            code_types.push(code_type_parsed.1);
-        }
-
-        Ok(code_types)
-    }
-
-    // TODO: make it as a method of CodeType
-    fn parse_code_type(bytes: &[Byte]) -> NomResult<&[Byte], CodeType> {
-        let (bytes, code_len) = U32Type::parse(bytes)?;
-        let (bytes, code_bytes) = take(code_len.0 as usize)(bytes)?;
-
-        let (code_bytes, locals_len) = U32Type::parse(code_bytes)?;
-        let mut remaining_bytes = code_bytes;
-        let mut locals: Vec<LocalsType> = Vec::with_capacity(locals_len.0 as usize);
-
-        for _ in 0..locals_len.0 {
-            let locals_type_parsed = LocalsType::parse(remaining_bytes)?;
-            remaining_bytes = locals_type_parsed.0;
-            locals.push(locals_type_parsed.1);
-        }
-
-        let (_, expression) = ExpressionType::parse(remaining_bytes)?;
-
-        Ok((
-            bytes,
-            CodeType {
-                size: code_len,
-                code: FuncCodeType { locals, expression },
-            },
-        ))
+    /// Parses a module read in chunks, without keeping the whole binary in memory
+    pub fn parse_reader(reader: impl Read) -> Result<Module, StreamError> {
+        Self::parse_payloads(PayloadReader::new(reader))
     }
 
-    fn parse_funcs_section(bytes: &[Byte]) -> ParseResult<Vec<TypeIdx>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed =
-            U32Type::parse(remaining_bytes).map_err(|_| SyntaxError::InvalidFuncsModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut func_types: Vec<TypeIdx> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let code_type_parsed = U32Type::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidFuncsModuleSection)?;
-            remaining_bytes = code_type_parsed.0;
-            func_types.push(TypeIdx(code_type_parsed.1));
-        }
-
-        Ok(func_types)
-    }
-
-    fn parse_import_section(bytes: &[Byte]) -> ParseResult<Vec<ImportType>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)
-            .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut import_types: Vec<ImportType> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let module_name_parsed = NameType::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-            let module_name = module_name_parsed.1;
-            remaining_bytes = module_name_parsed.0;
-
-            let func_name_parsed = NameType::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-            let func_name = func_name_parsed.1;
-            remaining_bytes = func_name_parsed.0;
-
-            let import_desc_parsed = ImportDescription::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-            let import_desc = import_desc_parsed.1;
-            remaining_bytes = import_desc_parsed.0;
-
-            import_types.push(ImportType {
-                module: module_name,
-                name: func_name,
-                desc: import_desc,
-            })
+    /// Builds the module from the payloads, e.g. of a reader with lazy function bodies
+    pub fn parse_payloads<E>(
+        payloads: impl Iterator<Item = Result<Payload, E>>,
+    ) -> Result<Module, E> {
+        let mut module = Module::default();
+        for payload in payloads {
+            Self::apply(&mut module, payload?);
         }
 
-        Ok(import_types)
+        Ok(module)
     }
 
-    fn parse_table_section(bytes: &[Byte]) -> ParseResult<Vec<TableType>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed =
-            U32Type::parse(remaining_bytes).map_err(|_| SyntaxError::InvalidTablesModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut table_types: Vec<TableType> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let table_type_parsed = TableType::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidTablesModuleSection)?;
-
-            remaining_bytes = table_type_parsed.0;
-            table_types.push(table_type_parsed.1);
-        }
+    /// Parses a module keeping the function bodies undecoded until they are called
+    pub fn parse_lazy(bytes: &[Byte]) -> ParseResult<Module> {
+        let mut parser = StreamingParser::new();
+        parser.lazy_function_bodies();
 
-        Ok(table_types)
+        Self::parse_bytes(bytes, parser)
     }
 
-    fn parse_memory_section(bytes: &[Byte]) -> ParseResult<Vec<MemType>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed =
-            U32Type::parse(remaining_bytes).map_err(|_| SyntaxError::InvalidMemsModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut mem_types: Vec<MemType> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let mem_type_parsed = MemType::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidMemsModuleSection)?;
-
-            remaining_bytes = mem_type_parsed.0;
-            mem_types.push(mem_type_parsed.1);
-        }
-
-        Ok(mem_types)
-    }
+    fn parse_bytes(bytes: &[Byte], mut parser: StreamingParser) -> ParseResult<Module> {
+        parser.feed(bytes);
+        parser.finish();
 
-    fn parse_globals_section(bytes: &[Byte]) -> ParseResult<Vec<Global>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)
-            .map_err(|_| SyntaxError::InvalidGlobalsModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut global_types: Vec<Global> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let global_type_parsed = Global::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidGlobalsModuleSection)?;
-
-            remaining_bytes = global_type_parsed.0;
-            global_types.push(global_type_parsed.1);
+        let mut module = Module::default();
+        while let Some(payload) = parser.next_payload()? {
+            Self::apply(&mut module, payload);
         }
 
-        Ok(global_types)
+        Ok(module)
     }
 
-    fn parse_exports_section(bytes: &[Byte]) -> ParseResult<Vec<ExportType>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)
-            .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut export_types: Vec<ExportType> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let func_name_parsed = NameType::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-            let func_name = func_name_parsed.1;
-            remaining_bytes = func_name_parsed.0;
-
-            let export_desc_parsed = ExportDescription::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-            let export_desc = export_desc_parsed.1;
-            remaining_bytes = export_desc_parsed.0;
-
-            export_types.push(ExportType {
-                name: func_name,
-                desc: export_desc,
-            })
+    fn apply(module: &mut Module, payload: Payload) {
+        match payload {
+            Payload::Types(types) => module.types = types,
+            Payload::Imports(imports) => module.imports = imports,
+            Payload::Functions(funcs) => module.funcs = funcs,
+            Payload::Tables(tables) => module.tables = tables,
+            Payload::Memories(mems) => module.mems = mems,
+            Payload::Tags(tags) => module.tags = tags,
+            Payload::Globals(globals) => module.globals = globals,
+            Payload::Exports(exports) => module.exports = exports,
+            Payload::Start(start) => module.start = Some(start),
+            Payload::Elements(elems) => module.elems = elems,
+            Payload::CodeSection { count } => module.code = Vec::with_capacity(count),
+            Payload::FunctionBody { code, .. } => module.code.push(code),
+            Payload::Datas(datas) => module.datas = datas,
+            Payload::DataCount(count) => module.data_count = Some(count),
+            Payload::CustomSection(custom) => module.customs.push(custom),
+            Payload::Header | Payload::End => {}
         }
-
-        Ok(export_types)
     }
+}
 
-    fn parse_start_section(bytes: &[Byte]) -> ParseResult<StartType> {
-        StartType::parse(bytes)
-            .map_err(|_| SyntaxError::InvalidStartModuleSection)
-            .map(|v| v.1)
+impl ParseBin<Module> for ModuleParser {
+    fn parse(bytes: &[Byte]) -> ParseResult<(Vec<Byte>, Module)> {
+        Ok((vec![], Self::parse_bytes(bytes, StreamingParser::new())?))
     }
+}
 
-    fn parse_elems_section(bytes: &[Byte]) -> ParseResult<Vec<ElementSegmentType>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)
-            .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut elems_types: Vec<ElementSegmentType> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let elem_segment_type_parsed = ElementSegmentType::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidElementSegmentModuleSection)?;
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::binary::syntax_error::{SyntaxError, SyntaxErrorCause};
+    use crate::entities::types::*;
 
-            remaining_bytes = elem_segment_type_parsed.0;
-            elems_types.push(elem_segment_type_parsed.1);
-        }
+    #[test]
+    fn test_error_position() {
+        let wasm = [
+            b"\0asm\x01\0\0\0".as_slice(),
+            // type section
+            &[0x01, 0x04, 0x01, 0x60, 0x00, 0x00],
+            // import section, "m" "f" func of type 0
+            &[0x02, 0x07, 0x01, 0x01, b'm', 0x01, b'f', 0x00, 0x00],
+            // function section
+            &[0x03, 0x02, 0x01, 0x00],
+            // code section with an unknown 0xfc prefixed opcode
+            &[0x0A, 0x06, 0x01, 0x04, 0x00, 0xFC, 0x1F, 0x0B],
+        ]
+        .concat();
+
+        let error = ModuleParser::parse(&wasm).unwrap_err();
 
-        Ok(elems_types)
+        assert_eq!(
+            error,
+            SyntaxError {
+                offset: wasm.len() - 3,
+                section: Some(SectionId::Code),
+                item: Some(1),
+                cause: SyntaxErrorCause::UnknownOpcode {
+                    opcode: 0xFC,
+                    subopcode: Some(0x1F)
+                },
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "error at 0x20 in code section, func #1: unknown opcode 0xfc 0x1f"
+        );
     }
 
-    fn parse_data_section(bytes: &[Byte]) -> ParseResult<Vec<DataType>> {
-        let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)
-            .map_err(|_| SyntaxError::InvalidImportsModuleSection)?;
-        remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
-        let mut data_types: Vec<DataType> = Vec::with_capacity(vector_len);
-
-        for _ in 0..vector_len {
-            let elem_segment_type_parsed = DataType::parse(remaining_bytes)
-                .map_err(|_| SyntaxError::InvalidDatasModuleSection)?;
-
-            remaining_bytes = elem_segment_type_parsed.0;
-            data_types.push(elem_segment_type_parsed.1);
+    #[test]
+    fn test_section_layout() {
+        let module = |sections: &[&[Byte]]| {
+            [b"\0asm\x01\0\0\0".as_slice()]
+                .iter()
+                .chain(sections)
+                .copied()
+                .collect::<Vec<&[Byte]>>()
+                .concat()
+        };
+        let types: &[Byte] = &[0x01, 0x04, 0x01, 0x60, 0x00, 0x00];
+        let funcs: &[Byte] = &[0x03, 0x02, 0x01, 0x00];
+        let code: &[Byte] = &[0x0A, 0x04, 0x01, 0x02, 0x00, 0x0B];
+        let custom: &[Byte] = &[0x00, 0x02, 0x01, b'c'];
+        let data_count: &[Byte] = &[0x0C, 0x01, 0x01];
+
+        let cases: [(&[&[Byte]], Option<SyntaxErrorCause>); 8] = [
+            (&[custom, types, custom, funcs, custom, code, custom], None),
+            (
+                &[types, types],
+                Some(SyntaxErrorCause::DuplicateSection(SectionId::Type)),
+            ),
+            (
+                &[funcs, types],
+                Some(SyntaxErrorCause::SectionOutOfOrder(SectionId::Type)),
+            ),
+            (
+                &[types, code, data_count],
+                Some(SyntaxErrorCause::SectionOutOfOrder(SectionId::DataCount)),
+            ),
+            (
+                &[types, funcs],
+                Some(SyntaxErrorCause::FunctionCodeMismatch),
+            ),
+            (
+                &[types, funcs, data_count, code],
+                Some(SyntaxErrorCause::DataCountMismatch),
+            ),
+            (
+                &[&[0x01, 0x05, 0x01, 0x60, 0x00, 0x00, 0x00]],
+                Some(SyntaxErrorCause::SectionSizeMismatch),
+            ),
+            // a nop after the end of the body
+            (
+                &[types, funcs, &[0x0A, 0x05, 0x01, 0x03, 0x00, 0x0B, 0x01]],
+                Some(SyntaxErrorCause::SectionSizeMismatch),
+            ),
+        ];
+
+        for (sections, expected) in cases {
+            let result = ModuleParser::parse(&module(sections));
+
+            assert_eq!(result.err().map(|error| error.cause), expected);
         }
-
-        Ok(data_types)
     }
-}
-
-impl ParseBin<Module> for ModuleParser {
-    fn parse(bytes: &[Byte]) -> ParseResult<(Vec<Byte>, Module)> {
-        let mut remainig_bytes = bytes;
-        remainig_bytes = Self::take_magic(remainig_bytes)
-            .map_err(|_| SyntaxError::ModuleMagicNotFound)?
-            .0;
-        remainig_bytes = Self::take_version(remainig_bytes)
-            .map_err(|_| SyntaxError::ModuleVersionNotFound)?
-            .0;
 
-        let mut module = Module::default();
+    #[test]
+    fn test_regression_corpus() {
+        let corpus = format!(
+            "{}/fuzz/corpus/parse_module",
+            std::env::var("CARGO_MANIFEST_DIR").unwrap()
+        );
 
-        while !remainig_bytes.is_empty() {
-            let (b, section_id, section_content) = get_section_content!(remainig_bytes);
-            match section_id {
-                SectionId::Custom => {
-                    // custom sections are not a part of the Module structure, so ignore so far
-                }
-                SectionId::Type => module.types = Self::parse_types_section(section_content)?,
-                SectionId::Code => module.code = Self::parse_code_section(section_content)?,
-                SectionId::Function => module.funcs = Self::parse_funcs_section(section_content)?,
-                SectionId::Import => module.imports = Self::parse_import_section(section_content)?,
-                SectionId::Table => module.tables = Self::parse_table_section(section_content)?,
-                SectionId::Memory => module.mems = Self::parse_memory_section(section_content)?,
-                SectionId::Global => module.globals = Self::parse_globals_section(section_content)?,
-                SectionId::Export => module.exports = Self::parse_exports_section(section_content)?,
-                SectionId::Start => {
-                    module.start = Some(Self::parse_start_section(section_content)?)
-                }
-                SectionId::Element => module.elems = Self::parse_elems_section(section_content)?,
-                SectionId::Data => module.datas = Self::parse_data_section(section_content)?,
-                SectionId::DataCount => {
-                    let (_, data_count) = U32Type::parse(section_content)
-                        .map_err(|_| SyntaxError::InvalidDataCountModuleSection)?;
-                    if module.datas.len() != data_count.0 as usize {
-                        return Err(SyntaxError::DataCountDoesntMatchDataLen);
-                    }
-                }
-            }
-            remainig_bytes = b;
+        for entry in std::fs::read_dir(corpus).unwrap() {
+            let path = entry.unwrap().path();
+            let wasm = std::fs::read(&path).unwrap();
+            let result = ModuleParser::parse(&wasm);
+
+            assert_eq!(
+                result.is_ok(),
+                path.ends_with("factorial"),
+                "{}",
+                path.display()
+            );
         }
-
-        Ok((remainig_bytes.to_vec(), module))
     }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::binary::parse_trait::ParseBin;
 
     #[test]
     fn test_complete_module() {
@@ -426,8 +252,8 @@ mod test {
             vec![TableType {
                 element_ref_type: RefType::FuncRef,
                 limits: LimitsType {
-                    max: Some(U32Type(1)),
-                    min: U32Type(0)
+                    max: Some(U64Type(1)),
+                    min: U64Type(0)
                 }
             }],
             "module.tables"
@@ -438,9 +264,11 @@ mod test {
             module.mems,
             vec![MemType {
                 limits: LimitsType {
-                    min: U32Type(1),
-                    max: Some(U32Type(1))
-                }
+                    min: U64Type(1),
+                    max: Some(U64Type(1))
+                },
+                index_type: IndexType::I32,
+                shared: false
             }],
             "module.mems"
         );
@@ -485,6 +313,7 @@ mod test {
                             instructions: vec![]
                         }
                     }
+                    .into()
                 },
                 CodeType {
                     size: U32Type(5),
@@ -497,6 +326,7 @@ mod test {
                             ]
                         }
                     }
+                    .into()
                 }
             ],
             "module.code"
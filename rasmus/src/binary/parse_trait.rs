@@ -1,7 +1,7 @@
 use crate::entities::types::*;
 use nom::{bytes::complete::take, IResult as NomResult};
 
-use super::syntax_error::ParseResult;
+use super::{parser_helpers::parse_vector_len, syntax_error::ParseResult};
 
 pub trait ParseBin<T: Sized> {
     fn parse(bytes: &[u8]) -> ParseResult<(Vec<Byte>, T)>
@@ -38,10 +38,10 @@ impl<A: ParseWithNom, B: ParseWithNom, C: ParseWithNom> ParseWithNom for (A, B,
 impl<T: ParseWithNom + Sized> ParseWithNom for Vec<T> {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
 
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut types: Vec<T> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
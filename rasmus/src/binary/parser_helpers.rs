@@ -1,77 +1,104 @@
 // use crate::types::*;
 
-use crate::entities::types::Byte;
+use crate::entities::types::{Byte, U32Type};
+use crate::nesting::enter_nesting;
 
 use super::parse_trait::ParseWithNom;
 
 use nom::{IResult as NomResult, Slice};
 
-#[macro_export]
-macro_rules! read_unsigned_leb128 {
-    ($int_ty:ty) => {
-        |slice: &[u8], position: &mut usize| -> $int_ty {
-            // The first iteration of this loop is unpeeled. This is a
-            // performance win because this code is hot and integer values less
-            // than 128 are very common, typically occurring 50-80% or more of
-            // the time, even for u64 and u128.
-            let byte = slice[*position];
-            *position += 1;
-            if (byte & 0x80) == 0 {
-                return byte as $int_ty;
-            }
-            let mut result = (byte & 0x7F) as $int_ty;
-            let mut shift = 7;
-            loop {
-                let byte = slice[*position];
-                *position += 1;
-                if (byte & 0x80) == 0 {
-                    result |= (byte as $int_ty) << shift;
-                    return result;
-                } else {
-                    result |= ((byte & 0x7F) as $int_ty) << shift;
-                }
-                shift += 7;
-            }
+/// Maximum number of locals declared by a function body, the parameters are not counted.
+/// The local index space allows far more, every local takes a slot of the frame though.
+pub const MAX_LOCALS: u64 = 50_000;
+
+// Reads an unsigned LEB128 number of `bits` width. The encoding may take at most
+// ceil(bits / 7) bytes and the unused bits of the last possible byte must be zero.
+fn read_unsigned_leb128(slice: &[u8], position: &mut usize, bits: u32) -> Option<u64> {
+    let mut result = 0u64;
+    let mut shift = 0;
+
+    loop {
+        let byte = *slice.get(*position)?;
+        *position += 1;
+
+        if bits - shift < 7 && (byte & 0x80 != 0 || byte >> (bits - shift) != 0) {
+            return None;
         }
-    };
-}
 
-// Copied from https://doc.rust-lang.org/stable/nightly-rustc/src/rustc_serialize/leb128.rs.html
-macro_rules! impl_read_unsigned_leb128 {
-    ($fn_name:ident, $int_ty:ty) => {
-        #[inline]
-        pub fn $fn_name(slice: &[u8], position: &mut usize) -> $int_ty {
-            $crate::read_unsigned_leb128!($int_ty)(slice, position)
+        result |= u64::from(byte & 0x7F) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Some(result);
         }
-    };
+    }
 }
 
-impl_read_unsigned_leb128!(read_u32_leb128, u32);
-impl_read_unsigned_leb128!(read_u64_leb128, u64);
-
-// impl_read_signed_leb128 with 33 bytes and i64 as container type
-pub fn read_s33_leb128(slice: &[u8], position: &mut usize) -> i64 {
-    let mut result = 0;
+// Reads a signed LEB128 number of `bits` width. The unused bits of the last possible byte
+// must be a sign extension of the value.
+fn read_signed_leb128(slice: &[u8], position: &mut usize, bits: u32) -> Option<i64> {
+    let mut result = 0i64;
     let mut shift = 0;
-    let mut byte;
 
     loop {
-        byte = slice[*position];
+        let byte = *slice.get(*position)?;
         *position += 1;
-        result |= <i64>::from(byte & 0x7F) << shift;
+
+        if bits - shift <= 7 {
+            let unused_bits = (byte & 0x7F) >> (bits - shift - 1);
+            if byte & 0x80 != 0 || (unused_bits != 0 && unused_bits != 0x7F >> (bits - shift - 1)) {
+                return None;
+            }
+        }
+
+        result |= i64::from(byte & 0x7F) << shift;
         shift += 7;
 
-        if (byte & 0x80) == 0 {
-            break;
+        if byte & 0x80 == 0 {
+            if shift < 64 && byte & 0x40 != 0 {
+                // sign extend
+                result |= !0 << shift;
+            }
+
+            return Some(result);
         }
     }
+}
+
+pub fn read_u32_leb128(slice: &[u8], position: &mut usize) -> Option<u32> {
+    read_unsigned_leb128(slice, position, 32).map(|v| v as u32)
+}
+
+pub fn read_u64_leb128(slice: &[u8], position: &mut usize) -> Option<u64> {
+    read_unsigned_leb128(slice, position, 64)
+}
 
-    if (shift < 33) && ((byte & 0x40) != 0) {
-        // sign extend
-        result |= !0 << shift;
+pub fn read_s32_leb128(slice: &[u8], position: &mut usize) -> Option<i32> {
+    read_signed_leb128(slice, position, 32).map(|v| v as i32)
+}
+
+// s33 is used for type indices of blocks, i64 is the container type
+pub fn read_s33_leb128(slice: &[u8], position: &mut usize) -> Option<i64> {
+    read_signed_leb128(slice, position, 33)
+}
+
+pub fn read_s64_leb128(slice: &[u8], position: &mut usize) -> Option<i64> {
+    read_signed_leb128(slice, position, 64)
+}
+
+/// Parses the length of a vector.
+/// Every element takes at least one byte, so a length above the number of remaining bytes is malformed.
+pub fn parse_vector_len(bytes: &[Byte]) -> NomResult<&[Byte], usize> {
+    let (remaining_bytes, len) = U32Type::parse(bytes)?;
+
+    if len.0 as usize > remaining_bytes.len() {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            bytes,
+            nom::error::ErrorKind::LengthValue,
+        )));
     }
 
-    result
+    Ok((remaining_bytes, len.0 as usize))
 }
 
 // Unlike to Vec::parse this function should be used for cases when a number
@@ -80,24 +107,38 @@ pub fn parse_all_to_vec<T>(bytes: &[Byte], till: Byte) -> NomResult<&[Byte], Vec
 where
     T: ParseWithNom + Sized,
 {
+    // the items are parsed in place instead of in a helper, every frame on the
+    // recursion of nested blocks counts against the stack
+    let Some(_nesting) = enter_nesting() else {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            bytes,
+            nom::error::ErrorKind::ManyTill,
+        )));
+    };
+
     let mut remaining_bytes = bytes;
     let mut accumulator: Vec<T> = Vec::new();
 
-    while !remaining_bytes.is_empty() {
-        let parsed = T::parse(remaining_bytes)?;
-        remaining_bytes = parsed.0;
-        accumulator.push(parsed.1);
-
-        if remaining_bytes.first().map(|b| *b == till).unwrap_or(true) {
-            break;
+    loop {
+        match remaining_bytes.first() {
+            Some(byte) if *byte == till => break Ok((remaining_bytes.slice(1..), accumulator)),
+            Some(_) => match T::parse(remaining_bytes) {
+                Ok((bytes, item)) => {
+                    remaining_bytes = bytes;
+                    accumulator.push(item);
+                }
+                Err(err) => break Err(err),
+            },
+            None => {
+                break Err(nom::Err::Failure(nom::error::Error::new(
+                    remaining_bytes,
+                    nom::error::ErrorKind::Eof,
+                )))
+            }
         }
     }
-
-    Ok((remaining_bytes.slice(1..), accumulator))
 }
 
-// TODO: create unit test for parse_all_to_vec
-
 pub fn parse<T>(bytes: &[Byte]) -> NomResult<&[Byte], T>
 where
     T: ParseWithNom,
@@ -137,3 +178,123 @@ macro_rules! impl_write_unsigned_leb128 {
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::instructions::InstructionType;
+    use crate::nesting::{set_max_nesting_depth, MAX_NESTING_DEPTH};
+
+    fn read<T>(bytes: &[u8], read: fn(&[u8], &mut usize) -> Option<T>) -> Option<(T, usize)> {
+        let mut position = 0;
+        read(bytes, &mut position).map(|v| (v, position))
+    }
+
+    #[test]
+    fn test_leb128_bounds() {
+        assert_eq!(
+            read(&[0xE5, 0x8E, 0x26], read_u32_leb128),
+            Some((624485, 3))
+        );
+        assert_eq!(
+            read(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F], read_u32_leb128),
+            Some((u32::MAX, 5))
+        );
+        assert_eq!(read(&[0x80, 0x80, 0x00], read_u32_leb128), Some((0, 3)));
+        // truncated
+        assert_eq!(read(&[0x80, 0x80], read_u32_leb128), None);
+        assert_eq!(read(&[], read_u64_leb128), None);
+        // longer than ceil(32 / 7) bytes
+        assert_eq!(
+            read(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x00], read_u32_leb128),
+            None
+        );
+        // unused bits are set
+        assert_eq!(read(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F], read_u32_leb128), None);
+        assert_eq!(read(&[0xFF; 10], read_u64_leb128), None);
+    }
+
+    #[test]
+    fn test_signed_leb128_bounds() {
+        assert_eq!(read(&[0x7F], read_s32_leb128), Some((-1, 1)));
+        assert_eq!(
+            read(&[0xC0, 0xBB, 0x78], read_s32_leb128),
+            Some((-123456, 3))
+        );
+        assert_eq!(
+            read(&[0x80, 0x80, 0x80, 0x80, 0x78], read_s32_leb128),
+            Some((i32::MIN, 5))
+        );
+        assert_eq!(
+            read(&[0x80, 0x80, 0x80, 0x80, 0x70], read_s33_leb128),
+            Some((-(1 << 32), 5))
+        );
+        assert_eq!(
+            read(
+                &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7F],
+                read_s64_leb128
+            ),
+            Some((i64::MIN, 10))
+        );
+        // sign bits of the last byte don't match
+        assert_eq!(read(&[0x80, 0x80, 0x80, 0x80, 0x70], read_s32_leb128), None);
+        assert_eq!(read(&[0x80, 0x80, 0x80, 0x80, 0x08], read_s32_leb128), None);
+        assert_eq!(
+            read(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F], read_s33_leb128),
+            None
+        );
+    }
+
+    #[test]
+    fn test_vector_len_is_bounded_by_input() {
+        assert_eq!(
+            parse_vector_len(&[0x02, 0x00, 0x00]),
+            Ok((&[0x00u8, 0x00][..], 2))
+        );
+        assert!(parse_vector_len(&[0x03, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_parse_all_to_vec() {
+        let (bytes, instructions) =
+            parse_all_to_vec::<InstructionType>(&[0x01, 0x01, 0x0B, 0x00], 0x0B).unwrap();
+        assert_eq!(
+            instructions,
+            vec![InstructionType::Nop, InstructionType::Nop]
+        );
+        assert_eq!(bytes, &[0x00]);
+
+        let (_, instructions) = parse_all_to_vec::<InstructionType>(&[0x0B], 0x0B).unwrap();
+        assert!(instructions.is_empty());
+
+        assert!(parse_all_to_vec::<InstructionType>(&[0x01], 0x0B).is_err());
+        assert!(parse_all_to_vec::<InstructionType>(&[], 0x0B).is_err());
+    }
+
+    #[test]
+    fn test_nesting_depth_is_bounded() {
+        let nested = |depth: usize| {
+            let mut bytes = [0x02, 0x40].repeat(depth);
+            bytes.extend(vec![0x0B; depth + 1]);
+            bytes
+        };
+
+        let parse =
+            move |depth: usize| parse_all_to_vec::<InstructionType>(&nested(depth), 0x0B).is_ok();
+        let check = move |limit: usize| {
+            assert!(parse(limit - 1));
+            assert!(!parse(limit));
+            // the depth is restored after a failure
+            assert!(parse(1));
+        };
+
+        // the limit is changed on a thread of its own
+        std::thread::spawn(move || {
+            check(MAX_NESTING_DEPTH);
+            set_max_nesting_depth(512);
+            check(512);
+        })
+        .join()
+        .unwrap();
+    }
+}
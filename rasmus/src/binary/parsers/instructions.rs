@@ -12,10 +12,11 @@ use crate::{
     },
     entities::{
         instructions::{
-            BlockInstructionType, BlockType, ExpressionType, IfElseInstructionType,
-            IfInstructionType, InstructionType, LoopInstructionType,
+            BlockInstructionType, BlockType, CatchType, ExpressionType, IfElseInstructionType,
+            IfInstructionType, InstructionType, LoopInstructionType, MemArgType,
+            TryTableInstructionType,
         },
-        types::{Byte, S33Type, U32Type},
+        types::{Byte, MemIdx, S33Type, TableIdx, TypeIdx, U32Type, U64Type},
     },
 };
 
@@ -35,6 +36,17 @@ const OPCODE_BR_TABLE: Byte = 0x0E;
 const OPCODE_RETURN: Byte = 0x0F;
 const OPCODE_CALL: Byte = 0x10;
 const OPCODE_CALL_INDIRECT: Byte = 0x11;
+const OPCODE_RETURN_CALL: Byte = 0x12;
+const OPCODE_RETURN_CALL_INDIRECT: Byte = 0x13;
+const OPCODE_THROW: Byte = 0x08;
+const OPCODE_THROW_REF: Byte = 0x0A;
+const OPCODE_TRY_TABLE: Byte = 0x1F;
+
+// Catch clauses of try_table
+const ENCODE_BYTE_CATCH: Byte = 0x00;
+const ENCODE_BYTE_CATCH_REF: Byte = 0x01;
+const ENCODE_BYTE_CATCH_ALL: Byte = 0x02;
+const ENCODE_BYTE_CATCH_ALL_REF: Byte = 0x03;
 
 // Reference Instructions
 const OPCODE_REF_NULL: Byte = 0xD0;
@@ -238,6 +250,76 @@ const BYTE_PREFIX_I64_TRUNC_SAT_F64_S: U32Type = U32Type(6);
 const BYTE_PREFIX_I64_TRUNC_SAT_F64_U: U32Type = U32Type(7);
 
 // Vector Instuctions
+// Atomic Memory Instructions
+const OPCODE_ATOMIC_INSTRUCTIONS: Byte = 0xFE;
+const BYTE_PREFIX_MEMORY_ATOMIC_NOTIFY: U32Type = U32Type(0x00);
+const BYTE_PREFIX_MEMORY_ATOMIC_WAIT32: U32Type = U32Type(0x01);
+const BYTE_PREFIX_MEMORY_ATOMIC_WAIT64: U32Type = U32Type(0x02);
+const BYTE_PREFIX_ATOMIC_FENCE: U32Type = U32Type(0x03);
+const BYTE_PREFIX_I32_ATOMIC_LOAD: U32Type = U32Type(0x10);
+const BYTE_PREFIX_I64_ATOMIC_LOAD: U32Type = U32Type(0x11);
+const BYTE_PREFIX_I32_ATOMIC_LOAD8_U: U32Type = U32Type(0x12);
+const BYTE_PREFIX_I32_ATOMIC_LOAD16_U: U32Type = U32Type(0x13);
+const BYTE_PREFIX_I64_ATOMIC_LOAD8_U: U32Type = U32Type(0x14);
+const BYTE_PREFIX_I64_ATOMIC_LOAD16_U: U32Type = U32Type(0x15);
+const BYTE_PREFIX_I64_ATOMIC_LOAD32_U: U32Type = U32Type(0x16);
+const BYTE_PREFIX_I32_ATOMIC_STORE: U32Type = U32Type(0x17);
+const BYTE_PREFIX_I64_ATOMIC_STORE: U32Type = U32Type(0x18);
+const BYTE_PREFIX_I32_ATOMIC_STORE8: U32Type = U32Type(0x19);
+const BYTE_PREFIX_I32_ATOMIC_STORE16: U32Type = U32Type(0x1A);
+const BYTE_PREFIX_I64_ATOMIC_STORE8: U32Type = U32Type(0x1B);
+const BYTE_PREFIX_I64_ATOMIC_STORE16: U32Type = U32Type(0x1C);
+const BYTE_PREFIX_I64_ATOMIC_STORE32: U32Type = U32Type(0x1D);
+const BYTE_PREFIX_I32_ATOMIC_RMW_ADD: U32Type = U32Type(0x1E);
+const BYTE_PREFIX_I64_ATOMIC_RMW_ADD: U32Type = U32Type(0x1F);
+const BYTE_PREFIX_I32_ATOMIC_RMW8_ADD_U: U32Type = U32Type(0x20);
+const BYTE_PREFIX_I32_ATOMIC_RMW16_ADD_U: U32Type = U32Type(0x21);
+const BYTE_PREFIX_I64_ATOMIC_RMW8_ADD_U: U32Type = U32Type(0x22);
+const BYTE_PREFIX_I64_ATOMIC_RMW16_ADD_U: U32Type = U32Type(0x23);
+const BYTE_PREFIX_I64_ATOMIC_RMW32_ADD_U: U32Type = U32Type(0x24);
+const BYTE_PREFIX_I32_ATOMIC_RMW_SUB: U32Type = U32Type(0x25);
+const BYTE_PREFIX_I64_ATOMIC_RMW_SUB: U32Type = U32Type(0x26);
+const BYTE_PREFIX_I32_ATOMIC_RMW8_SUB_U: U32Type = U32Type(0x27);
+const BYTE_PREFIX_I32_ATOMIC_RMW16_SUB_U: U32Type = U32Type(0x28);
+const BYTE_PREFIX_I64_ATOMIC_RMW8_SUB_U: U32Type = U32Type(0x29);
+const BYTE_PREFIX_I64_ATOMIC_RMW16_SUB_U: U32Type = U32Type(0x2A);
+const BYTE_PREFIX_I64_ATOMIC_RMW32_SUB_U: U32Type = U32Type(0x2B);
+const BYTE_PREFIX_I32_ATOMIC_RMW_AND: U32Type = U32Type(0x2C);
+const BYTE_PREFIX_I64_ATOMIC_RMW_AND: U32Type = U32Type(0x2D);
+const BYTE_PREFIX_I32_ATOMIC_RMW8_AND_U: U32Type = U32Type(0x2E);
+const BYTE_PREFIX_I32_ATOMIC_RMW16_AND_U: U32Type = U32Type(0x2F);
+const BYTE_PREFIX_I64_ATOMIC_RMW8_AND_U: U32Type = U32Type(0x30);
+const BYTE_PREFIX_I64_ATOMIC_RMW16_AND_U: U32Type = U32Type(0x31);
+const BYTE_PREFIX_I64_ATOMIC_RMW32_AND_U: U32Type = U32Type(0x32);
+const BYTE_PREFIX_I32_ATOMIC_RMW_OR: U32Type = U32Type(0x33);
+const BYTE_PREFIX_I64_ATOMIC_RMW_OR: U32Type = U32Type(0x34);
+const BYTE_PREFIX_I32_ATOMIC_RMW8_OR_U: U32Type = U32Type(0x35);
+const BYTE_PREFIX_I32_ATOMIC_RMW16_OR_U: U32Type = U32Type(0x36);
+const BYTE_PREFIX_I64_ATOMIC_RMW8_OR_U: U32Type = U32Type(0x37);
+const BYTE_PREFIX_I64_ATOMIC_RMW16_OR_U: U32Type = U32Type(0x38);
+const BYTE_PREFIX_I64_ATOMIC_RMW32_OR_U: U32Type = U32Type(0x39);
+const BYTE_PREFIX_I32_ATOMIC_RMW_XOR: U32Type = U32Type(0x3A);
+const BYTE_PREFIX_I64_ATOMIC_RMW_XOR: U32Type = U32Type(0x3B);
+const BYTE_PREFIX_I32_ATOMIC_RMW8_XOR_U: U32Type = U32Type(0x3C);
+const BYTE_PREFIX_I32_ATOMIC_RMW16_XOR_U: U32Type = U32Type(0x3D);
+const BYTE_PREFIX_I64_ATOMIC_RMW8_XOR_U: U32Type = U32Type(0x3E);
+const BYTE_PREFIX_I64_ATOMIC_RMW16_XOR_U: U32Type = U32Type(0x3F);
+const BYTE_PREFIX_I64_ATOMIC_RMW32_XOR_U: U32Type = U32Type(0x40);
+const BYTE_PREFIX_I32_ATOMIC_RMW_XCHG: U32Type = U32Type(0x41);
+const BYTE_PREFIX_I64_ATOMIC_RMW_XCHG: U32Type = U32Type(0x42);
+const BYTE_PREFIX_I32_ATOMIC_RMW8_XCHG_U: U32Type = U32Type(0x43);
+const BYTE_PREFIX_I32_ATOMIC_RMW16_XCHG_U: U32Type = U32Type(0x44);
+const BYTE_PREFIX_I64_ATOMIC_RMW8_XCHG_U: U32Type = U32Type(0x45);
+const BYTE_PREFIX_I64_ATOMIC_RMW16_XCHG_U: U32Type = U32Type(0x46);
+const BYTE_PREFIX_I64_ATOMIC_RMW32_XCHG_U: U32Type = U32Type(0x47);
+const BYTE_PREFIX_I32_ATOMIC_RMW_CMPXCHG: U32Type = U32Type(0x48);
+const BYTE_PREFIX_I64_ATOMIC_RMW_CMPXCHG: U32Type = U32Type(0x49);
+const BYTE_PREFIX_I32_ATOMIC_RMW8_CMPXCHG_U: U32Type = U32Type(0x4A);
+const BYTE_PREFIX_I32_ATOMIC_RMW16_CMPXCHG_U: U32Type = U32Type(0x4B);
+const BYTE_PREFIX_I64_ATOMIC_RMW8_CMPXCHG_U: U32Type = U32Type(0x4C);
+const BYTE_PREFIX_I64_ATOMIC_RMW16_CMPXCHG_U: U32Type = U32Type(0x4D);
+const BYTE_PREFIX_I64_ATOMIC_RMW32_CMPXCHG_U: U32Type = U32Type(0x4E);
+
 const OPCODE_VECTOR_INSTRUCTIONS: Byte = 0xFD;
 const BYTE_PREFIX_V128_LOAD: U32Type = U32Type(0);
 const BYTE_PREFIX_V128_LOAD_8x8_S: U32Type = U32Type(1);
@@ -479,6 +561,9 @@ const BYTE_PREFIX_F64x2_PROMOTE_LOW_F32x4: U32Type = U32Type(95);
 const OP_CODE_END: Byte = 0x0B;
 const OPCODE_EMPTY: Byte = 0x40;
 
+// Multi-memory: bit 6 of the alignment field signals an explicit memory index
+const MEMARG_MEMORY_IDX_FLAG: u32 = 0x40;
+
 impl ParseWithNom for IfElseInstructionType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
         let (bytes, blocktype) = BlockType::parse(bytes)?;
@@ -525,6 +610,29 @@ impl ParseWithNom for ExpressionType {
     }
 }
 
+impl ParseWithNom for MemArgType {
+    fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let (bytes, U32Type(flags)) = U32Type::parse(bytes)?;
+
+        let (bytes, memory) = if flags & MEMARG_MEMORY_IDX_FLAG != 0 {
+            MemIdx::parse(bytes)?
+        } else {
+            (bytes, MemIdx(U32Type(0)))
+        };
+
+        let (bytes, offset) = U64Type::parse(bytes)?;
+
+        Ok((
+            bytes,
+            MemArgType {
+                align: U32Type(flags & !MEMARG_MEMORY_IDX_FLAG),
+                offset,
+                memory,
+            },
+        ))
+    }
+}
+
 impl ParseWithNom for BlockType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
         match bytes.get(0) {
@@ -592,212 +700,498 @@ impl ParseWithNom for BlockInstructionType {
     }
 }
 
-impl ParseWithNom for InstructionType {
+impl ParseWithNom for CatchType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
-        let (bytes, opcode) = take(1usize)(bytes)?;
-
-        match opcode[0] {
-            OPCODE_UNREACHABLE => Ok((bytes, Self::Unreachable)),
-            OPCODE_NOP => Ok((bytes, Self::Nop)),
-            OPCODE_BLOCK => parse(bytes).map(|(b, v)| (b, Self::Block(v))),
-            OPCODE_LOOP => parse(bytes).map(|(b, v)| (b, Self::Loop(v))),
-            OPCODE_IF_ELSE => parse(bytes).map(|(b, v)| (b, Self::IfElse(v))),
-            OPCODE_BR => parse(bytes).map(|(b, v)| (b, Self::Br(v))),
-            OPCODE_BR_IF => parse(bytes).map(|(b, v)| (b, Self::BrIf(v))),
-            OPCODE_BR_TABLE => parse(bytes).map(|(b, v)| (b, Self::BrTable(v))),
-            OPCODE_RETURN => Ok((bytes, Self::Return)),
-            OPCODE_CALL => parse(bytes).map(|(b, v)| (b, Self::Call(v))),
-            OPCODE_CALL_INDIRECT => parse(bytes).map(|(b, v)| (b, Self::CallIndirect(v))),
-
-            OPCODE_REF_NULL => parse(bytes).map(|(b, v)| (b, Self::RefNull(v))),
-            OPCODE_REF_IS_NULL => Ok((bytes, Self::RefIsNull)),
-            OPCODE_REF_FUNC => parse(bytes).map(|(b, v)| (b, Self::RefFunc(v))),
-            OPCODE_DROP => Ok((bytes, Self::Drop)),
-            OPCODE_SELECT => Ok((bytes, Self::Select)),
-
-            OPCODE_SELECT_VEC => parse(bytes).map(|(b, v)| (b, Self::SelectVec(v))),
-            OPCODE_LOCAL_GET => parse(bytes).map(|(b, v)| (b, Self::LocalGet(v))),
-            OPCODE_LOCAL_SET => parse(bytes).map(|(b, v)| (b, Self::LocalSet(v))),
-            OPCODE_LOCAL_TEE => parse(bytes).map(|(b, v)| (b, Self::LocalTee(v))),
-            OPCODE_GLOBAL_GET => parse(bytes).map(|(b, v)| (b, Self::GlobalGet(v))),
-            OPCODE_GLOBAL_SET => parse(bytes).map(|(b, v)| (b, Self::GlobalSet(v))),
-
-            OPCODE_TABLE_GET => parse(bytes).map(|(b, v)| (b, Self::TableGet(v))),
-            OPCODE_TABLE_SET => parse(bytes).map(|(b, v)| (b, Self::TableSet(v))),
-            OPCODE_OTHER => parse_other(bytes),
-
-            OPCODE_I32_LOAD => parse(bytes).map(|(b, v)| (b, Self::I32Load(v))),
-            OPCODE_I64_LOAD => parse(bytes).map(|(b, v)| (b, Self::I64Load(v))),
-            OPCODE_F32_LOAD => parse(bytes).map(|(b, v)| (b, Self::F32Load(v))),
-            OPCODE_F64_LOAD => parse(bytes).map(|(b, v)| (b, Self::F64Load(v))),
-            OPCODE_I32_LOAD_8_S => parse(bytes).map(|(b, v)| (b, Self::I32Load8S(v))),
-            OPCODE_I32_LOAD_8_U => parse(bytes).map(|(b, v)| (b, Self::I32Load8U(v))),
-            OPCODE_I32_LOAD_16_S => parse(bytes).map(|(b, v)| (b, Self::I32Load16S(v))),
-            OPCODE_I32_LOAD_16_U => parse(bytes).map(|(b, v)| (b, Self::I32Load16U(v))),
-            OPCODE_I64_LOAD_8_S => parse(bytes).map(|(b, v)| (b, Self::I64Load8S(v))),
-            OPCODE_I64_LOAD_8_U => parse(bytes).map(|(b, v)| (b, Self::I64Load8U(v))),
-            OPCODE_I64_LOAD_16_S => parse(bytes).map(|(b, v)| (b, Self::I64Load16S(v))),
-            OPCODE_I64_LOAD_16_U => parse(bytes).map(|(b, v)| (b, Self::I64Load16U(v))),
-            OPCODE_I64_LOAD_32_S => parse(bytes).map(|(b, v)| (b, Self::I64Load32S(v))),
-            OPCODE_I64_LOAD_32_U => parse(bytes).map(|(b, v)| (b, Self::I64Load32U(v))),
-            OPCODE_I32_STORE => parse(bytes).map(|(b, v)| (b, Self::I32Store(v))),
-            OPCODE_I64_STORE => parse(bytes).map(|(b, v)| (b, Self::I64Store(v))),
-            OPCODE_F32_STORE => parse(bytes).map(|(b, v)| (b, Self::F32Store(v))),
-            OPCODE_F64_STORE => parse(bytes).map(|(b, v)| (b, Self::F64Store(v))),
-            OPCODE_I32_STORE_8 => parse(bytes).map(|(b, v)| (b, Self::I32Store8(v))),
-            OPCODE_I32_STORE_16 => parse(bytes).map(|(b, v)| (b, Self::I32Store16(v))),
-            OPCODE_I64_STORE_8 => parse(bytes).map(|(b, v)| (b, Self::I64Store8(v))),
-            OPCODE_I64_STORE_16 => parse(bytes).map(|(b, v)| (b, Self::I64Store16(v))),
-            OPCODE_I64_STORE_32 => parse(bytes).map(|(b, v)| (b, Self::I64Store32(v))),
-            OPCODE_MEMORY_SIZE => Ok((tag(&[0x00])(bytes).map(|v| v.0)?, Self::MemorySize)),
-            OPCODE_MEMORY_GROW => Ok((tag(&[0x00])(bytes).map(|v| v.0)?, Self::MemoryGrow)),
-
-            OPCODE_I32_CONST => parse(bytes).map(|(b, v)| (b, Self::I32Const(v))),
-            OPCODE_I64_CONST => parse(bytes).map(|(b, v)| (b, Self::I64Const(v))),
-            OPCODE_F32_CONST => parse(bytes).map(|(b, v)| (b, Self::F32Const(v))),
-            OPCODE_F64_CONST => parse(bytes).map(|(b, v)| (b, Self::F64Const(v))),
-            OPCODE_I32_EQZ => Ok((bytes, Self::I32Eqz)),
-            OPCODE_I32_EQ => Ok((bytes, Self::I32Eq)),
-            OPCODE_I32_NE => Ok((bytes, Self::I32Ne)),
-            OPCODE_I32_LT_S => Ok((bytes, Self::I32LtS)),
-            OPCODE_I32_LT_U => Ok((bytes, Self::I32LtU)),
-            OPCODE_I32_GT_S => Ok((bytes, Self::I32GtS)),
-            OPCODE_I32_GT_U => Ok((bytes, Self::I32GtU)),
-            OPCODE_I32_LE_S => Ok((bytes, Self::I32LeS)),
-            OPCODE_I32_LE_U => Ok((bytes, Self::I32LeU)),
-            OPCODE_I32_GE_S => Ok((bytes, Self::I32GeS)),
-            OPCODE_I32_GE_U => Ok((bytes, Self::I32GeU)),
-            OPCODE_I64_EQZ => Ok((bytes, Self::I64Eqz)),
-            OPCODE_I64_EQ => Ok((bytes, Self::I64Eq)),
-            OPCODE_I64_NE => Ok((bytes, Self::I64Ne)),
-            OPCODE_I64_LT_S => Ok((bytes, Self::I64LtS)),
-            OPCODE_I64_LT_U => Ok((bytes, Self::I64LtU)),
-            OPCODE_I64_GT_S => Ok((bytes, Self::I64GtS)),
-            OPCODE_I64_GT_U => Ok((bytes, Self::I64GtU)),
-            OPCODE_I64_LE_S => Ok((bytes, Self::I64LeS)),
-            OPCODE_I64_LE_U => Ok((bytes, Self::I64LeU)),
-            OPCODE_I64_GE_S => Ok((bytes, Self::I64GeS)),
-            OPCODE_I64_GE_U => Ok((bytes, Self::I64GeU)),
-            OPCODE_F32_EQ => Ok((bytes, Self::F32Eq)),
-            OPCODE_F32_NE => Ok((bytes, Self::F32Ne)),
-            OPCODE_F32_LT => Ok((bytes, Self::F32Lt)),
-            OPCODE_F32_GT => Ok((bytes, Self::F32Gt)),
-            OPCODE_F32_LE => Ok((bytes, Self::F32Le)),
-            OPCODE_F32_GE => Ok((bytes, Self::F32Ge)),
-            OPCODE_F64_EQ => Ok((bytes, Self::F64Eq)),
-            OPCODE_F64_NE => Ok((bytes, Self::F64Ne)),
-            OPCODE_F64_LT => Ok((bytes, Self::F64Lt)),
-            OPCODE_F64_GT => Ok((bytes, Self::F64Gt)),
-            OPCODE_F64_LE => Ok((bytes, Self::F64Le)),
-            OPCODE_F64_GE => Ok((bytes, Self::F64Ge)),
-            OPCODE_I32_CLZ => Ok((bytes, Self::I32Clz)),
-            OPCODE_I32_CTZ => Ok((bytes, Self::I32Ctz)),
-            OPCODE_I32_POPCNT => Ok((bytes, Self::I32Popcnt)),
-            OPCODE_I32_ADD => Ok((bytes, Self::I32Add)),
-            OPCODE_I32_SUB => Ok((bytes, Self::I32Sub)),
-            OPCODE_I32_MUL => Ok((bytes, Self::I32Mul)),
-            OPCODE_I32_DIV_S => Ok((bytes, Self::I32DivS)),
-            OPCODE_I32_DIV_U => Ok((bytes, Self::I32DivU)),
-            OPCODE_I32_REM_S => Ok((bytes, Self::I32RemS)),
-            OPCODE_I32_REM_U => Ok((bytes, Self::I32RemU)),
-            OPCODE_I32_AND => Ok((bytes, Self::I32And)),
-            OPCODE_I32_OR => Ok((bytes, Self::I32Or)),
-            OPCODE_I32_XOR => Ok((bytes, Self::I32Xor)),
-            OPCODE_I32_SHL => Ok((bytes, Self::I32Shl)),
-            OPCODE_I32_SHR_S => Ok((bytes, Self::I32ShrS)),
-            OPCODE_I32_SHR_U => Ok((bytes, Self::I32ShrU)),
-            OPCODE_I32_ROTL => Ok((bytes, Self::I32Rotl)),
-            OPCODE_I32_ROTR => Ok((bytes, Self::I32Rotr)),
-            OPCODE_I64_CLZ => Ok((bytes, Self::I64Clz)),
-            OPCODE_I64_CTZ => Ok((bytes, Self::I64Ctz)),
-            OPCODE_I64_POPCNT => Ok((bytes, Self::I64Popcnt)),
-            OPCODE_I64_ADD => Ok((bytes, Self::I64Add)),
-            OPCODE_I64_SUB => Ok((bytes, Self::I64Sub)),
-            OPCODE_I64_MUL => Ok((bytes, Self::I64Mul)),
-            OPCODE_I64_DIV_S => Ok((bytes, Self::I64DivS)),
-            OPCODE_I64_DIV_U => Ok((bytes, Self::I64DivU)),
-            OPCODE_I64_REM_S => Ok((bytes, Self::I64RemS)),
-            OPCODE_I64_REM_U => Ok((bytes, Self::I64RemU)),
-            OPCODE_I64_AND => Ok((bytes, Self::I64And)),
-            OPCODE_I64_OR => Ok((bytes, Self::I64Or)),
-            OPCODE_I64_XOR => Ok((bytes, Self::I64Xor)),
-            OPCODE_I64_SHL => Ok((bytes, Self::I64Shl)),
-            OPCODE_I64_SHR_S => Ok((bytes, Self::I64ShrS)),
-            OPCODE_I64_SHR_U => Ok((bytes, Self::I64ShrU)),
-            OPCODE_I64_ROTL => Ok((bytes, Self::I64Rotl)),
-            OPCODE_I64_ROTR => Ok((bytes, Self::I64Rotr)),
-
-            OPCODE_F32_ABS => Ok((bytes, Self::F32Abs)),
-            OPCODE_F32_NEG => Ok((bytes, Self::F32Neg)),
-            OPCODE_F32_CEIL => Ok((bytes, Self::F32Ceil)),
-            OPCODE_F32_FLOOR => Ok((bytes, Self::F32Floor)),
-            OPCODE_F32_TRUNC => Ok((bytes, Self::F32Trunc)),
-            OPCODE_F32_NEAREST => Ok((bytes, Self::F32Nearest)),
-            OPCODE_F32_SQRT => Ok((bytes, Self::F32Sqrt)),
-            OPCODE_F32_ADD => Ok((bytes, Self::F32Add)),
-            OPCODE_F32_SUB => Ok((bytes, Self::F32Sub)),
-            OPCODE_F32_MUL => Ok((bytes, Self::F32Mul)),
-            OPCODE_F32_DIV => Ok((bytes, Self::F32Div)),
-            OPCODE_F32_MIN => Ok((bytes, Self::F32Min)),
-            OPCODE_F32_MAX => Ok((bytes, Self::F32Max)),
-            OPCODE_F32_COPYSIGN => Ok((bytes, Self::F32Copysign)),
-            OPCODE_F64_ABS => Ok((bytes, Self::F64Abs)),
-            OPCODE_F64_NEG => Ok((bytes, Self::F64Neg)),
-            OPCODE_F64_CEIL => Ok((bytes, Self::F64Ceil)),
-            OPCODE_F64_FLOOR => Ok((bytes, Self::F64Floor)),
-            OPCODE_F64_TRUNC => Ok((bytes, Self::F64Trunc)),
-            OPCODE_F64_NEAREST => Ok((bytes, Self::F64Nearest)),
-            OPCODE_F64_SQRT => Ok((bytes, Self::F64Sqrt)),
-            OPCODE_F64_ADD => Ok((bytes, Self::F64Add)),
-            OPCODE_F64_SUB => Ok((bytes, Self::F64Sub)),
-            OPCODE_F64_MUL => Ok((bytes, Self::F64Mul)),
-            OPCODE_F64_DIV => Ok((bytes, Self::F64Div)),
-            OPCODE_F64_MIN => Ok((bytes, Self::F64Min)),
-            OPCODE_F64_MAX => Ok((bytes, Self::F64Max)),
-            OPCODE_F64_COPYSIGN => Ok((bytes, Self::F64Copysign)),
-            OPCODE_I32_WRAP_I64 => Ok((bytes, Self::I32WrapI64)),
-            OPCODE_I32_TRUNC_F32_S => Ok((bytes, Self::I32TruncF32S)),
-            OPCODE_I32_TRUNC_F32_U => Ok((bytes, Self::I32TruncF32U)),
-            OPCODE_I32_TRUNC_F64_S => Ok((bytes, Self::I32TruncF64S)),
-            OPCODE_I32_TRUNC_F64_U => Ok((bytes, Self::I32TruncF64U)),
-            OPCODE_I64_EXTEND_I32_S => Ok((bytes, Self::I64ExtendI32S)),
-            OPCODE_I64_EXTEND_I32_U => Ok((bytes, Self::I64ExtendI32U)),
-            OPCODE_I64_TRUNC_F32_S => Ok((bytes, Self::I64TruncF32S)),
-            OPCODE_I64_TRUNC_F32_U => Ok((bytes, Self::I64TruncF32U)),
-            OPCODE_I64_TRUNC_F64_S => Ok((bytes, Self::I64TruncF64S)),
-            OPCODE_I64_TRUNC_F64_U => Ok((bytes, Self::I64TruncF64U)),
-            OPCODE_F32_CONVERT_I32_S => Ok((bytes, Self::F32ConvertI32S)),
-            OPCODE_F32_CONVERT_I32_U => Ok((bytes, Self::F32ConvertI32U)),
-            OPCODE_F32_CONVERT_I64_S => Ok((bytes, Self::F32ConvertI64S)),
-            OPCODE_F32_CONVERT_I64_U => Ok((bytes, Self::F32ConvertI64U)),
-            OPCODE_F32_DEMOTE_F64 => Ok((bytes, Self::F32DemoteF64)),
-            OPCODE_F64_CONVERT_I32_S => Ok((bytes, Self::F64ConvertI32S)),
-            OPCODE_F64_CONVERT_I32_U => Ok((bytes, Self::F64ConvertI32U)),
-            OPCODE_F64_CONVERT_I64_S => Ok((bytes, Self::F64ConvertI64S)),
-            OPCODE_F64_CONVERT_I64_U => Ok((bytes, Self::F64ConvertI64U)),
-            OPCODE_F64_PROMOTE_F32 => Ok((bytes, Self::F64PromoteF32)),
-            OPCODE_I32_REINTERPRET_F32 => Ok((bytes, Self::I32ReinterpretF32)),
-            OPCODE_I64_REINTERPRET_F64 => Ok((bytes, Self::I64ReinterpretF64)),
-            OPCODE_F32_REINTERPRET_I32 => Ok((bytes, Self::F32ReinterpretI32)),
-            OPCODE_F64_REINTERPRET_I64 => Ok((bytes, Self::F64ReinterpretI64)),
-            OPCODE_I32_EXTEND_8_S => Ok((bytes, Self::I32Extend8S)),
-            OPCODE_I32_EXTEND_16_S => Ok((bytes, Self::I32Extend16S)),
-            OPCODE_I64_EXTEND_8_S => Ok((bytes, Self::I64Extend8S)),
-            OPCODE_I64_EXTEND_16_S => Ok((bytes, Self::I64Extend16S)),
-            OPCODE_I64_EXTEND_32_S => Ok((bytes, Self::I64Extend32S)),
-
-            OPCODE_VECTOR_INSTRUCTIONS => parse_vector_instruction(bytes),
+        let input = bytes;
+        let (bytes, kind) = take(1usize)(bytes)?;
 
+        match kind[0] {
+            ENCODE_BYTE_CATCH => parse(bytes).map(|(b, v)| (b, Self::Catch(v))),
+            ENCODE_BYTE_CATCH_REF => parse(bytes).map(|(b, v)| (b, Self::CatchRef(v))),
+            ENCODE_BYTE_CATCH_ALL => parse(bytes).map(|(b, v)| (b, Self::CatchAll(v))),
+            ENCODE_BYTE_CATCH_ALL_REF => parse(bytes).map(|(b, v)| (b, Self::CatchAllRef(v))),
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
+                input,
                 nom::error::ErrorKind::Fail,
             ))),
         }
     }
 }
 
-fn parse_other(bytes: &[Byte]) -> NomResult<&[Byte], InstructionType> {
-    let (bytes, bytecode) = U32Type::parse(bytes)?;
+impl ParseWithNom for TryTableInstructionType {
+    fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let (bytes, blocktype) = BlockType::parse(bytes)?;
+        let (bytes, catches) = Vec::<CatchType>::parse(bytes)?;
+        let (bytes, instructions) = parse_all_to_vec(bytes, OPCODE_END)?;
+
+        Ok((
+            bytes,
+            TryTableInstructionType {
+                blocktype,
+                catches,
+                instructions,
+            },
+        ))
+    }
+}
+
+impl ParseWithNom for InstructionType {
+    fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let (remaining_bytes, opcode) = take(1usize)(bytes)?;
+
+        // structured instructions are parsed recursively, so they are kept apart from
+        // the large frame of the other instructions to let blocks nest deeper
+        match opcode[0] {
+            OPCODE_BLOCK => {
+                BlockInstructionType::parse(remaining_bytes).map(|(b, v)| (b, Self::Block(v)))
+            }
+            OPCODE_LOOP => {
+                LoopInstructionType::parse(remaining_bytes).map(|(b, v)| (b, Self::Loop(v)))
+            }
+            OPCODE_IF_ELSE => {
+                IfElseInstructionType::parse(remaining_bytes).map(|(b, v)| (b, Self::IfElse(v)))
+            }
+            OPCODE_TRY_TABLE => {
+                TryTableInstructionType::parse(remaining_bytes).map(|(b, v)| (b, Self::TryTable(v)))
+            }
+            _ => parse_plain_instruction(bytes),
+        }
+    }
+}
+
+#[inline(never)]
+fn parse_plain_instruction(bytes: &[Byte]) -> NomResult<&[Byte], InstructionType> {
+    let instruction = bytes;
+    let (bytes, opcode) = take(1usize)(bytes)?;
+
+    match opcode[0] {
+        OPCODE_UNREACHABLE => Ok((bytes, InstructionType::Unreachable)),
+        OPCODE_NOP => Ok((bytes, InstructionType::Nop)),
+        OPCODE_BR => parse(bytes).map(|(b, v)| (b, InstructionType::Br(v))),
+        OPCODE_BR_IF => parse(bytes).map(|(b, v)| (b, InstructionType::BrIf(v))),
+        OPCODE_BR_TABLE => parse(bytes).map(|(b, v)| (b, InstructionType::BrTable(v))),
+        OPCODE_RETURN => Ok((bytes, InstructionType::Return)),
+        OPCODE_CALL => parse(bytes).map(|(b, v)| (b, InstructionType::Call(v))),
+        OPCODE_CALL_INDIRECT => {
+            parse_call_indirect_args(bytes).map(|(b, v)| (b, InstructionType::CallIndirect(v)))
+        }
+        OPCODE_RETURN_CALL => parse(bytes).map(|(b, v)| (b, InstructionType::ReturnCall(v))),
+        OPCODE_RETURN_CALL_INDIRECT => parse_call_indirect_args(bytes)
+            .map(|(b, v)| (b, InstructionType::ReturnCallIndirect(v))),
+        OPCODE_THROW => parse(bytes).map(|(b, v)| (b, InstructionType::Throw(v))),
+        OPCODE_THROW_REF => Ok((bytes, InstructionType::ThrowRef)),
+
+        OPCODE_REF_NULL => parse(bytes).map(|(b, v)| (b, InstructionType::RefNull(v))),
+        OPCODE_REF_IS_NULL => Ok((bytes, InstructionType::RefIsNull)),
+        OPCODE_REF_FUNC => parse(bytes).map(|(b, v)| (b, InstructionType::RefFunc(v))),
+        OPCODE_DROP => Ok((bytes, InstructionType::Drop)),
+        OPCODE_SELECT => Ok((bytes, InstructionType::Select)),
+
+        OPCODE_SELECT_VEC => parse(bytes).map(|(b, v)| (b, InstructionType::SelectVec(v))),
+        OPCODE_LOCAL_GET => parse(bytes).map(|(b, v)| (b, InstructionType::LocalGet(v))),
+        OPCODE_LOCAL_SET => parse(bytes).map(|(b, v)| (b, InstructionType::LocalSet(v))),
+        OPCODE_LOCAL_TEE => parse(bytes).map(|(b, v)| (b, InstructionType::LocalTee(v))),
+        OPCODE_GLOBAL_GET => parse(bytes).map(|(b, v)| (b, InstructionType::GlobalGet(v))),
+        OPCODE_GLOBAL_SET => parse(bytes).map(|(b, v)| (b, InstructionType::GlobalSet(v))),
+
+        OPCODE_TABLE_GET => parse(bytes).map(|(b, v)| (b, InstructionType::TableGet(v))),
+        OPCODE_TABLE_SET => parse(bytes).map(|(b, v)| (b, InstructionType::TableSet(v))),
+        OPCODE_OTHER => parse_other(instruction),
+
+        OPCODE_I32_LOAD => parse(bytes).map(|(b, v)| (b, InstructionType::I32Load(v))),
+        OPCODE_I64_LOAD => parse(bytes).map(|(b, v)| (b, InstructionType::I64Load(v))),
+        OPCODE_F32_LOAD => parse(bytes).map(|(b, v)| (b, InstructionType::F32Load(v))),
+        OPCODE_F64_LOAD => parse(bytes).map(|(b, v)| (b, InstructionType::F64Load(v))),
+        OPCODE_I32_LOAD_8_S => parse(bytes).map(|(b, v)| (b, InstructionType::I32Load8S(v))),
+        OPCODE_I32_LOAD_8_U => parse(bytes).map(|(b, v)| (b, InstructionType::I32Load8U(v))),
+        OPCODE_I32_LOAD_16_S => parse(bytes).map(|(b, v)| (b, InstructionType::I32Load16S(v))),
+        OPCODE_I32_LOAD_16_U => parse(bytes).map(|(b, v)| (b, InstructionType::I32Load16U(v))),
+        OPCODE_I64_LOAD_8_S => parse(bytes).map(|(b, v)| (b, InstructionType::I64Load8S(v))),
+        OPCODE_I64_LOAD_8_U => parse(bytes).map(|(b, v)| (b, InstructionType::I64Load8U(v))),
+        OPCODE_I64_LOAD_16_S => parse(bytes).map(|(b, v)| (b, InstructionType::I64Load16S(v))),
+        OPCODE_I64_LOAD_16_U => parse(bytes).map(|(b, v)| (b, InstructionType::I64Load16U(v))),
+        OPCODE_I64_LOAD_32_S => parse(bytes).map(|(b, v)| (b, InstructionType::I64Load32S(v))),
+        OPCODE_I64_LOAD_32_U => parse(bytes).map(|(b, v)| (b, InstructionType::I64Load32U(v))),
+        OPCODE_I32_STORE => parse(bytes).map(|(b, v)| (b, InstructionType::I32Store(v))),
+        OPCODE_I64_STORE => parse(bytes).map(|(b, v)| (b, InstructionType::I64Store(v))),
+        OPCODE_F32_STORE => parse(bytes).map(|(b, v)| (b, InstructionType::F32Store(v))),
+        OPCODE_F64_STORE => parse(bytes).map(|(b, v)| (b, InstructionType::F64Store(v))),
+        OPCODE_I32_STORE_8 => parse(bytes).map(|(b, v)| (b, InstructionType::I32Store8(v))),
+        OPCODE_I32_STORE_16 => parse(bytes).map(|(b, v)| (b, InstructionType::I32Store16(v))),
+        OPCODE_I64_STORE_8 => parse(bytes).map(|(b, v)| (b, InstructionType::I64Store8(v))),
+        OPCODE_I64_STORE_16 => parse(bytes).map(|(b, v)| (b, InstructionType::I64Store16(v))),
+        OPCODE_I64_STORE_32 => parse(bytes).map(|(b, v)| (b, InstructionType::I64Store32(v))),
+        OPCODE_MEMORY_SIZE => parse(bytes).map(|(b, v)| (b, InstructionType::MemorySize(v))),
+        OPCODE_MEMORY_GROW => parse(bytes).map(|(b, v)| (b, InstructionType::MemoryGrow(v))),
+
+        OPCODE_I32_CONST => parse(bytes).map(|(b, v)| (b, InstructionType::I32Const(v))),
+        OPCODE_I64_CONST => parse(bytes).map(|(b, v)| (b, InstructionType::I64Const(v))),
+        OPCODE_F32_CONST => parse(bytes).map(|(b, v)| (b, InstructionType::F32Const(v))),
+        OPCODE_F64_CONST => parse(bytes).map(|(b, v)| (b, InstructionType::F64Const(v))),
+        OPCODE_I32_EQZ => Ok((bytes, InstructionType::I32Eqz)),
+        OPCODE_I32_EQ => Ok((bytes, InstructionType::I32Eq)),
+        OPCODE_I32_NE => Ok((bytes, InstructionType::I32Ne)),
+        OPCODE_I32_LT_S => Ok((bytes, InstructionType::I32LtS)),
+        OPCODE_I32_LT_U => Ok((bytes, InstructionType::I32LtU)),
+        OPCODE_I32_GT_S => Ok((bytes, InstructionType::I32GtS)),
+        OPCODE_I32_GT_U => Ok((bytes, InstructionType::I32GtU)),
+        OPCODE_I32_LE_S => Ok((bytes, InstructionType::I32LeS)),
+        OPCODE_I32_LE_U => Ok((bytes, InstructionType::I32LeU)),
+        OPCODE_I32_GE_S => Ok((bytes, InstructionType::I32GeS)),
+        OPCODE_I32_GE_U => Ok((bytes, InstructionType::I32GeU)),
+        OPCODE_I64_EQZ => Ok((bytes, InstructionType::I64Eqz)),
+        OPCODE_I64_EQ => Ok((bytes, InstructionType::I64Eq)),
+        OPCODE_I64_NE => Ok((bytes, InstructionType::I64Ne)),
+        OPCODE_I64_LT_S => Ok((bytes, InstructionType::I64LtS)),
+        OPCODE_I64_LT_U => Ok((bytes, InstructionType::I64LtU)),
+        OPCODE_I64_GT_S => Ok((bytes, InstructionType::I64GtS)),
+        OPCODE_I64_GT_U => Ok((bytes, InstructionType::I64GtU)),
+        OPCODE_I64_LE_S => Ok((bytes, InstructionType::I64LeS)),
+        OPCODE_I64_LE_U => Ok((bytes, InstructionType::I64LeU)),
+        OPCODE_I64_GE_S => Ok((bytes, InstructionType::I64GeS)),
+        OPCODE_I64_GE_U => Ok((bytes, InstructionType::I64GeU)),
+        OPCODE_F32_EQ => Ok((bytes, InstructionType::F32Eq)),
+        OPCODE_F32_NE => Ok((bytes, InstructionType::F32Ne)),
+        OPCODE_F32_LT => Ok((bytes, InstructionType::F32Lt)),
+        OPCODE_F32_GT => Ok((bytes, InstructionType::F32Gt)),
+        OPCODE_F32_LE => Ok((bytes, InstructionType::F32Le)),
+        OPCODE_F32_GE => Ok((bytes, InstructionType::F32Ge)),
+        OPCODE_F64_EQ => Ok((bytes, InstructionType::F64Eq)),
+        OPCODE_F64_NE => Ok((bytes, InstructionType::F64Ne)),
+        OPCODE_F64_LT => Ok((bytes, InstructionType::F64Lt)),
+        OPCODE_F64_GT => Ok((bytes, InstructionType::F64Gt)),
+        OPCODE_F64_LE => Ok((bytes, InstructionType::F64Le)),
+        OPCODE_F64_GE => Ok((bytes, InstructionType::F64Ge)),
+        OPCODE_I32_CLZ => Ok((bytes, InstructionType::I32Clz)),
+        OPCODE_I32_CTZ => Ok((bytes, InstructionType::I32Ctz)),
+        OPCODE_I32_POPCNT => Ok((bytes, InstructionType::I32Popcnt)),
+        OPCODE_I32_ADD => Ok((bytes, InstructionType::I32Add)),
+        OPCODE_I32_SUB => Ok((bytes, InstructionType::I32Sub)),
+        OPCODE_I32_MUL => Ok((bytes, InstructionType::I32Mul)),
+        OPCODE_I32_DIV_S => Ok((bytes, InstructionType::I32DivS)),
+        OPCODE_I32_DIV_U => Ok((bytes, InstructionType::I32DivU)),
+        OPCODE_I32_REM_S => Ok((bytes, InstructionType::I32RemS)),
+        OPCODE_I32_REM_U => Ok((bytes, InstructionType::I32RemU)),
+        OPCODE_I32_AND => Ok((bytes, InstructionType::I32And)),
+        OPCODE_I32_OR => Ok((bytes, InstructionType::I32Or)),
+        OPCODE_I32_XOR => Ok((bytes, InstructionType::I32Xor)),
+        OPCODE_I32_SHL => Ok((bytes, InstructionType::I32Shl)),
+        OPCODE_I32_SHR_S => Ok((bytes, InstructionType::I32ShrS)),
+        OPCODE_I32_SHR_U => Ok((bytes, InstructionType::I32ShrU)),
+        OPCODE_I32_ROTL => Ok((bytes, InstructionType::I32Rotl)),
+        OPCODE_I32_ROTR => Ok((bytes, InstructionType::I32Rotr)),
+        OPCODE_I64_CLZ => Ok((bytes, InstructionType::I64Clz)),
+        OPCODE_I64_CTZ => Ok((bytes, InstructionType::I64Ctz)),
+        OPCODE_I64_POPCNT => Ok((bytes, InstructionType::I64Popcnt)),
+        OPCODE_I64_ADD => Ok((bytes, InstructionType::I64Add)),
+        OPCODE_I64_SUB => Ok((bytes, InstructionType::I64Sub)),
+        OPCODE_I64_MUL => Ok((bytes, InstructionType::I64Mul)),
+        OPCODE_I64_DIV_S => Ok((bytes, InstructionType::I64DivS)),
+        OPCODE_I64_DIV_U => Ok((bytes, InstructionType::I64DivU)),
+        OPCODE_I64_REM_S => Ok((bytes, InstructionType::I64RemS)),
+        OPCODE_I64_REM_U => Ok((bytes, InstructionType::I64RemU)),
+        OPCODE_I64_AND => Ok((bytes, InstructionType::I64And)),
+        OPCODE_I64_OR => Ok((bytes, InstructionType::I64Or)),
+        OPCODE_I64_XOR => Ok((bytes, InstructionType::I64Xor)),
+        OPCODE_I64_SHL => Ok((bytes, InstructionType::I64Shl)),
+        OPCODE_I64_SHR_S => Ok((bytes, InstructionType::I64ShrS)),
+        OPCODE_I64_SHR_U => Ok((bytes, InstructionType::I64ShrU)),
+        OPCODE_I64_ROTL => Ok((bytes, InstructionType::I64Rotl)),
+        OPCODE_I64_ROTR => Ok((bytes, InstructionType::I64Rotr)),
+
+        OPCODE_F32_ABS => Ok((bytes, InstructionType::F32Abs)),
+        OPCODE_F32_NEG => Ok((bytes, InstructionType::F32Neg)),
+        OPCODE_F32_CEIL => Ok((bytes, InstructionType::F32Ceil)),
+        OPCODE_F32_FLOOR => Ok((bytes, InstructionType::F32Floor)),
+        OPCODE_F32_TRUNC => Ok((bytes, InstructionType::F32Trunc)),
+        OPCODE_F32_NEAREST => Ok((bytes, InstructionType::F32Nearest)),
+        OPCODE_F32_SQRT => Ok((bytes, InstructionType::F32Sqrt)),
+        OPCODE_F32_ADD => Ok((bytes, InstructionType::F32Add)),
+        OPCODE_F32_SUB => Ok((bytes, InstructionType::F32Sub)),
+        OPCODE_F32_MUL => Ok((bytes, InstructionType::F32Mul)),
+        OPCODE_F32_DIV => Ok((bytes, InstructionType::F32Div)),
+        OPCODE_F32_MIN => Ok((bytes, InstructionType::F32Min)),
+        OPCODE_F32_MAX => Ok((bytes, InstructionType::F32Max)),
+        OPCODE_F32_COPYSIGN => Ok((bytes, InstructionType::F32Copysign)),
+        OPCODE_F64_ABS => Ok((bytes, InstructionType::F64Abs)),
+        OPCODE_F64_NEG => Ok((bytes, InstructionType::F64Neg)),
+        OPCODE_F64_CEIL => Ok((bytes, InstructionType::F64Ceil)),
+        OPCODE_F64_FLOOR => Ok((bytes, InstructionType::F64Floor)),
+        OPCODE_F64_TRUNC => Ok((bytes, InstructionType::F64Trunc)),
+        OPCODE_F64_NEAREST => Ok((bytes, InstructionType::F64Nearest)),
+        OPCODE_F64_SQRT => Ok((bytes, InstructionType::F64Sqrt)),
+        OPCODE_F64_ADD => Ok((bytes, InstructionType::F64Add)),
+        OPCODE_F64_SUB => Ok((bytes, InstructionType::F64Sub)),
+        OPCODE_F64_MUL => Ok((bytes, InstructionType::F64Mul)),
+        OPCODE_F64_DIV => Ok((bytes, InstructionType::F64Div)),
+        OPCODE_F64_MIN => Ok((bytes, InstructionType::F64Min)),
+        OPCODE_F64_MAX => Ok((bytes, InstructionType::F64Max)),
+        OPCODE_F64_COPYSIGN => Ok((bytes, InstructionType::F64Copysign)),
+        OPCODE_I32_WRAP_I64 => Ok((bytes, InstructionType::I32WrapI64)),
+        OPCODE_I32_TRUNC_F32_S => Ok((bytes, InstructionType::I32TruncF32S)),
+        OPCODE_I32_TRUNC_F32_U => Ok((bytes, InstructionType::I32TruncF32U)),
+        OPCODE_I32_TRUNC_F64_S => Ok((bytes, InstructionType::I32TruncF64S)),
+        OPCODE_I32_TRUNC_F64_U => Ok((bytes, InstructionType::I32TruncF64U)),
+        OPCODE_I64_EXTEND_I32_S => Ok((bytes, InstructionType::I64ExtendI32S)),
+        OPCODE_I64_EXTEND_I32_U => Ok((bytes, InstructionType::I64ExtendI32U)),
+        OPCODE_I64_TRUNC_F32_S => Ok((bytes, InstructionType::I64TruncF32S)),
+        OPCODE_I64_TRUNC_F32_U => Ok((bytes, InstructionType::I64TruncF32U)),
+        OPCODE_I64_TRUNC_F64_S => Ok((bytes, InstructionType::I64TruncF64S)),
+        OPCODE_I64_TRUNC_F64_U => Ok((bytes, InstructionType::I64TruncF64U)),
+        OPCODE_F32_CONVERT_I32_S => Ok((bytes, InstructionType::F32ConvertI32S)),
+        OPCODE_F32_CONVERT_I32_U => Ok((bytes, InstructionType::F32ConvertI32U)),
+        OPCODE_F32_CONVERT_I64_S => Ok((bytes, InstructionType::F32ConvertI64S)),
+        OPCODE_F32_CONVERT_I64_U => Ok((bytes, InstructionType::F32ConvertI64U)),
+        OPCODE_F32_DEMOTE_F64 => Ok((bytes, InstructionType::F32DemoteF64)),
+        OPCODE_F64_CONVERT_I32_S => Ok((bytes, InstructionType::F64ConvertI32S)),
+        OPCODE_F64_CONVERT_I32_U => Ok((bytes, InstructionType::F64ConvertI32U)),
+        OPCODE_F64_CONVERT_I64_S => Ok((bytes, InstructionType::F64ConvertI64S)),
+        OPCODE_F64_CONVERT_I64_U => Ok((bytes, InstructionType::F64ConvertI64U)),
+        OPCODE_F64_PROMOTE_F32 => Ok((bytes, InstructionType::F64PromoteF32)),
+        OPCODE_I32_REINTERPRET_F32 => Ok((bytes, InstructionType::I32ReinterpretF32)),
+        OPCODE_I64_REINTERPRET_F64 => Ok((bytes, InstructionType::I64ReinterpretF64)),
+        OPCODE_F32_REINTERPRET_I32 => Ok((bytes, InstructionType::F32ReinterpretI32)),
+        OPCODE_F64_REINTERPRET_I64 => Ok((bytes, InstructionType::F64ReinterpretI64)),
+        OPCODE_I32_EXTEND_8_S => Ok((bytes, InstructionType::I32Extend8S)),
+        OPCODE_I32_EXTEND_16_S => Ok((bytes, InstructionType::I32Extend16S)),
+        OPCODE_I64_EXTEND_8_S => Ok((bytes, InstructionType::I64Extend8S)),
+        OPCODE_I64_EXTEND_16_S => Ok((bytes, InstructionType::I64Extend16S)),
+        OPCODE_I64_EXTEND_32_S => Ok((bytes, InstructionType::I64Extend32S)),
+
+        OPCODE_VECTOR_INSTRUCTIONS => parse_vector_instruction(instruction),
+        OPCODE_ATOMIC_INSTRUCTIONS => parse_atomic_instruction(instruction),
+
+        _ => unknown_opcode(instruction),
+    }
+}
+
+// The error points to the first byte of the instruction, so the opcode can be reported with its prefix
+fn unknown_opcode(instruction: &[Byte]) -> NomResult<&[Byte], InstructionType> {
+    Err(nom::Err::Failure(nom::error::Error::new(
+        instruction,
+        nom::error::ErrorKind::Switch,
+    )))
+}
+
+// Type index comes before table index in the binary format
+fn parse_call_indirect_args(bytes: &[Byte]) -> NomResult<&[Byte], (TableIdx, TypeIdx)> {
+    let (bytes, (type_idx, table_idx)) = parse(bytes)?;
+
+    Ok((bytes, (table_idx, type_idx)))
+}
+
+fn parse_atomic_instruction(instruction: &[Byte]) -> NomResult<&[Byte], InstructionType> {
+    let (bytes, bytecode) = U32Type::parse(&instruction[1..])?;
+
+    match bytecode {
+        BYTE_PREFIX_ATOMIC_FENCE => {
+            let (bytes, _) = tag(&[0x00u8][..])(bytes)?;
+            Ok((bytes, InstructionType::AtomicFence))
+        }
+        BYTE_PREFIX_MEMORY_ATOMIC_NOTIFY => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::MemoryAtomicNotify(v)))
+        }
+        BYTE_PREFIX_MEMORY_ATOMIC_WAIT32 => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::MemoryAtomicWait32(v)))
+        }
+        BYTE_PREFIX_MEMORY_ATOMIC_WAIT64 => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::MemoryAtomicWait64(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_LOAD => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicLoad(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_LOAD => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicLoad(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_LOAD8_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicLoad8U(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_LOAD16_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicLoad16U(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_LOAD8_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicLoad8U(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_LOAD16_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicLoad16U(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_LOAD32_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicLoad32U(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_STORE => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicStore(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_STORE => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicStore(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_STORE8 => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicStore8(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_STORE16 => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicStore16(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_STORE8 => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicStore8(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_STORE16 => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicStore16(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_STORE32 => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicStore32(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW_ADD => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmwAdd(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW_ADD => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmwAdd(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW8_ADD_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw8AddU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW16_ADD_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw16AddU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW8_ADD_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw8AddU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW16_ADD_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw16AddU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW32_ADD_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw32AddU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW_SUB => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmwSub(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW_SUB => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmwSub(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW8_SUB_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw8SubU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW16_SUB_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw16SubU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW8_SUB_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw8SubU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW16_SUB_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw16SubU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW32_SUB_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw32SubU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW_AND => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmwAnd(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW_AND => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmwAnd(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW8_AND_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw8AndU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW16_AND_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw16AndU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW8_AND_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw8AndU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW16_AND_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw16AndU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW32_AND_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw32AndU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW_OR => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmwOr(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW_OR => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmwOr(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW8_OR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw8OrU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW16_OR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw16OrU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW8_OR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw8OrU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW16_OR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw16OrU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW32_OR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw32OrU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW_XOR => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmwXor(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW_XOR => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmwXor(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW8_XOR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw8XorU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW16_XOR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw16XorU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW8_XOR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw8XorU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW16_XOR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw16XorU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW32_XOR_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw32XorU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW_XCHG => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmwXchg(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW_XCHG => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmwXchg(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW8_XCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw8XchgU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW16_XCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw16XchgU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW8_XCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw8XchgU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW16_XCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw16XchgU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW32_XCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw32XchgU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW_CMPXCHG => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmwCmpxchg(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW_CMPXCHG => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmwCmpxchg(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW8_CMPXCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw8CmpxchgU(v)))
+        }
+        BYTE_PREFIX_I32_ATOMIC_RMW16_CMPXCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I32AtomicRmw16CmpxchgU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW8_CMPXCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw8CmpxchgU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW16_CMPXCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw16CmpxchgU(v)))
+        }
+        BYTE_PREFIX_I64_ATOMIC_RMW32_CMPXCHG_U => {
+            parse(bytes).map(|(b, v)| (b, InstructionType::I64AtomicRmw32CmpxchgU(v)))
+        }
+        _ => unknown_opcode(instruction),
+    }
+}
+
+fn parse_other(instruction: &[Byte]) -> NomResult<&[Byte], InstructionType> {
+    let (bytes, bytecode) = U32Type::parse(&instruction[1..])?;
 
     match bytecode {
         BYTE_PREFIX_I32_TRUNC_SAT_F32_S => Ok((bytes, InstructionType::I32TruncSatF32S)),
@@ -811,29 +1205,20 @@ fn parse_other(bytes: &[Byte]) -> NomResult<&[Byte], InstructionType> {
 
         BYTECODE_MEMORY_INIT => parse(bytes).map(|(b, v)| (b, InstructionType::MemoryInit(v))),
         BYTECODE_DATA_DROP => parse(bytes).map(|(b, v)| (b, InstructionType::DataDrop(v))),
-        BYTECODE_MEMORY_COPY => Ok((
-            tag(&[0x00, 0x00])(bytes).map(|r| r.0)?,
-            InstructionType::MemoryCopy,
-        )),
-        BYTECODE_MEMORY_FILL => Ok((
-            tag(&[0x00])(bytes).map(|r| r.0)?,
-            InstructionType::MemoryFill,
-        )),
+        BYTECODE_MEMORY_COPY => parse(bytes).map(|(b, v)| (b, InstructionType::MemoryCopy(v))),
+        BYTECODE_MEMORY_FILL => parse(bytes).map(|(b, v)| (b, InstructionType::MemoryFill(v))),
         BYTECODE_TABLE_INIT => parse(bytes).map(|(b, v)| (b, InstructionType::TableInit(v))),
         BYTECODE_TABLE_DROP => parse(bytes).map(|(b, v)| (b, InstructionType::ElemDrop(v))),
         BYTECODE_TABLE_COPY => parse(bytes).map(|(b, v)| (b, InstructionType::TableCopy(v))),
         BYTECODE_TABLE_GROW => parse(bytes).map(|(b, v)| (b, InstructionType::TableGrow(v))),
         BYTECODE_TABLE_SIZE => parse(bytes).map(|(b, v)| (b, InstructionType::TableSize(v))),
         BYTECODE_TABLE_FILL => parse(bytes).map(|(b, v)| (b, InstructionType::TableFill(v))),
-        _ => Err(nom::Err::Failure(nom::error::Error::new(
-            bytes,
-            nom::error::ErrorKind::Fail,
-        ))),
+        _ => unknown_opcode(instruction),
     }
 }
 
-fn parse_vector_instruction(bytes: &[Byte]) -> NomResult<&[Byte], InstructionType> {
-    let (bytes, byteprefix) = U32Type::parse(bytes)?;
+fn parse_vector_instruction(instruction: &[Byte]) -> NomResult<&[Byte], InstructionType> {
+    let (bytes, byteprefix) = U32Type::parse(&instruction[1..])?;
 
     match byteprefix {
         BYTE_PREFIX_V128_LOAD => parse(bytes).map(|(b, v)| (b, InstructionType::V128Load(v))),
@@ -1183,9 +1568,119 @@ fn parse_vector_instruction(bytes: &[Byte]) -> NomResult<&[Byte], InstructionTyp
         BYTE_PREFIX_F32x4_DEMOTE_F64x2_ZERO => Ok((bytes, InstructionType::F32x4DemoteF64x2Zero)),
         BYTE_PREFIX_F64x2_PROMOTE_LOW_F32x4 => Ok((bytes, InstructionType::F64x2PromoteLowF32x4)),
 
-        _ => Err(nom::Err::Failure(nom::error::Error::new(
-            bytes,
-            nom::error::ErrorKind::Fail,
-        ))),
+        _ => unknown_opcode(instruction),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::types::{FuncIdx, LabelIdx, TagIdx};
+
+    #[test]
+    fn test_memarg_without_memory_idx() {
+        let (bytes, instruction) = InstructionType::parse(&[0x28, 0x02, 0x08]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            instruction,
+            InstructionType::I32Load(MemArgType {
+                align: U32Type(2),
+                offset: U64Type(8),
+                memory: MemIdx(U32Type(0)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_memarg_with_memory_idx() {
+        let (bytes, instruction) = InstructionType::parse(&[0x36, 0x42, 0x03, 0x08]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            instruction,
+            InstructionType::I32Store(MemArgType {
+                align: U32Type(2),
+                offset: U64Type(8),
+                memory: MemIdx(U32Type(3)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_memory_copy_with_memory_idxes() {
+        let (bytes, instruction) = InstructionType::parse(&[0xFC, 0x0A, 0x01, 0x02]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            instruction,
+            InstructionType::MemoryCopy((MemIdx(U32Type(1)), MemIdx(U32Type(2))))
+        );
+    }
+
+    #[test]
+    fn test_atomic_instructions() {
+        let (bytes, instruction) = InstructionType::parse(&[0xFE, 0x48, 0x02, 0x04]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            instruction,
+            InstructionType::I32AtomicRmwCmpxchg(MemArgType {
+                align: U32Type(2),
+                offset: U64Type(4),
+                memory: MemIdx(U32Type(0)),
+            })
+        );
+
+        let (bytes, instruction) = InstructionType::parse(&[0xFE, 0x03, 0x00]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(instruction, InstructionType::AtomicFence);
+    }
+
+    #[test]
+    fn test_tail_call_instructions() {
+        let (bytes, instruction) = InstructionType::parse(&[0x12, 0x05]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            instruction,
+            InstructionType::ReturnCall(FuncIdx(U32Type(5)))
+        );
+
+        let (bytes, instruction) = InstructionType::parse(&[0x13, 0x02, 0x01]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            instruction,
+            InstructionType::ReturnCallIndirect((TableIdx(U32Type(1)), TypeIdx(U32Type(2))))
+        );
+    }
+
+    #[test]
+    fn test_exception_instructions() {
+        let (bytes, instruction) = InstructionType::parse(&[0x08, 0x03]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(instruction, InstructionType::Throw(TagIdx(U32Type(3))));
+
+        // try_table (catch 0 1) (catch_all_ref 0) throw 0 end
+        let (bytes, instruction) = InstructionType::parse(&[
+            0x1F, 0x40, 0x02, 0x00, 0x00, 0x01, 0x03, 0x00, 0x08, 0x00, 0x0B,
+        ])
+        .unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            instruction,
+            InstructionType::TryTable(TryTableInstructionType {
+                blocktype: BlockType::Empty,
+                catches: vec![
+                    CatchType::Catch((TagIdx(U32Type(0)), LabelIdx(U32Type(1)))),
+                    CatchType::CatchAllRef(LabelIdx(U32Type(0))),
+                ],
+                instructions: vec![InstructionType::Throw(TagIdx(U32Type(0)))],
+            })
+        );
     }
 }
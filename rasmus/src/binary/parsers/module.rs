@@ -3,7 +3,8 @@ use nom::{bytes::complete::take, IResult as NomResult};
 use crate::{
     binary::{
         parse_trait::ParseWithNom,
-        syntax_error::{ParseResult, SyntaxError},
+        parser_helpers::parse_vector_len,
+        syntax_error::{ParseResult, SyntaxError, SyntaxErrorCause},
     },
     entities::{
         instructions::ExpressionType,
@@ -20,7 +21,7 @@ use crate::{
         },
         types::{
             Byte, FuncIdx, GlobalIdx, GlobalType, MemIdx, MemType, RefType, TableIdx, TableType,
-            TypeIdx, U32Type, ValType,
+            TagIdx, TagType, TypeIdx, U32Type, ValType,
         },
     },
 };
@@ -31,6 +32,7 @@ const IMPORT_SECTION_ID_VALUE: u8 = 2;
 const FUNCTION_SECTION_ID_VALUE: u8 = 3;
 const TABLE_SECTION_ID_VALUE: u8 = 4;
 const MEMORY_SECTION_ID_VALUE: u8 = 5;
+const TAG_SECTION_ID_VALUE: u8 = 13;
 const GLOBAL_SECTION_ID_VALUE: u8 = 6;
 const EXPORT_SECTION_ID_VALUE: u8 = 7;
 const START_SECTION_ID_VALUE: u8 = 8;
@@ -58,14 +60,17 @@ pub const ENCODE_BYTE_IMPORT_BYTE_FUNC: Byte = 0x00;
 pub const ENCODE_BYTE_IMPORT_TABLE: Byte = 0x01;
 pub const ENCODE_BYTE_IMPORT_MEM: Byte = 0x02;
 pub const ENCODE_BYTE_IMPORT_GLOBAL: Byte = 0x03;
+pub const ENCODE_BYTE_IMPORT_TAG: Byte = 0x04;
 
 pub const ENCODE_BYTE_EXPORT_FUNC: Byte = 0x00;
 pub const ENCODE_BYTE_EXPORT_TABLE: Byte = 0x01;
 pub const ENCODE_BYTE_EXPORT_MEM: Byte = 0x02;
 pub const ENCODE_BYTE_EXPORT_GLOBAL: Byte = 0x03;
+pub const ENCODE_BYTE_EXPORT_TAG: Byte = 0x04;
 
 impl ParseWithNom for ExportDescription {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], ExportDescription> {
+        let input = bytes;
         let (bytes, encode_byte) =
             take(1usize)(bytes).map(|(b, encode_byte_slice)| (b, encode_byte_slice[0]))?;
 
@@ -78,9 +83,11 @@ impl ParseWithNom for ExportDescription {
                 .map(|(b, u32_val)| (b, ExportDescription::Mem(MemIdx(u32_val)))),
             ENCODE_BYTE_EXPORT_GLOBAL => U32Type::parse(bytes)
                 .map(|(b, u32_val)| (b, ExportDescription::Global(GlobalIdx(u32_val)))),
+            ENCODE_BYTE_EXPORT_TAG => U32Type::parse(bytes)
+                .map(|(b, u32_val)| (b, ExportDescription::Tag(TagIdx(u32_val)))),
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
-                nom::error::ErrorKind::Char,
+                input,
+                nom::error::ErrorKind::Fail,
             ))),
         }
     }
@@ -88,6 +95,7 @@ impl ParseWithNom for ExportDescription {
 
 impl ParseWithNom for ImportDescription {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], ImportDescription> {
+        let input = bytes;
         let (bytes, encode_byte) =
             take(1usize)(bytes).map(|(b, encode_byte_slice)| (b, encode_byte_slice[0]))?;
 
@@ -103,9 +111,12 @@ impl ParseWithNom for ImportDescription {
             ENCODE_BYTE_IMPORT_GLOBAL => {
                 GlobalType::parse(bytes).map(|(b, val)| (b, ImportDescription::Global(val)))
             }
+            ENCODE_BYTE_IMPORT_TAG => {
+                TagType::parse(bytes).map(|(b, val)| (b, ImportDescription::Tag(val)))
+            }
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
-                nom::error::ErrorKind::Char,
+                input,
+                nom::error::ErrorKind::Fail,
             ))),
         }
     }
@@ -122,6 +133,7 @@ impl TryFrom<SectionIdValue> for SectionId {
             FUNCTION_SECTION_ID_VALUE => Ok(SectionId::Function),
             TABLE_SECTION_ID_VALUE => Ok(SectionId::Table),
             MEMORY_SECTION_ID_VALUE => Ok(SectionId::Memory),
+            TAG_SECTION_ID_VALUE => Ok(SectionId::Tag),
             GLOBAL_SECTION_ID_VALUE => Ok(SectionId::Global),
             EXPORT_SECTION_ID_VALUE => Ok(SectionId::Export),
             START_SECTION_ID_VALUE => Ok(SectionId::Start),
@@ -129,7 +141,7 @@ impl TryFrom<SectionIdValue> for SectionId {
             CODE_SECTION_ID_VALUE => Ok(SectionId::Code),
             DATA_SECTION_ID_VALUE => Ok(SectionId::Data),
             DATA_COUNT_SECTION_ID_VALUE => Ok(SectionId::DataCount),
-            _ => Err(SyntaxError::UnexpectedSectionIdValue),
+            _ => Err(SyntaxError::new(SyntaxErrorCause::UnknownSection(byte), 0)),
         }
     }
 }
@@ -151,6 +163,7 @@ impl ParseWithNom for Global {
 
 impl ParseWithNom for ElementSegmentType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let input = bytes;
         let (bytes, bitfield) = U32Type::parse(bytes)?;
 
         match bitfield {
@@ -179,7 +192,7 @@ impl ParseWithNom for ElementSegmentType {
                     .map(|(b, v)| (b, ElementSegmentType::DeclarativeRef(v)))
             }
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
+                input,
                 nom::error::ErrorKind::Fail,
             ))),
         }
@@ -190,9 +203,9 @@ impl ParseWithNom for Active0FunctionsElementSegmentType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
         let (bytes, expression) = ExpressionType::parse(bytes)?;
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut init: Vec<FuncIdx> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
@@ -217,9 +230,9 @@ impl ParseWithNom for ElemKindPassiveFunctionsElementSegmentType {
         let (bytes, elem_kind) = ElemKind::parse(bytes)?;
 
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut init: Vec<FuncIdx> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
@@ -247,9 +260,9 @@ impl ParseWithNom for ElemKindActiveFunctionsElementSegmentType {
         let (bytes, elem_kind) = ElemKind::parse(bytes)?;
 
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut init: Vec<FuncIdx> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
@@ -278,9 +291,9 @@ impl ParseWithNom for ElemKindDeclarativeFunctionsElementSegmentType {
         let (bytes, elem_kind) = ElemKind::parse(bytes)?;
 
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut init: Vec<FuncIdx> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
@@ -306,9 +319,9 @@ impl ParseWithNom for Active0ExprElementSegmentType {
         let (bytes, offset) = ExpressionType::parse(bytes)?;
 
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut init: Vec<ExpressionType> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
@@ -333,9 +346,9 @@ impl ParseWithNom for PassiveRefElementSegmentType {
         let (bytes, ref_type) = RefType::parse(bytes)?;
 
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut init: Vec<ExpressionType> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
@@ -362,9 +375,9 @@ impl ParseWithNom for ActiveRefElementSegmentType {
         let (bytes, offset) = ExpressionType::parse(bytes)?;
         let (bytes, ref_type) = RefType::parse(bytes)?;
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut init: Vec<ExpressionType> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
@@ -392,9 +405,9 @@ impl ParseWithNom for DeclarativeRefElementSegmentType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
         let (bytes, ref_type) = RefType::parse(bytes)?;
         let mut remaining_bytes = bytes;
-        let vector_len_parsed = U32Type::parse(remaining_bytes)?;
+        let vector_len_parsed = parse_vector_len(remaining_bytes)?;
         remaining_bytes = vector_len_parsed.0;
-        let vector_len = vector_len_parsed.1 .0 as usize;
+        let vector_len = vector_len_parsed.1;
         let mut init: Vec<ExpressionType> = Vec::with_capacity(vector_len);
 
         for _ in 0..vector_len {
@@ -417,12 +430,13 @@ impl ParseWithNom for DeclarativeRefElementSegmentType {
 
 impl ParseWithNom for ElemKind {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let input = bytes;
         let (bytes, elem_kind) = take(1usize)(bytes)?;
 
         match elem_kind[0] {
             ENCODE_BYTE_ELEM_KIND_FUNC_REF => Ok((bytes, ElemKind::FuncRef)),
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
+                input,
                 nom::error::ErrorKind::Fail,
             ))),
         }
@@ -440,6 +454,7 @@ impl ParseWithNom for LocalsType {
 
 impl ParseWithNom for DataType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let input = bytes;
         let (bytes, bitfield) = U32Type::parse(bytes)?;
 
         match bitfield {
@@ -453,7 +468,7 @@ impl ParseWithNom for DataType {
                 Ok(PassiveDataType::parse(bytes).map(|(b, v)| (b, DataType::Passive(v)))?)
             }
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
+                input,
                 nom::error::ErrorKind::Fail,
             ))),
         }
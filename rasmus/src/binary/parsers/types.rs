@@ -8,12 +8,16 @@ use nom::{
 use crate::{
     binary::{
         parse_trait::ParseWithNom,
-        parser_helpers::{parse as nom_parse, read_s33_leb128, read_u32_leb128, read_u64_leb128},
+        parser_helpers::{
+            parse as nom_parse, read_s32_leb128, read_s33_leb128, read_s64_leb128, read_u32_leb128,
+            read_u64_leb128,
+        },
     },
     entities::types::{
         Byte, DataIdx, ElemIdx, F32Type, F64Type, FuncIdx, GlobalIdx, GlobalType, I32Type, I64Type,
-        LabelIdx, LaneIdx, LimitsType, LocalIdx, MemIdx, MemType, MutType, NameType, NumType,
-        RefType, ResultType, S33Type, TableIdx, TableType, TypeIdx, U32Type, ValType, VecType,
+        IndexType, LabelIdx, LaneIdx, LimitsType, LocalIdx, MemIdx, MemType, MutType, NameType,
+        NumType, RefType, ResultType, S33Type, TableIdx, TableType, TagIdx, TagType, TypeIdx,
+        U32Type, U64Type, ValType, VecType,
     },
 };
 
@@ -24,14 +28,21 @@ const ENCODE_BYTE_F64: Byte = 0x7C;
 
 const ENCODE_BYTE_FUNC_REF: Byte = 0x70;
 const ENCODE_BYTE_EXTERN_REF: Byte = 0x6F;
+const ENCODE_BYTE_EXN_REF: Byte = 0x69;
 const ENCODE_BYTE_V128: Byte = 0x7B;
 
 const ENCODE_BYTE_LIMITS_MAX_NOT_PRESENT: Byte = 0x00;
 const ENCODE_BYTE_LIMITS_MAX_PRESENT: Byte = 0x01;
 
+const MEM_LIMITS_MAX_FLAG: Byte = 0x01;
+const MEM_LIMITS_SHARED_FLAG: Byte = 0x02;
+const MEM_LIMITS_INDEX_TYPE_I64_FLAG: Byte = 0x04;
+
 const ENCODE_BYTE_CONST: Byte = 0x00;
 const ENCODE_BYTE_VAR: Byte = 0x01;
 
+const ENCODE_BYTE_TAG_ATTRIBUTE_EXCEPTION: Byte = 0x00;
+
 pub fn recognize_type(byte: Byte) -> Option<ValType> {
     match byte {
         ENCODE_BYTE_I32 => Some(ValType::NumType(NumType::I32)),
@@ -41,6 +52,7 @@ pub fn recognize_type(byte: Byte) -> Option<ValType> {
         ENCODE_BYTE_V128 => Some(ValType::VecType(VecType::V128)),
         ENCODE_BYTE_FUNC_REF => Some(ValType::RefType(RefType::FuncRef)),
         ENCODE_BYTE_EXTERN_REF => Some(ValType::RefType(RefType::ExternRef)),
+        ENCODE_BYTE_EXN_REF => Some(ValType::RefType(RefType::ExnRef)),
         _ => None,
     }
 }
@@ -59,13 +71,15 @@ impl ParseWithNom for ValType {
 
 impl ParseWithNom for RefType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let input = bytes;
         let (bytes, encode_byte_slice) = take(1usize)(bytes)?;
 
         match encode_byte_slice[0] {
             ENCODE_BYTE_EXTERN_REF => Ok((bytes, RefType::ExternRef)),
             ENCODE_BYTE_FUNC_REF => Ok((bytes, RefType::FuncRef)),
+            ENCODE_BYTE_EXN_REF => Ok((bytes, RefType::ExnRef)),
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
+                input,
                 nom::error::ErrorKind::Fail,
             ))),
         }
@@ -102,17 +116,18 @@ impl ParseWithNom for TableType {
 
 impl ParseWithNom for LimitsType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let input = bytes;
         let (bytes, has_max_limit_byte_slice) = take(1usize)(bytes)?;
 
         match has_max_limit_byte_slice[0] {
             ENCODE_BYTE_LIMITS_MAX_NOT_PRESENT => {
-                let (bytes, min) = U32Type::parse(bytes)?;
+                let (bytes, min) = parse_limit(bytes, &IndexType::I32)?;
 
                 Ok((bytes, LimitsType { min, max: None }))
             }
             ENCODE_BYTE_LIMITS_MAX_PRESENT => {
-                let (bytes, min) = U32Type::parse(bytes)?;
-                let (bytes, max) = U32Type::parse(bytes)?;
+                let (bytes, min) = parse_limit(bytes, &IndexType::I32)?;
+                let (bytes, max) = parse_limit(bytes, &IndexType::I32)?;
 
                 Ok((
                     bytes,
@@ -123,18 +138,56 @@ impl ParseWithNom for LimitsType {
                 ))
             }
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
+                input,
                 nom::error::ErrorKind::Fail,
             ))),
         }
     }
 }
 
+fn parse_limit<'a>(bytes: &'a [Byte], index_type: &IndexType) -> NomResult<&'a [Byte], U64Type> {
+    match index_type {
+        IndexType::I32 => U32Type::parse(bytes).map(|(b, U32Type(v))| (b, U64Type(v as u64))),
+        IndexType::I64 => U64Type::parse(bytes),
+    }
+}
+
 impl ParseWithNom for MemType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
-        let (bytes, limits) = LimitsType::parse(bytes)?;
+        let input = bytes;
+        let (bytes, flags_slice) = take(1usize)(bytes)?;
+        let flags = flags_slice[0];
+
+        if flags & !(MEM_LIMITS_MAX_FLAG | MEM_LIMITS_SHARED_FLAG | MEM_LIMITS_INDEX_TYPE_I64_FLAG)
+            != 0
+        {
+            return Err(nom::Err::Failure(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Fail,
+            )));
+        }
+
+        let index_type = if flags & MEM_LIMITS_INDEX_TYPE_I64_FLAG != 0 {
+            IndexType::I64
+        } else {
+            IndexType::I32
+        };
 
-        Ok((bytes, MemType { limits }))
+        let (bytes, min) = parse_limit(bytes, &index_type)?;
+        let (bytes, max) = if flags & MEM_LIMITS_MAX_FLAG != 0 {
+            parse_limit(bytes, &index_type).map(|(b, max)| (b, Some(max)))?
+        } else {
+            (bytes, None)
+        };
+
+        Ok((
+            bytes,
+            MemType {
+                limits: LimitsType { min, max },
+                index_type,
+                shared: flags & MEM_LIMITS_SHARED_FLAG != 0,
+            },
+        ))
     }
 }
 
@@ -147,15 +200,34 @@ impl ParseWithNom for GlobalType {
     }
 }
 
+impl ParseWithNom for TagType {
+    fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let input = bytes;
+        let (bytes, attribute) = take(1usize)(bytes)?;
+
+        if attribute[0] != ENCODE_BYTE_TAG_ATTRIBUTE_EXCEPTION {
+            return Err(nom::Err::Failure(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Fail,
+            )));
+        }
+
+        let (bytes, type_idx) = TypeIdx::parse(bytes)?;
+
+        Ok((bytes, TagType { type_idx }))
+    }
+}
+
 impl ParseWithNom for MutType {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
+        let input = bytes;
         let (bytes, mut_type_byte_slice) = take(1usize)(bytes)?;
 
         match mut_type_byte_slice[0] {
             ENCODE_BYTE_CONST => Ok((bytes, MutType::Const)),
             ENCODE_BYTE_VAR => Ok((bytes, MutType::Var)),
             _ => Err(nom::Err::Failure(nom::error::Error::new(
-                bytes,
+                input,
                 nom::error::ErrorKind::Fail,
             ))),
         }
@@ -198,6 +270,15 @@ impl ParseWithNom for MemIdx {
     }
 }
 
+impl ParseWithNom for TagIdx {
+    fn parse(bytes: &[u8]) -> NomResult<&[Byte], Self>
+    where
+        Self: Sized,
+    {
+        U32Type::parse(bytes).map(|(b, v)| (b, Self(v)))
+    }
+}
+
 impl ParseWithNom for GlobalIdx {
     fn parse(bytes: &[u8]) -> NomResult<&[Byte], Self>
     where
@@ -252,35 +333,48 @@ impl ParseWithNom for LaneIdx {
     }
 }
 
+fn parse_leb128<T>(
+    bytes: &[Byte],
+    read: fn(&[u8], &mut usize) -> Option<T>,
+) -> NomResult<&[Byte], T> {
+    let mut pos = 0usize;
+    match read(bytes, &mut pos) {
+        Some(val) => Ok((bytes.slice(pos..), val)),
+        None => Err(nom::Err::Failure(nom::error::Error::new(
+            bytes,
+            nom::error::ErrorKind::TooLarge,
+        ))),
+    }
+}
+
 impl ParseWithNom for U32Type {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], U32Type> {
-        let mut pos = 0usize;
-        let val = read_u32_leb128(bytes, &mut pos);
-        Ok((bytes.slice(pos..), U32Type(val)))
+        parse_leb128(bytes, read_u32_leb128).map(|(b, v)| (b, U32Type(v)))
+    }
+}
+
+impl ParseWithNom for U64Type {
+    fn parse(bytes: &[Byte]) -> NomResult<&[Byte], U64Type> {
+        parse_leb128(bytes, read_u64_leb128).map(|(b, v)| (b, U64Type(v)))
     }
 }
 
 impl ParseWithNom for S33Type {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
-        let mut pos = 0usize;
-        let val = read_s33_leb128(bytes, &mut pos);
-        Ok((bytes.slice(pos..), S33Type(val)))
+        parse_leb128(bytes, read_s33_leb128).map(|(b, v)| (b, S33Type(v)))
     }
 }
 
+// integers are uninterpreted, the constants are encoded as signed numbers
 impl ParseWithNom for I32Type {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
-        let mut pos = 0usize;
-        let val = read_u32_leb128(bytes, &mut pos);
-        Ok((bytes.slice(pos..), Self(val)))
+        parse_leb128(bytes, read_s32_leb128).map(|(b, v)| (b, Self(v as u32)))
     }
 }
 
 impl ParseWithNom for I64Type {
     fn parse(bytes: &[Byte]) -> NomResult<&[Byte], Self> {
-        let mut pos = 0usize;
-        let val = read_u64_leb128(bytes, &mut pos);
-        Ok((bytes.slice(pos..), Self(val)))
+        parse_leb128(bytes, read_s64_leb128).map(|(b, v)| (b, Self(v as u64)))
     }
 }
 
@@ -319,3 +413,49 @@ impl NomParseError<NomError> for NomError {
         NomError
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_memory64_mem_type() {
+        let (bytes, mem_type) = MemType::parse(&[0x05, 0x01, 0x80, 0x80, 0x04]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            mem_type,
+            MemType {
+                limits: LimitsType {
+                    min: U64Type(1),
+                    max: Some(U64Type(65536)),
+                },
+                index_type: IndexType::I64,
+                shared: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_shared_mem_type() {
+        let (bytes, mem_type) = MemType::parse(&[0x03, 0x01, 0x02]).unwrap();
+
+        assert!(bytes.is_empty());
+        assert_eq!(
+            mem_type,
+            MemType {
+                limits: LimitsType {
+                    min: U64Type(1),
+                    max: Some(U64Type(2)),
+                },
+                index_type: IndexType::I32,
+                shared: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_mem_type_flags() {
+        assert!(MemType::parse(&[0x08, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,908 @@
+use std::io::Read;
+use std::rc::Rc;
+
+use nom::{
+    bytes::complete::{tag, take},
+    IResult as NomResult,
+};
+
+use crate::binary::{
+    parse_trait::ParseWithNom,
+    parser_helpers::{parse_vector_len, MAX_LOCALS},
+    syntax_error::{ParseResult, SyntaxError, SyntaxErrorCause},
+};
+use crate::entities::{module::*, types::*};
+
+// A section size or a vector length takes at most 5 bytes
+const MAX_U32_LEB128_LEN: usize = 5;
+
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Part of a module emitted by the streaming parser as soon as its bytes are available
+#[derive(Debug)]
+pub enum Payload {
+    /// Magic and version of the module are checked
+    Header,
+    Types(Vec<FuncType>),
+    Imports(Vec<ImportType>),
+    Functions(Vec<TypeIdx>),
+    Tables(Vec<TableType>),
+    Memories(Vec<MemType>),
+    Tags(Vec<TagType>),
+    Globals(Vec<Global>),
+    Exports(Vec<ExportType>),
+    Start(StartType),
+    Elements(Vec<ElementSegmentType>),
+    DataCount(u32),
+    /// Start of the code section, the bodies follow one by one
+    CodeSection {
+        count: usize,
+    },
+    FunctionBody {
+        func_idx: usize,
+        code: CodeType,
+    },
+    Datas(Vec<DataType>),
+    CustomSection(CustomSection),
+    /// The module is complete, section counts are checked
+    End,
+}
+
+enum State {
+    Header,
+    SectionHeader,
+    Content {
+        section: SectionId,
+        size: usize,
+    },
+    Skip {
+        remaining: usize,
+    },
+    CodeCount {
+        end: usize,
+    },
+    Code {
+        remaining: usize,
+        func_idx: usize,
+        end: usize,
+    },
+    End,
+}
+
+/// Parser of a module received in chunks.
+/// Bytes are passed with `feed`, the end of the input with `finish`,
+/// and `next_payload` returns the parts which are complete so far.
+pub struct StreamingParser {
+    buffer: Vec<Byte>,
+    // unconsumed bytes start at this index of the buffer
+    position: usize,
+    // offset of the first byte of the buffer in the module
+    buffer_offset: usize,
+    finished: bool,
+    state: State,
+    skipped: Vec<SectionId>,
+    lazy_function_bodies: bool,
+    last_section: Option<SectionId>,
+    // index space of every import
+    imports: Vec<SectionId>,
+    funcs: usize,
+    code: Option<(usize, usize)>,
+    data_count: Option<(usize, usize)>,
+    datas: usize,
+}
+
+// Builds errors of a section with offsets from the start of the module
+struct SectionErrors<'a> {
+    bytes: &'a [Byte],
+    // offset of the bytes in the module
+    offset: usize,
+    section: SectionId,
+    // imported items precede the items of the section in their index space
+    first_index: usize,
+}
+
+impl SectionErrors<'_> {
+    fn section(&self, error: nom::Err<nom::error::Error<&[Byte]>>) -> SyntaxError {
+        let mut error = SyntaxError::from_nom(self.bytes, error).in_section(self.section);
+        error.offset += self.offset;
+        error
+    }
+
+    fn item(&self, index: usize, error: nom::Err<nom::error::Error<&[Byte]>>) -> SyntaxError {
+        self.section(error).at_item(self.first_index + index)
+    }
+
+    // The content of a section must be consumed completely
+    fn check_consumed(&self, remaining_bytes: &[Byte]) -> ParseResult<()> {
+        if remaining_bytes.is_empty() {
+            return Ok(());
+        }
+
+        let mut error = SyntaxError::at_input(
+            SyntaxErrorCause::SectionSizeMismatch,
+            self.bytes,
+            remaining_bytes,
+        )
+        .in_section(self.section);
+        error.offset += self.offset;
+
+        Err(error)
+    }
+}
+
+// Position of the section in the module, custom sections may appear anywhere
+fn section_order(section: SectionId) -> Option<u8> {
+    match section {
+        SectionId::Custom => None,
+        SectionId::Type => Some(1),
+        SectionId::Import => Some(2),
+        SectionId::Function => Some(3),
+        SectionId::Table => Some(4),
+        SectionId::Memory => Some(5),
+        SectionId::Tag => Some(6),
+        SectionId::Global => Some(7),
+        SectionId::Export => Some(8),
+        SectionId::Start => Some(9),
+        SectionId::Element => Some(10),
+        SectionId::DataCount => Some(11),
+        SectionId::Code => Some(12),
+        SectionId::Data => Some(13),
+    }
+}
+
+// Section of the index space the import belongs to
+fn index_space(import: &ImportType) -> SectionId {
+    match import.desc {
+        ImportDescription::Func(_) => SectionId::Function,
+        ImportDescription::Table(_) => SectionId::Table,
+        ImportDescription::Mem(_) => SectionId::Memory,
+        ImportDescription::Global(_) => SectionId::Global,
+        ImportDescription::Tag(_) => SectionId::Tag,
+    }
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        StreamingParser {
+            buffer: vec![],
+            position: 0,
+            buffer_offset: 0,
+            finished: false,
+            state: State::Header,
+            skipped: vec![],
+            lazy_function_bodies: false,
+            last_section: None,
+            imports: vec![],
+            funcs: 0,
+            code: None,
+            data_count: None,
+            datas: 0,
+        }
+    }
+
+    /// Sections which content is dropped without decoding as it arrives
+    pub fn skip_sections(&mut self, sections: &[SectionId]) {
+        self.skipped.extend_from_slice(sections);
+    }
+
+    /// Function bodies are kept undecoded, see `RawCode::decode`
+    pub fn lazy_function_bodies(&mut self) {
+        self.lazy_function_bodies = true;
+    }
+
+    pub fn feed(&mut self, chunk: &[Byte]) {
+        self.buffer.drain(..self.position);
+        self.buffer_offset += self.position;
+        self.position = 0;
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// No more bytes follow
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// True once `Payload::End` or an error is returned
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::End)
+    }
+
+    /// Next complete part of the module, None when more bytes are needed or the module is done
+    pub fn next_payload(&mut self) -> ParseResult<Option<Payload>> {
+        let payload = self.step();
+        if payload.is_err() {
+            self.state = State::End;
+        }
+
+        payload
+    }
+
+    fn step(&mut self) -> ParseResult<Option<Payload>> {
+        loop {
+            match self.state {
+                State::Header => return self.parse_header(),
+                State::SectionHeader => {
+                    if self.available().is_empty() {
+                        return match self.finished {
+                            true => self.parse_end().map(Some),
+                            false => Ok(None),
+                        };
+                    }
+                    if !self.parse_section_header()? {
+                        return Ok(None);
+                    }
+                }
+                State::Skip { remaining } => {
+                    let skipped = remaining.min(self.available().len());
+                    self.position += skipped;
+                    if skipped == remaining {
+                        self.state = State::SectionHeader;
+                        continue;
+                    }
+                    if self.finished {
+                        return Err(SyntaxError::new(
+                            SyntaxErrorCause::LengthOutOfBounds,
+                            self.offset(),
+                        ));
+                    }
+                    self.state = State::Skip {
+                        remaining: remaining - skipped,
+                    };
+                    return Ok(None);
+                }
+                State::Content { section, size } => {
+                    if self.available().len() < size {
+                        return match self.finished {
+                            true => Err(SyntaxError::new(
+                                SyntaxErrorCause::LengthOutOfBounds,
+                                self.buffer_offset + self.buffer.len(),
+                            )
+                            .in_section(section)),
+                            false => Ok(None),
+                        };
+                    }
+
+                    let payload = self.parse_section(section, size)?;
+                    self.position += size;
+                    self.state = State::SectionHeader;
+
+                    return Ok(Some(payload));
+                }
+                State::CodeCount { end } => return self.parse_code_count(end),
+                State::Code {
+                    remaining,
+                    func_idx,
+                    end,
+                } => {
+                    if remaining == 0 {
+                        if self.offset() != end {
+                            return Err(SyntaxError::new(
+                                SyntaxErrorCause::SectionSizeMismatch,
+                                self.offset(),
+                            )
+                            .in_section(SectionId::Code));
+                        }
+                        self.state = State::SectionHeader;
+                        continue;
+                    }
+
+                    return self.parse_function_body(remaining, func_idx, end);
+                }
+                State::End => return Ok(None),
+            }
+        }
+    }
+
+    fn available(&self) -> &[Byte] {
+        &self.buffer[self.position..]
+    }
+
+    // Offset of the first unconsumed byte in the module
+    fn offset(&self) -> usize {
+        self.buffer_offset + self.position
+    }
+
+    fn errors(&self, section: SectionId) -> SectionErrors<'_> {
+        let index_space = match section {
+            SectionId::Code => SectionId::Function,
+            _ => section,
+        };
+
+        SectionErrors {
+            bytes: self.available(),
+            offset: self.offset(),
+            section,
+            first_index: self
+                .imports
+                .iter()
+                .filter(|import| **import == index_space)
+                .count(),
+        }
+    }
+
+    // Reads a u32 at the start of the bytes, None when it may continue in the next chunk
+    fn read_u32<'a>(
+        &self,
+        bytes: &'a [Byte],
+        errors: &SectionErrors,
+    ) -> ParseResult<Option<(&'a [Byte], usize)>> {
+        match U32Type::parse(bytes) {
+            Ok((bytes, value)) => Ok(Some((bytes, value.0 as usize))),
+            Err(_) if !self.finished && bytes.len() < MAX_U32_LEB128_LEN => Ok(None),
+            Err(error) => Err(errors.section(error)),
+        }
+    }
+
+    fn parse_header(&mut self) -> ParseResult<Option<Payload>> {
+        let header_len = Module::MAGIC.len() + Module::VERSION.len();
+        if self.available().len() < header_len && !self.finished {
+            return Ok(None);
+        }
+
+        let bytes = tag::<_, _, ()>(Module::MAGIC)(self.available())
+            .map_err(|_| SyntaxError::new(SyntaxErrorCause::MagicNotFound, 0))?
+            .0;
+        tag::<_, _, ()>(Module::VERSION)(bytes).map_err(|_| {
+            SyntaxError::new(SyntaxErrorCause::VersionNotFound, Module::MAGIC.len())
+        })?;
+
+        self.position += header_len;
+        self.state = State::SectionHeader;
+
+        Ok(Some(Payload::Header))
+    }
+
+    // Returns false when more bytes are needed
+    fn parse_section_header(&mut self) -> ParseResult<bool> {
+        let section_offset = self.offset();
+        let section_id_byte = self.available()[0];
+        let section = SectionId::try_from(section_id_byte).map_err(|error| SyntaxError {
+            offset: section_offset,
+            ..error
+        })?;
+
+        let errors = self.errors(section);
+        let Some((content, size)) = self.read_u32(&self.available()[1..], &errors)? else {
+            return Ok(false);
+        };
+        let header_len = self.available().len() - content.len();
+
+        if let Some(order) = section_order(section) {
+            match self.last_section {
+                Some(last) if last == section => {
+                    return Err(SyntaxError::new(
+                        SyntaxErrorCause::DuplicateSection(section),
+                        section_offset,
+                    ))
+                }
+                Some(last) if section_order(last) > Some(order) => {
+                    return Err(SyntaxError::new(
+                        SyntaxErrorCause::SectionOutOfOrder(section),
+                        section_offset,
+                    ))
+                }
+                _ => self.last_section = Some(section),
+            }
+        }
+
+        self.position += header_len;
+        let end = self.offset() + size;
+        self.state = match section {
+            _ if self.skipped.contains(&section) => State::Skip { remaining: size },
+            SectionId::Code => {
+                self.code = Some((0, section_offset));
+                State::CodeCount { end }
+            }
+            _ => State::Content { section, size },
+        };
+
+        Ok(true)
+    }
+
+    fn parse_section(&mut self, section: SectionId, size: usize) -> ParseResult<Payload> {
+        let errors = SectionErrors {
+            bytes: &self.available()[..size],
+            ..self.errors(section)
+        };
+        let content = errors.bytes;
+
+        let payload = match section {
+            SectionId::Custom => {
+                let (bytes, name) =
+                    NameType::parse(content).map_err(|error| errors.section(error))?;
+                Payload::CustomSection(CustomSection {
+                    name: name.0,
+                    bytes: bytes.to_vec(),
+                })
+            }
+            SectionId::Type => Payload::Types(parse_items(&errors, parse_func_type)?),
+            SectionId::Import => {
+                let imports = parse_items(&errors, parse_import_type)?;
+                self.imports.extend(imports.iter().map(index_space));
+                Payload::Imports(imports)
+            }
+            SectionId::Function => {
+                let funcs = parse_items(&errors, |bytes| {
+                    U32Type::parse(bytes).map(|(b, v)| (b, TypeIdx(v)))
+                })?;
+                self.funcs = funcs.len();
+                Payload::Functions(funcs)
+            }
+            SectionId::Table => Payload::Tables(parse_items(&errors, TableType::parse)?),
+            SectionId::Memory => Payload::Memories(parse_items(&errors, MemType::parse)?),
+            SectionId::Tag => Payload::Tags(parse_items(&errors, TagType::parse)?),
+            SectionId::Global => Payload::Globals(parse_items(&errors, Global::parse)?),
+            SectionId::Export => Payload::Exports(parse_items(&errors, parse_export_type)?),
+            SectionId::Start => {
+                let (bytes, start) =
+                    StartType::parse(content).map_err(|error| errors.section(error))?;
+                errors.check_consumed(bytes)?;
+                Payload::Start(start)
+            }
+            SectionId::Element => {
+                Payload::Elements(parse_items(&errors, ElementSegmentType::parse)?)
+            }
+            SectionId::DataCount => {
+                let (bytes, count) =
+                    U32Type::parse(content).map_err(|error| errors.section(error))?;
+                errors.check_consumed(bytes)?;
+                self.data_count = Some((count.0 as usize, errors.offset));
+                Payload::DataCount(count.0)
+            }
+            SectionId::Data => {
+                let datas = parse_items(&errors, DataType::parse)?;
+                self.datas = datas.len();
+                Payload::Datas(datas)
+            }
+            // the code section is parsed body by body
+            SectionId::Code => unreachable!(),
+        };
+
+        Ok(payload)
+    }
+
+    fn parse_code_count(&mut self, end: usize) -> ParseResult<Option<Payload>> {
+        let errors = SectionErrors {
+            bytes: &self.available()[..(end - self.offset()).min(self.available().len())],
+            ..self.errors(SectionId::Code)
+        };
+        let section_complete = errors.bytes.len() == end - self.offset();
+        let count = match parse_vector_len(errors.bytes) {
+            Ok((bytes, count)) => (errors.bytes.len() - bytes.len(), count),
+            // the count can't be checked against the section size until the section is read
+            Err(_) if !section_complete => match self.read_u32(errors.bytes, &errors)? {
+                Some((bytes, count)) => (errors.bytes.len() - bytes.len(), count),
+                None => return Ok(None),
+            },
+            Err(error) => return Err(errors.section(error)),
+        };
+        if count.1 > end - self.offset() - count.0 {
+            return Err(
+                SyntaxError::new(SyntaxErrorCause::LengthOutOfBounds, self.offset())
+                    .in_section(SectionId::Code),
+            );
+        }
+
+        let func_idx = errors.first_index;
+        self.position += count.0;
+        self.code = self.code.map(|(_, offset)| (count.1, offset));
+        self.state = State::Code {
+            remaining: count.1,
+            func_idx,
+            end,
+        };
+
+        Ok(Some(Payload::CodeSection { count: count.1 }))
+    }
+
+    fn parse_function_body(
+        &mut self,
+        remaining: usize,
+        func_idx: usize,
+        end: usize,
+    ) -> ParseResult<Option<Payload>> {
+        let section_bytes = end - self.offset();
+        let errors = SectionErrors {
+            bytes: &self.available()[..section_bytes.min(self.available().len())],
+            first_index: func_idx,
+            ..self.errors(SectionId::Code)
+        };
+        let Some((body, size)) = self
+            .read_u32(errors.bytes, &errors)
+            .map_err(|error| error.at_item(func_idx))?
+        else {
+            return Ok(None);
+        };
+        let entry_len = errors.bytes.len() - body.len() + size;
+
+        if entry_len > section_bytes {
+            return Err(
+                SyntaxError::at_input(SyntaxErrorCause::UnexpectedEnd, errors.bytes, body)
+                    .in_section(SectionId::Code)
+                    .at_item(func_idx),
+            );
+        }
+        if entry_len > errors.bytes.len() {
+            return match self.finished {
+                true => Err(SyntaxError::new(
+                    SyntaxErrorCause::UnexpectedEnd,
+                    self.buffer_offset + self.buffer.len(),
+                )
+                .in_section(SectionId::Code)
+                .at_item(func_idx)),
+                false => Ok(None),
+            };
+        }
+
+        let code = match self.lazy_function_bodies {
+            true => CodeType {
+                size: U32Type(size as u32),
+                code: CodeBody::Raw(RawCode {
+                    bytes: Rc::from(&body[..size]),
+                    offset: errors.offset + entry_len - size,
+                    func_idx,
+                }),
+            },
+            false => {
+                parse_code_type(&errors.bytes[..entry_len])
+                    .map_err(|error| errors.item(0, error))?
+                    .1
+            }
+        };
+
+        self.position += entry_len;
+        self.state = State::Code {
+            remaining: remaining - 1,
+            func_idx: func_idx + 1,
+            end,
+        };
+
+        Ok(Some(Payload::FunctionBody { func_idx, code }))
+    }
+
+    fn parse_end(&mut self) -> ParseResult<Payload> {
+        let end = self.offset();
+        let code_skipped = self.skipped.contains(&SectionId::Code);
+        let funcs_skipped = self.skipped.contains(&SectionId::Function);
+
+        if !code_skipped && !funcs_skipped {
+            let (code, offset) = self.code.unwrap_or((0, end));
+            if code != self.funcs {
+                let error = SyntaxError::new(SyntaxErrorCause::FunctionCodeMismatch, offset);
+                return Err(match self.code {
+                    Some(_) => error.in_section(SectionId::Code),
+                    None => error,
+                });
+            }
+        }
+
+        if let Some((count, offset)) = self.data_count {
+            if count != self.datas && !self.skipped.contains(&SectionId::Data) {
+                return Err(
+                    SyntaxError::new(SyntaxErrorCause::DataCountMismatch, offset)
+                        .in_section(SectionId::DataCount),
+                );
+            }
+        }
+
+        self.state = State::End;
+
+        Ok(Payload::End)
+    }
+}
+
+impl Default for StreamingParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_items<'a, T>(
+    errors: &SectionErrors<'a>,
+    parse_item: impl Fn(&'a [Byte]) -> NomResult<&'a [Byte], T>,
+) -> ParseResult<Vec<T>> {
+    let (mut remaining_bytes, vector_len) =
+        parse_vector_len(errors.bytes).map_err(|error| errors.section(error))?;
+    let mut items: Vec<T> = Vec::with_capacity(vector_len);
+
+    for index in 0..vector_len {
+        let item_parsed = parse_item(remaining_bytes).map_err(|error| errors.item(index, error))?;
+        remaining_bytes = item_parsed.0;
+        items.push(item_parsed.1);
+    }
+    errors.check_consumed(remaining_bytes)?;
+
+    Ok(items)
+}
+
+fn parse_func_type(bytes: &[Byte]) -> NomResult<&[Byte], FuncType> {
+    let (bytes, _) = tag(&[FuncType::ENCODE_BYTE_FUNC])(bytes)?;
+    let (bytes, parameters) = Vec::<ValType>::parse(bytes)?;
+    let (bytes, results) = Vec::<ValType>::parse(bytes)?;
+
+    Ok((
+        bytes,
+        FuncType {
+            parameters,
+            results,
+        },
+    ))
+}
+
+// TODO: make it as a method of CodeType
+fn parse_code_type(bytes: &[Byte]) -> NomResult<&[Byte], CodeType> {
+    let (bytes, code_len) = U32Type::parse(bytes)?;
+    let (bytes, code_bytes) = take(code_len.0 as usize)(bytes)?;
+    let (_, code) = parse_func_code(code_bytes)?;
+
+    Ok((
+        bytes,
+        CodeType {
+            size: code_len,
+            code: code.into(),
+        },
+    ))
+}
+
+fn parse_func_code(code_bytes: &[Byte]) -> NomResult<&[Byte], FuncCodeType> {
+    let (code_bytes, locals_len) = parse_vector_len(code_bytes)?;
+    let mut remaining_bytes = code_bytes;
+    let mut locals: Vec<LocalsType> = Vec::with_capacity(locals_len);
+    let mut locals_count = 0u64;
+
+    for _ in 0..locals_len {
+        let locals_type_parsed = LocalsType::parse(remaining_bytes)?;
+        remaining_bytes = locals_type_parsed.0;
+        locals_count += locals_type_parsed.1.n.0 as u64;
+        locals.push(locals_type_parsed.1);
+    }
+
+    if locals_count > MAX_LOCALS {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            code_bytes,
+            nom::error::ErrorKind::Count,
+        )));
+    }
+
+    let (bytes, expression) = ExpressionType::parse(remaining_bytes)?;
+
+    // the body ends with the end of its expression
+    if !bytes.is_empty() {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            bytes,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+
+    Ok((bytes, FuncCodeType { locals, expression }))
+}
+
+impl RawCode {
+    /// Decodes the body kept by the lazy parser
+    pub fn decode(&self) -> ParseResult<FuncCodeType> {
+        let errors = SectionErrors {
+            bytes: &self.bytes,
+            offset: self.offset,
+            section: SectionId::Code,
+            first_index: self.func_idx,
+        };
+
+        parse_func_code(&self.bytes)
+            .map(|(_, code)| code)
+            .map_err(|error| errors.item(0, error))
+    }
+}
+
+fn parse_import_type(bytes: &[Byte]) -> NomResult<&[Byte], ImportType> {
+    let (bytes, (module, name, desc)) = <(NameType, NameType, ImportDescription)>::parse(bytes)?;
+
+    Ok((bytes, ImportType { module, name, desc }))
+}
+
+fn parse_export_type(bytes: &[Byte]) -> NomResult<&[Byte], ExportType> {
+    let (bytes, (name, desc)) = <(NameType, ExportDescription)>::parse(bytes)?;
+
+    Ok((bytes, ExportType { name, desc }))
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(std::io::Error),
+    Syntax(SyntaxError),
+}
+
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "unable to read module, {error}"),
+            StreamError::Syntax(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl From<SyntaxError> for StreamError {
+    fn from(error: SyntaxError) -> Self {
+        StreamError::Syntax(error)
+    }
+}
+
+/// Payloads of a module read from any reader in chunks
+pub struct PayloadReader<R> {
+    reader: R,
+    parser: StreamingParser,
+    chunk: Vec<Byte>,
+}
+
+impl<R: Read> PayloadReader<R> {
+    pub fn new(reader: R) -> Self {
+        PayloadReader {
+            reader,
+            parser: StreamingParser::new(),
+            chunk: vec![0; READ_CHUNK_SIZE],
+        }
+    }
+
+    pub fn skip_sections(mut self, sections: &[SectionId]) -> Self {
+        self.parser.skip_sections(sections);
+        self
+    }
+
+    pub fn lazy_function_bodies(mut self) -> Self {
+        self.parser.lazy_function_bodies();
+        self
+    }
+}
+
+impl<R: Read> Iterator for PayloadReader<R> {
+    type Item = Result<Payload, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.parser.next_payload() {
+                Ok(Some(payload)) => return Some(Ok(payload)),
+                Ok(None) if self.parser.is_done() => return None,
+                Ok(None) => {}
+                Err(error) => return Some(Err(error.into())),
+            }
+
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => self.parser.finish(),
+                Ok(len) => self.parser.feed(&self.chunk[..len]),
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.parser.state = State::End;
+                    return Some(Err(StreamError::Io(error)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn payloads(wasm: &[Byte], chunk_size: usize) -> ParseResult<Vec<String>> {
+        let mut parser = StreamingParser::new();
+        let mut payloads = vec![];
+
+        for chunk in wasm.chunks(chunk_size) {
+            parser.feed(chunk);
+            while let Some(payload) = parser.next_payload()? {
+                payloads.push(format!("{payload:?}"));
+            }
+        }
+        parser.finish();
+        while let Some(payload) = parser.next_payload()? {
+            payloads.push(format!("{payload:?}"));
+        }
+
+        Ok(payloads)
+    }
+
+    #[test]
+    fn test_chunked_input() {
+        let corpus = format!(
+            "{}/fuzz/corpus/parse_module",
+            std::env::var("CARGO_MANIFEST_DIR").unwrap()
+        );
+
+        for entry in std::fs::read_dir(corpus).unwrap() {
+            let path = entry.unwrap().path();
+            let wasm = std::fs::read(&path).unwrap();
+
+            let expected = payloads(&wasm, wasm.len().max(1));
+            assert_eq!(payloads(&wasm, 1), expected, "{}", path.display());
+            assert_eq!(payloads(&wasm, 3), expected, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_locals_limit() {
+        let code = |locals: &[&[Byte]]| {
+            let mut bytes = vec![locals.len() as Byte];
+            for n in locals {
+                bytes.extend_from_slice(n);
+                bytes.push(0x7F);
+            }
+            bytes.push(0x0B);
+            bytes
+        };
+
+        // 50000 and 50001 locals, also when split across several declarations
+        assert!(parse_func_code(&code(&[&[0xD0, 0x86, 0x03]])).is_ok());
+        assert!(parse_func_code(&code(&[&[0xA8, 0xC3, 0x01], &[0xA8, 0xC3, 0x01]])).is_ok());
+
+        for locals in [
+            code(&[&[0xD1, 0x86, 0x03]]),
+            code(&[&[0xA8, 0xC3, 0x01], &[0xA9, 0xC3, 0x01]]),
+        ] {
+            let Err(nom::Err::Failure(error)) = parse_func_code(&locals) else {
+                panic!("too many locals are accepted");
+            };
+            assert_eq!(error.code, nom::error::ErrorKind::Count);
+        }
+    }
+
+    #[test]
+    fn test_bytes_after_body_end() {
+        // no locals, the end of the body and a nop after it
+        let raw = RawCode {
+            bytes: vec![0x00, 0x0B, 0x01].into(),
+            offset: 0x20,
+            func_idx: 1,
+        };
+        let error = raw.decode().unwrap_err();
+
+        assert_eq!(error.cause, SyntaxErrorCause::SectionSizeMismatch);
+        assert_eq!(error.offset, 0x22);
+    }
+
+    #[test]
+    fn test_skip_sections() {
+        let wasm = [
+            b"\0asm\x01\0\0\0".as_slice(),
+            // type section
+            &[0x01, 0x04, 0x01, 0x60, 0x00, 0x00],
+            // function section
+            &[0x03, 0x02, 0x01, 0x00],
+            // custom section "c"
+            &[0x00, 0x03, 0x01, b'c', 0xFF],
+            // code section with an unknown opcode, never decoded when skipped
+            &[0x0A, 0x05, 0x01, 0x03, 0x00, 0xFF, 0x0B],
+        ]
+        .concat();
+
+        let mut payloads = PayloadReader::new(wasm.as_slice())
+            .skip_sections(&[SectionId::Code])
+            .map(|payload| payload.map(|payload| format!("{payload:?}")));
+
+        assert_eq!(payloads.next().unwrap().unwrap(), "Header");
+        assert!(payloads.next().unwrap().unwrap().starts_with("Types"));
+        assert!(payloads.next().unwrap().unwrap().starts_with("Functions"));
+        assert_eq!(
+            payloads.next().unwrap().unwrap(),
+            format!(
+                "{:?}",
+                Payload::CustomSection(CustomSection {
+                    name: "c".into(),
+                    bytes: vec![0xFF]
+                })
+            )
+        );
+        assert_eq!(payloads.next().unwrap().unwrap(), "End");
+        assert!(payloads.next().is_none());
+
+        let error = PayloadReader::new(wasm.as_slice())
+            .find_map(|payload| payload.err())
+            .unwrap();
+        assert!(matches!(
+            error,
+            StreamError::Syntax(SyntaxError {
+                item: Some(0),
+                cause: SyntaxErrorCause::UnknownOpcode { opcode: 0xFF, .. },
+                ..
+            })
+        ));
+    }
+}
@@ -1,21 +1,234 @@
+use std::fmt;
+
+use nom::error::ErrorKind;
+
+use crate::entities::{module::SectionId, types::Byte};
+
+use super::parser_helpers::read_u32_leb128;
+
 pub type ParseResult<T> = Result<T, SyntaxError>;
 
-#[derive(Debug, PartialEq)]
-pub enum SyntaxError {
-    UnexpectedSectionIdValue,
-    ModuleMagicNotFound,
-    ModuleVersionNotFound,
-    InvalidModuleSection,
-    InvalidTypesModuleSection,
-    InvalidCodeModuleSection,
-    InvalidFuncsModuleSection,
-    InvalidImportsModuleSection,
-    InvalidTablesModuleSection,
-    InvalidMemsModuleSection,
-    InvalidGlobalsModuleSection,
-    InvalidStartModuleSection,
-    InvalidElementSegmentModuleSection,
-    InvalidDatasModuleSection,
-    InvalidDataCountModuleSection,
-    DataCountDoesntMatchDataLen,
+type NomError<'a> = nom::Err<nom::error::Error<&'a [Byte]>>;
+
+const OPCODE_PREFIXES: [Byte; 3] = [0xFC, 0xFD, 0xFE];
+
+/// Malformed module with the position where decoding stopped
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyntaxError {
+    /// Offset from the start of the module bytes
+    pub offset: usize,
+    pub section: Option<SectionId>,
+    /// Index of the item in its index space, e.g. the function index of a code entry
+    pub item: Option<usize>,
+    pub cause: SyntaxErrorCause,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SyntaxErrorCause {
+    MagicNotFound,
+    VersionNotFound,
+    UnknownSection(Byte),
+    UnexpectedEnd,
+    UnexpectedByte(Byte),
+    UnknownOpcode {
+        opcode: Byte,
+        // opcode following the prefix byte of the instruction
+        subopcode: Option<u32>,
+    },
+    // Too long or with non zero unused bits
+    IntegerRepresentation,
+    LengthOutOfBounds,
+    MalformedUtf8,
+    TooManyLocals,
+    NestingTooDeep,
+    // Items of the section end before the declared size of the section
+    SectionSizeMismatch,
+    SectionOutOfOrder(SectionId),
+    DuplicateSection(SectionId),
+    FunctionCodeMismatch,
+    DataCountMismatch,
+}
+
+impl SyntaxError {
+    pub fn new(cause: SyntaxErrorCause, offset: usize) -> Self {
+        SyntaxError {
+            offset,
+            section: None,
+            item: None,
+            cause,
+        }
+    }
+
+    /// Error returned by the nom parsers for a part of `module_bytes`.
+    /// The parsers report the reason with the error kind:
+    /// - `TooLarge`: malformed LEB128 integer
+    /// - `LengthValue`: vector or section length exceeding the remaining bytes
+    /// - `Count`: number of locals exceeding the implementation limit
+    /// - `ManyTill`: too deeply nested blocks
+    /// - `Char`: malformed UTF-8 name
+    /// - `Verify`: bytes left after the end of a code entry
+    /// - `Switch`: unknown opcode, the input starts at the instruction
+    /// - the rest: unexpected byte at the start of the input
+    pub fn from_nom(module_bytes: &[Byte], error: NomError) -> Self {
+        let error = match error {
+            nom::Err::Error(error) | nom::Err::Failure(error) => error,
+            nom::Err::Incomplete(_) => {
+                return Self::new(SyntaxErrorCause::UnexpectedEnd, module_bytes.len())
+            }
+        };
+
+        let cause = match (error.code, error.input.first()) {
+            (ErrorKind::TooLarge, _) => SyntaxErrorCause::IntegerRepresentation,
+            (ErrorKind::LengthValue, _) => SyntaxErrorCause::LengthOutOfBounds,
+            (ErrorKind::Count, _) => SyntaxErrorCause::TooManyLocals,
+            (ErrorKind::ManyTill, _) => SyntaxErrorCause::NestingTooDeep,
+            (ErrorKind::Char, _) => SyntaxErrorCause::MalformedUtf8,
+            (ErrorKind::Verify, _) => SyntaxErrorCause::SectionSizeMismatch,
+            (_, None) | (ErrorKind::Eof, _) => SyntaxErrorCause::UnexpectedEnd,
+            (ErrorKind::Switch, Some(opcode)) => SyntaxErrorCause::UnknownOpcode {
+                opcode: *opcode,
+                subopcode: OPCODE_PREFIXES
+                    .contains(opcode)
+                    .then(|| read_u32_leb128(&error.input[1..], &mut 0))
+                    .flatten(),
+            },
+            (_, Some(byte)) => SyntaxErrorCause::UnexpectedByte(*byte),
+        };
+
+        Self::at_input(cause, module_bytes, error.input)
+    }
+
+    /// Error at the start of `input`, which is a part of `module_bytes`
+    pub fn at_input(cause: SyntaxErrorCause, module_bytes: &[Byte], input: &[Byte]) -> Self {
+        let offset = (input.as_ptr() as usize)
+            .saturating_sub(module_bytes.as_ptr() as usize)
+            .min(module_bytes.len());
+
+        Self::new(cause, offset)
+    }
+
+    pub fn in_section(mut self, section: SectionId) -> Self {
+        self.section = Some(section);
+        self
+    }
+
+    pub fn at_item(mut self, index: usize) -> Self {
+        self.item = Some(index);
+        self
+    }
+}
+
+fn section_name(section: SectionId) -> &'static str {
+    match section {
+        SectionId::Custom => "custom",
+        SectionId::Type => "type",
+        SectionId::Import => "import",
+        SectionId::Function => "function",
+        SectionId::Table => "table",
+        SectionId::Memory => "memory",
+        SectionId::Tag => "tag",
+        SectionId::Global => "global",
+        SectionId::Export => "export",
+        SectionId::Start => "start",
+        SectionId::Element => "element",
+        SectionId::Code => "code",
+        SectionId::Data => "data",
+        SectionId::DataCount => "data count",
+    }
+}
+
+fn item_name(section: Option<SectionId>) -> &'static str {
+    match section {
+        Some(SectionId::Type) => "type",
+        Some(SectionId::Import) => "import",
+        Some(SectionId::Function | SectionId::Code) => "func",
+        Some(SectionId::Table) => "table",
+        Some(SectionId::Memory) => "memory",
+        Some(SectionId::Tag) => "tag",
+        Some(SectionId::Global) => "global",
+        Some(SectionId::Export) => "export",
+        Some(SectionId::Element) => "elem",
+        Some(SectionId::Data) => "data",
+        _ => "item",
+    }
+}
+
+impl fmt::Display for SyntaxErrorCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxErrorCause::MagicNotFound => write!(f, "magic header not detected"),
+            SyntaxErrorCause::VersionNotFound => write!(f, "unknown binary version"),
+            SyntaxErrorCause::UnknownSection(id) => write!(f, "malformed section id {id:#04x}"),
+            SyntaxErrorCause::UnexpectedEnd => write!(f, "unexpected end"),
+            SyntaxErrorCause::UnexpectedByte(byte) => write!(f, "unexpected byte {byte:#04x}"),
+            SyntaxErrorCause::UnknownOpcode {
+                opcode,
+                subopcode: None,
+            } => write!(f, "unknown opcode {opcode:#04x}"),
+            SyntaxErrorCause::UnknownOpcode {
+                opcode,
+                subopcode: Some(subopcode),
+            } => write!(f, "unknown opcode {opcode:#04x} {subopcode:#04x}"),
+            SyntaxErrorCause::IntegerRepresentation => write!(f, "integer representation too long"),
+            SyntaxErrorCause::LengthOutOfBounds => write!(f, "length out of bounds"),
+            SyntaxErrorCause::MalformedUtf8 => write!(f, "malformed UTF-8 encoding"),
+            SyntaxErrorCause::TooManyLocals => write!(f, "too many locals"),
+            SyntaxErrorCause::NestingTooDeep => write!(f, "blocks nested too deeply"),
+            SyntaxErrorCause::SectionSizeMismatch => write!(f, "section size mismatch"),
+            // the spec reports sections in a wrong order as content after the last section
+            SyntaxErrorCause::SectionOutOfOrder(section) => write!(
+                f,
+                "unexpected content after last section, {} section out of order",
+                section_name(*section)
+            ),
+            SyntaxErrorCause::DuplicateSection(section) => write!(
+                f,
+                "unexpected content after last section, duplicate {} section",
+                section_name(*section)
+            ),
+            SyntaxErrorCause::FunctionCodeMismatch => {
+                write!(f, "function and code section have inconsistent lengths")
+            }
+            SyntaxErrorCause::DataCountMismatch => {
+                write!(f, "data count and data section have inconsistent lengths")
+            }
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error at {:#x}", self.offset)?;
+        if let Some(section) = self.section {
+            write!(f, " in {} section", section_name(section))?;
+        }
+        if let Some(item) = self.item {
+            write!(f, ", {} #{}", item_name(self.section), item)?;
+        }
+
+        write!(f, ": {}", self.cause)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_unknown_prefixed_opcode() {
+        let module_bytes = [0x00, 0x01, 0xFC, 0x1F];
+        let error = nom::Err::Failure(nom::error::Error::new(
+            &module_bytes[2..],
+            ErrorKind::Switch,
+        ));
+
+        let error = SyntaxError::from_nom(&module_bytes, error)
+            .in_section(SectionId::Code)
+            .at_item(4);
+
+        assert_eq!(
+            error.to_string(),
+            "error at 0x2 in code section, func #4: unknown opcode 0xfc 0x1f"
+        );
+    }
 }
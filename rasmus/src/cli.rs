@@ -1,17 +1,46 @@
 use std::env;
 
-pub struct UserInput {
-    pub source_file_path: String,
+pub enum UserInput {
+    Run {
+        source_file_path: String,
+    },
+    // rasmus inspect <file> [--json]
+    Inspect {
+        source_file_path: String,
+        json: bool,
+    },
+    // rasmus wat2text <file>
+    Wat2Text {
+        source_file_path: String,
+    },
 }
 
 impl UserInput {
     pub fn parse_args() -> UserInput {
-        let mut args = env::args();
+        let args: Vec<String> = env::args().skip(1).collect();
 
-        UserInput {
-            source_file_path: args
-                .nth(1)
-                .unwrap_or("./rasmus/tests/files/factorial-main.wasm".into()),
+        match args.first().map(String::as_str) {
+            Some("inspect") => UserInput::Inspect {
+                source_file_path: args
+                    .iter()
+                    .skip(1)
+                    .find(|arg| !arg.starts_with("--"))
+                    .cloned()
+                    .unwrap_or_else(|| panic!("Usage: rasmus inspect <file> [--json]")),
+                json: args.iter().any(|arg| arg == "--json"),
+            },
+            Some("wat2text") => UserInput::Wat2Text {
+                source_file_path: args
+                    .get(1)
+                    .cloned()
+                    .unwrap_or_else(|| panic!("Usage: rasmus wat2text <file>")),
+            },
+            _ => UserInput::Run {
+                source_file_path: args
+                    .first()
+                    .cloned()
+                    .unwrap_or("./rasmus/tests/files/factorial-main.wasm".into()),
+            },
         }
     }
 }
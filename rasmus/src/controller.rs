@@ -6,9 +6,9 @@ use crate::{
         module::{ExportDescription, Module},
         types::NameType,
     },
-    execute::{execute_instruction, pop_values_original_order},
+    execute::{execute_instruction, executor::ExitType, pop_values_original_order},
     instances::{frame::Frame, module::ModuleInst, stack::Stack, store::Store, value::Val},
-    result::{RResult, Trap},
+    result::{RuntimeError, Trap},
 };
 
 pub fn run_func(
@@ -18,7 +18,7 @@ pub fn run_func(
     mut values: Vec<Val>,
     stack: &mut Stack,
     store: &mut Store,
-) -> RResult<Vec<Val>> {
+) -> Result<Vec<Val>, RuntimeError> {
     let export = module
         .exports
         .iter()
@@ -35,7 +35,7 @@ pub fn run_func(
     let func_idx = match &export {
         ExportDescription::Func(ref func_idx) => func_idx.clone(),
         _ => {
-            return Err(Trap);
+            return Err(Trap.into());
         }
     };
 
@@ -66,7 +66,16 @@ pub fn run_func(
         stack.push_value(val);
     }
 
-    execute_instruction(&InstructionType::Call(func_idx), stack, store)?;
+    let exit = execute_instruction(&InstructionType::Call(func_idx), stack, store)?;
+
+    if let ExitType::Thrown(exn_addr) = exit {
+        let exn = store.exns.get(exn_addr).ok_or(Trap)?;
+
+        return Err(RuntimeError::UncaughtException {
+            tag: exn.tag,
+            values: exn.fields.clone(),
+        });
+    }
 
     let values = pop_values_original_order(stack, return_arity)?;
 
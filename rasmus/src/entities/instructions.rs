@@ -18,6 +18,16 @@ pub enum BlockType {
     TypeIndex(S33Type),
 }
 
+/// Memory immediate of load and store instructions.
+/// `memory` is always 0 unless the multi-memory encoding is used.
+/// `offset` is 64-bit wide to address memory64 memories.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemArgType {
+    pub align: U32Type,
+    pub offset: U64Type,
+    pub memory: MemIdx,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum InstructionType {
     // Control Instructions
@@ -32,6 +42,11 @@ pub enum InstructionType {
     Return,
     Call(FuncIdx),
     CallIndirect((TableIdx, TypeIdx)),
+    ReturnCall(FuncIdx),
+    ReturnCallIndirect((TableIdx, TypeIdx)),
+    Throw(TagIdx),
+    ThrowRef,
+    TryTable(TryTableInstructionType),
 
     // Reference Instructions
     RefNull(RefType),
@@ -61,35 +76,104 @@ pub enum InstructionType {
     TableFill(TableIdx),
 
     // Memory Instructions
-    I32Load((U32Type, U32Type)),
-    I64Load((U32Type, U32Type)),
-    F32Load((U32Type, U32Type)),
-    F64Load((U32Type, U32Type)),
-    I32Load8S((U32Type, U32Type)),
-    I32Load8U((U32Type, U32Type)),
-    I32Load16S((U32Type, U32Type)),
-    I32Load16U((U32Type, U32Type)),
-    I64Load8S((U32Type, U32Type)),
-    I64Load8U((U32Type, U32Type)),
-    I64Load16S((U32Type, U32Type)),
-    I64Load16U((U32Type, U32Type)),
-    I64Load32S((U32Type, U32Type)),
-    I64Load32U((U32Type, U32Type)),
-    I32Store((U32Type, U32Type)),
-    I64Store((U32Type, U32Type)),
-    F32Store((U32Type, U32Type)),
-    F64Store((U32Type, U32Type)),
-    I32Store8((U32Type, U32Type)),
-    I32Store16((U32Type, U32Type)),
-    I64Store8((U32Type, U32Type)),
-    I64Store16((U32Type, U32Type)),
-    I64Store32((U32Type, U32Type)),
-    MemorySize,
-    MemoryGrow,
-    MemoryInit(DataIdx),
+    I32Load(MemArgType),
+    I64Load(MemArgType),
+    F32Load(MemArgType),
+    F64Load(MemArgType),
+    I32Load8S(MemArgType),
+    I32Load8U(MemArgType),
+    I32Load16S(MemArgType),
+    I32Load16U(MemArgType),
+    I64Load8S(MemArgType),
+    I64Load8U(MemArgType),
+    I64Load16S(MemArgType),
+    I64Load16U(MemArgType),
+    I64Load32S(MemArgType),
+    I64Load32U(MemArgType),
+    I32Store(MemArgType),
+    I64Store(MemArgType),
+    F32Store(MemArgType),
+    F64Store(MemArgType),
+    I32Store8(MemArgType),
+    I32Store16(MemArgType),
+    I64Store8(MemArgType),
+    I64Store16(MemArgType),
+    I64Store32(MemArgType),
+    MemorySize(MemIdx),
+    MemoryGrow(MemIdx),
+    MemoryInit((DataIdx, MemIdx)),
     DataDrop(DataIdx),
-    MemoryCopy,
-    MemoryFill,
+    MemoryCopy((MemIdx, MemIdx)),
+    MemoryFill(MemIdx),
+
+    // Atomic Memory Instructions
+    MemoryAtomicNotify(MemArgType),
+    MemoryAtomicWait32(MemArgType),
+    MemoryAtomicWait64(MemArgType),
+    AtomicFence,
+    I32AtomicLoad(MemArgType),
+    I64AtomicLoad(MemArgType),
+    I32AtomicLoad8U(MemArgType),
+    I32AtomicLoad16U(MemArgType),
+    I64AtomicLoad8U(MemArgType),
+    I64AtomicLoad16U(MemArgType),
+    I64AtomicLoad32U(MemArgType),
+    I32AtomicStore(MemArgType),
+    I64AtomicStore(MemArgType),
+    I32AtomicStore8(MemArgType),
+    I32AtomicStore16(MemArgType),
+    I64AtomicStore8(MemArgType),
+    I64AtomicStore16(MemArgType),
+    I64AtomicStore32(MemArgType),
+    I32AtomicRmwAdd(MemArgType),
+    I64AtomicRmwAdd(MemArgType),
+    I32AtomicRmw8AddU(MemArgType),
+    I32AtomicRmw16AddU(MemArgType),
+    I64AtomicRmw8AddU(MemArgType),
+    I64AtomicRmw16AddU(MemArgType),
+    I64AtomicRmw32AddU(MemArgType),
+    I32AtomicRmwSub(MemArgType),
+    I64AtomicRmwSub(MemArgType),
+    I32AtomicRmw8SubU(MemArgType),
+    I32AtomicRmw16SubU(MemArgType),
+    I64AtomicRmw8SubU(MemArgType),
+    I64AtomicRmw16SubU(MemArgType),
+    I64AtomicRmw32SubU(MemArgType),
+    I32AtomicRmwAnd(MemArgType),
+    I64AtomicRmwAnd(MemArgType),
+    I32AtomicRmw8AndU(MemArgType),
+    I32AtomicRmw16AndU(MemArgType),
+    I64AtomicRmw8AndU(MemArgType),
+    I64AtomicRmw16AndU(MemArgType),
+    I64AtomicRmw32AndU(MemArgType),
+    I32AtomicRmwOr(MemArgType),
+    I64AtomicRmwOr(MemArgType),
+    I32AtomicRmw8OrU(MemArgType),
+    I32AtomicRmw16OrU(MemArgType),
+    I64AtomicRmw8OrU(MemArgType),
+    I64AtomicRmw16OrU(MemArgType),
+    I64AtomicRmw32OrU(MemArgType),
+    I32AtomicRmwXor(MemArgType),
+    I64AtomicRmwXor(MemArgType),
+    I32AtomicRmw8XorU(MemArgType),
+    I32AtomicRmw16XorU(MemArgType),
+    I64AtomicRmw8XorU(MemArgType),
+    I64AtomicRmw16XorU(MemArgType),
+    I64AtomicRmw32XorU(MemArgType),
+    I32AtomicRmwXchg(MemArgType),
+    I64AtomicRmwXchg(MemArgType),
+    I32AtomicRmw8XchgU(MemArgType),
+    I32AtomicRmw16XchgU(MemArgType),
+    I64AtomicRmw8XchgU(MemArgType),
+    I64AtomicRmw16XchgU(MemArgType),
+    I64AtomicRmw32XchgU(MemArgType),
+    I32AtomicRmwCmpxchg(MemArgType),
+    I64AtomicRmwCmpxchg(MemArgType),
+    I32AtomicRmw8CmpxchgU(MemArgType),
+    I32AtomicRmw16CmpxchgU(MemArgType),
+    I64AtomicRmw8CmpxchgU(MemArgType),
+    I64AtomicRmw16CmpxchgU(MemArgType),
+    I64AtomicRmw32CmpxchgU(MemArgType),
 
     // Numeric Instructions
     I32Const(I32Type),
@@ -234,28 +318,28 @@ pub enum InstructionType {
     I64TruncSatF64U,
 
     // Vector Instuctions
-    V128Load((U32Type, U32Type)),
-    V128Load8x8S((U32Type, U32Type)),
-    V128Load8x8U((U32Type, U32Type)),
-    V128Load16x4S((U32Type, U32Type)),
-    V128Load16x4U((U32Type, U32Type)),
-    V128Load32x2S((U32Type, U32Type)),
-    V128Load32x2U((U32Type, U32Type)),
-    V128Load8Splat((U32Type, U32Type)),
-    V128Load16Splat((U32Type, U32Type)),
-    V128Load32Splat((U32Type, U32Type)),
-    V128Load64Splat((U32Type, U32Type)),
-    V128Load32Zero((U32Type, U32Type)),
-    V128Load64Zero((U32Type, U32Type)),
-    V128Store((U32Type, U32Type)),
-    V128Load8Lane(((U32Type, U32Type), LaneIdx)),
-    V128Load16Lane(((U32Type, U32Type), LaneIdx)),
-    V128Load32Lane(((U32Type, U32Type), LaneIdx)),
-    V128Load64Lane(((U32Type, U32Type), LaneIdx)),
-    V128Store8Lane(((U32Type, U32Type), LaneIdx)),
-    V128Store16Lane(((U32Type, U32Type), LaneIdx)),
-    V128Store32Lane(((U32Type, U32Type), LaneIdx)),
-    V128Store64Lane(((U32Type, U32Type), LaneIdx)),
+    V128Load(MemArgType),
+    V128Load8x8S(MemArgType),
+    V128Load8x8U(MemArgType),
+    V128Load16x4S(MemArgType),
+    V128Load16x4U(MemArgType),
+    V128Load32x2S(MemArgType),
+    V128Load32x2U(MemArgType),
+    V128Load8Splat(MemArgType),
+    V128Load16Splat(MemArgType),
+    V128Load32Splat(MemArgType),
+    V128Load64Splat(MemArgType),
+    V128Load32Zero(MemArgType),
+    V128Load64Zero(MemArgType),
+    V128Store(MemArgType),
+    V128Load8Lane((MemArgType, LaneIdx)),
+    V128Load16Lane((MemArgType, LaneIdx)),
+    V128Load32Lane((MemArgType, LaneIdx)),
+    V128Load64Lane((MemArgType, LaneIdx)),
+    V128Store8Lane((MemArgType, LaneIdx)),
+    V128Store16Lane((MemArgType, LaneIdx)),
+    V128Store32Lane((MemArgType, LaneIdx)),
+    V128Store64Lane((MemArgType, LaneIdx)),
     // 16 Bytes
     V128Const(Vec<Byte>),
     // 16 LaneIdxs
@@ -499,3 +583,20 @@ pub struct IfElseInstructionType {
     pub if_instructions: Vec<InstructionType>,
     pub else_instructions: Vec<InstructionType>,
 }
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TryTableInstructionType {
+    pub blocktype: BlockType,
+    pub catches: Vec<CatchType>,
+    pub instructions: Vec<InstructionType>,
+}
+
+/// Handler clause of try_table.
+/// Labels are relative to the block enclosing the try_table, not to the try_table itself.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CatchType {
+    Catch((TagIdx, LabelIdx)),
+    CatchRef((TagIdx, LabelIdx)),
+    CatchAll(LabelIdx),
+    CatchAllRef(LabelIdx),
+}
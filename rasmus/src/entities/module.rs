@@ -1,4 +1,12 @@
-use crate::{instances::module::ExternalDependency, validation::module::validate};
+use std::rc::Rc;
+
+use crate::{
+    instances::func::{FuncCode, LazyFunc},
+    validation::{
+        module::{create_context, func_export_names, validate},
+        validation_error::ModuleValidationError,
+    },
+};
 
 pub use super::instructions::*;
 use super::types::*;
@@ -10,54 +18,71 @@ pub struct Module {
     pub funcs: Vec<TypeIdx>,
     pub tables: Vec<TableType>,
     pub mems: Vec<MemType>,
+    pub tags: Vec<TagType>,
     pub globals: Vec<Global>,
     pub exports: Vec<ExportType>,
     pub start: Option<StartType>,
     pub elems: Vec<ElementSegmentType>,
     pub code: Vec<CodeType>,
     pub datas: Vec<DataType>,
+    // memory.init and data.drop are only valid when the data count section is present
+    pub data_count: Option<u32>,
+    pub customs: Vec<CustomSection>,
 }
 
 impl Module {
     pub const MAGIC: [u8; 4] = [0x00, 0x61, 0x73, 0x6D];
     pub const VERSION: [u8; 4] = [0x01, 0x00, 0x00, 0x00];
 
-    pub fn is_valid(&self, externals: &Vec<ExternalDependency>) -> bool {
-        match validate(&self, externals) {
-            Err(validation_error) => {
-                println!("Invalid module. Validation error {validation_error:?}");
-                false
+    pub fn validate(&self) -> Result<(), ModuleValidationError> {
+        validate(self)
+    }
+
+    pub fn get_import_type(&self, import: &ImportType) -> Option<ExternType> {
+        Some(match import.desc {
+            ImportDescription::Func(ref type_idx) => {
+                ExternType::Func(self.types.get(type_idx.0 .0 as usize)?.clone())
             }
-            Ok(_) => true,
-        }
+            ImportDescription::Table(ref table_type) => ExternType::Table(table_type.clone()),
+            ImportDescription::Mem(ref mem_type) => ExternType::Mem(mem_type.clone()),
+            ImportDescription::Global(ref global_type) => ExternType::Global(global_type.clone()),
+            ImportDescription::Tag(ref tag_type) => {
+                ExternType::Tag(self.types.get(tag_type.type_idx.0 .0 as usize)?.clone())
+            }
+        })
     }
 
-    pub fn get_funcs(&self) -> Option<Vec<Func>> {
+    /// Code of the defined functions. Lazy bodies are shared by everything using the list,
+    /// so it is built once per validated module.
+    pub fn get_funcs(&self) -> Option<Vec<FuncCode>> {
         let num = self.funcs.len();
         let mut funcs = Vec::with_capacity(num);
+        // lazy bodies share the context they are validated with on the first call
+        let mut lazy_ctx = None;
+        let mut export_names = None;
         for i in 0..num {
             let type_idx = self.funcs.get(i)?;
             let code = self.code.get(i)?;
-            let locals =
-                code.code
-                    .locals
-                    .clone()
-                    .iter()
-                    .fold(vec![], |mut locals_acc, current_locals| {
-                        locals_acc.append(&mut vec![
-                            current_locals.val_type.clone();
-                            current_locals.n.0 as usize
-                        ]);
-                        locals_acc
-                    });
-            let body = ExpressionType {
-                instructions: code.code.expression.instructions.clone(),
-            };
-
-            funcs.push(Func {
-                func_type: type_idx.clone(),
-                locals,
-                body,
+
+            funcs.push(match code.code {
+                CodeBody::Decoded(ref code) => FuncCode::Decoded(code.to_func(type_idx.clone())),
+                CodeBody::Raw(ref raw_code) => {
+                    let ctx: Rc<_> = lazy_ctx
+                        .get_or_insert_with(|| Rc::new(create_context(self)))
+                        .clone();
+                    // diagnostics use the function index space, after the imported functions
+                    let func_idx = ctx.funcs.len() - num + i;
+                    let name = export_names
+                        .get_or_insert_with(|| func_export_names(self))
+                        .remove(&func_idx);
+                    FuncCode::Lazy(Rc::new(LazyFunc::new(
+                        type_idx.clone(),
+                        raw_code.clone(),
+                        ctx,
+                        func_idx,
+                        name,
+                    )))
+                }
             });
         }
 
@@ -67,7 +92,7 @@ impl Module {
 
 pub type SectionIdValue = Byte;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum SectionId {
     Custom,
     Type,
@@ -75,6 +100,7 @@ pub enum SectionId {
     Function,
     Table,
     Memory,
+    Tag,
     Global,
     Export,
     Start,
@@ -84,7 +110,7 @@ pub enum SectionId {
     DataCount,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct CustomSection {
     pub name: String,
     pub bytes: Vec<Byte>,
@@ -103,6 +129,7 @@ pub enum ImportDescription {
     Table(TableType),
     Mem(MemType),
     Global(GlobalType),
+    Tag(TagType),
 }
 
 impl ImportDescription {}
@@ -119,6 +146,7 @@ pub enum ExportDescription {
     Table(TableIdx),
     Mem(MemIdx),
     Global(GlobalIdx),
+    Tag(TagIdx),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -158,8 +186,41 @@ impl ElementSegmentType {
         }
     }
 
-    pub fn get_init(&self) -> &Vec<ExpressionType> {
-        unimplemented!()
+    /// Initializer expressions of the references, function indices are turned into `ref.func`
+    pub fn get_init(&self) -> Vec<ExpressionType> {
+        let ref_funcs = |funcs: &Vec<FuncIdx>| {
+            funcs
+                .iter()
+                .map(|func_idx| {
+                    ExpressionType::new(vec![InstructionType::RefFunc(func_idx.clone())])
+                })
+                .collect()
+        };
+
+        match self {
+            Self::Active0Functions(t) => ref_funcs(&t.init),
+            Self::ElemKindPassiveFunctions(t) => ref_funcs(&t.init),
+            Self::ElemKindActiveFunctions(t) => ref_funcs(&t.init),
+            Self::ElemKindDeclarativeFunctions(t) => ref_funcs(&t.init),
+            Self::Active0Expr(t) => t.init.clone(),
+            Self::PassiveRef(t) => t.init.clone(),
+            Self::ActiveRef(t) => t.init.clone(),
+            Self::DeclarativeRef(t) => t.init.clone(),
+        }
+    }
+
+    /// Number of references in the segment
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Active0Functions(t) => t.init.len(),
+            Self::ElemKindPassiveFunctions(t) => t.init.len(),
+            Self::ElemKindActiveFunctions(t) => t.init.len(),
+            Self::ElemKindDeclarativeFunctions(t) => t.init.len(),
+            Self::Active0Expr(t) => t.init.len(),
+            Self::PassiveRef(t) => t.init.len(),
+            Self::ActiveRef(t) => t.init.len(),
+            Self::DeclarativeRef(t) => t.init.len(),
+        }
     }
 }
 
@@ -249,7 +310,29 @@ impl Into<RefType> for &ElemKind {
 #[derive(Debug, PartialEq, Clone)]
 pub struct CodeType {
     pub size: U32Type,
-    pub code: FuncCodeType,
+    pub code: CodeBody,
+}
+
+/// Body of a code entry, lazily parsed modules keep the bytes until the function is called
+#[derive(Debug, PartialEq, Clone)]
+pub enum CodeBody {
+    Decoded(FuncCodeType),
+    Raw(RawCode),
+}
+
+impl From<FuncCodeType> for CodeBody {
+    fn from(code: FuncCodeType) -> Self {
+        CodeBody::Decoded(code)
+    }
+}
+
+/// Undecoded body of a code entry, starting after the size of the entry
+#[derive(Debug, PartialEq, Clone)]
+pub struct RawCode {
+    pub bytes: Rc<[Byte]>,
+    // offset of the bytes in the module, for errors found on decoding
+    pub offset: usize,
+    pub func_idx: usize,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -258,6 +341,29 @@ pub struct FuncCodeType {
     pub expression: ExpressionType,
 }
 
+impl FuncCodeType {
+    pub fn to_func(&self, func_type: TypeIdx) -> Func {
+        Func {
+            func_type,
+            locals: self.local_types(),
+            body: self.expression.clone(),
+        }
+    }
+
+    /// Types of the declared locals, one per local
+    pub fn local_types(&self) -> Vec<ValType> {
+        self.locals
+            .iter()
+            .fold(vec![], |mut locals_acc, current_locals| {
+                locals_acc.append(&mut vec![
+                    current_locals.val_type.clone();
+                    current_locals.n.0 as usize
+                ]);
+                locals_acc
+            })
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct LocalsType {
     pub n: U32Type,
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use super::instructions::ExpressionType;
 
 pub type Byte = u8;
@@ -27,19 +29,55 @@ pub enum NumType {
     F64,
 }
 
+// Variant names follow the reference types of the spec
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum RefType {
     FuncRef,
     ExternRef,
+    // exnref comes from the exception handling proposal
+    ExnRef,
 }
 
 impl RefType {
     pub fn get_all() -> Vec<Self> {
-        vec![RefType::FuncRef, RefType::ExternRef]
+        vec![RefType::FuncRef, RefType::ExternRef, RefType::ExnRef]
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+// Types are rendered with their names in the text format
+impl fmt::Display for ValType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValType::NumType(num_type) => write!(f, "{num_type}"),
+            ValType::VecType(VecType::V128) => write!(f, "v128"),
+            ValType::RefType(ref_type) => write!(f, "{ref_type}"),
+        }
+    }
+}
+
+impl fmt::Display for NumType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumType::I32 => write!(f, "i32"),
+            NumType::I64 => write!(f, "i64"),
+            NumType::F32 => write!(f, "f32"),
+            NumType::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+impl fmt::Display for RefType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefType::FuncRef => write!(f, "funcref"),
+            RefType::ExternRef => write!(f, "externref"),
+            RefType::ExnRef => write!(f, "exnref"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct FuncType {
     pub parameters: Vec<ValType>,
     pub results: Vec<ValType>,
@@ -66,13 +104,25 @@ pub struct TableType {
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct LimitsType {
-    pub min: U32Type,
-    pub max: Option<U32Type>,
+    pub min: U64Type,
+    pub max: Option<U64Type>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct MemType {
     pub limits: LimitsType,
+    pub index_type: IndexType,
+    // shared memories come from the threads proposal
+    pub shared: bool,
+}
+
+/// Type of addresses used to access a memory.
+/// `I64` memories come from the memory64 proposal.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum IndexType {
+    #[default]
+    I32,
+    I64,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -81,6 +131,55 @@ pub struct GlobalType {
     pub val_type: ValType,
 }
 
+/// Type of an exception tag, refers to a function type with no results
+#[derive(Debug, PartialEq, Clone)]
+pub struct TagType {
+    pub type_idx: TypeIdx,
+}
+
+/// Type of an import or an export, function types are resolved from type indices
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExternType {
+    Func(FuncType),
+    Table(TableType),
+    Mem(MemType),
+    Global(GlobalType),
+    Tag(FuncType),
+}
+
+impl ExternType {
+    pub fn kind(&self) -> ExternKind {
+        match self {
+            ExternType::Func(_) => ExternKind::Func,
+            ExternType::Table(_) => ExternKind::Table,
+            ExternType::Mem(_) => ExternKind::Mem,
+            ExternType::Global(_) => ExternKind::Global,
+            ExternType::Tag(_) => ExternKind::Tag,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExternKind {
+    Func,
+    Table,
+    Mem,
+    Global,
+    Tag,
+}
+
+impl fmt::Display for ExternKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternKind::Func => write!(f, "func"),
+            ExternKind::Table => write!(f, "table"),
+            ExternKind::Mem => write!(f, "memory"),
+            ExternKind::Global => write!(f, "global"),
+            ExternKind::Tag => write!(f, "tag"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum MutType {
     Const,
@@ -102,6 +201,9 @@ pub struct MemIdx(pub U32Type);
 #[derive(Debug, PartialEq, Clone)]
 pub struct GlobalIdx(pub U32Type);
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct TagIdx(pub U32Type);
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ElemIdx(pub U32Type);
 
@@ -120,6 +222,9 @@ pub struct LaneIdx(pub Byte);
 #[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
 pub struct U32Type(pub u32);
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Clone)]
+pub struct U64Type(pub u64);
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct S33Type(pub i64);
 
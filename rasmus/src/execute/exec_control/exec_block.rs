@@ -1,5 +1,3 @@
-use std::rc::Rc;
-
 use crate::{
     entities::{
         module::{BlockInstructionType, BlockType, InstructionType},
@@ -24,42 +22,74 @@ pub fn block(
     execute_instruction_fn: impl FnOnce(&InstructionType, &mut Stack, &mut Store) -> RResult<ExitType>
         + Copy,
 ) -> RResult<ExitType> {
+    execute_block(
+        stack,
+        store,
+        blocktype,
+        instructions,
+        execute_instruction_fn,
+    )
+}
+
+pub(super) fn expand_blocktype(stack: &mut Stack, blocktype: &BlockType) -> RResult<FuncType> {
     let current_frame = stack.current_frame().ok_or(Trap)?;
-    let expand_blocktype = match blocktype {
-        &BlockType::Empty => FuncType {
+
+    match blocktype {
+        &BlockType::Empty => Ok(FuncType {
             parameters: vec![],
             results: vec![],
-        },
-        &BlockType::ValType(ref val_type) => FuncType {
+        }),
+        &BlockType::ValType(ref val_type) => Ok(FuncType {
             parameters: vec![],
             results: vec![val_type.clone()],
-        },
+        }),
         &BlockType::TypeIndex(S33Type(idx)) => current_frame
             .module
             .borrow()
             .types
             .get(idx as usize)
             .cloned()
-            .ok_or(Trap)?,
-    };
+            .ok_or(Trap),
+    }
+}
+
+// Executes instructions of a block, an if branch or a try_table body.
+// A thrown exception leaves the block with its label and operands dropped.
+pub(super) fn execute_block(
+    stack: &mut Stack,
+    store: &mut Store,
+    blocktype: &BlockType,
+    instructions: &[InstructionType],
+    execute_instruction_fn: impl FnOnce(&InstructionType, &mut Stack, &mut Store) -> RResult<ExitType>
+        + Copy,
+) -> RResult<ExitType> {
+    let expand_blocktype = expand_blocktype(stack, blocktype)?;
+
+    // block input values according to blocktype
+    let block_values = pop_values_original_order(stack, expand_blocktype.parameters.len())?;
 
     let label = LabelInst {
         arity: expand_blocktype.results.len(),
-        instructions: Rc::new(vec![]),
     };
 
     stack.push_label(label);
 
-    // block input values according to blocktype
-    let block_values = pop_values_original_order(stack, expand_blocktype.parameters.len())?;
     for value in block_values {
         stack.push_value(value);
     }
 
     // instructions execution
-    for ref instruction in instructions {
-        if execute_instruction_fn(instruction, stack, store)? == ExitType::Returned {
-            return Ok(ExitType::Returned);
+    for instruction in instructions {
+        match execute_instruction_fn(instruction, stack, store)? {
+            ExitType::Completed => {}
+            // br to the label of this block has already dropped it
+            ExitType::Branch(0) => return Ok(ExitType::Completed),
+            ExitType::Branch(label_idx) => return Ok(ExitType::Branch(label_idx - 1)),
+            ExitType::Thrown(exn_addr) => {
+                stack.unwind_label().ok_or(Trap)?;
+                return Ok(ExitType::Thrown(exn_addr));
+            }
+            exit => return Ok(exit),
         }
     }
 
@@ -1,53 +1,177 @@
 use crate::{
-    entities::{
-        module::InstructionType,
-        types::{LabelIdx, U32Type},
-    },
+    entities::types::{LabelIdx, U32Type},
     execute::executor::ExitType,
 };
 
 use crate::{
-    instances::{stack::Stack, store::Store},
+    instances::stack::Stack,
     result::{RResult, Trap},
 };
 
 use super::utils::pop_values_original_order;
 
-pub fn exec_br(
-    stack: &mut Stack,
-    store: &mut Store,
-    &LabelIdx(U32Type(label_idx)): &LabelIdx,
-    execute_instruction_fn: impl FnOnce(&InstructionType, &mut Stack, &mut Store) -> RResult<ExitType>
-        + Copy,
-) -> RResult<ExitType> {
+pub fn exec_br(stack: &mut Stack, &LabelIdx(U32Type(label_idx)): &LabelIdx) -> RResult<ExitType> {
     if stack.count_labels() < (label_idx + 1) as usize {
         return Err(Trap);
     }
 
     let label = stack.get_label(label_idx as usize).ok_or(Trap)?;
-    let label_instructions = label.instructions.clone();
     let n = label.arity;
 
     let values = pop_values_original_order(stack, n)?;
 
     for _ in 0..(label_idx + 1) {
-        loop {
-            if stack.pop_value().is_none() {
-                break;
-            }
-        }
-        stack.pop_label().ok_or(Trap)?;
+        stack.unwind_label().ok_or(Trap)?;
     }
 
     for value in values {
         stack.push_value(value);
     }
 
-    for ref instruction in label_instructions.iter() {
-        if execute_instruction_fn(instruction, stack, store)? == ExitType::Returned {
-            return Ok(ExitType::Returned);
-        }
+    // the enclosing blocks stop executing their instructions until the target label is reached
+    Ok(ExitType::Branch(label_idx))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        controller::run_func,
+        entities::{
+            instructions::{BlockInstructionType, BlockType, LoopInstructionType},
+            module::InstructionType,
+            types::{GlobalIdx, I32Type, LabelIdx, MutType, U32Type},
+        },
+        instances::{module::ModuleInst, stack::Stack, store::Store, value::Val},
+        test_utils::{i32_global, i32_type, module_with_run_func},
+        validation::validated_module::ValidatedModule,
+    };
+
+    fn block(blocktype: BlockType, instructions: Vec<InstructionType>) -> InstructionType {
+        InstructionType::Block(BlockInstructionType {
+            blocktype,
+            instructions,
+        })
+    }
+
+    fn br(label_idx: u32) -> InstructionType {
+        InstructionType::Br(LabelIdx(U32Type(label_idx)))
+    }
+
+    fn i32_const(value: u32) -> InstructionType {
+        InstructionType::I32Const(I32Type(value))
+    }
+
+    fn run(instructions: Vec<InstructionType>) -> Vec<Val> {
+        let mut module = module_with_run_func(vec![], vec![], instructions);
+        module.globals = vec![i32_global(MutType::Var, vec![i32_const(0)])];
+        let module = ValidatedModule::new(module).unwrap();
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap()
+    }
+
+    #[test]
+    fn test_br_out_of_nested_blocks() {
+        // the operand left below the branch value is dropped with the inner label
+        let result = run(vec![block(
+            BlockType::ValType(i32_type()),
+            vec![
+                block(BlockType::Empty, vec![i32_const(1), i32_const(2), br(1)]),
+                i32_const(99),
+            ],
+        )]);
+
+        assert_eq!(result, vec![Val::I32(2)]);
+    }
+
+    #[test]
+    fn test_br_out_of_function_body() {
+        let result = run(vec![
+            block(BlockType::Empty, vec![i32_const(7), br(1)]),
+            i32_const(8),
+        ]);
+
+        assert_eq!(result, vec![Val::I32(7)]);
+    }
+
+    #[test]
+    fn test_br_if() {
+        let branch_on = |condition| {
+            vec![block(
+                BlockType::ValType(i32_type()),
+                vec![
+                    i32_const(1),
+                    i32_const(condition),
+                    InstructionType::BrIf(LabelIdx(U32Type(0))),
+                    InstructionType::Drop,
+                    i32_const(2),
+                ],
+            )]
+        };
+
+        assert_eq!(run(branch_on(1)), vec![Val::I32(1)]);
+        assert_eq!(run(branch_on(0)), vec![Val::I32(2)]);
+    }
+
+    #[test]
+    fn test_br_table() {
+        // every label carries the value, the blocks left by the branch don't add to it
+        let branch_to = |index| {
+            let result_type = BlockType::ValType(i32_type());
+            vec![block(
+                result_type.clone(),
+                vec![
+                    block(
+                        result_type.clone(),
+                        vec![
+                            block(
+                                result_type,
+                                vec![
+                                    i32_const(30),
+                                    i32_const(index),
+                                    InstructionType::BrTable((
+                                        vec![LabelIdx(U32Type(0)), LabelIdx(U32Type(1))],
+                                        LabelIdx(U32Type(2)),
+                                    )),
+                                ],
+                            ),
+                            i32_const(10),
+                            InstructionType::I32Add,
+                        ],
+                    ),
+                    i32_const(100),
+                    InstructionType::I32Add,
+                ],
+            )]
+        };
+
+        assert_eq!(run(branch_to(0)), vec![Val::I32(140)]);
+        assert_eq!(run(branch_to(1)), vec![Val::I32(130)]);
+        assert_eq!(run(branch_to(5)), vec![Val::I32(30)]);
     }
 
-    Ok(ExitType::Completed)
+    #[test]
+    fn test_br_if_restarts_loop() {
+        let counter = GlobalIdx(U32Type(0));
+        let result = run(vec![
+            InstructionType::Loop(LoopInstructionType {
+                blocktype: BlockType::Empty,
+                instructions: vec![
+                    InstructionType::GlobalGet(counter.clone()),
+                    i32_const(1),
+                    InstructionType::I32Add,
+                    InstructionType::GlobalSet(counter.clone()),
+                    InstructionType::GlobalGet(counter.clone()),
+                    i32_const(10),
+                    InstructionType::I32LtU,
+                    InstructionType::BrIf(LabelIdx(U32Type(0))),
+                ],
+            }),
+            InstructionType::GlobalGet(counter),
+        ]);
+
+        assert_eq!(result, vec![Val::I32(10)]);
+    }
 }
@@ -31,3 +31,59 @@ pub fn exec_call(
 
     invoke(stack, store, function_addr, execute_instruction_fn)
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        controller::run_func,
+        entities::{
+            module::{InstructionType, Module},
+            types::{FuncIdx, FuncType, I32Type, TypeIdx, U32Type},
+        },
+        instances::module::ModuleInst,
+        instances::{stack::Stack, store::Store, value::Val},
+        result::RuntimeError,
+        test_utils::{code, func_export, i32_type},
+        validation::validated_module::ValidatedModule,
+    };
+
+    // $recurse calls itself without end, $run returns 1
+    fn recursion_module() -> Module {
+        Module {
+            types: vec![FuncType {
+                parameters: vec![],
+                results: vec![i32_type()],
+            }],
+            funcs: vec![TypeIdx(U32Type(0)), TypeIdx(U32Type(0))],
+            exports: vec![func_export("recurse", 0), func_export("run", 1)],
+            code: vec![
+                code(vec![InstructionType::Call(FuncIdx(U32Type(0)))]),
+                code(vec![InstructionType::I32Const(I32Type(1))]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unbounded_recursion_traps() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let module = ValidatedModule::new(recursion_module()).unwrap();
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+
+        let result = run_func(
+            module_inst.clone(),
+            &module,
+            "recurse",
+            vec![],
+            &mut stack,
+            &mut store,
+        );
+        assert!(matches!(result, Err(RuntimeError::Trap(_))));
+
+        // the nesting budget is restored after the trap
+        let mut stack = Stack::new();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store);
+        assert_eq!(result.unwrap(), vec![Val::I32(1)]);
+    }
+}
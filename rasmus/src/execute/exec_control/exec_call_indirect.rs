@@ -7,7 +7,7 @@ use crate::{
 };
 
 use crate::{
-    address::TableAddr,
+    address::{FuncAddr, TableAddr},
     instances::{stack::Stack, store::Store},
     result::{RResult, Trap},
 };
@@ -17,10 +17,20 @@ use super::invoke;
 pub fn exec_call_indirect(
     stack: &mut Stack,
     store: &mut Store,
-    &(TableIdx(U32Type(table_idx)), TypeIdx(U32Type(type_idx))): &(TableIdx, TypeIdx),
+    call_indirect_args: &(TableIdx, TypeIdx),
     execute_instruction_fn: impl FnOnce(&InstructionType, &mut Stack, &mut Store) -> RResult<ExitType>
         + Copy,
 ) -> RResult<ExitType> {
+    let a = get_indirect_func_addr(stack, store, call_indirect_args)?;
+
+    invoke(stack, store, a, execute_instruction_fn)
+}
+
+pub(super) fn get_indirect_func_addr(
+    stack: &mut Stack,
+    store: &mut Store,
+    &(TableIdx(U32Type(table_idx)), TypeIdx(U32Type(type_idx))): &(TableIdx, TypeIdx),
+) -> RResult<FuncAddr> {
     let table_addr = get_table_addr(stack, table_idx)?;
     let table_inst = store.tables.get(table_addr).ok_or(Trap)?;
     let expected_type = stack
@@ -56,7 +66,7 @@ pub fn exec_call_indirect(
         return Err(Trap);
     }
 
-    invoke(stack, store, a, execute_instruction_fn)
+    Ok(a)
 }
 
 fn get_table_addr(stack: &mut Stack, idx: u32) -> RResult<TableAddr> {
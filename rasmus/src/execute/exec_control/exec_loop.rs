@@ -1,10 +1,5 @@
-use std::rc::Rc;
-
 use crate::{
-    entities::{
-        module::{BlockType, InstructionType, LoopInstructionType},
-        types::{FuncType, S33Type},
-    },
+    entities::module::{InstructionType, LoopInstructionType},
     execute::executor::ExitType,
 };
 
@@ -13,6 +8,7 @@ use crate::{
     result::{RResult, Trap},
 };
 
+use super::exec_block::expand_blocktype;
 use super::utils::pop_values_original_order;
 
 pub fn exec_loop(
@@ -25,46 +21,38 @@ pub fn exec_loop(
     execute_instruction_fn: impl FnOnce(&InstructionType, &mut Stack, &mut Store) -> RResult<ExitType>
         + Copy,
 ) -> RResult<ExitType> {
-    let current_frame = stack.current_frame().ok_or(Trap)?;
-    let expand_blocktype = match blocktype {
-        &BlockType::Empty => FuncType {
-            parameters: vec![],
-            results: vec![],
-        },
-        &BlockType::ValType(ref val_type) => FuncType {
-            parameters: vec![],
-            results: vec![val_type.clone()],
-        },
-        &BlockType::TypeIndex(S33Type(idx)) => current_frame
-            .module
-            .borrow()
-            .types
-            .get(idx as usize)
-            .cloned()
-            .ok_or(Trap)?,
-    };
+    let expand_blocktype = expand_blocktype(stack, blocktype)?;
 
-    let label = LabelInst {
-        arity: expand_blocktype.results.len(),
-        instructions: Rc::new(vec![InstructionType::Loop(LoopInstructionType {
-            blocktype: blocktype.clone(),
-            instructions: instructions.clone(),
-        })]),
-    };
+    // br to the label of a loop passes the loop parameters and starts the next iteration
+    'iteration: loop {
+        // block input values according to blocktype
+        let block_values = pop_values_original_order(stack, expand_blocktype.parameters.len())?;
 
-    stack.push_label(label);
+        let label = LabelInst {
+            arity: expand_blocktype.parameters.len(),
+        };
 
-    // block input values according to blocktype
-    let block_values = pop_values_original_order(stack, expand_blocktype.parameters.len())?;
-    for value in block_values {
-        stack.push_value(value);
-    }
+        stack.push_label(label);
 
-    // instructions execution
-    for ref instruction in instructions {
-        if execute_instruction_fn(instruction, stack, store)? == ExitType::Returned {
-            return Ok(ExitType::Returned);
+        for value in block_values {
+            stack.push_value(value);
         }
+
+        // instructions execution
+        for instruction in instructions {
+            match execute_instruction_fn(instruction, stack, store)? {
+                ExitType::Completed => {}
+                ExitType::Branch(0) => continue 'iteration,
+                ExitType::Branch(label_idx) => return Ok(ExitType::Branch(label_idx - 1)),
+                ExitType::Thrown(exn_addr) => {
+                    stack.unwind_label().ok_or(Trap)?;
+                    return Ok(ExitType::Thrown(exn_addr));
+                }
+                exit => return Ok(exit),
+            }
+        }
+
+        break;
     }
 
     // taking result values according to blocktype
@@ -0,0 +1,193 @@
+use crate::{
+    entities::types::{FuncIdx, TableIdx, TypeIdx, U32Type},
+    execute::executor::ExitType,
+};
+
+use crate::{
+    address::FuncAddr,
+    instances::{
+        stack::{Stack, StackEntry},
+        store::Store,
+    },
+    result::{RResult, Trap},
+};
+
+use super::{exec_call_indirect::get_indirect_func_addr, utils::pop_values_original_order};
+
+pub fn exec_return_call(
+    stack: &mut Stack,
+    store: &mut Store,
+    &FuncIdx(U32Type(func_idx)): &FuncIdx,
+) -> RResult<ExitType> {
+    let current_frame = stack.current_frame().ok_or(Trap)?;
+    let function_addr = current_frame
+        .module
+        .borrow()
+        .funcaddrs
+        .get(func_idx as usize)
+        .cloned()
+        .ok_or(Trap)?;
+
+    replace_frame(stack, store, function_addr)
+}
+
+pub fn exec_return_call_indirect(
+    stack: &mut Stack,
+    store: &mut Store,
+    call_indirect_args: &(TableIdx, TypeIdx),
+) -> RResult<ExitType> {
+    let function_addr = get_indirect_func_addr(stack, store, call_indirect_args)?;
+
+    replace_frame(stack, store, function_addr)
+}
+
+// Drops the current frame keeping only the callee arguments on the stack.
+// The callee is invoked by `invoke` once the caller has unwound.
+fn replace_frame(
+    stack: &mut Stack,
+    store: &mut Store,
+    function_addr: FuncAddr,
+) -> RResult<ExitType> {
+    let function = store.funcs.get(function_addr).ok_or(Trap)?;
+    let values = pop_values_original_order(stack, function.get_type().parameters.len())?;
+
+    loop {
+        if let StackEntry::Frame(_) = stack.pop().ok_or(Trap)? {
+            break;
+        }
+    }
+
+    for value in values {
+        stack.push_value(value);
+    }
+
+    Ok(ExitType::TailCall(function_addr))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        controller::run_func,
+        entities::{
+            instructions::{BlockType, IfElseInstructionType},
+            module::{InstructionType, Module},
+            types::{
+                FuncIdx, FuncType, I32Type, I64Type, LimitsType, LocalIdx, NumType, RefType,
+                TableIdx, TableType, TypeIdx, U32Type, U64Type, ValType,
+            },
+        },
+        instances::{
+            module::ModuleInst, ref_inst::RefInst, stack::Stack, store::Store, value::Val,
+        },
+        test_utils::{code, func_export},
+        validation::validated_module::ValidatedModule,
+    };
+
+    // $sum (n, acc) sums numbers from 1 to n with a tail call per step,
+    // $sum_indirect tail calls whatever is in the first slot of the table
+    fn sum_module(n: u64) -> Module {
+        let i64_type = ValType::NumType(NumType::I64);
+        let local_get = |idx| InstructionType::LocalGet(LocalIdx(U32Type(idx)));
+
+        Module {
+            types: vec![
+                FuncType {
+                    parameters: vec![i64_type.clone(), i64_type.clone()],
+                    results: vec![i64_type.clone()],
+                },
+                FuncType {
+                    parameters: vec![],
+                    results: vec![i64_type.clone()],
+                },
+            ],
+            funcs: vec![
+                TypeIdx(U32Type(0)),
+                TypeIdx(U32Type(0)),
+                TypeIdx(U32Type(1)),
+                TypeIdx(U32Type(1)),
+            ],
+            tables: vec![TableType {
+                limits: LimitsType {
+                    min: U64Type(1),
+                    max: None,
+                },
+                element_ref_type: RefType::FuncRef,
+            }],
+            exports: vec![func_export("run", 2), func_export("run_indirect", 3)],
+            code: vec![
+                code(vec![
+                    local_get(0),
+                    InstructionType::I64Eqz,
+                    InstructionType::IfElse(IfElseInstructionType {
+                        blocktype: BlockType::ValType(i64_type),
+                        if_instructions: vec![local_get(1)],
+                        else_instructions: vec![
+                            local_get(0),
+                            InstructionType::I64Const(I64Type(1)),
+                            InstructionType::I64Sub,
+                            local_get(1),
+                            local_get(0),
+                            InstructionType::I64Add,
+                            InstructionType::ReturnCall(FuncIdx(U32Type(0))),
+                        ],
+                    }),
+                ]),
+                code(vec![
+                    local_get(0),
+                    local_get(1),
+                    InstructionType::I32Const(I32Type(0)),
+                    InstructionType::ReturnCallIndirect((
+                        TableIdx(U32Type(0)),
+                        TypeIdx(U32Type(0)),
+                    )),
+                ]),
+                code(vec![
+                    InstructionType::I64Const(I64Type(n)),
+                    InstructionType::I64Const(I64Type(0)),
+                    InstructionType::Call(FuncIdx(U32Type(0))),
+                ]),
+                code(vec![
+                    InstructionType::I64Const(I64Type(n)),
+                    InstructionType::I64Const(I64Type(0)),
+                    InstructionType::ReturnCall(FuncIdx(U32Type(1))),
+                ]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_deep_tail_recursion() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let module = ValidatedModule::new(sum_module(100_000)).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+
+        assert_eq!(result, vec![Val::I64(5_000_050_000)]);
+    }
+
+    #[test]
+    fn test_return_call_indirect() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let module = ValidatedModule::new(sum_module(10)).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let sum_addr = module_inst.borrow().funcaddrs[0];
+        let table_addr = module_inst.borrow().tableaddrs[0];
+        store.tables[table_addr].elem[0] = RefInst::Func(sum_addr);
+        let result = run_func(
+            module_inst,
+            &module,
+            "run_indirect",
+            vec![],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+
+        assert_eq!(result, vec![Val::I64(55)]);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    entities::types::{TagIdx, U32Type},
+    execute::executor::ExitType,
+};
+
+use crate::{
+    instances::{stack::Stack, store::Store},
+    result::{RResult, Trap},
+};
+
+use super::utils::pop_values_original_order;
+
+pub fn exec_throw(
+    stack: &mut Stack,
+    store: &mut Store,
+    &TagIdx(U32Type(tag_idx)): &TagIdx,
+) -> RResult<ExitType> {
+    let current_frame = stack.current_frame().ok_or(Trap)?;
+    let tag_addr = current_frame
+        .module
+        .borrow()
+        .tagaddrs
+        .get(tag_idx as usize)
+        .cloned()
+        .ok_or(Trap)?;
+
+    let tag = store.tags.get(tag_addr).ok_or(Trap)?;
+    let fields = pop_values_original_order(stack, tag.tag_type.parameters.len())?;
+    let exn_addr = store.allocate_exn(tag_addr, fields);
+
+    Ok(ExitType::Thrown(exn_addr))
+}
+
+pub fn exec_throw_ref(stack: &mut Stack) -> RResult<ExitType> {
+    let exn_addr = stack.pop_ref().ok_or(Trap)?.as_exn()?;
+
+    Ok(ExitType::Thrown(exn_addr))
+}
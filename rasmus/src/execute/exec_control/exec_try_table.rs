@@ -0,0 +1,228 @@
+use crate::{
+    entities::{
+        instructions::{CatchType, TryTableInstructionType},
+        module::InstructionType,
+        types::{TagIdx, U32Type},
+    },
+    execute::executor::ExitType,
+};
+
+use crate::{
+    address::ExnAddr,
+    instances::{ref_inst::RefInst, stack::Stack, store::Store, value::Val},
+    result::{RResult, Trap},
+};
+
+use super::{exec_block::execute_block, exec_br};
+
+pub fn exec_try_table(
+    stack: &mut Stack,
+    store: &mut Store,
+    TryTableInstructionType {
+        blocktype,
+        catches,
+        instructions,
+    }: &TryTableInstructionType,
+    execute_instruction_fn: impl FnOnce(&InstructionType, &mut Stack, &mut Store) -> RResult<ExitType>
+        + Copy,
+) -> RResult<ExitType> {
+    let exn_addr = match execute_block(
+        stack,
+        store,
+        blocktype,
+        instructions,
+        execute_instruction_fn,
+    )? {
+        ExitType::Thrown(exn_addr) => exn_addr,
+        exit => return Ok(exit),
+    };
+
+    // the label of the try_table is dropped at this point,
+    // so catch labels are resolved against the enclosing blocks
+    for catch in catches {
+        let label_idx = match catch {
+            CatchType::Catch((tag_idx, label_idx)) | CatchType::CatchRef((tag_idx, label_idx)) => {
+                if !is_tag_matching(stack, store, exn_addr, tag_idx)? {
+                    continue;
+                }
+                label_idx
+            }
+            CatchType::CatchAll(label_idx) | CatchType::CatchAllRef(label_idx) => label_idx,
+        };
+
+        if let CatchType::Catch(_) | CatchType::CatchRef(_) = catch {
+            let exn = store.exns.get(exn_addr).ok_or(Trap)?;
+            for value in exn.fields.iter().cloned() {
+                stack.push_value(value);
+            }
+        }
+
+        if let CatchType::CatchRef(_) | CatchType::CatchAllRef(_) = catch {
+            stack.push_value(Val::Ref(RefInst::Exn(exn_addr)));
+        }
+
+        return exec_br(stack, label_idx);
+    }
+
+    Ok(ExitType::Thrown(exn_addr))
+}
+
+fn is_tag_matching(
+    stack: &mut Stack,
+    store: &Store,
+    exn_addr: ExnAddr,
+    &TagIdx(U32Type(tag_idx)): &TagIdx,
+) -> RResult<bool> {
+    let current_frame = stack.current_frame().ok_or(Trap)?;
+    let tag_addr = current_frame
+        .module
+        .borrow()
+        .tagaddrs
+        .get(tag_idx as usize)
+        .cloned()
+        .ok_or(Trap)?;
+
+    Ok(store.exns.get(exn_addr).ok_or(Trap)?.tag == tag_addr)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        controller::run_func,
+        entities::{
+            instructions::{BlockInstructionType, BlockType, CatchType, TryTableInstructionType},
+            module::{InstructionType, Module},
+            types::{
+                FuncIdx, FuncType, I32Type, LabelIdx, NumType, RefType, TagIdx, TagType, TypeIdx,
+                U32Type, ValType,
+            },
+        },
+        instances::{module::ModuleInst, stack::Stack, store::Store, value::Val},
+        result::RuntimeError,
+        test_utils::{code, func_export},
+        validation::validated_module::ValidatedModule,
+    };
+
+    fn block(blocktype: BlockType, instructions: Vec<InstructionType>) -> InstructionType {
+        InstructionType::Block(BlockInstructionType {
+            blocktype,
+            instructions,
+        })
+    }
+
+    fn try_table(catch: CatchType) -> InstructionType {
+        InstructionType::TryTable(TryTableInstructionType {
+            blocktype: BlockType::Empty,
+            catches: vec![catch],
+            instructions: vec![InstructionType::Call(FuncIdx(U32Type(0)))],
+        })
+    }
+
+    // $throw raises the tag with 7 as its only field,
+    // the other functions call $throw and handle the exception in different ways
+    fn exception_module() -> Module {
+        let i32_type = ValType::NumType(NumType::I32);
+        let label_0 = LabelIdx(U32Type(0));
+
+        Module {
+            types: vec![
+                FuncType {
+                    parameters: vec![],
+                    results: vec![],
+                },
+                FuncType {
+                    parameters: vec![i32_type.clone()],
+                    results: vec![],
+                },
+                FuncType {
+                    parameters: vec![],
+                    results: vec![i32_type.clone()],
+                },
+            ],
+            funcs: vec![
+                TypeIdx(U32Type(0)),
+                TypeIdx(U32Type(2)),
+                TypeIdx(U32Type(2)),
+                TypeIdx(U32Type(2)),
+            ],
+            tags: vec![TagType {
+                type_idx: TypeIdx(U32Type(1)),
+            }],
+            exports: vec![
+                func_export("catch", 1),
+                func_export("uncaught", 2),
+                func_export("rethrow", 3),
+            ],
+            code: vec![
+                code(vec![
+                    InstructionType::I32Const(I32Type(7)),
+                    InstructionType::Throw(TagIdx(U32Type(0))),
+                ]),
+                code(vec![block(
+                    BlockType::ValType(i32_type),
+                    vec![
+                        try_table(CatchType::Catch((TagIdx(U32Type(0)), label_0.clone()))),
+                        InstructionType::I32Const(I32Type(1)),
+                    ],
+                )]),
+                code(vec![
+                    InstructionType::Call(FuncIdx(U32Type(0))),
+                    InstructionType::I32Const(I32Type(1)),
+                ]),
+                code(vec![
+                    block(
+                        BlockType::ValType(ValType::RefType(RefType::ExnRef)),
+                        vec![
+                            try_table(CatchType::CatchAllRef(label_0)),
+                            InstructionType::Unreachable,
+                        ],
+                    ),
+                    InstructionType::ThrowRef,
+                ]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn run(func_name: &str) -> Result<Vec<Val>, RuntimeError> {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let module = ValidatedModule::new(exception_module()).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        run_func(
+            module_inst,
+            &module,
+            func_name,
+            vec![],
+            &mut stack,
+            &mut store,
+        )
+    }
+
+    #[test]
+    fn test_catch_branches_to_enclosing_label() {
+        assert_eq!(run("catch").unwrap(), vec![Val::I32(7)]);
+    }
+
+    #[test]
+    fn test_uncaught_exception() {
+        match run("uncaught") {
+            Err(RuntimeError::UncaughtException { tag, values }) => {
+                assert_eq!(tag.index, 0);
+                assert_eq!(values, vec![Val::I32(7)]);
+            }
+            result => panic!("exception should not be caught, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_rethrow_caught_exnref() {
+        match run("rethrow") {
+            Err(RuntimeError::UncaughtException { values, .. }) => {
+                assert_eq!(values, vec![Val::I32(7)]);
+            }
+            result => panic!("exception should be rethrown, got {:?}", result),
+        }
+    }
+}
@@ -7,6 +7,9 @@ mod exec_call_indirect;
 mod exec_ifelse;
 mod exec_loop;
 mod exec_return;
+mod exec_return_call;
+mod exec_throw;
+mod exec_try_table;
 mod exec_unreachable;
 mod utils;
 
@@ -19,5 +22,8 @@ pub use exec_call_indirect::*;
 pub use exec_ifelse::*;
 pub use exec_loop::*;
 pub use exec_return::*;
+pub use exec_return_call::*;
+pub use exec_throw::*;
+pub use exec_try_table::*;
 pub use exec_unreachable::*;
-pub use utils::*; 
+pub use utils::*;
@@ -4,7 +4,10 @@ use crate::entities::module::InstructionType;
 
 use crate::execute::executor::ExitType;
 use crate::{
-    instances::{frame::Frame, label::LabelInst, stack::Stack, store::Store, value::Val},
+    address::FuncAddr,
+    instances::{
+        frame::Frame, func::FuncInst, label::LabelInst, stack::Stack, store::Store, value::Val,
+    },
     result::{RResult, Trap},
 };
 
@@ -22,28 +25,42 @@ pub fn pop_values_original_order(stack: &mut Stack, m: usize) -> RResult<Vec<Val
 pub fn invoke(
     stack: &mut Stack,
     store: &mut Store,
-    function_addr: usize,
+    mut function_addr: FuncAddr,
     execute_instruction_fn: impl FnOnce(&InstructionType, &mut Stack, &mut Store) -> RResult<ExitType>
         + Copy,
 ) -> RResult<ExitType> {
-    let function = store.funcs.get(function_addr).cloned().ok_or(Trap)?;
-    let func_type = function.get_type();
-    let arity = func_type.results.len();
-
-    let values = pop_values_original_order(stack, func_type.parameters.len())?;
-    let activation_frame = Frame {
-        arity: Some(arity),
-        module: function.get_module(),
-        locals: Rc::new(RefCell::new(values)),
-    };
-
-    stack.push_frame(activation_frame);
-
-    let label = LabelInst {
-        arity,
-        instructions: Rc::new(vec![]),
-    };
-    stack.push_label(label);
-
-    function.invoke(stack, store, execute_instruction_fn)
+    // tail calls are run in this loop instead of recursion, so the native stack does not grow
+    loop {
+        let function = store.funcs.get(function_addr).cloned().ok_or(Trap)?;
+        if let FuncInst::HostFunc(host_func) = function {
+            return host_func.invoke(stack, store);
+        }
+
+        let func_type = function.get_type();
+        let arity = func_type.results.len();
+
+        let mut values = pop_values_original_order(stack, func_type.parameters.len())?;
+        if let FuncInst::FuncInst(ref func) = function {
+            let code = func
+                .code
+                .get()
+                .map_err(|error| Trap::invalid_function(error.clone()))?;
+            values.extend(code.locals.iter().map(Val::default_of));
+        }
+        let activation_frame = Frame {
+            arity: Some(arity),
+            module: function.get_module(),
+            locals: Rc::new(RefCell::new(values)),
+        };
+
+        stack.push_frame(activation_frame);
+
+        let label = LabelInst { arity };
+        stack.push_label(label);
+
+        match function.invoke(stack, store, execute_instruction_fn)? {
+            ExitType::TailCall(next_function_addr) => function_addr = next_function_addr,
+            exit => return Ok(exit),
+        }
+    }
 }
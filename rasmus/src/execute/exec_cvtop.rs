@@ -102,13 +102,7 @@ macro_rules! trunc_sat_s {
 
 macro_rules! reinterpret {
     ($arg_type: ty, $ret_type: ty) => {
-        |arg: $arg_type| -> RResult<$ret_type> {
-            let mut bytes = arg.to_le_bytes();
-            Ok($crate::read_unsigned_leb128!($ret_type)(
-                &mut bytes,
-                &mut 0usize,
-            ))
-        }
+        |arg: $arg_type| -> RResult<$ret_type> { Ok(<$ret_type>::from_le_bytes(arg.to_le_bytes())) }
     };
 }
 
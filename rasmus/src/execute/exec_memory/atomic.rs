@@ -0,0 +1,446 @@
+use std::sync::atomic::{fence, Ordering};
+
+use crate::entities::{instructions::MemArgType, types::U64Type};
+
+use crate::{
+    address::MemAddr,
+    execute::exec_const::{i32_const, i64_const},
+    instances::{memory::WaitResult, stack::Stack, store::Store},
+    result::{RResult, Trap},
+};
+
+use super::memory::{get_mem_addr, pop_effective_address};
+
+pub enum RmwOp {
+    Add,
+    Sub,
+    And,
+    Or,
+    Xor,
+    Xchg,
+}
+
+impl RmwOp {
+    fn apply(&self, old: u64, operand: u64) -> u64 {
+        match self {
+            RmwOp::Add => old.wrapping_add(operand),
+            RmwOp::Sub => old.wrapping_sub(operand),
+            RmwOp::And => old & operand,
+            RmwOp::Or => old | operand,
+            RmwOp::Xor => old ^ operand,
+            RmwOp::Xchg => operand,
+        }
+    }
+}
+
+pub fn i32_atomic_load(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+) -> RResult<()> {
+    let c = atomic_access(stack, store, memarg, bits, |_| None)?;
+
+    i32_const(&(c as u32), stack)
+}
+
+pub fn i64_atomic_load(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+) -> RResult<()> {
+    let c = atomic_access(stack, store, memarg, bits, |_| None)?;
+
+    i64_const(&c, stack)
+}
+
+pub fn i32_atomic_store(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+) -> RResult<()> {
+    let c = stack.pop_i32().ok_or(Trap)? as u64;
+    atomic_access(stack, store, memarg, bits, |_| Some(c))?;
+
+    Ok(())
+}
+
+pub fn i64_atomic_store(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+) -> RResult<()> {
+    let c = stack.pop_i64().ok_or(Trap)?;
+    atomic_access(stack, store, memarg, bits, |_| Some(c))?;
+
+    Ok(())
+}
+
+pub fn i32_atomic_rmw(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+    op: RmwOp,
+) -> RResult<()> {
+    let operand = stack.pop_i32().ok_or(Trap)? as u64;
+    let old = atomic_access(stack, store, memarg, bits, |old| {
+        Some(op.apply(old, operand))
+    })?;
+
+    i32_const(&(old as u32), stack)
+}
+
+pub fn i64_atomic_rmw(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+    op: RmwOp,
+) -> RResult<()> {
+    let operand = stack.pop_i64().ok_or(Trap)?;
+    let old = atomic_access(stack, store, memarg, bits, |old| {
+        Some(op.apply(old, operand))
+    })?;
+
+    i64_const(&old, stack)
+}
+
+pub fn i32_atomic_cmpxchg(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+) -> RResult<()> {
+    let replacement = stack.pop_i32().ok_or(Trap)? as u64;
+    let expected = wrap(stack.pop_i32().ok_or(Trap)? as u64, bits);
+    let old = atomic_access(stack, store, memarg, bits, |old| {
+        (old == expected).then_some(replacement)
+    })?;
+
+    i32_const(&(old as u32), stack)
+}
+
+pub fn i64_atomic_cmpxchg(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+) -> RResult<()> {
+    let replacement = stack.pop_i64().ok_or(Trap)?;
+    let expected = wrap(stack.pop_i64().ok_or(Trap)?, bits);
+    let old = atomic_access(stack, store, memarg, bits, |old| {
+        (old == expected).then_some(replacement)
+    })?;
+
+    i64_const(&old, stack)
+}
+
+pub fn memory_atomic_notify(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+) -> RResult<()> {
+    let count = stack.pop_i32().ok_or(Trap)?;
+    let (mem_addr, ea) = pop_aligned_address(stack, store, memarg, 32)?;
+    let woken = store.mems.get(mem_addr).ok_or(Trap)?.notify(ea, count);
+
+    i32_const(&woken, stack)
+}
+
+pub fn memory_atomic_wait32(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+) -> RResult<()> {
+    let timeout = stack.pop_i64().ok_or(Trap)? as i64;
+    let expected = stack.pop_i32().ok_or(Trap)?;
+    let (mem_addr, ea) = pop_aligned_address(stack, store, memarg, 32)?;
+    let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
+    let result = mem_inst.wait(ea, &expected.to_le_bytes(), timeout)?;
+
+    i32_const(&wait_result_code(result), stack)
+}
+
+pub fn memory_atomic_wait64(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+) -> RResult<()> {
+    let timeout = stack.pop_i64().ok_or(Trap)? as i64;
+    let expected = stack.pop_i64().ok_or(Trap)?;
+    let (mem_addr, ea) = pop_aligned_address(stack, store, memarg, 64)?;
+    let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
+    let result = mem_inst.wait(ea, &expected.to_le_bytes(), timeout)?;
+
+    i32_const(&wait_result_code(result), stack)
+}
+
+pub fn atomic_fence() -> RResult<()> {
+    fence(Ordering::SeqCst);
+
+    Ok(())
+}
+
+// Reads `bits` wide value at the effective address and writes back what `update` returns.
+// A shared memory is locked for the whole access, so it is atomic for other threads.
+fn atomic_access(
+    stack: &mut Stack,
+    store: &mut Store,
+    memarg: &MemArgType,
+    bits: usize,
+    update: impl FnOnce(u64) -> Option<u64>,
+) -> RResult<u64> {
+    let (mem_addr, ea) = pop_aligned_address(stack, store, memarg, bits)?;
+    let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
+    let n = bits / 8;
+
+    let mut data = mem_inst.data_mut();
+    let bytes = data.get_mut(ea..ea + n).ok_or(Trap)?;
+
+    let mut buf = [0u8; 8];
+    buf[..n].copy_from_slice(bytes);
+    let old = u64::from_le_bytes(buf);
+
+    if let Some(new) = update(old) {
+        bytes.copy_from_slice(&new.to_le_bytes()[..n]);
+    }
+
+    Ok(old)
+}
+
+// Atomic accesses trap when the effective address is not naturally aligned
+fn pop_aligned_address(
+    stack: &mut Stack,
+    store: &mut Store,
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
+    bits: usize,
+) -> RResult<(MemAddr, usize)> {
+    let mem_addr = get_mem_addr(stack, memory)?;
+    let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
+
+    if ea + bits / 8 > mem_inst.byte_len() {
+        return Err(Trap);
+    }
+
+    if !ea.is_multiple_of(bits / 8) {
+        return Err(Trap);
+    }
+
+    Ok((mem_addr, ea))
+}
+
+fn wrap(value: u64, bits: usize) -> u64 {
+    if bits == 64 {
+        value
+    } else {
+        value & ((1u64 << bits) - 1)
+    }
+}
+
+fn wait_result_code(result: WaitResult) -> u32 {
+    match result {
+        WaitResult::Ok => 0,
+        WaitResult::NotEqual => 1,
+        WaitResult::TimedOut => 2,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::validation::validated_module::ValidatedModule;
+    use std::thread;
+
+    use crate::{
+        controller::run_func,
+        entities::{
+            module::{ImportDescription, InstructionType},
+            types::{I32Type, I64Type, IndexType, LimitsType, MemType},
+        },
+        instances::{
+            memory::MemInst,
+            module::{ExternalDependency, ModuleInst},
+            value::Val,
+        },
+        result::RuntimeError,
+        test_utils::{import, memarg, module_with_run_func},
+    };
+
+    fn shared_mem_type() -> MemType {
+        MemType {
+            limits: LimitsType {
+                min: U64Type(1),
+                max: Some(U64Type(1)),
+            },
+            index_type: IndexType::I32,
+            shared: true,
+        }
+    }
+
+    fn run_with_mem(
+        mem_inst: MemInst,
+        instructions: Vec<InstructionType>,
+    ) -> Result<Vec<Val>, RuntimeError> {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mem_addr = store.allocate_shared_mem(mem_inst)?;
+        let imports = vec![import(
+            "env",
+            "mem",
+            ImportDescription::Mem(shared_mem_type()),
+        )];
+        let module =
+            ValidatedModule::new(module_with_run_func(imports, vec![], instructions)).unwrap();
+        let externals = vec![ExternalDependency::Mem {
+            mem_addr,
+            mem_type: shared_mem_type(),
+        }];
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, externals)
+            .map_err(|_| Trap)?;
+        run_func(module_inst, &module, "run", vec![], &mut stack, &mut store)
+    }
+
+    #[test]
+    fn test_rmw_and_cmpxchg() {
+        let mem_inst = MemInst::new(shared_mem_type()).unwrap();
+        let result = run_with_mem(
+            mem_inst.share().unwrap(),
+            vec![
+                InstructionType::I32Const(I32Type(8)),
+                InstructionType::I64Const(I64Type(10)),
+                InstructionType::I64AtomicStore(memarg(3, 0)),
+                // old value 10 is returned, 15 is stored
+                InstructionType::I32Const(I32Type(8)),
+                InstructionType::I32Const(I32Type(5)),
+                InstructionType::I32AtomicRmwAdd(memarg(2, 0)),
+                // 15 matches, so 0x1FF is stored in the lowest byte as 0xFF
+                InstructionType::I32Const(I32Type(8)),
+                InstructionType::I32Const(I32Type(15)),
+                InstructionType::I32Const(I32Type(0x1FF)),
+                InstructionType::I32AtomicRmw8CmpxchgU(memarg(0, 0)),
+                InstructionType::I32Add,
+                InstructionType::I32Const(I32Type(8)),
+                InstructionType::I32AtomicLoad(memarg(2, 0)),
+                InstructionType::I32Add,
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(result, vec![Val::I32(10 + 15 + 0xFF)]);
+        assert_eq!(mem_inst.data()[8..12], [0xFF, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_misaligned_access_traps() {
+        let result = run_with_mem(
+            MemInst::new(shared_mem_type()).unwrap(),
+            vec![
+                InstructionType::I32Const(I32Type(2)),
+                InstructionType::I32AtomicLoad(memarg(2, 0)),
+            ],
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_wait_without_notify() {
+        let wait = |expected: u32| {
+            vec![
+                InstructionType::I32Const(I32Type(0)),
+                InstructionType::I32Const(I32Type(expected)),
+                InstructionType::I64Const(I64Type(0)),
+                InstructionType::MemoryAtomicWait32(memarg(2, 0)),
+            ]
+        };
+
+        let not_equal = run_with_mem(MemInst::new(shared_mem_type()).unwrap(), wait(1)).unwrap();
+        let timed_out = run_with_mem(MemInst::new(shared_mem_type()).unwrap(), wait(0)).unwrap();
+
+        assert_eq!(not_equal, vec![Val::I32(1)]);
+        assert_eq!(timed_out, vec![Val::I32(2)]);
+    }
+
+    #[test]
+    fn test_shared_memory_across_threads() {
+        let mem_inst = MemInst::new(shared_mem_type()).unwrap();
+
+        let adders: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = mem_inst.share().unwrap();
+                thread::spawn(move || {
+                    let instructions = (0..100)
+                        .flat_map(|_| {
+                            vec![
+                                InstructionType::I32Const(I32Type(0)),
+                                InstructionType::I32Const(I32Type(1)),
+                                InstructionType::I32AtomicRmwAdd(memarg(2, 0)),
+                                InstructionType::Drop,
+                            ]
+                        })
+                        .chain([InstructionType::I32Const(I32Type(0))])
+                        .collect();
+                    run_with_mem(shared, instructions).unwrap()
+                })
+            })
+            .collect();
+        for adder in adders {
+            adder.join().unwrap();
+        }
+
+        let shared = mem_inst.share().unwrap();
+        let waiter = thread::spawn(move || {
+            run_with_mem(
+                shared,
+                vec![
+                    InstructionType::I32Const(I32Type(0)),
+                    InstructionType::I32Const(I32Type(400)),
+                    InstructionType::I64Const(I64Type(u64::MAX)),
+                    InstructionType::MemoryAtomicWait32(memarg(2, 0)),
+                ],
+            )
+            .unwrap()
+        });
+        while mem_inst.notify(0, 1) == 0 {
+            thread::yield_now();
+        }
+
+        assert_eq!(waiter.join().unwrap(), vec![Val::I32(0)]);
+        assert_eq!(mem_inst.data()[0..4], 400u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_shared_memory_size() {
+        let mut mem_inst = MemInst::new(MemType {
+            limits: LimitsType {
+                min: U64Type(1),
+                max: Some(U64Type(2)),
+            },
+            ..shared_mem_type()
+        })
+        .unwrap();
+        let shared = mem_inst.share().unwrap();
+
+        mem_inst.grow_n(1).unwrap();
+
+        assert_eq!(shared.size(), 2);
+        assert_eq!(shared.mem_type().limits.min, U64Type(2));
+        assert!(MemInst::new(MemType {
+            shared: false,
+            ..shared_mem_type()
+        })
+        .unwrap()
+        .share()
+        .is_none());
+    }
+}
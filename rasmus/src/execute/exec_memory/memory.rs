@@ -1,116 +1,103 @@
-use crate::entities::types::{DataIdx, U32Type};
+use crate::entities::types::{DataIdx, IndexType, MemIdx, U32Type};
 
 use crate::{
     address::{DataAddr, MemAddr},
-    execute::exec_const::i32_const,
-    instances::{
-        stack::{Stack, StackEntry},
-        store::Store,
-        value::Val,
-    },
+    execute::exec_const::{i32_const, i64_const},
+    instances::{memory::MemInst, stack::Stack, store::Store},
     result::{RResult, Trap},
-    sign::Sign,
 };
 
-use super::{i32_load_8, i32_store8};
-
-pub fn memory_size(stack: &mut Stack, store: &mut Store) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+pub fn memory_size(stack: &mut Stack, store: &mut Store, memory: &MemIdx) -> RResult<()> {
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
 
     let size = mem_inst.size();
-    i32_const(&size, stack)
+    push_address(stack, mem_inst.index_type(), size)
 }
 
-pub fn memory_grow(stack: &mut Stack, store: &mut Store) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+pub fn memory_grow(stack: &mut Stack, store: &mut Store, memory: &MemIdx) -> RResult<()> {
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
+    let index_type = mem_inst.index_type().clone();
     let size = mem_inst.size();
-    let n = stack.pop_i32().ok_or(Trap)?;
-    let err = -1i32 as u32;
+    let n = pop_address(stack, &index_type)?;
+    let err = u64::MAX;
 
     if mem_inst.grow_n(n).is_ok() {
-        i32_const(&(size as u32), stack)
+        push_address(stack, &index_type, size)
     } else {
-        i32_const(&err, stack)
+        push_address(stack, &index_type, err)
     }
 }
 
-pub fn memory_fill(stack: &mut Stack, store: &mut Store) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+pub fn memory_fill(stack: &mut Stack, store: &mut Store, memory: &MemIdx) -> RResult<()> {
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
+    let index_type = mem_inst.index_type().clone();
 
-    let n = stack.pop_i32().ok_or(Trap)?;
-    let val = Val::I32(stack.pop_i32().ok_or(Trap)?);
-    let d = stack.pop_i32().ok_or(Trap)?;
+    let n = pop_address(stack, &index_type)?;
+    let val = stack.pop_i32().ok_or(Trap)?;
+    let d = pop_address(stack, &index_type)?;
 
-    if (d + n) as usize > mem_inst.data.len() {
+    if d.checked_add(n).ok_or(Trap)? > mem_inst.byte_len() as u64 {
         return Err(Trap);
     }
 
-    if n == 0 {
-        return Ok(());
-    }
-
-    i32_const(&d, stack)?;
-    stack.push_entry(StackEntry::Value(val.clone()));
-    i32_store8(stack, store, &(U32Type(0), U32Type(0)))?;
-    let next_d = d.checked_add(1).ok_or(Trap)?;
-    i32_const(&next_d, stack)?;
-    stack.push_entry(StackEntry::Value(val));
-    i32_const(&(n - 1), stack)?;
+    mem_inst.data_mut()[d as usize..(d + n) as usize].fill(val as u8);
 
-    return memory_fill(stack, store);
+    Ok(())
 }
 
-pub fn memory_copy(stack: &mut Stack, store: &mut Store) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
-    let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
+pub fn memory_copy(
+    stack: &mut Stack,
+    store: &mut Store,
+    (dst_memory, src_memory): &(MemIdx, MemIdx),
+) -> RResult<()> {
+    let dst_mem_addr = get_mem_addr(stack, dst_memory)?;
+    let src_mem_addr = get_mem_addr(stack, src_memory)?;
+    let dst_mem_inst = store.mems.get(dst_mem_addr).ok_or(Trap)?;
+    let src_mem_inst = store.mems.get(src_mem_addr).ok_or(Trap)?;
+    let dst_index_type = dst_mem_inst.index_type().clone();
+    let src_index_type = src_mem_inst.index_type().clone();
+    let dst_len = dst_mem_inst.byte_len() as u64;
+    let src_len = src_mem_inst.byte_len() as u64;
+    // length is i64 only when both memories are 64-bit
+    let n_index_type = match (&dst_index_type, &src_index_type) {
+        (IndexType::I64, IndexType::I64) => IndexType::I64,
+        _ => IndexType::I32,
+    };
 
-    let n = stack.pop_i32().ok_or(Trap)?;
-    let s = stack.pop_i32().ok_or(Trap)?;
-    let d = stack.pop_i32().ok_or(Trap)?;
+    let n = pop_address(stack, &n_index_type)?;
+    let s = pop_address(stack, &src_index_type)?;
+    let d = pop_address(stack, &dst_index_type)?;
 
-    if (s + n) as usize > mem_inst.data.len() || (d + n) as usize > mem_inst.data.len() {
+    if s.checked_add(n).ok_or(Trap)? > src_len || d.checked_add(n).ok_or(Trap)? > dst_len {
         return Err(Trap);
     }
 
-    if n == 0 {
-        return Ok(());
-    }
-
-    if d <= s {
-        i32_const(&n, stack)?;
-        i32_const(&s, stack)?;
-        i32_load_8(stack, store, &(U32Type(0), U32Type(0)), Sign::Unsigned)?;
-        i32_store8(stack, store, &(U32Type(0), U32Type(0)))?;
-        let next_d = d.checked_add(1).ok_or(Trap)?;
-        i32_const(&next_d, stack)?;
-        let next_s = s.checked_add(1).ok_or(Trap)?;
-        i32_const(&next_s, stack)?;
+    let (s, d, n) = (s as usize, d as usize, n as usize);
+    if dst_mem_addr == src_mem_addr {
+        let mut data = store.mems.get_mut(dst_mem_addr).ok_or(Trap)?.data_mut();
+        data.copy_within(s..s + n, d);
     } else {
-        let next_d = d.checked_add(n).ok_or(Trap)? - 1;
-        i32_const(&next_d, stack)?;
-        let next_s = s.checked_add(n).ok_or(Trap)? - 1;
-        i32_const(&next_s, stack)?;
-        i32_load_8(stack, store, &(U32Type(0), U32Type(0)), Sign::Unsigned)?;
-        i32_store8(stack, store, &(U32Type(0), U32Type(0)))?;
-        i32_const(&d, stack)?;
-        i32_const(&s, stack)?;
+        // the source is released before the destination is locked, both may be shared
+        let bytes = store.mems.get(src_mem_addr).ok_or(Trap)?.data()[s..s + n].to_vec();
+        let mut data = store.mems.get_mut(dst_mem_addr).ok_or(Trap)?.data_mut();
+        data[d..d + n].copy_from_slice(&bytes);
     }
 
-    i32_const(&(n - 1), stack)?;
-
-    return memory_copy(stack, store);
+    Ok(())
 }
 
 pub fn memory_init(
     stack: &mut Stack,
     store: &mut Store,
-    &DataIdx(U32Type(x)): &DataIdx,
+    (DataIdx(U32Type(x)), memory): &(DataIdx, MemIdx),
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let x = *x;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
+    let index_type = mem_inst.index_type().clone();
 
     let data_addr = get_data_addr(stack, x as usize)?;
     let data_inst = store
@@ -122,28 +109,18 @@ pub fn memory_init(
 
     let n = stack.pop_i32().ok_or(Trap)?;
     let s = stack.pop_i32().ok_or(Trap)?;
-    let d = stack.pop_i32().ok_or(Trap)?;
+    let d = pop_address(stack, &index_type)?;
 
-    if (s + n) as usize > mem_inst.data.len() || (d + n) as usize > mem_inst.data.len() {
+    if (s as u64) + (n as u64) > data_inst.data.len() as u64
+        || d.checked_add(n as u64).ok_or(Trap)? > mem_inst.byte_len() as u64
+    {
         return Err(Trap);
     }
 
-    if n == 0 {
-        return Ok(());
-    }
-
-    let b = data_inst.data.get(s as usize).ok_or(Trap)?;
-    i32_const(&d, stack)?;
-    i32_const(&(*b as u32), stack)?;
-    i32_store8(stack, store, &(U32Type(0), U32Type(0)))?;
+    let (s, d, n) = (s as usize, d as usize, n as usize);
+    mem_inst.data_mut()[d..d + n].copy_from_slice(&data_inst.data[s..s + n]);
 
-    let new_d = d.checked_add(1).ok_or(Trap)?;
-    i32_const(&new_d, stack)?;
-    let new_s = s.checked_add(1).ok_or(Trap)?;
-    i32_const(&new_s, stack)?;
-    i32_const(&(n - 1), stack)?;
-
-    return memory_init(stack, store, &DataIdx(U32Type(x)));
+    Ok(())
 }
 
 pub fn data_drop(stack: &mut Stack, store: &mut Store, x: &DataIdx) -> RResult<()> {
@@ -153,17 +130,48 @@ pub fn data_drop(stack: &mut Stack, store: &mut Store, x: &DataIdx) -> RResult<(
     return store.drop_data(data_addr);
 }
 
-fn get_mem_addr(stack: &mut Stack) -> RResult<MemAddr> {
+pub(super) fn get_mem_addr(stack: &mut Stack, &MemIdx(U32Type(x)): &MemIdx) -> RResult<MemAddr> {
     let current_frame = stack.current_frame().ok_or(Trap)?;
     current_frame
         .module
         .borrow()
         .memaddrs
-        .get(0)
+        .get(x as usize)
         .cloned()
         .ok_or(Trap)
 }
 
+/// Pops an address of the memory's index type and adds the static offset to it.
+pub(super) fn pop_effective_address(
+    stack: &mut Stack,
+    mem_inst: &MemInst,
+    offset: u64,
+) -> RResult<usize> {
+    let i = pop_address(stack, mem_inst.index_type())?;
+    let ea = i.checked_add(offset).ok_or(Trap)?;
+
+    if ea > mem_inst.byte_len() as u64 {
+        return Err(Trap);
+    }
+
+    Ok(ea as usize)
+}
+
+fn pop_address(stack: &mut Stack, index_type: &IndexType) -> RResult<u64> {
+    match index_type {
+        IndexType::I32 => stack.pop_i32().map(|v| v as u64),
+        IndexType::I64 => stack.pop_i64(),
+    }
+    .ok_or(Trap)
+}
+
+fn push_address(stack: &mut Stack, index_type: &IndexType, v: u64) -> RResult<()> {
+    match index_type {
+        IndexType::I32 => i32_const(&(v as u32), stack),
+        IndexType::I64 => i64_const(&v, stack),
+    }
+}
+
 fn get_data_addr(stack: &mut Stack, x: usize) -> RResult<DataAddr> {
     let current_frame = stack.current_frame().ok_or(Trap)?;
     current_frame
@@ -174,3 +182,252 @@ fn get_data_addr(stack: &mut Stack, x: usize) -> RResult<DataAddr> {
         .cloned()
         .ok_or(Trap)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::validation::validated_module::ValidatedModule;
+    use crate::{
+        controller::run_func,
+        entities::{
+            instructions::MemArgType,
+            module::{
+                CodeBody, DataModePassive, DataType, ImportDescription, InstructionType,
+                PassiveDataType,
+            },
+            types::{I32Type, I64Type, MemType, NumType, U64Type, ValType},
+        },
+        instances::{
+            module::{ExternalDependency, ModuleInst},
+            value::Val,
+        },
+        test_utils::{import, mem_type, memarg, module_with_run_func},
+    };
+
+    #[test]
+    fn test_bulk_memory() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let i32_const = |n| InstructionType::I32Const(I32Type(n));
+        let mut module = module_with_run_func(
+            vec![],
+            vec![mem_type(1)],
+            vec![
+                // 1 2 3 4 at 0 from the passive data segment
+                i32_const(0),
+                i32_const(0),
+                i32_const(4),
+                InstructionType::MemoryInit((DataIdx(U32Type(0)), MemIdx(U32Type(0)))),
+                // 5 5 at 4
+                i32_const(4),
+                i32_const(5),
+                i32_const(2),
+                InstructionType::MemoryFill(MemIdx(U32Type(0))),
+                // overlapping copies of 6 bytes, one forward and one backward
+                i32_const(1),
+                i32_const(0),
+                i32_const(6),
+                InstructionType::MemoryCopy((MemIdx(U32Type(0)), MemIdx(U32Type(0)))),
+                i32_const(0),
+                i32_const(1),
+                i32_const(6),
+                InstructionType::MemoryCopy((MemIdx(U32Type(0)), MemIdx(U32Type(0)))),
+                i32_const(0),
+                InstructionType::I32Load(memarg(2, 0)),
+            ],
+        );
+        module.datas = vec![DataType::Passive(PassiveDataType {
+            mode: DataModePassive,
+            init: vec![1, 2, 3, 4],
+        })];
+        module.data_count = Some(1);
+        let module = ValidatedModule::new(module).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let memaddr = module_inst.borrow().memaddrs[0];
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+
+        assert_eq!(&store.mems[memaddr].data()[..8], &[1, 2, 3, 4, 5, 5, 5, 0]);
+        assert_eq!(result, vec![Val::I32(0x04030201)]);
+    }
+
+    #[test]
+    fn test_declared_memories() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let module = ValidatedModule::new(module_with_run_func(
+            vec![],
+            vec![mem_type(1), mem_type(2)],
+            vec![
+                InstructionType::I32Const(I32Type(0)),
+                InstructionType::I32Const(I32Type(42)),
+                InstructionType::I32Store(memarg(2, 1)),
+                InstructionType::I32Const(I32Type(8)),
+                InstructionType::I32Const(I32Type(0)),
+                InstructionType::I32Const(I32Type(4)),
+                InstructionType::MemoryCopy((MemIdx(U32Type(0)), MemIdx(U32Type(1)))),
+                InstructionType::I32Const(I32Type(8)),
+                InstructionType::I32Load(memarg(2, 0)),
+                InstructionType::MemorySize(MemIdx(U32Type(1))),
+                InstructionType::I32Add,
+            ],
+        ))
+        .unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let memaddrs = module_inst.borrow().memaddrs.clone();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+
+        assert_eq!(result, vec![Val::I32(44)]);
+        assert_eq!(store.mems[memaddrs[0]].data()[8], 42);
+        assert_eq!(store.mems[memaddrs[1]].data()[0], 42);
+    }
+
+    #[test]
+    fn test_imported_memories() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let first_mem_addr = store.allocate_mem(mem_type(1)).unwrap();
+        let second_mem_addr = store.allocate_mem(mem_type(1)).unwrap();
+        store.mems[second_mem_addr].data_mut()[4] = 7;
+
+        let import_mem = |name: &str| import("env", name, ImportDescription::Mem(mem_type(1)));
+        let module = ValidatedModule::new(module_with_run_func(
+            vec![import_mem("first"), import_mem("second")],
+            vec![mem_type(1)],
+            vec![
+                InstructionType::I32Const(I32Type(4)),
+                InstructionType::I32Load(memarg(2, 1)),
+                InstructionType::I32Const(I32Type(1)),
+                InstructionType::MemoryGrow(MemIdx(U32Type(0))),
+                InstructionType::I32Add,
+                InstructionType::MemorySize(MemIdx(U32Type(2))),
+                InstructionType::I32Add,
+            ],
+        ))
+        .unwrap();
+        let externals = vec![
+            ExternalDependency::Mem {
+                mem_addr: first_mem_addr,
+                mem_type: mem_type(1),
+            },
+            ExternalDependency::Mem {
+                mem_addr: second_mem_addr,
+                mem_type: mem_type(1),
+            },
+        ];
+
+        let module_inst =
+            ModuleInst::instantiate(&mut store, &mut stack, &module, externals).unwrap();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+
+        assert_eq!(result, vec![Val::I32(9)]);
+        assert_eq!(store.mems[first_mem_addr].size(), 2);
+        assert_eq!(store.mems[second_mem_addr].size(), 1);
+    }
+
+    #[test]
+    fn test_memory64() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut module = module_with_run_func(
+            vec![],
+            vec![MemType {
+                index_type: IndexType::I64,
+                ..mem_type(1)
+            }],
+            vec![
+                InstructionType::I64Const(I64Type(65536 + 4)),
+                InstructionType::I32Const(I32Type(42)),
+                InstructionType::I32Store(MemArgType {
+                    offset: U64Type(4),
+                    ..memarg(2, 0)
+                }),
+                InstructionType::I64Const(I64Type(65536 + 8)),
+                InstructionType::I64Load32U(memarg(2, 0)),
+                InstructionType::MemorySize(MemIdx(U32Type(0))),
+                InstructionType::I64Add,
+            ],
+        );
+        module.types[0].results = vec![ValType::NumType(NumType::I64)];
+        let CodeBody::Decoded(ref mut code) = module.code[0].code else {
+            unreachable!()
+        };
+        code.expression.instructions.splice(
+            0..0,
+            vec![
+                InstructionType::I64Const(I64Type(1)),
+                InstructionType::MemoryGrow(MemIdx(U32Type(0))),
+                InstructionType::Drop,
+            ],
+        );
+        let module = ValidatedModule::new(module).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+
+        assert_eq!(result, vec![Val::I64(44)]);
+    }
+
+    #[test]
+    fn test_memory_limits() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let memory64 = |min| MemType {
+            index_type: IndexType::I64,
+            ..mem_type(min)
+        };
+        let mut module = module_with_run_func(
+            vec![],
+            vec![memory64(1)],
+            vec![
+                InstructionType::I64Const(I64Type(MemInst::MAX_PAGES_64)),
+                InstructionType::MemoryGrow(MemIdx(U32Type(0))),
+            ],
+        );
+        module.types[0].results = vec![ValType::NumType(NumType::I64)];
+        let module = ValidatedModule::new(module).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+
+        assert_eq!(result, vec![Val::I64(u64::MAX)]);
+
+        let huge = ValidatedModule::new(module_with_run_func(
+            vec![],
+            vec![memory64(2u64.pow(47))],
+            vec![InstructionType::I32Const(I32Type(0))],
+        ))
+        .unwrap();
+
+        assert!(ModuleInst::instantiate(&mut store, &mut stack, &huge, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_memory64_rejects_i32_address() {
+        let module = module_with_run_func(
+            vec![],
+            vec![MemType {
+                index_type: IndexType::I64,
+                ..mem_type(1)
+            }],
+            vec![
+                InstructionType::I32Const(I32Type(0)),
+                InstructionType::I32Load(memarg(2, 0)),
+            ],
+        );
+
+        assert!(ValidatedModule::new(module).is_err());
+    }
+
+    #[test]
+    fn test_unknown_memory_is_invalid() {
+        let module = module_with_run_func(
+            vec![],
+            vec![mem_type(1)],
+            vec![InstructionType::MemorySize(MemIdx(U32Type(1)))],
+        );
+
+        assert!(ValidatedModule::new(module).is_err());
+    }
+}
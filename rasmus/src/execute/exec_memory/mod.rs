@@ -1,3 +1,4 @@
+mod atomic;
 mod memory;
 mod memory_bytes;
 mod t_load;
@@ -5,6 +6,7 @@ mod t_store;
 mod v_load;
 mod v_store;
 
+pub use atomic::*;
 pub use memory::*;
 pub use t_load::*;
 pub use t_store::*;
@@ -1,4 +1,7 @@
-use crate::entities::types::U32Type;
+use crate::entities::{
+    instructions::MemArgType,
+    types::{MemIdx, U32Type, U64Type},
+};
 
 use crate::{
     address::MemAddr,
@@ -8,24 +11,30 @@ use crate::{
     sign::Sign,
 };
 
-use super::memory_bytes::{BytesGetter, MemoryBytesGetter};
+use super::{
+    memory::pop_effective_address,
+    memory_bytes::{BytesGetter, MemoryBytesGetter},
+};
 
 pub fn i32_load(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 32;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let c = u32::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let c = u32::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
 
     i32_const(&c, stack)
 }
@@ -33,19 +42,22 @@ pub fn i32_load(
 pub fn i64_load(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 64;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let c = u64::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let c = u64::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
 
     i64_const(&c, stack)
 }
@@ -53,19 +65,22 @@ pub fn i64_load(
 pub fn f32_load(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 32;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let c = f32::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let c = f32::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
 
     f32_const(&c, stack)
 }
@@ -73,19 +88,22 @@ pub fn f32_load(
 pub fn f64_load(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 64;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let c = f64::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let c = f64::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
 
     f64_const(&c, stack)
 }
@@ -93,20 +111,23 @@ pub fn f64_load(
 pub fn i32_load_8(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     sign: Sign,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 8;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u8::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let n = u8::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
     let c = match sign {
         Sign::Signed => n as i8 as u32,
         Sign::Unsigned => n as u32,
@@ -118,20 +139,23 @@ pub fn i32_load_8(
 pub fn i32_load_16(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     sign: Sign,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 16;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u16::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let n = u16::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
     let c = match sign {
         Sign::Signed => n as i16 as u32,
         Sign::Unsigned => n as u32,
@@ -143,20 +167,23 @@ pub fn i32_load_16(
 pub fn i64_load_8(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     sign: Sign,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 8;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u8::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let n = u8::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
     let c = match sign {
         Sign::Signed => n as i8 as u64,
         Sign::Unsigned => n as u64,
@@ -168,20 +195,23 @@ pub fn i64_load_8(
 pub fn i64_load_16(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     sign: Sign,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 16;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u16::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let n = u16::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
     let c = match sign {
         Sign::Signed => n as i16 as u64,
         Sign::Unsigned => n as u64,
@@ -193,20 +223,23 @@ pub fn i64_load_16(
 pub fn i64_load_32(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     sign: Sign,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 32;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u32::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data, ea));
+    let n = u32::from_le_bytes(MemoryBytesGetter::get_bytes(&mem_inst.data(), ea));
     let c = match sign {
         Sign::Signed => n as i32 as u64,
         Sign::Unsigned => n as u64,
@@ -215,13 +248,13 @@ pub fn i64_load_32(
     i64_const(&c, stack)
 }
 
-fn get_mem_addr(stack: &mut Stack) -> RResult<MemAddr> {
+fn get_mem_addr(stack: &mut Stack, &MemIdx(U32Type(x)): &MemIdx) -> RResult<MemAddr> {
     let current_frame = stack.current_frame().ok_or(Trap)?;
     current_frame
         .module
         .borrow()
         .memaddrs
-        .get(0)
+        .get(x as usize)
         .cloned()
         .ok_or(Trap)
 }
@@ -1,4 +1,7 @@
-use crate::entities::types::U32Type;
+use crate::entities::{
+    instructions::MemArgType,
+    types::{MemIdx, U32Type, U64Type},
+};
 
 use crate::{
     address::MemAddr,
@@ -6,26 +9,29 @@ use crate::{
     result::{RResult, Trap},
 };
 
-use super::memory_bytes::set_bytes;
+use super::{memory::pop_effective_address, memory_bytes::set_bytes};
 
 pub fn i32_store(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_i32().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 32;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = t.to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -33,21 +39,24 @@ pub fn i32_store(
 pub fn i64_store(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_i64().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 64;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = t.to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -55,21 +64,24 @@ pub fn i64_store(
 pub fn f32_store(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_f32().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 32;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = t.to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -77,21 +89,24 @@ pub fn f32_store(
 pub fn f64_store(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_f64().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 64;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = t.to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -99,21 +114,24 @@ pub fn f64_store(
 pub fn i32_store8(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_i32().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 8;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = i32_wrap_8(t).to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -121,21 +139,24 @@ pub fn i32_store8(
 pub fn i32_store16(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_i32().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 16;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = i32_wrap_16(t).to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -143,21 +164,24 @@ pub fn i32_store16(
 pub fn i64_store8(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_i64().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 8;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = i64_wrap_8(t).to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -165,21 +189,24 @@ pub fn i64_store8(
 pub fn i64_store16(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_i64().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 16;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = i64_wrap_16(t).to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -187,32 +214,35 @@ pub fn i64_store16(
 pub fn i64_store32(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_i64().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 32;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = i64_wrap_32(t).to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
 
-fn get_mem_addr(stack: &mut Stack) -> RResult<MemAddr> {
+fn get_mem_addr(stack: &mut Stack, &MemIdx(U32Type(x)): &MemIdx) -> RResult<MemAddr> {
     let current_frame = stack.current_frame().ok_or(Trap)?;
     current_frame
         .module
         .borrow()
         .memaddrs
-        .get(0)
+        .get(x as usize)
         .cloned()
         .ok_or(Trap)
 }
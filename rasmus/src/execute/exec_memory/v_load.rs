@@ -1,4 +1,7 @@
-use crate::entities::types::{LaneIdx, U32Type};
+use crate::entities::{
+    instructions::MemArgType,
+    types::{LaneIdx, MemIdx, U32Type, U64Type},
+};
 
 use crate::{
     address::MemAddr,
@@ -15,44 +18,54 @@ use crate::{
     sign::Sign,
 };
 
-use super::memory_bytes::{
-    get_u16_bytes, get_u32_bytes, get_u64_bytes, get_u8_bytes, BytesGetter, MemoryBytesGetter,
+use super::{
+    memory::pop_effective_address,
+    memory_bytes::{
+        get_u16_bytes, get_u32_bytes, get_u64_bytes, get_u8_bytes, BytesGetter, MemoryBytesGetter,
+    },
 };
 
 pub fn v128_load(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 128;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    v128_const(&MemoryBytesGetter::get_bytes(&mem_inst.data, ea), stack)
+    v128_const(&MemoryBytesGetter::get_bytes(&mem_inst.data(), ea), stack)
 }
 
 pub fn v128_load_8x8(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     sign: Sign,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + (8 * 8) / 8) as usize > mem_inst.data.len() {
+    if (ea + (8 * 8) / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let b = mem_inst.data[ea..(ea + (8usize * 8usize) / 8)].as_ref();
+    let data = mem_inst.data();
+    let b = &data[ea..(ea + (8usize * 8usize) / 8)];
     let mk: Vec<u16> = (0..8)
         .map(|k| {
             let offset = k * 8 / 8;
@@ -72,19 +85,23 @@ pub fn v128_load_8x8(
 pub fn v128_load_16x4(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     sign: Sign,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + (16 * 4) / 8) as usize > mem_inst.data.len() {
+    if (ea + (16 * 4) / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let b = mem_inst.data[ea..(ea + (16usize * 4usize) / 8)].as_ref();
+    let data = mem_inst.data();
+    let b = &data[ea..(ea + (16usize * 4usize) / 8)];
     let mk: Vec<u32> = (0..4)
         .map(|k| {
             let offset = k * 16 / 8;
@@ -104,19 +121,23 @@ pub fn v128_load_16x4(
 pub fn v128_load_32x2(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     sign: Sign,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + (32 * 2) / 8) as usize > mem_inst.data.len() {
+    if (ea + (32 * 2) / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let b = mem_inst.data[ea..(ea + (32usize * 2usize) / 8)].as_ref();
+    let data = mem_inst.data();
+    let b = &data[ea..(ea + (32usize * 2usize) / 8)];
     let mk: Vec<u64> = (0..2)
         .map(|k| {
             let offset = k * 32 / 8;
@@ -136,18 +157,21 @@ pub fn v128_load_32x2(
 pub fn v128_load8_splat(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 8 / 8) as usize > mem_inst.data.len() {
+    if (ea + 8 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u8::from_le_bytes(get_u8_bytes(&mem_inst.data, ea));
+    let n = u8::from_le_bytes(get_u8_bytes(&*mem_inst.data(), ea));
     let lanes = (0..128 / 8).map(|_| n).collect();
 
     stack.push_entry(StackEntry::Value(Val::Vec(vec_from_lanes(lanes))));
@@ -158,18 +182,21 @@ pub fn v128_load8_splat(
 pub fn v128_load16_splat(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 16 / 8) as usize > mem_inst.data.len() {
+    if (ea + 16 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u16::from_le_bytes(get_u16_bytes(&mem_inst.data, ea));
+    let n = u16::from_le_bytes(get_u16_bytes(&*mem_inst.data(), ea));
     let lanes = (0..128 / 16).map(|_| n).collect();
 
     stack.push_entry(StackEntry::Value(Val::Vec(vec_from_lanes(lanes))));
@@ -180,18 +207,21 @@ pub fn v128_load16_splat(
 pub fn v128_load32_splat(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 32 / 8) as usize > mem_inst.data.len() {
+    if (ea + 32 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u32::from_le_bytes(get_u32_bytes(&mem_inst.data, ea));
+    let n = u32::from_le_bytes(get_u32_bytes(&*mem_inst.data(), ea));
     let lanes = (0..128 / 32).map(|_| n).collect();
 
     stack.push_entry(StackEntry::Value(Val::Vec(vec_from_lanes(lanes))));
@@ -202,18 +232,21 @@ pub fn v128_load32_splat(
 pub fn v128_load64_splat(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 64 / 8) as usize > mem_inst.data.len() {
+    if (ea + 64 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u64::from_le_bytes(get_u64_bytes(&mem_inst.data, ea));
+    let n = u64::from_le_bytes(get_u64_bytes(&*mem_inst.data(), ea));
     let lanes = (0..128 / 64).map(|_| n).collect();
 
     stack.push_entry(StackEntry::Value(Val::Vec(vec_from_lanes(lanes))));
@@ -224,18 +257,21 @@ pub fn v128_load64_splat(
 pub fn v128_load32_zero(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 32 / 8) as usize > mem_inst.data.len() {
+    if (ea + 32 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u32::from_le_bytes(get_u32_bytes(&mem_inst.data, ea));
+    let n = u32::from_le_bytes(get_u32_bytes(&*mem_inst.data(), ea));
 
     stack.push_entry(StackEntry::Value(Val::Vec(n as u128)));
 
@@ -245,18 +281,21 @@ pub fn v128_load32_zero(
 pub fn v128_load64_zero(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 64 / 8) as usize > mem_inst.data.len() {
+    if (ea + 64 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u64::from_le_bytes(get_u64_bytes(&mem_inst.data, ea));
+    let n = u64::from_le_bytes(get_u64_bytes(&*mem_inst.data(), ea));
 
     stack.push_entry(StackEntry::Value(Val::Vec(n as u128)));
 
@@ -266,20 +305,23 @@ pub fn v128_load64_zero(
 pub fn v128_load8_lane(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     &LaneIdx(lane_idx): &LaneIdx,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
     let v = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 8 / 8) as usize > mem_inst.data.len() {
+    if (ea + 8 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u8::from_le_bytes(get_u8_bytes(&mem_inst.data, ea));
+    let n = u8::from_le_bytes(get_u8_bytes(&*mem_inst.data(), ea));
     let mut lanes = to_lanes_8x16(v);
     lanes[lane_idx as usize] = n;
 
@@ -291,20 +333,23 @@ pub fn v128_load8_lane(
 pub fn v128_load16_lane(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     &LaneIdx(lane_idx): &LaneIdx,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
     let v = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 16 / 8) as usize > mem_inst.data.len() {
+    if (ea + 16 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u16::from_le_bytes(get_u16_bytes(&mem_inst.data, ea));
+    let n = u16::from_le_bytes(get_u16_bytes(&*mem_inst.data(), ea));
     let mut lanes = to_lanes_16x8(v);
     lanes[lane_idx as usize] = n;
 
@@ -316,20 +361,23 @@ pub fn v128_load16_lane(
 pub fn v128_load32_lane(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     &LaneIdx(lane_idx): &LaneIdx,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
     let v = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 32 / 8) as usize > mem_inst.data.len() {
+    if (ea + 32 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u32::from_le_bytes(get_u32_bytes(&mem_inst.data, ea));
+    let n = u32::from_le_bytes(get_u32_bytes(&*mem_inst.data(), ea));
     let mut lanes = to_lanes_32x4(v);
     lanes[lane_idx as usize] = n;
 
@@ -341,20 +389,23 @@ pub fn v128_load32_lane(
 pub fn v128_load64_lane(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
     &LaneIdx(lane_idx): &LaneIdx,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get(mem_addr).ok_or(Trap)?;
     let v = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
 
-    if (ea + 64 / 8) as usize > mem_inst.data.len() {
+    if (ea + 64 / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
-    let n = u64::from_le_bytes(get_u64_bytes(&mem_inst.data, ea));
+    let n = u64::from_le_bytes(get_u64_bytes(&*mem_inst.data(), ea));
     let mut lanes = to_lanes_64x2(v);
     lanes[lane_idx as usize] = n;
 
@@ -363,13 +414,13 @@ pub fn v128_load64_lane(
     Ok(())
 }
 
-fn get_mem_addr(stack: &mut Stack) -> RResult<MemAddr> {
+fn get_mem_addr(stack: &mut Stack, &MemIdx(U32Type(x)): &MemIdx) -> RResult<MemAddr> {
     let current_frame = stack.current_frame().ok_or(Trap)?;
     current_frame
         .module
         .borrow()
         .memaddrs
-        .get(0)
+        .get(x as usize)
         .cloned()
         .ok_or(Trap)
 }
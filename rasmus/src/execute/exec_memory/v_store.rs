@@ -1,4 +1,7 @@
-use crate::entities::types::{LaneIdx, U32Type};
+use crate::entities::{
+    instructions::MemArgType,
+    types::{LaneIdx, MemIdx, U32Type, U64Type},
+};
 
 use crate::{
     address::MemAddr,
@@ -7,26 +10,29 @@ use crate::{
     result::{RResult, Trap},
 };
 
-use super::memory_bytes::set_bytes;
+use super::{memory::pop_effective_address, memory_bytes::set_bytes};
 
 pub fn v128_store(
     stack: &mut Stack,
     store: &mut Store,
-    &(U32Type(offset), U32Type(_align)): &(U32Type, U32Type),
+    &MemArgType {
+        offset: U64Type(offset),
+        ref memory,
+        ..
+    }: &MemArgType,
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 128;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let b = t.to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -34,22 +40,28 @@ pub fn v128_store(
 pub fn v128_store8_lane(
     stack: &mut Stack,
     store: &mut Store,
-    &((U32Type(offset), U32Type(_align)), LaneIdx(x)): &((U32Type, U32Type), LaneIdx),
+    &(
+        MemArgType {
+            offset: U64Type(offset),
+            ref memory,
+            ..
+        },
+        LaneIdx(x),
+    ): &(MemArgType, LaneIdx),
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 8;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let lanes = to_lanes_8x16(t);
     let b = lanes[x as usize].to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -57,22 +69,28 @@ pub fn v128_store8_lane(
 pub fn v128_store16_lane(
     stack: &mut Stack,
     store: &mut Store,
-    &((U32Type(offset), U32Type(_align)), LaneIdx(x)): &((U32Type, U32Type), LaneIdx),
+    &(
+        MemArgType {
+            offset: U64Type(offset),
+            ref memory,
+            ..
+        },
+        LaneIdx(x),
+    ): &(MemArgType, LaneIdx),
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 16;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let lanes = to_lanes_16x8(t);
     let b = lanes[x as usize].to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -80,22 +98,28 @@ pub fn v128_store16_lane(
 pub fn v128_store32_lane(
     stack: &mut Stack,
     store: &mut Store,
-    &((U32Type(offset), U32Type(_align)), LaneIdx(x)): &((U32Type, U32Type), LaneIdx),
+    &(
+        MemArgType {
+            offset: U64Type(offset),
+            ref memory,
+            ..
+        },
+        LaneIdx(x),
+    ): &(MemArgType, LaneIdx),
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 32;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let lanes = to_lanes_32x4(t);
     let b = lanes[x as usize].to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
@@ -103,33 +127,39 @@ pub fn v128_store32_lane(
 pub fn v128_store64_lane(
     stack: &mut Stack,
     store: &mut Store,
-    &((U32Type(offset), U32Type(_align)), LaneIdx(x)): &((U32Type, U32Type), LaneIdx),
+    &(
+        MemArgType {
+            offset: U64Type(offset),
+            ref memory,
+            ..
+        },
+        LaneIdx(x),
+    ): &(MemArgType, LaneIdx),
 ) -> RResult<()> {
-    let mem_addr = get_mem_addr(stack)?;
+    let mem_addr = get_mem_addr(stack, memory)?;
     let mem_inst = store.mems.get_mut(mem_addr).ok_or(Trap)?;
     let t = stack.pop_v128().ok_or(Trap)?;
-    let i = stack.pop_i32().ok_or(Trap)?;
-    let ea = (i + offset) as usize;
+    let ea = pop_effective_address(stack, mem_inst, offset)?;
     let bits = 64;
 
-    if (ea + bits / 8) as usize > mem_inst.data.len() {
+    if (ea + bits / 8) as usize > mem_inst.byte_len() {
         return Err(Trap);
     }
 
     let lanes = to_lanes_64x2(t);
     let b = lanes[x as usize].to_le_bytes();
-    set_bytes(&mut mem_inst.data, ea, b);
+    set_bytes(&mut mem_inst.data_mut(), ea, b);
 
     Ok(())
 }
 
-fn get_mem_addr(stack: &mut Stack) -> RResult<MemAddr> {
+fn get_mem_addr(stack: &mut Stack, &MemIdx(U32Type(x)): &MemIdx) -> RResult<MemAddr> {
     let current_frame = stack.current_frame().ok_or(Trap)?;
     current_frame
         .module
         .borrow()
         .memaddrs
-        .get(0)
+        .get(x as usize)
         .cloned()
         .ok_or(Trap)
 }
@@ -56,9 +56,8 @@ pub fn table_size(
 ) -> RResult<()> {
     let table_addr = get_table_addr(stack, idx)?;
     let table_instance = store.tables.get(table_addr).ok_or(Trap)?;
-    let size = table_instance.elem.len();
 
-    i32_const(&(size as u32), stack)
+    i32_const(&table_instance.size(), stack)
 }
 
 pub fn table_grow(
@@ -68,16 +67,14 @@ pub fn table_grow(
 ) -> RResult<()> {
     let table_addr = get_table_addr(stack, idx)?;
     let table_instance = store.tables.get_mut(table_addr).ok_or(Trap)?;
-    let size = table_instance.elem.len();
 
     let n = stack.pop_i32().ok_or(Trap)?;
     let ref_val = stack.pop_ref().ok_or(Trap)?;
-    let err = -1i32 as u32;
 
-    let to_stack = match table_instance.grow_n(n, ref_val) {
-        Ok(_) => size as u32,
-        Err(_) => err,
-    };
+    // failed growth is reported to the program with -1 instead of trapping
+    let to_stack = table_instance
+        .grow_n(n, ref_val, store.limiter.as_deref_mut())
+        .unwrap_or(-1i32 as u32);
 
     i32_const(&to_stack, stack)
 }
@@ -1,5 +1,15 @@
+use crate::address::{ExnAddr, FuncAddr};
+
 #[derive(Debug, PartialEq)]
 pub enum ExitType {
     Returned,
     Completed,
+    // The current frame has been dropped by return_call
+    // and the function at the address has to be invoked in its place
+    TailCall(FuncAddr),
+    // Labels up to and including the one with the index have been dropped by br,
+    // every enclosing block decrements the index until it reaches its target
+    Branch(u32),
+    // The exception at the address is being propagated to the nearest try_table handler
+    Thrown(ExnAddr),
 }
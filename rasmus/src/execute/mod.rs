@@ -26,6 +26,7 @@ use crate::instances::instruction::{
 use crate::instances::stack::{Stack, StackEntry};
 use crate::instances::store::Store;
 use crate::instances::value::Val;
+use crate::nesting::enter_nesting;
 use crate::sign::Sign;
 use crate::{relop_impl, testop_impl};
 
@@ -39,7 +40,8 @@ use self::exec_binop::{
 use self::exec_const::{f32_const, f64_const, i32_const, i64_const, v128_const};
 use self::exec_control::{
     block, exec_br, exec_brif, exec_brtable, exec_call, exec_call_indirect, exec_ifelse, exec_loop,
-    exec_return, exec_unreachable,
+    exec_return, exec_return_call, exec_return_call_indirect, exec_throw, exec_throw_ref,
+    exec_try_table, exec_unreachable,
 };
 use self::exec_cvtop::{
     f32_convert_i32_s, f32_convert_i32_u, f32_convert_i64_s, f32_convert_i64_u, f32_demote_f64,
@@ -51,13 +53,16 @@ use self::exec_cvtop::{
     i64_trunc_sat_f32_u, i64_trunc_sat_f64_s, i64_trunc_sat_f64_u,
 };
 use self::exec_memory::{
-    data_drop, f32_load, f32_store, f64_load, f64_store, i32_load, i32_load_16, i32_load_8,
-    i32_store, i32_store16, i32_store8, i64_load, i64_load_16, i64_load_32, i64_load_8, i64_store,
-    i64_store16, i64_store32, i64_store8, memory_copy, memory_fill, memory_grow, memory_init,
-    memory_size, v128_load, v128_load16_lane, v128_load16_splat, v128_load32_lane,
-    v128_load32_splat, v128_load32_zero, v128_load64_lane, v128_load64_splat, v128_load64_zero,
-    v128_load8_lane, v128_load8_splat, v128_load_16x4, v128_load_32x2, v128_load_8x8, v128_store,
-    v128_store16_lane, v128_store32_lane, v128_store64_lane, v128_store8_lane,
+    atomic_fence, data_drop, f32_load, f32_store, f64_load, f64_store, i32_atomic_cmpxchg,
+    i32_atomic_load, i32_atomic_rmw, i32_atomic_store, i32_load, i32_load_16, i32_load_8,
+    i32_store, i32_store16, i32_store8, i64_atomic_cmpxchg, i64_atomic_load, i64_atomic_rmw,
+    i64_atomic_store, i64_load, i64_load_16, i64_load_32, i64_load_8, i64_store, i64_store16,
+    i64_store32, i64_store8, memory_atomic_notify, memory_atomic_wait32, memory_atomic_wait64,
+    memory_copy, memory_fill, memory_grow, memory_init, memory_size, v128_load, v128_load16_lane,
+    v128_load16_splat, v128_load32_lane, v128_load32_splat, v128_load32_zero, v128_load64_lane,
+    v128_load64_splat, v128_load64_zero, v128_load8_lane, v128_load8_splat, v128_load_16x4,
+    v128_load_32x2, v128_load_8x8, v128_store, v128_store16_lane, v128_store32_lane,
+    v128_store64_lane, v128_store8_lane, RmwOp,
 };
 use self::exec_parametric::{exec_drop, exec_select, exec_select_vec};
 use self::exec_ref::{is_ref_null, ref_func, ref_null};
@@ -129,11 +134,53 @@ pub fn execute_expression(
     stack.pop_value().ok_or(Trap)
 }
 
-// TODO: rewrite remaining singed instructions using AsSigned trait
+/// Executes the instruction, instructions nested in blocks and called functions are executed
+/// recursively. Blocks and calls take one level of the nesting budget of the thread,
+/// exceeding it traps.
 pub fn execute_instruction(
     instr: &InstructionType,
     stack: &mut Stack,
     store: &mut Store,
+) -> RResult<ExitType> {
+    // the match on every instruction takes a large frame in debug builds,
+    // it is kept out of the recursion of nested blocks and calls
+    let _nesting = match instr {
+        InstructionType::Block(_)
+        | InstructionType::Loop(_)
+        | InstructionType::IfElse(_)
+        | InstructionType::Call(_)
+        | InstructionType::CallIndirect(_)
+        | InstructionType::TryTable(_) => enter_nesting().ok_or(Trap)?,
+        _ => return execute_flat_instruction(instr, stack, store),
+    };
+
+    match instr {
+        InstructionType::Block(block_instruction) => {
+            block(stack, store, block_instruction, execute_instruction)
+        }
+        InstructionType::Loop(loop_instruction) => {
+            exec_loop(stack, store, loop_instruction, execute_instruction)
+        }
+        InstructionType::IfElse(ifelse_instruction) => {
+            exec_ifelse(stack, store, ifelse_instruction, execute_instruction)
+        }
+        InstructionType::Call(func_idx) => exec_call(stack, store, func_idx, execute_instruction),
+        InstructionType::CallIndirect(call_indirect_args) => {
+            exec_call_indirect(stack, store, call_indirect_args, execute_instruction)
+        }
+        InstructionType::TryTable(try_table_instruction) => {
+            exec_try_table(stack, store, try_table_instruction, execute_instruction)
+        }
+        _ => execute_flat_instruction(instr, stack, store),
+    }
+}
+
+// TODO: rewrite remaining singed instructions using AsSigned trait
+// Executes an instruction without nested instructions
+fn execute_flat_instruction(
+    instr: &InstructionType,
+    stack: &mut Stack,
+    store: &mut Store,
     // frame_ref: &Frame,
 ) -> RResult<ExitType> {
     match instr {
@@ -671,42 +718,215 @@ pub fn execute_instruction(
         InstructionType::V128Store16Lane(arg) => v128_store16_lane(stack, store, arg)?,
         InstructionType::V128Store32Lane(arg) => v128_store32_lane(stack, store, arg)?,
         InstructionType::V128Store64Lane(arg) => v128_store64_lane(stack, store, arg)?,
-        InstructionType::MemorySize => memory_size(stack, store)?,
-        InstructionType::MemoryGrow => memory_grow(stack, store)?,
-        InstructionType::MemoryFill => memory_fill(stack, store)?,
-        InstructionType::MemoryCopy => memory_copy(stack, store)?,
-        InstructionType::MemoryInit(data_idx) => memory_init(stack, store, data_idx)?,
+        InstructionType::MemorySize(mem_idx) => memory_size(stack, store, mem_idx)?,
+        InstructionType::MemoryGrow(mem_idx) => memory_grow(stack, store, mem_idx)?,
+        InstructionType::MemoryFill(mem_idx) => memory_fill(stack, store, mem_idx)?,
+        InstructionType::MemoryCopy(mem_idxes) => memory_copy(stack, store, mem_idxes)?,
+        InstructionType::MemoryInit(idxes) => memory_init(stack, store, idxes)?,
         InstructionType::DataDrop(data_idx) => data_drop(stack, store, data_idx)?,
 
+        // atomic memory instructions
+        InstructionType::MemoryAtomicNotify(memarg) => memory_atomic_notify(stack, store, memarg)?,
+        InstructionType::MemoryAtomicWait32(memarg) => memory_atomic_wait32(stack, store, memarg)?,
+        InstructionType::MemoryAtomicWait64(memarg) => memory_atomic_wait64(stack, store, memarg)?,
+        InstructionType::AtomicFence => atomic_fence()?,
+        InstructionType::I32AtomicLoad(memarg) => i32_atomic_load(stack, store, memarg, 32)?,
+        InstructionType::I64AtomicLoad(memarg) => i64_atomic_load(stack, store, memarg, 64)?,
+        InstructionType::I32AtomicLoad8U(memarg) => i32_atomic_load(stack, store, memarg, 8)?,
+        InstructionType::I32AtomicLoad16U(memarg) => i32_atomic_load(stack, store, memarg, 16)?,
+        InstructionType::I64AtomicLoad8U(memarg) => i64_atomic_load(stack, store, memarg, 8)?,
+        InstructionType::I64AtomicLoad16U(memarg) => i64_atomic_load(stack, store, memarg, 16)?,
+        InstructionType::I64AtomicLoad32U(memarg) => i64_atomic_load(stack, store, memarg, 32)?,
+        InstructionType::I32AtomicStore(memarg) => i32_atomic_store(stack, store, memarg, 32)?,
+        InstructionType::I64AtomicStore(memarg) => i64_atomic_store(stack, store, memarg, 64)?,
+        InstructionType::I32AtomicStore8(memarg) => i32_atomic_store(stack, store, memarg, 8)?,
+        InstructionType::I32AtomicStore16(memarg) => i32_atomic_store(stack, store, memarg, 16)?,
+        InstructionType::I64AtomicStore8(memarg) => i64_atomic_store(stack, store, memarg, 8)?,
+        InstructionType::I64AtomicStore16(memarg) => i64_atomic_store(stack, store, memarg, 16)?,
+        InstructionType::I64AtomicStore32(memarg) => i64_atomic_store(stack, store, memarg, 32)?,
+        InstructionType::I32AtomicRmwAdd(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 32, RmwOp::Add)?
+        }
+        InstructionType::I64AtomicRmwAdd(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 64, RmwOp::Add)?
+        }
+        InstructionType::I32AtomicRmw8AddU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 8, RmwOp::Add)?
+        }
+        InstructionType::I32AtomicRmw16AddU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 16, RmwOp::Add)?
+        }
+        InstructionType::I64AtomicRmw8AddU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 8, RmwOp::Add)?
+        }
+        InstructionType::I64AtomicRmw16AddU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 16, RmwOp::Add)?
+        }
+        InstructionType::I64AtomicRmw32AddU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 32, RmwOp::Add)?
+        }
+        InstructionType::I32AtomicRmwSub(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 32, RmwOp::Sub)?
+        }
+        InstructionType::I64AtomicRmwSub(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 64, RmwOp::Sub)?
+        }
+        InstructionType::I32AtomicRmw8SubU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 8, RmwOp::Sub)?
+        }
+        InstructionType::I32AtomicRmw16SubU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 16, RmwOp::Sub)?
+        }
+        InstructionType::I64AtomicRmw8SubU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 8, RmwOp::Sub)?
+        }
+        InstructionType::I64AtomicRmw16SubU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 16, RmwOp::Sub)?
+        }
+        InstructionType::I64AtomicRmw32SubU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 32, RmwOp::Sub)?
+        }
+        InstructionType::I32AtomicRmwAnd(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 32, RmwOp::And)?
+        }
+        InstructionType::I64AtomicRmwAnd(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 64, RmwOp::And)?
+        }
+        InstructionType::I32AtomicRmw8AndU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 8, RmwOp::And)?
+        }
+        InstructionType::I32AtomicRmw16AndU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 16, RmwOp::And)?
+        }
+        InstructionType::I64AtomicRmw8AndU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 8, RmwOp::And)?
+        }
+        InstructionType::I64AtomicRmw16AndU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 16, RmwOp::And)?
+        }
+        InstructionType::I64AtomicRmw32AndU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 32, RmwOp::And)?
+        }
+        InstructionType::I32AtomicRmwOr(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 32, RmwOp::Or)?
+        }
+        InstructionType::I64AtomicRmwOr(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 64, RmwOp::Or)?
+        }
+        InstructionType::I32AtomicRmw8OrU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 8, RmwOp::Or)?
+        }
+        InstructionType::I32AtomicRmw16OrU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 16, RmwOp::Or)?
+        }
+        InstructionType::I64AtomicRmw8OrU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 8, RmwOp::Or)?
+        }
+        InstructionType::I64AtomicRmw16OrU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 16, RmwOp::Or)?
+        }
+        InstructionType::I64AtomicRmw32OrU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 32, RmwOp::Or)?
+        }
+        InstructionType::I32AtomicRmwXor(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 32, RmwOp::Xor)?
+        }
+        InstructionType::I64AtomicRmwXor(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 64, RmwOp::Xor)?
+        }
+        InstructionType::I32AtomicRmw8XorU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 8, RmwOp::Xor)?
+        }
+        InstructionType::I32AtomicRmw16XorU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 16, RmwOp::Xor)?
+        }
+        InstructionType::I64AtomicRmw8XorU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 8, RmwOp::Xor)?
+        }
+        InstructionType::I64AtomicRmw16XorU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 16, RmwOp::Xor)?
+        }
+        InstructionType::I64AtomicRmw32XorU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 32, RmwOp::Xor)?
+        }
+        InstructionType::I32AtomicRmwXchg(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 32, RmwOp::Xchg)?
+        }
+        InstructionType::I64AtomicRmwXchg(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 64, RmwOp::Xchg)?
+        }
+        InstructionType::I32AtomicRmw8XchgU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 8, RmwOp::Xchg)?
+        }
+        InstructionType::I32AtomicRmw16XchgU(memarg) => {
+            i32_atomic_rmw(stack, store, memarg, 16, RmwOp::Xchg)?
+        }
+        InstructionType::I64AtomicRmw8XchgU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 8, RmwOp::Xchg)?
+        }
+        InstructionType::I64AtomicRmw16XchgU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 16, RmwOp::Xchg)?
+        }
+        InstructionType::I64AtomicRmw32XchgU(memarg) => {
+            i64_atomic_rmw(stack, store, memarg, 32, RmwOp::Xchg)?
+        }
+        InstructionType::I32AtomicRmwCmpxchg(memarg) => {
+            i32_atomic_cmpxchg(stack, store, memarg, 32)?
+        }
+        InstructionType::I64AtomicRmwCmpxchg(memarg) => {
+            i64_atomic_cmpxchg(stack, store, memarg, 64)?
+        }
+        InstructionType::I32AtomicRmw8CmpxchgU(memarg) => {
+            i32_atomic_cmpxchg(stack, store, memarg, 8)?
+        }
+        InstructionType::I32AtomicRmw16CmpxchgU(memarg) => {
+            i32_atomic_cmpxchg(stack, store, memarg, 16)?
+        }
+        InstructionType::I64AtomicRmw8CmpxchgU(memarg) => {
+            i64_atomic_cmpxchg(stack, store, memarg, 8)?
+        }
+        InstructionType::I64AtomicRmw16CmpxchgU(memarg) => {
+            i64_atomic_cmpxchg(stack, store, memarg, 16)?
+        }
+        InstructionType::I64AtomicRmw32CmpxchgU(memarg) => {
+            i64_atomic_cmpxchg(stack, store, memarg, 32)?
+        }
+
         // control instructions
         InstructionType::Nop => {}
         InstructionType::Unreachable => exec_unreachable()?,
-        InstructionType::Block(block_instruction) => {
-            return block(stack, store, block_instruction, execute_instruction);
-        }
-        InstructionType::Loop(loop_instruction) => {
-            return exec_loop(stack, store, loop_instruction, execute_instruction);
-        }
-        InstructionType::IfElse(ifelse_instruction) => {
-            return exec_ifelse(stack, store, ifelse_instruction, execute_instruction);
+        InstructionType::Block(_)
+        | InstructionType::Loop(_)
+        | InstructionType::IfElse(_)
+        | InstructionType::Call(_)
+        | InstructionType::CallIndirect(_)
+        | InstructionType::TryTable(_) => {
+            // executed by execute_instruction
+            return Err(Trap);
         }
         InstructionType::Br(label_idx) => {
-            return exec_br(stack, store, label_idx, execute_instruction);
+            return exec_br(stack, label_idx);
         }
         InstructionType::BrIf(label_idx) => {
-            return exec_brif(stack, store, label_idx, execute_instruction);
+            return exec_brif(stack, label_idx);
         }
         InstructionType::BrTable(brtable_arg) => {
-            return exec_brtable(stack, store, brtable_arg, execute_instruction);
+            return exec_brtable(stack, brtable_arg);
         }
         InstructionType::Return => {
             return exec_return(stack);
         }
-        InstructionType::Call(func_idx) => {
-            return exec_call(stack, store, func_idx, execute_instruction);
+        InstructionType::ReturnCall(func_idx) => {
+            return exec_return_call(stack, store, func_idx);
         }
-        InstructionType::CallIndirect(call_indirect_args) => {
-            return exec_call_indirect(stack, store, call_indirect_args, execute_instruction);
+        InstructionType::ReturnCallIndirect(call_indirect_args) => {
+            return exec_return_call_indirect(stack, store, call_indirect_args);
+        }
+        InstructionType::Throw(tag_idx) => {
+            return exec_throw(stack, store, tag_idx);
+        }
+        InstructionType::ThrowRef => {
+            return exec_throw_ref(stack);
         }
     };
 
@@ -0,0 +1,548 @@
+use std::fmt;
+
+use crate::{
+    entities::{
+        instructions::InstructionType,
+        module::{CodeBody, ExportDescription, ImportDescription, Module},
+        types::{ExternKind, FuncType, GlobalType, IndexType, LimitsType, MutType, ValType},
+    },
+    wat::instruction_text,
+};
+
+/// Summary of what a module contains, rendered as text with `Display` or as JSON
+#[derive(Debug, PartialEq)]
+pub struct ModuleSummary {
+    pub types: Vec<String>,
+    pub imports: Vec<ImportSummary>,
+    pub exports: Vec<ExportSummary>,
+    pub tables: Vec<LimitsSummary>,
+    pub mems: Vec<LimitsSummary>,
+    pub globals: Vec<GlobalSummary>,
+    /// Number of references of every element segment
+    pub elems: Vec<usize>,
+    /// Number of bytes of every data segment
+    pub datas: Vec<usize>,
+    pub start: Option<u32>,
+    pub customs: Vec<String>,
+    pub funcs: Vec<FuncSummary>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImportSummary {
+    pub module: String,
+    pub name: String,
+    pub kind: ExternKind,
+    pub import_type: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExportSummary {
+    pub name: String,
+    pub kind: ExternKind,
+    pub index: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LimitsSummary {
+    /// Element type of a table, index type of a memory
+    pub item_type: String,
+    pub min: u64,
+    pub max: Option<u64>,
+    pub shared: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GlobalSummary {
+    pub global_type: String,
+    pub init: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FuncSummary {
+    /// Index in the function index space, after the imported functions
+    pub index: usize,
+    pub type_idx: u32,
+    /// None when a lazily parsed body can't be decoded
+    pub instructions: Option<usize>,
+}
+
+impl ModuleSummary {
+    pub fn new(module: &Module) -> Self {
+        let imported_funcs = module
+            .imports
+            .iter()
+            .filter(|import| matches!(import.desc, ImportDescription::Func(_)))
+            .count();
+
+        ModuleSummary {
+            types: module.types.iter().map(func_type).collect(),
+            imports: module
+                .imports
+                .iter()
+                .map(|import| {
+                    let (kind, import_type) = match import.desc {
+                        ImportDescription::Func(ref type_idx) => (
+                            ExternKind::Func,
+                            module
+                                .types
+                                .get(type_idx.0 .0 as usize)
+                                .map(func_type)
+                                .unwrap_or_else(|| format!("type {}", type_idx.0 .0)),
+                        ),
+                        ImportDescription::Table(ref table_type) => (
+                            ExternKind::Table,
+                            format!(
+                                "{} {}",
+                                table_type.element_ref_type,
+                                limits(&table_type.limits)
+                            ),
+                        ),
+                        ImportDescription::Mem(ref mem_type) => (
+                            ExternKind::Mem,
+                            format!(
+                                "{} {}{}",
+                                index_type(&mem_type.index_type),
+                                limits(&mem_type.limits),
+                                if mem_type.shared { " shared" } else { "" }
+                            ),
+                        ),
+                        ImportDescription::Global(ref global_type) => {
+                            (ExternKind::Global, global(global_type))
+                        }
+                        ImportDescription::Tag(ref tag_type) => {
+                            (ExternKind::Tag, format!("type {}", tag_type.type_idx.0 .0))
+                        }
+                    };
+
+                    ImportSummary {
+                        module: import.module.0.clone(),
+                        name: import.name.0.clone(),
+                        kind,
+                        import_type,
+                    }
+                })
+                .collect(),
+            exports: module
+                .exports
+                .iter()
+                .map(|export| {
+                    let (kind, index) = match export.desc {
+                        ExportDescription::Func(ref idx) => (ExternKind::Func, idx.0 .0),
+                        ExportDescription::Table(ref idx) => (ExternKind::Table, idx.0 .0),
+                        ExportDescription::Mem(ref idx) => (ExternKind::Mem, idx.0 .0),
+                        ExportDescription::Global(ref idx) => (ExternKind::Global, idx.0 .0),
+                        ExportDescription::Tag(ref idx) => (ExternKind::Tag, idx.0 .0),
+                    };
+
+                    ExportSummary {
+                        name: export.name.0.clone(),
+                        kind,
+                        index,
+                    }
+                })
+                .collect(),
+            tables: module
+                .tables
+                .iter()
+                .map(|table_type| LimitsSummary {
+                    item_type: table_type.element_ref_type.to_string(),
+                    min: table_type.limits.min.0,
+                    max: table_type.limits.max.as_ref().map(|max| max.0),
+                    shared: false,
+                })
+                .collect(),
+            mems: module
+                .mems
+                .iter()
+                .map(|mem_type| LimitsSummary {
+                    item_type: index_type(&mem_type.index_type).into(),
+                    min: mem_type.limits.min.0,
+                    max: mem_type.limits.max.as_ref().map(|max| max.0),
+                    shared: mem_type.shared,
+                })
+                .collect(),
+            globals: module
+                .globals
+                .iter()
+                .map(|g| GlobalSummary {
+                    global_type: global(&g.global_type),
+                    init: g
+                        .init
+                        .instructions
+                        .iter()
+                        .map(instruction_text)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                })
+                .collect(),
+            elems: module.elems.iter().map(|elem| elem.size()).collect(),
+            datas: module
+                .datas
+                .iter()
+                .map(|data| data.clone_data().len())
+                .collect(),
+            start: module.start.as_ref().map(|start| start.func.0 .0),
+            customs: module
+                .customs
+                .iter()
+                .map(|custom| custom.name.clone())
+                .collect(),
+            funcs: module
+                .funcs
+                .iter()
+                .zip(&module.code)
+                .enumerate()
+                .map(|(i, (type_idx, code))| FuncSummary {
+                    index: imported_funcs + i,
+                    type_idx: type_idx.0 .0,
+                    instructions: match code.code {
+                        CodeBody::Decoded(ref code) => {
+                            Some(count_instructions(&code.expression.instructions))
+                        }
+                        CodeBody::Raw(ref raw_code) => raw_code
+                            .decode()
+                            .ok()
+                            .map(|code| count_instructions(&code.expression.instructions)),
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let fields = [
+            (
+                "types",
+                json_array(self.types.iter().map(|t| json_string(t))),
+            ),
+            (
+                "imports",
+                json_array(self.imports.iter().map(|import| {
+                    json_object(&[
+                        ("module", json_string(&import.module)),
+                        ("name", json_string(&import.name)),
+                        ("kind", json_string(&import.kind.to_string())),
+                        ("type", json_string(&import.import_type)),
+                    ])
+                })),
+            ),
+            (
+                "exports",
+                json_array(self.exports.iter().map(|export| {
+                    json_object(&[
+                        ("name", json_string(&export.name)),
+                        ("kind", json_string(&export.kind.to_string())),
+                        ("index", export.index.to_string()),
+                    ])
+                })),
+            ),
+            ("tables", json_array(self.tables.iter().map(json_limits))),
+            ("memories", json_array(self.mems.iter().map(json_limits))),
+            (
+                "globals",
+                json_array(self.globals.iter().map(|g| {
+                    json_object(&[
+                        ("type", json_string(&g.global_type)),
+                        ("init", json_string(&g.init)),
+                    ])
+                })),
+            ),
+            (
+                "elements",
+                json_array(self.elems.iter().map(|size| size.to_string())),
+            ),
+            (
+                "datas",
+                json_array(self.datas.iter().map(|size| size.to_string())),
+            ),
+            ("start", json_option(self.start)),
+            (
+                "custom_sections",
+                json_array(self.customs.iter().map(|name| json_string(name))),
+            ),
+            (
+                "functions",
+                json_array(self.funcs.iter().map(|func| {
+                    json_object(&[
+                        ("index", func.index.to_string()),
+                        ("type", func.type_idx.to_string()),
+                        ("instructions", json_option(func.instructions)),
+                    ])
+                })),
+            ),
+        ];
+
+        json_object(&fields)
+    }
+}
+
+impl fmt::Display for ModuleSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "types:")?;
+        for (i, t) in self.types.iter().enumerate() {
+            writeln!(f, "  {i}: {t}")?;
+        }
+        writeln!(f, "imports:")?;
+        for import in &self.imports {
+            writeln!(
+                f,
+                "  {}.{}: {} {}",
+                import.module, import.name, import.kind, import.import_type
+            )?;
+        }
+        writeln!(f, "exports:")?;
+        for export in &self.exports {
+            writeln!(f, "  {}: {} {}", export.name, export.kind, export.index)?;
+        }
+        writeln!(f, "tables:")?;
+        for (i, table) in self.tables.iter().enumerate() {
+            writeln!(f, "  {i}: {table}")?;
+        }
+        writeln!(f, "memories:")?;
+        for (i, mem) in self.mems.iter().enumerate() {
+            writeln!(f, "  {i}: {mem}")?;
+        }
+        writeln!(f, "globals:")?;
+        for (i, g) in self.globals.iter().enumerate() {
+            writeln!(f, "  {i}: {} = {}", g.global_type, g.init)?;
+        }
+        writeln!(f, "elements:")?;
+        for (i, size) in self.elems.iter().enumerate() {
+            writeln!(f, "  {i}: {size} refs")?;
+        }
+        writeln!(f, "datas:")?;
+        for (i, size) in self.datas.iter().enumerate() {
+            writeln!(f, "  {i}: {size} bytes")?;
+        }
+        match self.start {
+            Some(start) => writeln!(f, "start: func {start}")?,
+            None => writeln!(f, "start: none")?,
+        }
+        writeln!(f, "custom sections:")?;
+        for name in &self.customs {
+            writeln!(f, "  {name}")?;
+        }
+        writeln!(f, "functions:")?;
+        for func in &self.funcs {
+            match func.instructions {
+                Some(count) => writeln!(
+                    f,
+                    "  {}: type {}, {} instructions",
+                    func.index, func.type_idx, count
+                )?,
+                None => writeln!(
+                    f,
+                    "  {}: type {}, malformed body",
+                    func.index, func.type_idx
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for LimitsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} min {}", self.item_type, self.min)?;
+        if let Some(max) = self.max {
+            write!(f, " max {max}")?;
+        }
+        if self.shared {
+            write!(f, " shared")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn func_type(func_type: &FuncType) -> String {
+    format!(
+        "({}) -> ({})",
+        val_types(&func_type.parameters),
+        val_types(&func_type.results)
+    )
+}
+
+fn val_types(val_types: &[ValType]) -> String {
+    val_types
+        .iter()
+        .map(ValType::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn global(global_type: &GlobalType) -> String {
+    match global_type.mut_type {
+        MutType::Const => global_type.val_type.to_string(),
+        MutType::Var => format!("mut {}", global_type.val_type),
+    }
+}
+
+fn limits(limits: &LimitsType) -> String {
+    match limits.max {
+        Some(ref max) => format!("min {} max {}", limits.min.0, max.0),
+        None => format!("min {}", limits.min.0),
+    }
+}
+
+fn index_type(index_type: &IndexType) -> &'static str {
+    match index_type {
+        IndexType::I32 => "i32",
+        IndexType::I64 => "i64",
+    }
+}
+
+// Instructions allowed in constant expressions
+// Nested instructions of blocks are counted too
+fn count_instructions(instructions: &[InstructionType]) -> usize {
+    instructions
+        .iter()
+        .map(|instruction| {
+            1 + match instruction {
+                InstructionType::Block(block) => count_instructions(&block.instructions),
+                InstructionType::Loop(block) => count_instructions(&block.instructions),
+                InstructionType::IfElse(block) => {
+                    count_instructions(&block.if_instructions)
+                        + count_instructions(&block.else_instructions)
+                }
+                InstructionType::TryTable(block) => count_instructions(&block.instructions),
+                _ => 0,
+            }
+        })
+        .sum()
+}
+
+fn json_limits(limits: &LimitsSummary) -> String {
+    json_object(&[
+        ("type", json_string(&limits.item_type)),
+        ("min", limits.min.to_string()),
+        ("max", json_option(limits.max)),
+        ("shared", limits.shared.to_string()),
+    ])
+}
+
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{}:{}", json_string(name), value))
+        .collect();
+
+    format!("{{{}}}", fields.join(","))
+}
+
+fn json_array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
+
+fn json_option(value: Option<impl ToString>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "null".into())
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::binary::{module_parser::ModuleParser, parse_trait::ParseBin};
+
+    #[test]
+    fn test_inspect_module() {
+        let wasm = [
+            b"\0asm\x01\0\0\0".as_slice(),
+            // type section, [i32] -> [i32]
+            &[0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F],
+            // import section, "env" "g" const i32 global
+            &[
+                0x02, 0x0A, 0x01, 0x03, b'e', b'n', b'v', 0x01, b'g', 0x03, 0x7F, 0x00,
+            ],
+            // function section
+            &[0x03, 0x02, 0x01, 0x00],
+            // memory section, min 1 max 2
+            &[0x05, 0x04, 0x01, 0x01, 0x01, 0x02],
+            // global section, mut i64 = 7
+            &[0x06, 0x06, 0x01, 0x7E, 0x01, 0x42, 0x07, 0x0B],
+            // export section, "f" func 0
+            &[0x07, 0x05, 0x01, 0x01, b'f', 0x00, 0x00],
+            // code section, block with local.get 0 inside
+            &[
+                0x0A, 0x09, 0x01, 0x07, 0x00, 0x02, 0x7F, 0x20, 0x00, 0x0B, 0x0B,
+            ],
+            // custom section "name\"s"
+            &[0x00, 0x08, 0x06, b'n', b'a', b'm', b'e', b'"', b's', 0xAA],
+        ]
+        .concat();
+        let (_, module) = ModuleParser::parse(&wasm).unwrap();
+
+        let summary = ModuleSummary::new(&module);
+
+        assert_eq!(
+            summary.to_string(),
+            [
+                "types:",
+                "  0: (i32) -> (i32)",
+                "imports:",
+                "  env.g: global i32",
+                "exports:",
+                "  f: func 0",
+                "tables:",
+                "memories:",
+                "  0: i32 min 1 max 2",
+                "globals:",
+                "  0: mut i64 = i64.const 7",
+                "elements:",
+                "datas:",
+                "start: none",
+                "custom sections:",
+                "  name\"s",
+                "functions:",
+                "  0: type 0, 2 instructions",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            summary.to_json(),
+            concat!(
+                r#"{"types":["(i32) -> (i32)"],"#,
+                r#""imports":[{"module":"env","name":"g","kind":"global","type":"i32"}],"#,
+                r#""exports":[{"name":"f","kind":"func","index":0}],"tables":[],"#,
+                r#""memories":[{"type":"i32","min":1,"max":2,"shared":false}],"#,
+                r#""globals":[{"type":"mut i64","init":"i64.const 7"}],"elements":[],"#,
+                r#""datas":[],"start":null,"custom_sections":["name\"s"],"#,
+                r#""functions":[{"index":0,"type":0,"instructions":2}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_json_string_escaping() {
+        assert_eq!(json_string(r#"a"b\c"#), r#""a\"b\\c""#);
+        assert_eq!(json_string("line\nnext"), r#""line\nnext""#);
+        // other control characters are written as unicode escapes
+        assert_eq!(
+            json_string("\t\r\u{1}\u{7f}"),
+            r#""\u0009\u000d\u0001\u007f""#
+        );
+        // names are UTF-8, printable characters beyond ASCII are kept
+        assert_eq!(json_string("naïve ✓"), r#""naïve ✓""#);
+        assert_eq!(json_string(""), r#""""#);
+    }
+}
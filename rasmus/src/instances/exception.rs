@@ -0,0 +1,9 @@
+use super::value::Val;
+use crate::address::TagAddr;
+
+/// Exception created by throw, referenced from the operand stack by exnref
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExnInst {
+    pub tag: TagAddr,
+    pub fields: Vec<Val>,
+}
@@ -13,4 +13,5 @@ pub enum ExternVal {
     Table(TableAddr),
     Mem(MemAddr),
     Global(GlobalAddr),
+    Tag(TagAddr),
 }
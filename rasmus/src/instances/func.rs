@@ -1,14 +1,24 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{OnceCell, RefCell},
+    fmt,
+    rc::Rc,
+};
 
 use crate::{
+    binary::syntax_error::SyntaxError,
     execute::{executor::ExitType, pop_values_original_order},
     result::{RResult, Trap},
+    validation::{
+        context::ValidationContext,
+        module::validate_func_code,
+        validation_error::{ModuleValidationError, ValidationError},
+    },
 };
 
-use super::{module::ModuleInst, stack::Stack, store::Store};
+use super::{module::ModuleInst, stack::Stack, store::Store, value::Val};
 use crate::entities::{
-    module::InstructionType,
-    types::{Func, FuncType},
+    module::{InstructionType, RawCode},
+    types::{Func, FuncType, TypeIdx, ValType},
 };
 
 #[derive(Clone, Debug)]
@@ -50,7 +60,102 @@ impl FuncInst {
 pub struct FuncInstLocal {
     pub func_type: FuncType,
     pub module: Rc<RefCell<ModuleInst>>,
-    pub code: Func,
+    pub code: FuncCode,
+}
+
+/// Code of a local function, lazy bodies are decoded and validated on the first call
+#[derive(Clone, Debug)]
+pub enum FuncCode {
+    Decoded(Func),
+    Lazy(Rc<LazyFunc>),
+}
+
+impl FuncCode {
+    pub fn type_idx(&self) -> &TypeIdx {
+        match self {
+            FuncCode::Decoded(func) => &func.func_type,
+            FuncCode::Lazy(lazy_func) => &lazy_func.func_type,
+        }
+    }
+
+    pub fn get(&self) -> Result<&Func, &LazyFuncError> {
+        match self {
+            FuncCode::Decoded(func) => Ok(func),
+            FuncCode::Lazy(lazy_func) => lazy_func.get(),
+        }
+    }
+}
+
+/// Reason why a lazy function body can't be run
+#[derive(Debug, Clone, PartialEq)]
+pub enum LazyFuncError {
+    Malformed(SyntaxError),
+    Invalid(ModuleValidationError),
+}
+
+impl fmt::Display for LazyFuncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LazyFuncError::Malformed(error) => write!(f, "malformed function body, {error}"),
+            LazyFuncError::Invalid(error) => write!(f, "invalid function body, {error}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LazyFunc {
+    func_type: TypeIdx,
+    raw_code: RawCode,
+    ctx: Rc<ValidationContext>,
+    // index in the function index space and export name, for validation errors
+    func_idx: usize,
+    name: Option<String>,
+    // the error is kept as well, so the body is decoded and validated only once
+    func: OnceCell<Result<Func, LazyFuncError>>,
+}
+
+impl LazyFunc {
+    pub fn new(
+        func_type: TypeIdx,
+        raw_code: RawCode,
+        ctx: Rc<ValidationContext>,
+        func_idx: usize,
+        name: Option<String>,
+    ) -> Self {
+        LazyFunc {
+            func_type,
+            raw_code,
+            ctx,
+            func_idx,
+            name,
+            func: OnceCell::new(),
+        }
+    }
+
+    pub fn is_decoded(&self) -> bool {
+        self.func.get().is_some()
+    }
+
+    pub fn get(&self) -> Result<&Func, &LazyFuncError> {
+        self.func.get_or_init(|| self.decode()).as_ref()
+    }
+
+    fn decode(&self) -> Result<Func, LazyFuncError> {
+        let code = self.raw_code.decode().map_err(LazyFuncError::Malformed)?;
+        let func_type = self
+            .ctx
+            .types
+            .get(self.func_type.0 .0 as usize)
+            .ok_or(LazyFuncError::Invalid(ValidationError::TypeNotFound.into()))?;
+        validate_func_code(&self.ctx, func_type, &code, self.func_idx).map_err(|mut error| {
+            if let Some(ref mut func) = error.func {
+                func.name = self.name.clone();
+            }
+            LazyFuncError::Invalid(error)
+        })?;
+
+        Ok(code.to_func(self.func_type.clone()))
+    }
 }
 
 impl FuncInstLocal {
@@ -61,14 +166,35 @@ impl FuncInstLocal {
         execute_instruction_fn: impl FnOnce(&InstructionType, &mut Stack, &mut Store) -> RResult<ExitType>
             + Copy,
     ) -> RResult<ExitType> {
-        for ref instruction in &self.code.body.instructions {
-            if execute_instruction_fn(instruction, stack, store)? == ExitType::Returned {
-                return Ok(ExitType::Completed);
+        let mut label_dropped = false;
+
+        let func = self
+            .code
+            .get()
+            .map_err(|error| Trap::invalid_function(error.clone()))?;
+
+        for ref instruction in &func.body.instructions {
+            match execute_instruction_fn(instruction, stack, store)? {
+                ExitType::Completed => {}
+                ExitType::Returned => return Ok(ExitType::Completed),
+                // br to the function body label has already dropped it
+                ExitType::Branch(0) => {
+                    label_dropped = true;
+                    break;
+                }
+                ExitType::Branch(_) => return Err(Trap),
+                ExitType::Thrown(exn_addr) => {
+                    stack.unwind_frame().ok_or(Trap)?;
+                    return Ok(ExitType::Thrown(exn_addr));
+                }
+                tail_call => return Ok(tail_call),
             }
         }
 
         let result = pop_values_original_order(stack, self.func_type.results.len())?;
-        stack.pop_label().ok_or(Trap)?;
+        if !label_dropped {
+            stack.pop_label().ok_or(Trap)?;
+        }
         stack.pop_frame().ok_or(Trap)?;
 
         for value in result {
@@ -86,10 +212,44 @@ pub struct HostFunc {
 }
 
 impl HostFunc {
-    pub fn invoke(&self, _stack: &mut Stack, _store: &mut Store) -> RResult<ExitType> {
-        todo!()
+    // Host code is run without a frame, arguments are taken from the stack and results are pushed back
+    pub fn invoke(&self, stack: &mut Stack, store: &mut Store) -> RResult<ExitType> {
+        let args = pop_values_original_order(stack, self.func_type.parameters.len())?;
+        let results = (self.host_code.0)(store, args)?;
+
+        let result_types: Vec<ValType> = results.iter().map(Val::get_type).collect();
+        if result_types != self.func_type.results {
+            return Err(Trap);
+        }
+
+        for value in results {
+            stack.push_value(value);
+        }
+
+        Ok(ExitType::Completed)
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct HostCode;
+type HostFn = dyn Fn(&mut Store, Vec<Val>) -> RResult<Vec<Val>>;
+
+/// Function implemented by the embedder, receives the arguments and returns the results
+#[derive(Clone)]
+pub struct HostCode(Rc<HostFn>);
+
+impl HostCode {
+    pub fn new(code: impl Fn(&mut Store, Vec<Val>) -> RResult<Vec<Val>> + 'static) -> Self {
+        HostCode(Rc::new(code))
+    }
+}
+
+impl std::fmt::Debug for HostCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HostCode")
+    }
+}
+
+impl PartialEq for HostCode {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,353 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::address::*;
+
+use super::func::FuncInst;
+use super::module::ModuleInst;
+use super::ref_inst::RefInst;
+use super::stack::{Stack, StackEntry};
+use super::store::Store;
+use super::value::Val;
+
+/// Entries the host holds handles to, e.g. `Table`, `Global` or entries allocated by the host
+/// through the `Store`. They are roots of every collection until their handle is released.
+#[derive(Default)]
+pub struct HostRoots {
+    pub funcs: HashSet<FuncAddr>,
+    pub tables: HashSet<TableAddr>,
+    pub mems: HashSet<MemAddr>,
+    pub globals: HashSet<GlobalAddr>,
+    pub externs: HashSet<ExternAddr>,
+}
+
+/// Entries reachable from the roots of a collection
+#[derive(Default)]
+struct Reachable {
+    modules: HashSet<*const RefCell<ModuleInst>>,
+    funcs: HashSet<FuncAddr>,
+    tables: HashSet<TableAddr>,
+    mems: HashSet<MemAddr>,
+    globals: HashSet<GlobalAddr>,
+    elems: HashSet<ElemAddr>,
+    datas: HashSet<DataAddr>,
+    tags: HashSet<TagAddr>,
+    exns: HashSet<ExnAddr>,
+    externs: HashSet<ExternAddr>,
+}
+
+enum Pending {
+    Module(Rc<RefCell<ModuleInst>>),
+    Table(TableAddr),
+    Global(GlobalAddr),
+    Ref(RefInst),
+}
+
+impl Store {
+    /// Removes entries which are not reachable from the instances, the values, the stack or the host roots.
+    /// Instances and values kept by the host must be passed as roots, otherwise they become dangling.
+    /// Returns the number of removed entries.
+    pub fn collect_garbage(
+        &mut self,
+        instances: &[Rc<RefCell<ModuleInst>>],
+        values: &[Val],
+        stack: &Stack,
+    ) -> usize {
+        let mut pending: Vec<Pending> = instances.iter().cloned().map(Pending::Module).collect();
+        for value in values {
+            pending.extend(as_ref(value).map(Pending::Ref));
+        }
+        pending.extend(
+            self.host_roots
+                .funcs
+                .iter()
+                .map(|addr| Pending::Ref(RefInst::Func(*addr))),
+        );
+        pending.extend(self.host_roots.tables.iter().copied().map(Pending::Table));
+        pending.extend(self.host_roots.globals.iter().copied().map(Pending::Global));
+        pending.extend(
+            self.host_roots
+                .externs
+                .iter()
+                .map(|addr| Pending::Ref(RefInst::Extern(*addr))),
+        );
+        for entry in stack.entries() {
+            match entry {
+                StackEntry::Value(value) => pending.extend(as_ref(value).map(Pending::Ref)),
+                StackEntry::Frame(frame) => {
+                    pending.push(Pending::Module(frame.module.clone()));
+                    for value in frame.locals.borrow().iter() {
+                        pending.extend(as_ref(value).map(Pending::Ref));
+                    }
+                }
+                StackEntry::Label(_) => {}
+            }
+        }
+
+        let mut reachable = self.mark(pending);
+        reachable.mems.extend(self.host_roots.mems.iter().copied());
+
+        let before = self.count_entries();
+        self.funcs.retain(|addr, _| reachable.funcs.contains(&addr));
+        self.tables
+            .retain(|addr, _| reachable.tables.contains(&addr));
+        self.mems.retain(|addr, _| reachable.mems.contains(&addr));
+        self.globals
+            .retain(|addr, _| reachable.globals.contains(&addr));
+        self.elems.retain(|addr, _| reachable.elems.contains(&addr));
+        self.datas.retain(|addr, _| reachable.datas.contains(&addr));
+        self.tags.retain(|addr, _| reachable.tags.contains(&addr));
+        self.exns.retain(|addr, _| reachable.exns.contains(&addr));
+        self.externs
+            .retain(|addr, _| reachable.externs.contains(&addr));
+
+        before - self.count_entries()
+    }
+
+    fn count_entries(&self) -> usize {
+        self.funcs.len()
+            + self.tables.len()
+            + self.mems.len()
+            + self.globals.len()
+            + self.elems.len()
+            + self.datas.len()
+            + self.tags.len()
+            + self.exns.len()
+            + self.externs.len()
+    }
+
+    fn mark(&self, mut pending: Vec<Pending>) -> Reachable {
+        let mut reachable = Reachable::default();
+
+        while let Some(item) = pending.pop() {
+            match item {
+                Pending::Module(module_inst) => {
+                    if !reachable.modules.insert(Rc::as_ptr(&module_inst)) {
+                        continue;
+                    }
+
+                    let module_inst = module_inst.borrow();
+                    for func_addr in &module_inst.funcaddrs {
+                        pending.push(Pending::Ref(RefInst::Func(*func_addr)));
+                    }
+                    pending.extend(module_inst.tableaddrs.iter().copied().map(Pending::Table));
+                    reachable.mems.extend(module_inst.memaddrs.iter().copied());
+                    pending.extend(module_inst.globaladdrs.iter().copied().map(Pending::Global));
+                    for elem_addr in &module_inst.elemaddrs {
+                        if reachable.elems.insert(*elem_addr) {
+                            if let Some(Some(elem)) = self.elems.get(*elem_addr) {
+                                pending.extend(elem.elem.iter().cloned().map(Pending::Ref));
+                            }
+                        }
+                    }
+                    reachable
+                        .datas
+                        .extend(module_inst.dataaddrs.iter().copied());
+                    reachable.tags.extend(module_inst.tagaddrs.iter().copied());
+                }
+                Pending::Table(table_addr) => {
+                    if !reachable.tables.insert(table_addr) {
+                        continue;
+                    }
+
+                    if let Some(table) = self.tables.get(table_addr) {
+                        pending.extend(table.elem.iter().cloned().map(Pending::Ref));
+                    }
+                }
+                Pending::Global(global_addr) => {
+                    if !reachable.globals.insert(global_addr) {
+                        continue;
+                    }
+
+                    if let Some(global) = self.globals.get(global_addr) {
+                        pending.extend(as_ref(&global.value).map(Pending::Ref));
+                    }
+                }
+                Pending::Ref(RefInst::Func(func_addr)) => {
+                    if !reachable.funcs.insert(func_addr) {
+                        continue;
+                    }
+
+                    if let Some(FuncInst::FuncInst(func)) = self.funcs.get(func_addr) {
+                        pending.push(Pending::Module(func.module.clone()));
+                    }
+                }
+                Pending::Ref(RefInst::Exn(exn_addr)) => {
+                    if !reachable.exns.insert(exn_addr) {
+                        continue;
+                    }
+
+                    if let Some(exn) = self.exns.get(exn_addr) {
+                        reachable.tags.insert(exn.tag);
+                        for value in &exn.fields {
+                            pending.extend(as_ref(value).map(Pending::Ref));
+                        }
+                    }
+                }
+                Pending::Ref(RefInst::Extern(extern_addr)) => {
+                    reachable.externs.insert(extern_addr);
+                }
+                Pending::Ref(RefInst::Null(_)) => {}
+            }
+        }
+
+        reachable
+    }
+}
+
+fn as_ref(value: &Val) -> Option<RefInst> {
+    match value {
+        Val::Ref(reference) => Some(reference.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::controller::run_func;
+    use crate::entities::types::{GlobalType, MutType, RefType, ValType};
+    use crate::entities::{
+        module::{ExportDescription, ExportType, Module},
+        types::{FuncType, IndexType, LimitsType, MemIdx, MemType, NameType, U32Type, U64Type},
+    };
+    use crate::entities::{
+        module::{ImportDescription, InstructionType},
+        types::{FuncIdx, I32Type, TypeIdx},
+    };
+    use crate::instances::{
+        func::HostCode, global::Global, module::ExternalDependency, module::ModuleInst,
+        ref_inst::RefInst, stack::Stack, store::Store, value::Val,
+    };
+    use crate::test_utils::{func_export, import, mem_type, memarg, module_with_run_func};
+    use crate::validation::validated_module::ValidatedModule;
+    use std::rc::Rc;
+
+    fn mem_module() -> Module {
+        Module {
+            types: vec![FuncType {
+                parameters: vec![],
+                results: vec![],
+            }],
+            mems: vec![MemType {
+                limits: LimitsType {
+                    min: U64Type(1),
+                    max: None,
+                },
+                index_type: IndexType::I32,
+                shared: false,
+            }],
+            exports: vec![ExportType {
+                name: NameType("mem".into()),
+                desc: ExportDescription::Mem(MemIdx(U32Type(0))),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_collect_unreachable_instances() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let module = ValidatedModule::new(mem_module()).unwrap();
+
+        let kept = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let dropped = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let kept_mem = kept.borrow().memaddrs[0];
+        let dropped_mem = dropped.borrow().memaddrs[0];
+        drop(dropped);
+
+        assert_eq!(
+            store.collect_garbage(std::slice::from_ref(&kept), &[], &stack),
+            1
+        );
+        assert!(store.mems.get(kept_mem).is_some());
+        assert!(store.mems.get(dropped_mem).is_none());
+
+        let reused = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let reused_mem = reused.borrow().memaddrs[0];
+
+        assert_eq!(reused_mem.index, dropped_mem.index);
+        assert!(store.mems.get(dropped_mem).is_none());
+        assert!(store.mems.get(reused_mem).is_some());
+    }
+
+    #[test]
+    fn test_host_roots() {
+        let mut store = Store::new();
+        let stack = Stack::new();
+        let object = store.allocate_extern(Rc::new(1));
+        let global_type = GlobalType {
+            mut_type: MutType::Var,
+            val_type: ValType::RefType(RefType::ExternRef),
+        };
+        let global =
+            Global::allocate(&mut store, global_type, Val::Ref(RefInst::Extern(object))).unwrap();
+        store.release_extern(object);
+
+        assert_eq!(store.collect_garbage(&[], &[], &stack), 0);
+        assert!(store.get_extern::<i32>(object).is_some());
+
+        global.release(&mut store);
+
+        assert_eq!(store.collect_garbage(&[], &[], &stack), 2);
+        assert!(store.globals.get(global.addr()).is_none());
+        assert!(store.get_extern::<i32>(object).is_none());
+    }
+
+    #[test]
+    fn test_host_allocations_are_roots() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut module = module_with_run_func(
+            vec![
+                import("env", "get", ImportDescription::Func(TypeIdx(U32Type(0)))),
+                import("env", "mem", ImportDescription::Mem(mem_type(1))),
+            ],
+            vec![],
+            vec![
+                InstructionType::I32Const(I32Type(0)),
+                InstructionType::I32Load(memarg(2, 0)),
+                InstructionType::Call(FuncIdx(U32Type(0))),
+                InstructionType::I32Add,
+            ],
+        );
+        module.exports = vec![func_export("run", 1)];
+        let module = ValidatedModule::new(module).unwrap();
+
+        let func_type = module.types[0].clone();
+        let func_addr = store.allocate_host_func(
+            func_type.clone(),
+            HostCode::new(|_, _| Ok(vec![Val::I32(37)])),
+        );
+        let mem_addr = store.allocate_mem(mem_type(1)).unwrap();
+        store.mems[mem_addr].data_mut()[0] = 5;
+
+        // nothing references the entries before they are linked
+        assert_eq!(store.collect_garbage(&[], &[], &stack), 0);
+
+        let externals = vec![
+            ExternalDependency::Func {
+                func_addr,
+                func_type,
+            },
+            ExternalDependency::Mem {
+                mem_addr,
+                mem_type: mem_type(1),
+            },
+        ];
+        let module_inst =
+            ModuleInst::instantiate(&mut store, &mut stack, &module, externals).unwrap();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store);
+        assert_eq!(result.unwrap(), vec![Val::I32(42)]);
+
+        store.release_func(func_addr);
+        store.release_mem(mem_addr);
+        // the frame left by run_func keeps the instance alive
+        stack = Stack::new();
+
+        assert_eq!(store.collect_garbage(&[], &[], &stack), 3);
+        assert!(store.funcs.get(func_addr).is_none());
+        assert!(store.mems.get(mem_addr).is_none());
+    }
+}
@@ -1,7 +1,249 @@
+use super::export::ExternVal;
+use super::module::{ExternalDependency, ModuleInst};
+use super::store::Store;
 use super::value::Val;
-use crate::entities::types::GlobalType;
+use crate::address::GlobalAddr;
+use crate::entities::types::{GlobalType, MutType};
+use crate::result::HandleError;
 
 pub struct GlobalInst {
     pub global_type: GlobalType,
     pub value: Val,
 }
+
+/// Handle of a global for embedders.
+/// Values are checked against the type of the global, only mutable globals can be set.
+/// The global is a root of garbage collection until the handle is released, copies share the root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Global {
+    addr: GlobalAddr,
+}
+
+impl Global {
+    pub fn new(store: &mut Store, addr: GlobalAddr) -> Self {
+        store.host_roots.globals.insert(addr);
+
+        Global { addr }
+    }
+
+    /// Allocates a global owned by the host, it can be provided to modules as an import
+    pub fn allocate(
+        store: &mut Store,
+        global_type: GlobalType,
+        value: Val,
+    ) -> Result<Self, HandleError> {
+        check_type(&global_type, &value)?;
+
+        let addr = store.allocate_global(global_type, value);
+
+        Ok(Global::new(store, addr))
+    }
+
+    /// Global exported by the module instance with the name
+    pub fn from_export(store: &mut Store, module_inst: &ModuleInst, name: &str) -> Option<Self> {
+        module_inst
+            .exports
+            .iter()
+            .find(|export| export.name.0 == name)
+            .and_then(|export| match export.value {
+                ExternVal::Global(addr) => Some(Global::new(store, addr)),
+                _ => None,
+            })
+    }
+
+    /// The global is collected once it isn't reachable from instances anymore
+    pub fn release(self, store: &mut Store) {
+        store.host_roots.globals.remove(&self.addr);
+    }
+
+    pub fn addr(&self) -> GlobalAddr {
+        self.addr
+    }
+
+    pub fn get_type(&self, store: &Store) -> Result<GlobalType, HandleError> {
+        Ok(self.global(store)?.global_type.clone())
+    }
+
+    pub fn get(&self, store: &Store) -> Result<Val, HandleError> {
+        Ok(self.global(store)?.value.clone())
+    }
+
+    pub fn set(&self, store: &mut Store, value: Val) -> Result<(), HandleError> {
+        let global = store
+            .globals
+            .get_mut(self.addr)
+            .ok_or(HandleError::UnknownEntry)?;
+        if global.global_type.mut_type != MutType::Var {
+            return Err(HandleError::ImmutableGlobal);
+        }
+        check_type(&global.global_type, &value)?;
+
+        global.value = value;
+
+        Ok(())
+    }
+
+    /// Dependency to satisfy a global import of a module with this global
+    pub fn to_external(self, store: &Store) -> Result<ExternalDependency, HandleError> {
+        Ok(ExternalDependency::Global {
+            global_addr: self.addr,
+            global_type: self.get_type(store)?,
+        })
+    }
+
+    fn global<'s>(&self, store: &'s Store) -> Result<&'s GlobalInst, HandleError> {
+        store
+            .globals
+            .get(self.addr)
+            .ok_or(HandleError::UnknownEntry)
+    }
+}
+
+fn check_type(global_type: &GlobalType, value: &Val) -> Result<(), HandleError> {
+    let actual = value.get_type();
+
+    if actual != global_type.val_type {
+        return Err(HandleError::TypeMismatch {
+            expected: global_type.val_type.clone(),
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::controller::run_func;
+    use crate::entities::{
+        module::{
+            CodeType, ExportDescription, ExportType, ExpressionType, FuncCodeType,
+            Global as ModuleGlobal, ImportDescription, ImportType, InstructionType, Module,
+        },
+        types::{
+            FuncIdx, FuncType, GlobalIdx, I32Type, NameType, NumType, TypeIdx, U32Type, ValType,
+        },
+    };
+    use crate::instances::stack::Stack;
+    use crate::validation::validated_module::ValidatedModule;
+
+    fn i32_global_type(mut_type: MutType) -> GlobalType {
+        GlobalType {
+            mut_type,
+            val_type: ValType::NumType(NumType::I32),
+        }
+    }
+
+    // $run adds the imported "step" to the exported "count"
+    fn counter_module() -> Module {
+        Module {
+            types: vec![FuncType {
+                parameters: vec![],
+                results: vec![],
+            }],
+            imports: vec![ImportType {
+                module: NameType("env".into()),
+                name: NameType("step".into()),
+                desc: ImportDescription::Global(i32_global_type(MutType::Const)),
+            }],
+            funcs: vec![TypeIdx(U32Type(0))],
+            globals: vec![ModuleGlobal {
+                global_type: i32_global_type(MutType::Var),
+                init: ExpressionType::new(vec![InstructionType::I32Const(I32Type(0))]),
+            }],
+            exports: vec![
+                ExportType {
+                    name: NameType("run".into()),
+                    desc: ExportDescription::Func(FuncIdx(U32Type(0))),
+                },
+                ExportType {
+                    name: NameType("count".into()),
+                    desc: ExportDescription::Global(GlobalIdx(U32Type(1))),
+                },
+            ],
+            code: vec![CodeType {
+                size: U32Type(0),
+                code: FuncCodeType {
+                    locals: vec![],
+                    expression: ExpressionType::new(vec![
+                        InstructionType::GlobalGet(GlobalIdx(U32Type(1))),
+                        InstructionType::GlobalGet(GlobalIdx(U32Type(0))),
+                        InstructionType::I32Add,
+                        InstructionType::GlobalSet(GlobalIdx(U32Type(1))),
+                    ]),
+                }
+                .into(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_host_and_exported_globals() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let module = ValidatedModule::new(counter_module()).unwrap();
+
+        assert_eq!(
+            Global::allocate(&mut store, i32_global_type(MutType::Const), Val::I64(5)),
+            Err(HandleError::TypeMismatch {
+                expected: ValType::NumType(NumType::I32),
+                actual: ValType::NumType(NumType::I64),
+            })
+        );
+
+        let step =
+            Global::allocate(&mut store, i32_global_type(MutType::Const), Val::I32(5)).unwrap();
+        let externals = vec![step.to_external(&store).unwrap()];
+        let module_inst =
+            ModuleInst::instantiate(&mut store, &mut stack, &module, externals).unwrap();
+        let count = Global::from_export(&mut store, &module_inst.borrow(), "count").unwrap();
+
+        run_func(
+            module_inst.clone(),
+            &module,
+            "run",
+            vec![],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+        assert_eq!(count.get(&store).ok(), Some(Val::I32(5)));
+
+        assert_eq!(
+            step.set(&mut store, Val::I32(1)),
+            Err(HandleError::ImmutableGlobal)
+        );
+        assert_eq!(
+            count.set(&mut store, Val::I64(1)),
+            Err(HandleError::TypeMismatch {
+                expected: ValType::NumType(NumType::I32),
+                actual: ValType::NumType(NumType::I64),
+            })
+        );
+        count.set(&mut store, Val::I32(10)).unwrap();
+
+        run_func(
+            module_inst.clone(),
+            &module,
+            "run",
+            vec![],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+        assert_eq!(count.get(&store), Ok(Val::I32(15)));
+
+        // released globals are collected once no instance refers to them
+        step.release(&mut store);
+        count.release(&mut store);
+        drop(module_inst);
+        store.collect_garbage(&[], &[], &Stack::new());
+        assert_eq!(step.get(&store), Err(HandleError::UnknownEntry));
+        assert_eq!(
+            count.set(&mut store, Val::I32(1)),
+            Err(HandleError::UnknownEntry)
+        );
+    }
+}
@@ -0,0 +1,6 @@
+/// Lets the embedder restrict growth of store entries beyond their declared limits
+pub trait ResourceLimiter {
+    /// Called before a table grows from `current` to `desired` entries,
+    /// returning false makes the growth fail without changing the table
+    fn table_growing(&mut self, current: u32, desired: u32, maximum: Option<u64>) -> bool;
+}
@@ -1,55 +1,324 @@
+use std::collections::{HashSet, VecDeque};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
 use super::value::Val;
-use crate::entities::types::{Byte, LimitsType, MemType, U32Type};
+use crate::entities::types::{Byte, IndexType, LimitsType, MemType, U64Type};
 use crate::result::{RResult, Trap};
 use crate::validation::types_validation::is_memory_type_valid;
 
+/// Memory instance.
+/// Bytes of a shared memory live behind an `Arc` so that it can be handed
+/// to a `Store` running on another OS thread with `MemInst::share`.
 pub struct MemInst {
-    pub mem_type: MemType,
-    pub data: Vec<Byte>,
+    // declared type, the current size is kept with the bytes
+    mem_type: MemType,
+    data: MemData,
+}
+
+enum MemData {
+    // accessed without locking, only through the store owning it
+    Owned(Vec<Byte>),
+    Shared(Arc<SharedMem>),
+}
+
+// State every handle of a shared memory sees, its size is the length of `data`
+struct SharedMem {
+    data: Mutex<Vec<Byte>>,
+    waiters: WaitQueue,
+}
+
+/// Bytes of a memory, a shared memory stays locked while they are borrowed
+pub enum MemBytes<'a, B> {
+    Owned(B),
+    Shared(MutexGuard<'a, Vec<Byte>>),
+}
+
+// Threads suspended by memory.atomic.wait* on this memory
+#[derive(Default)]
+struct WaitQueue {
+    state: Mutex<WaitQueueState>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct WaitQueueState {
+    next_ticket: u64,
+    waiting: VecDeque<(usize, u64)>,
+    notified: HashSet<u64>,
+}
+
+pub enum WaitResult {
+    Ok,
+    NotEqual,
+    TimedOut,
 }
 
 impl MemInst {
     pub const PAGE_SIZE: usize = 2usize.pow(16);
+    /// Maximum number of pages of a 32-bit memory, its whole index space (4 GiB)
+    pub const MAX_PAGES: u64 = 2u64.pow(16);
+    /// Implementation limit on the number of pages of a memory64 (256 TiB),
+    /// the index space allows 2^48 pages whose size doesn't fit into 64 bits
+    pub const MAX_PAGES_64: u64 = 2u64.pow(32);
 
-    #[inline]
-    #[allow(dead_code)]
-    pub fn grow(&mut self, n_val: &Val) -> RResult<()> {
-        if let Val::I32(n) = n_val {
-            return self.grow_n(*n);
-        }
+    /// Fails when the minimum size exceeds the maximum number of pages or can't be allocated
+    pub fn new(mem_type: MemType) -> RResult<MemInst> {
+        let size = Self::byte_size(mem_type.limits.min.0, &mem_type.index_type)?;
+        let mut data = Vec::new();
+        data.try_reserve_exact(size).map_err(|_| Trap)?;
+        data.resize(size, 0x00);
 
-        Err(Trap)
+        let data = match mem_type.shared {
+            true => MemData::Shared(Arc::new(SharedMem {
+                data: Mutex::new(data),
+                waiters: WaitQueue::default(),
+            })),
+            false => MemData::Owned(data),
+        };
+
+        Ok(MemInst { mem_type, data })
     }
 
-    pub fn grow_n(&mut self, n: u32) -> RResult<()> {
-        let num = n as usize;
-        if self.data.len().checked_rem(Self::PAGE_SIZE).is_some() {
+    fn byte_size(pages: u64, index_type: &IndexType) -> RResult<usize> {
+        let max_pages = match index_type {
+            IndexType::I32 => Self::MAX_PAGES,
+            IndexType::I64 => Self::MAX_PAGES_64,
+        };
+        if pages > max_pages {
             return Err(Trap);
         }
 
-        let len = num + self.data.len() / Self::PAGE_SIZE;
+        usize::try_from(pages)
+            .ok()
+            .and_then(|pages| pages.checked_mul(Self::PAGE_SIZE))
+            .ok_or(Trap)
+    }
+
+    /// Creates another handle to the same shared memory.
+    /// Returns `None` for memories which are not shared.
+    pub fn share(&self) -> Option<MemInst> {
+        match &self.data {
+            MemData::Shared(shared) => Some(MemInst {
+                mem_type: self.mem_type.clone(),
+                data: MemData::Shared(shared.clone()),
+            }),
+            MemData::Owned(_) => None,
+        }
+    }
+
+    /// Type of the memory with its current size as the minimum
+    pub fn mem_type(&self) -> MemType {
+        MemType {
+            limits: LimitsType {
+                min: U64Type(self.size()),
+                max: self.mem_type.limits.max.clone(),
+            },
+            ..self.mem_type.clone()
+        }
+    }
+
+    pub fn index_type(&self) -> &IndexType {
+        &self.mem_type.index_type
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.mem_type.shared
+    }
+
+    pub fn data(&self) -> MemBytes<'_, &Vec<Byte>> {
+        match &self.data {
+            MemData::Owned(data) => MemBytes::Owned(data),
+            MemData::Shared(shared) => MemBytes::Shared(shared.lock()),
+        }
+    }
+
+    pub fn data_mut(&mut self) -> MemBytes<'_, &mut Vec<Byte>> {
+        match &mut self.data {
+            MemData::Owned(data) => MemBytes::Owned(data),
+            MemData::Shared(shared) => MemBytes::Shared(shared.lock()),
+        }
+    }
+
+    pub fn byte_len(&self) -> usize {
+        self.data().len()
+    }
+
+    #[inline]
+    pub fn grow(&mut self, n_val: &Val) -> RResult<()> {
+        match (n_val, &self.mem_type.index_type) {
+            (Val::I32(n), IndexType::I32) => self.grow_n(*n as u64),
+            (Val::I64(n), IndexType::I64) => self.grow_n(*n),
+            _ => Err(Trap),
+        }
+    }
+
+    pub fn grow_n(&mut self, n: u64) -> RResult<()> {
+        let max = self.mem_type.limits.max.clone();
+        let mem_type = self.mem_type.clone();
+        let mut data = self.data_mut();
 
-        if len > 2usize.pow(16) {
+        if !data.len().is_multiple_of(Self::PAGE_SIZE) {
             return Err(Trap);
         }
 
+        let size = (data.len() / Self::PAGE_SIZE) as u64;
+        let len = n.checked_add(size).ok_or(Trap)?;
         let new_limits = LimitsType {
-            min: U32Type(len as u32),
-            max: self.mem_type.limits.max.clone(),
+            min: U64Type(len),
+            max,
+        };
+        let new_mem_type = MemType {
+            limits: new_limits,
+            ..mem_type
         };
-        let new_mem_type = MemType { limits: new_limits };
 
         if !is_memory_type_valid(&new_mem_type) {
             return Err(Trap);
         }
 
-        self.data.append(&mut vec![0x00; num]);
-        self.mem_type = new_mem_type;
+        let new_size = Self::byte_size(len, &new_mem_type.index_type)?;
+        // memory.grow reports a failed allocation instead of aborting the host
+        let additional = new_size - data.len();
+        data.try_reserve(additional).map_err(|_| Trap)?;
+        data.resize(new_size, 0x00);
 
         return Ok(());
     }
 
-    pub fn size(&self) -> u32 {
-        (self.data.len() as u32) / (Self::PAGE_SIZE as u32)
+    pub fn size(&self) -> u64 {
+        (self.byte_len() / Self::PAGE_SIZE) as u64
+    }
+
+    /// Suspends the current thread until `notify` is called for `ea`,
+    /// unless the value at `ea` differs from `expected`.
+    /// Negative `timeout` (in nanoseconds) means waiting forever.
+    pub fn wait(&self, ea: usize, expected: &[Byte], timeout: i64) -> RResult<WaitResult> {
+        let MemData::Shared(shared) = &self.data else {
+            return Err(Trap);
+        };
+        let waiters = &shared.waiters;
+
+        let mut state = waiters.lock();
+
+        {
+            let data = shared.lock();
+            let actual = data.get(ea..ea + expected.len()).ok_or(Trap)?;
+            if actual != expected {
+                return Ok(WaitResult::NotEqual);
+            }
+        }
+
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.push_back((ea, ticket));
+
+        let deadline = u64::try_from(timeout)
+            .ok()
+            .map(|timeout| Instant::now() + Duration::from_nanos(timeout));
+
+        loop {
+            if state.notified.remove(&ticket) {
+                return Ok(WaitResult::Ok);
+            }
+
+            state = match deadline {
+                None => waiters
+                    .condvar
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        state.waiting.retain(|(_, t)| *t != ticket);
+                        return Ok(WaitResult::TimedOut);
+                    }
+
+                    waiters
+                        .condvar
+                        .wait_timeout(state, deadline - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+            };
+        }
+    }
+
+    /// Wakes up to `count` threads waiting on `ea` and returns how many were woken.
+    pub fn notify(&self, ea: usize, count: u32) -> u32 {
+        let MemData::Shared(shared) = &self.data else {
+            return 0;
+        };
+        let waiters = &shared.waiters;
+
+        let mut state = waiters.lock();
+        let mut woken = 0;
+
+        while woken < count {
+            let Some(position) = state.waiting.iter().position(|(addr, _)| *addr == ea) else {
+                break;
+            };
+            if let Some((_, ticket)) = state.waiting.remove(position) {
+                state.notified.insert(ticket);
+                woken += 1;
+            }
+        }
+
+        if woken > 0 {
+            waiters.condvar.notify_all();
+        }
+
+        woken
+    }
+}
+
+impl SharedMem {
+    fn lock(&self) -> MutexGuard<'_, Vec<Byte>> {
+        self.data.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<B: Deref<Target = Vec<Byte>>> Deref for MemBytes<'_, B> {
+    type Target = Vec<Byte>;
+
+    fn deref(&self) -> &Vec<Byte> {
+        match self {
+            MemBytes::Owned(data) => data,
+            MemBytes::Shared(data) => data,
+        }
+    }
+}
+
+impl<B: DerefMut<Target = Vec<Byte>>> DerefMut for MemBytes<'_, B> {
+    fn deref_mut(&mut self) -> &mut Vec<Byte> {
+        match self {
+            MemBytes::Owned(data) => data,
+            MemBytes::Shared(data) => data,
+        }
+    }
+}
+
+impl WaitQueue {
+    fn lock(&self) -> MutexGuard<'_, WaitQueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MemInst;
+    use crate::entities::types::IndexType;
+
+    #[test]
+    fn test_max_pages_of_index_type() {
+        let pages = MemInst::MAX_PAGES + 1;
+
+        assert!(MemInst::byte_size(pages, &IndexType::I32).is_err());
+        assert_eq!(
+            MemInst::byte_size(pages, &IndexType::I64).ok(),
+            Some(pages as usize * MemInst::PAGE_SIZE)
+        );
+        assert!(MemInst::byte_size(MemInst::MAX_PAGES_64 + 1, &IndexType::I64).is_err());
     }
 }
@@ -1,15 +1,20 @@
-pub(crate) mod data;
-pub(crate) mod elem;
-pub(crate) mod export;
-pub(crate) mod frame;
-pub(crate) mod func;
-pub(crate) mod global;
-pub(crate) mod instruction;
-pub(crate) mod label;
-pub(crate) mod memory;
-pub(crate) mod module;
-pub(crate) mod ref_inst;
-pub(crate) mod stack;
-pub(crate) mod store;
-pub(crate) mod table;
-pub(crate) mod value;
+pub mod data;
+pub mod elem;
+pub mod exception;
+pub mod export;
+pub mod frame;
+pub mod func;
+pub mod gc;
+pub mod global;
+pub mod instruction;
+pub mod label;
+pub mod limiter;
+pub mod memory;
+pub mod module;
+pub mod ref_inst;
+pub mod slots;
+pub mod stack;
+pub mod store;
+pub mod table;
+pub mod tag;
+pub mod value;
@@ -7,8 +7,12 @@ use crate::entities::types::*;
 use crate::execute::{execute_expression, execute_instruction};
 use crate::instances::{frame::Frame, stack::Stack, stack::StackEntry, store::Store};
 use crate::result::{RResult, Trap};
+use crate::validation::{
+    types_validation::is_extern_type_matching, validated_module::ValidatedModule,
+};
 
 use super::export::ExportInst;
+use super::func::LazyFuncError;
 use super::value::Val;
 
 #[derive(Debug, Default)]
@@ -18,12 +22,45 @@ pub struct ModuleInst {
     pub tableaddrs: Vec<TableAddr>,
     pub memaddrs: Vec<MemAddr>,
     pub globaladdrs: Vec<GlobalAddr>,
+    pub tagaddrs: Vec<TagAddr>,
     pub elemaddrs: Vec<ElemAddr>,
     pub dataaddrs: Vec<DataAddr>,
     pub exports: Vec<ExportInst>,
     pub start: Option<StartType>,
 }
 
+/// Reason why a validated module can't be instantiated
+#[derive(Debug)]
+pub enum InstantiationError {
+    // Every import needs exactly one external
+    ImportCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    // The type of the external does not match the type declared by the import
+    IncompatibleImportType {
+        expected: ExternType,
+        actual: ExternType,
+    },
+    // The store entry of the external for the import at the index was removed
+    UnknownExternal {
+        index: usize,
+    },
+    // Evaluation of the initializers or the start function trapped
+    Trap(Trap),
+    // The start function called a malformed or invalid lazy function body
+    InvalidFunction(LazyFuncError),
+}
+
+impl From<Trap> for InstantiationError {
+    fn from(mut trap: Trap) -> Self {
+        match trap.take_invalid_function() {
+            Some(error) => InstantiationError::InvalidFunction(error),
+            None => InstantiationError::Trap(trap),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ExternalDependency {
     Func {
@@ -42,6 +79,34 @@ pub enum ExternalDependency {
         global_addr: GlobalAddr,
         global_type: GlobalType,
     },
+    Tag {
+        tag_addr: TagAddr,
+        tag_type: FuncType,
+    },
+}
+
+impl ExternalDependency {
+    /// Type of the store entry the external refers to, None when the entry was removed.
+    /// The type given with the external is not trusted, the entry may have another one.
+    pub fn get_type(&self, store: &Store) -> Option<ExternType> {
+        Some(match self {
+            ExternalDependency::Func { func_addr, .. } => {
+                ExternType::Func(store.funcs.get(*func_addr)?.get_type().clone())
+            }
+            ExternalDependency::Table { table_addr, .. } => {
+                ExternType::Table(store.tables.get(*table_addr)?.table_type.clone())
+            }
+            ExternalDependency::Mem { mem_addr, .. } => {
+                ExternType::Mem(store.mems.get(*mem_addr)?.mem_type())
+            }
+            ExternalDependency::Global { global_addr, .. } => {
+                ExternType::Global(store.globals.get(*global_addr)?.global_type.clone())
+            }
+            ExternalDependency::Tag { tag_addr, .. } => {
+                ExternType::Tag(store.tags.get(*tag_addr)?.tag_type.clone())
+            }
+        })
+    }
 }
 
 // Takes module declaration type,
@@ -54,16 +119,57 @@ impl ModuleInst {
     pub fn instantiate(
         store: &mut Store,
         stack: &mut Stack,
-        module: &Module,
+        module: &ValidatedModule,
         // externals: Vec<ExportInst>,
         externals: Vec<ExternalDependency>,
-    ) -> RResult<Rc<RefCell<Self>>> {
-        if !module.is_valid(&externals) {
-            return Err(Trap);
+    ) -> Result<Rc<RefCell<Self>>, InstantiationError> {
+        Self::check_externals(store, module, &externals)?;
+
+        Self::allocate(store, stack, module, externals).map_err(InstantiationError::from)
+    }
+
+    // The module is already validated, only the externals given for its imports are checked
+    fn check_externals(
+        store: &Store,
+        module: &ValidatedModule,
+        externals: &[ExternalDependency],
+    ) -> Result<(), InstantiationError> {
+        let import_types = module.import_types();
+        if import_types.len() != externals.len() {
+            return Err(InstantiationError::ImportCountMismatch {
+                expected: import_types.len(),
+                actual: externals.len(),
+            });
         }
 
-        let aux_module_raw = RefCell::new(ModuleInst {
+        for (index, (expected, external)) in import_types.iter().zip(externals).enumerate() {
+            let actual = external
+                .get_type(store)
+                .ok_or(InstantiationError::UnknownExternal { index })?;
+            if !is_extern_type_matching(&actual, expected) {
+                return Err(InstantiationError::IncompatibleImportType {
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn allocate(
+        store: &mut Store,
+        stack: &mut Stack,
+        module: &ValidatedModule,
+        externals: Vec<ExternalDependency>,
+    ) -> RResult<Rc<RefCell<Self>>> {
+        let module_inst_rc = store.allocate_funcs(module, &externals);
+
+        // initializers are evaluated in an auxiliary instance with the functions of the module
+        // and the imported globals only
+        let aux_module = Rc::new(RefCell::new(ModuleInst {
             types: module.types.clone(),
+            funcaddrs: module_inst_rc.borrow().funcaddrs.clone(),
             globaladdrs: externals
                 .iter()
                 .filter_map(|external| match external {
@@ -72,18 +178,7 @@ impl ModuleInst {
                 })
                 .collect(),
             ..Default::default()
-        });
-
-        let aux_module = Rc::new(aux_module_raw);
-
-        let funcaddrs_aux: Vec<FuncAddr> = module
-            .get_funcs()
-            .ok_or(Trap)?
-            .iter()
-            .map(|func| store.allocate_local_func(func.clone(), aux_module.clone()))
-            .collect();
-
-        aux_module.borrow_mut().funcaddrs = funcaddrs_aux;
+        }));
 
         stack.push_entry(StackEntry::Frame(Frame {
             module: aux_module.clone(),
@@ -102,7 +197,7 @@ impl ModuleInst {
 
         let mut refs_refs = Vec::with_capacity(module.elems.len());
         for elem in &module.elems {
-            let init = Self::resolve_element_segment_init(elem);
+            let init = elem.get_init();
 
             let mut refs = Vec::with_capacity(init.len());
 
@@ -129,7 +224,7 @@ impl ModuleInst {
         //     .map(|export_inst| export_inst.value.clone())
         //     .collect();
 
-        let module_inst_rc = store.allocate_module(&module, &externals, vals, refs_refs)?;
+        store.allocate_module(&module_inst_rc, module, &externals, vals, refs_refs)?;
 
         stack.push_entry(StackEntry::Frame(Frame {
             module: module_inst_rc.clone(),
@@ -148,49 +243,6 @@ impl ModuleInst {
         return Ok(module_inst_rc);
     }
 
-    fn resolve_element_segment_init<'a>(elem: &ElementSegmentType) -> Vec<ExpressionType> {
-        match elem {
-            ElementSegmentType::Active0Expr(active0_expr) => active0_expr.init.clone(),
-            ElementSegmentType::Active0Functions(active0_funcs) => active0_funcs
-                .init
-                .iter()
-                .map(|func_idx| {
-                    ExpressionType::new(vec![InstructionType::RefFunc(func_idx.clone())])
-                })
-                .collect(),
-            ElementSegmentType::ActiveRef(active0_ref) => active0_ref.init.clone(),
-            ElementSegmentType::DeclarativeRef(declarative_ref) => declarative_ref.init.clone(),
-            ElementSegmentType::ElemKindActiveFunctions(elemkind_active_funcs) => {
-                elemkind_active_funcs
-                    .init
-                    .iter()
-                    .map(|func_idx| {
-                        ExpressionType::new(vec![InstructionType::RefFunc(func_idx.clone())])
-                    })
-                    .collect()
-            }
-            ElementSegmentType::ElemKindDeclarativeFunctions(elemkind_declarative_funcs) => {
-                elemkind_declarative_funcs
-                    .init
-                    .iter()
-                    .map(|func_idx| {
-                        ExpressionType::new(vec![InstructionType::RefFunc(func_idx.clone())])
-                    })
-                    .collect()
-            }
-            ElementSegmentType::ElemKindPassiveFunctions(elemkind_passive_funcs) => {
-                elemkind_passive_funcs
-                    .init
-                    .iter()
-                    .map(|func_idx| {
-                        ExpressionType::new(vec![InstructionType::RefFunc(func_idx.clone())])
-                    })
-                    .collect()
-            }
-            ElementSegmentType::PassiveRef(passive_ref) => passive_ref.init.clone(),
-        }
-    }
-
     fn apply_elems(module: &Module, stack: &mut Stack, store: &mut Store) -> RResult<()> {
         for (i, elem) in module.elems.iter().enumerate() {
             let init_len = elem.get_init().len() as u32;
@@ -262,7 +314,8 @@ impl ModuleInst {
         stack: &mut Stack,
         store: &mut Store,
     ) -> RResult<()> {
-        execute_expression(offset_instructions, stack, store)?;
+        let offset = execute_expression(offset_instructions, stack, store)?;
+        stack.push_value(offset);
         execute_instruction(&InstructionType::I32Const(I32Type(0)), stack, store)?;
         execute_instruction(&InstructionType::I32Const(I32Type(n)), stack, store)?;
         execute_instruction(
@@ -298,25 +351,30 @@ impl ModuleInst {
             match data {
                 DataType::Active0(data_active) => {
                     let n = data_active.init.len() as u32;
-                    execute_expression(&data_active.mode.offset, stack, store)?;
+                    let offset = execute_expression(&data_active.mode.offset, stack, store)?;
+                    stack.push_value(offset);
                     execute_instruction(&InstructionType::I32Const(I32Type(0)), stack, store)?;
                     execute_instruction(&InstructionType::I32Const(I32Type(n)), stack, store)?;
                     execute_instruction(
-                        &InstructionType::MemoryInit(DataIdx(U32Type(i as u32))),
+                        &InstructionType::MemoryInit((
+                            DataIdx(U32Type(i as u32)),
+                            MemIdx(U32Type(0)),
+                        )),
                         stack,
                         store,
                     )?;
                 }
                 DataType::Active(data_active) => {
-                    if data_active.mode.memory.0 .0 != 0 {
-                        return Err(Trap);
-                    }
                     let n = data_active.init.len() as u32;
-                    execute_expression(&data_active.mode.offset, stack, store)?;
+                    let offset = execute_expression(&data_active.mode.offset, stack, store)?;
+                    stack.push_value(offset);
                     execute_instruction(&InstructionType::I32Const(I32Type(0)), stack, store)?;
                     execute_instruction(&InstructionType::I32Const(I32Type(n)), stack, store)?;
                     execute_instruction(
-                        &InstructionType::MemoryInit(DataIdx(U32Type(i as u32))),
+                        &InstructionType::MemoryInit((
+                            DataIdx(U32Type(i as u32)),
+                            data_active.mode.memory.clone(),
+                        )),
                         stack,
                         store,
                     )?;
@@ -340,26 +398,186 @@ impl ModuleInst {
     }
 }
 
-// FIXME:
-// #[cfg(test)]
-// mod test {
-//     use crate::entities::module::Module;
-
-//     use crate::{
-//         instances::{stack::Stack, store::Store},
-//         module_registry::ModuleRegistry,
-//     };
-
-//     use super::ModuleInst;
-
-//     #[test]
-//     fn instantiate_empty() {
-//         let registry = ModuleRegistry::new();
-//         let empty_module = Module::default();
-//         let mut store = Store::new();
-//         let mut stack = Stack::new();
-
-//         let instance = ModuleInst::instantiate(&mut store, &mut stack, &empty_module, &registry)
-//             .expect("should instantiate empty instance");
-//     }
-// }
+#[cfg(test)]
+mod test {
+    use crate::controller::run_func;
+    use crate::entities::module::{
+        Active0DataType, Active0FunctionsElementSegmentType, DataModeActive0, DataType,
+        ElemModeActive0, ElementSegmentType, ExpressionType, ImportDescription, InstructionType,
+    };
+    use crate::entities::types::{
+        FuncIdx, GlobalIdx, GlobalType, I32Type, MutType, RefType, TableIdx, TypeIdx, U32Type,
+    };
+    use crate::instances::{stack::Stack, store::Store, value::Val};
+    use crate::test_utils::{
+        code, i32_global, import, mem_type, memarg, module_with_run_func, table_type,
+    };
+    use crate::validation::validated_module::ValidatedModule;
+
+    use super::{ExternalDependency, InstantiationError, ModuleInst};
+
+    fn offset(n: u32) -> ExpressionType {
+        ExpressionType::new(vec![InstructionType::I32Const(I32Type(n))])
+    }
+
+    #[test]
+    fn instantiate_active_data() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut module = module_with_run_func(
+            vec![],
+            vec![mem_type(1)],
+            vec![
+                InstructionType::I32Const(I32Type(4)),
+                InstructionType::I32Load(memarg(2, 0)),
+            ],
+        );
+        module.datas = vec![DataType::Active0(Active0DataType {
+            mode: DataModeActive0 { offset: offset(4) },
+            init: vec![42, 0, 0, 0],
+        })];
+        let module = ValidatedModule::new(module).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+
+        assert_eq!(result, vec![Val::I32(42)]);
+    }
+
+    #[test]
+    fn instantiate_active_elems() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        // "run" calls the function at table entry 0, which is the second function returning 7
+        let mut module = module_with_run_func(
+            vec![],
+            vec![],
+            vec![
+                InstructionType::I32Const(I32Type(0)),
+                InstructionType::CallIndirect((TableIdx(U32Type(0)), TypeIdx(U32Type(0)))),
+            ],
+        );
+        module.funcs.push(TypeIdx(U32Type(0)));
+        module
+            .code
+            .push(code(vec![InstructionType::I32Const(I32Type(7))]));
+        module.tables = vec![table_type(RefType::FuncRef)];
+        module.elems = vec![ElementSegmentType::Active0Functions(
+            Active0FunctionsElementSegmentType {
+                mode: ElemModeActive0 { offset: offset(0) },
+                init: vec![FuncIdx(U32Type(1))],
+            },
+        )];
+        let module = ValidatedModule::new(module).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+
+        assert_eq!(result, vec![Val::I32(7)]);
+    }
+
+    #[test]
+    fn call_indirect_uses_instance_memory() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        // the function at table entry 0 loads the value written by the data segment
+        let mut module = module_with_run_func(
+            vec![],
+            vec![mem_type(1)],
+            vec![
+                InstructionType::I32Const(I32Type(0)),
+                InstructionType::CallIndirect((TableIdx(U32Type(0)), TypeIdx(U32Type(0)))),
+            ],
+        );
+        module.funcs.push(TypeIdx(U32Type(0)));
+        module.code.push(code(vec![
+            InstructionType::I32Const(I32Type(0)),
+            InstructionType::I32Load(memarg(2, 0)),
+        ]));
+        module.tables = vec![table_type(RefType::FuncRef)];
+        module.elems = vec![ElementSegmentType::Active0Functions(
+            Active0FunctionsElementSegmentType {
+                mode: ElemModeActive0 { offset: offset(0) },
+                init: vec![FuncIdx(U32Type(1))],
+            },
+        )];
+        module.datas = vec![DataType::Active0(Active0DataType {
+            mode: DataModeActive0 { offset: offset(0) },
+            init: vec![42, 0, 0, 0],
+        })];
+        let module = ValidatedModule::new(module).unwrap();
+
+        let module_inst = ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).unwrap();
+        assert_eq!(store.funcs.len(), 2);
+
+        let result = run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+        assert_eq!(result, vec![Val::I32(42)]);
+    }
+
+    #[test]
+    fn instantiate_out_of_bounds_data() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut module = module_with_run_func(
+            vec![],
+            vec![mem_type(1)],
+            vec![InstructionType::I32Const(I32Type(0))],
+        );
+        module.datas = vec![DataType::Active0(Active0DataType {
+            mode: DataModeActive0 {
+                offset: offset(65535),
+            },
+            init: vec![1, 2],
+        })];
+        let module = ValidatedModule::new(module).unwrap();
+
+        assert!(ModuleInst::instantiate(&mut store, &mut stack, &module, vec![]).is_err());
+    }
+
+    #[test]
+    fn instantiate_validated_module_repeatedly() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let base = i32_global(MutType::Const, vec![]).global_type;
+        let module = ValidatedModule::new(module_with_run_func(
+            vec![import(
+                "env",
+                "base",
+                ImportDescription::Global(base.clone()),
+            )],
+            vec![],
+            vec![
+                InstructionType::GlobalGet(GlobalIdx(U32Type(0))),
+                InstructionType::I32Const(I32Type(1)),
+                InstructionType::I32Add,
+            ],
+        ))
+        .unwrap();
+        let global =
+            |store: &mut Store, global_type: &GlobalType, value| ExternalDependency::Global {
+                global_addr: store.allocate_global(global_type.clone(), Val::I32(value)),
+                global_type: global_type.clone(),
+            };
+
+        // only the types of the externals are checked for every instance
+        for value in [10, 20, 30] {
+            let externals = vec![global(&mut store, &base, value)];
+            let module_inst =
+                ModuleInst::instantiate(&mut store, &mut stack, &module, externals).unwrap();
+
+            let result =
+                run_func(module_inst, &module, "run", vec![], &mut stack, &mut store).unwrap();
+            assert_eq!(result, vec![Val::I32(value + 1)]);
+        }
+
+        let mutable = GlobalType {
+            mut_type: MutType::Var,
+            ..base
+        };
+        let externals = vec![global(&mut store, &mutable, 1)];
+        assert!(matches!(
+            ModuleInst::instantiate(&mut store, &mut stack, &module, externals),
+            Err(InstantiationError::IncompatibleImportType { .. })
+        ));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::entities::types::RefType;
 use crate::{
-    address::{ExternAddr, FuncAddr},
+    address::{ExnAddr, ExternAddr, FuncAddr},
     result::{RResult, Trap},
 };
 
@@ -9,6 +9,7 @@ pub enum RefInst {
     Null(RefType),
     Func(FuncAddr),
     Extern(ExternAddr),
+    Exn(ExnAddr),
 }
 
 impl RefInst {
@@ -20,6 +21,15 @@ impl RefInst {
         false
     }
 
+    pub fn get_type(&self) -> RefType {
+        match self {
+            RefInst::Null(ref_type) => ref_type.clone(),
+            RefInst::Func(_) => RefType::FuncRef,
+            RefInst::Extern(_) => RefType::ExternRef,
+            RefInst::Exn(_) => RefType::ExnRef,
+        }
+    }
+
     pub fn as_func(&self) -> RResult<FuncAddr> {
         if let RefInst::Func(func_addr) = self {
             return Ok(*func_addr);
@@ -27,4 +37,20 @@ impl RefInst {
 
         Err(Trap)
     }
+
+    pub fn as_extern(&self) -> RResult<ExternAddr> {
+        if let RefInst::Extern(extern_addr) = self {
+            return Ok(*extern_addr);
+        }
+
+        Err(Trap)
+    }
+
+    pub fn as_exn(&self) -> RResult<ExnAddr> {
+        if let RefInst::Exn(exn_addr) = self {
+            return Ok(*exn_addr);
+        }
+
+        Err(Trap)
+    }
 }
@@ -0,0 +1,146 @@
+use std::ops::{Index, IndexMut};
+
+use crate::address::Addr;
+
+#[derive(Debug)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Storage of store entries addressed by generational handles.
+/// Slots of removed entries are reused, a handle is valid only for the generation it was issued for.
+#[derive(Debug)]
+pub struct Slots<T> {
+    entries: Vec<Slot<T>>,
+    vacant: Vec<usize>,
+}
+
+impl<T> Slots<T> {
+    pub fn new() -> Self {
+        Slots {
+            entries: vec![],
+            vacant: vec![],
+        }
+    }
+
+    pub fn push(&mut self, value: T) -> Addr {
+        if let Some(index) = self.vacant.pop() {
+            let slot = &mut self.entries[index];
+            slot.generation += 1;
+            slot.value = Some(value);
+
+            return Addr {
+                index,
+                generation: slot.generation,
+            };
+        }
+
+        self.entries.push(Slot {
+            generation: 0,
+            value: Some(value),
+        });
+
+        Addr {
+            index: self.entries.len() - 1,
+            generation: 0,
+        }
+    }
+
+    pub fn get(&self, addr: Addr) -> Option<&T> {
+        self.entries
+            .get(addr.index)
+            .filter(|slot| slot.generation == addr.generation)?
+            .value
+            .as_ref()
+    }
+
+    pub fn get_mut(&mut self, addr: Addr) -> Option<&mut T> {
+        self.entries
+            .get_mut(addr.index)
+            .filter(|slot| slot.generation == addr.generation)?
+            .value
+            .as_mut()
+    }
+
+    pub fn remove(&mut self, addr: Addr) -> Option<T> {
+        let value = self
+            .entries
+            .get_mut(addr.index)
+            .filter(|slot| slot.generation == addr.generation)?
+            .value
+            .take()?;
+        self.vacant.push(addr.index);
+
+        Some(value)
+    }
+
+    /// Number of live entries
+    pub fn len(&self) -> usize {
+        self.entries.len() - self.vacant.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every entry for which the predicate returns false
+    pub fn retain(&mut self, mut keep: impl FnMut(Addr, &T) -> bool) {
+        for (index, slot) in self.entries.iter_mut().enumerate() {
+            let addr = Addr {
+                index,
+                generation: slot.generation,
+            };
+            if matches!(&slot.value, Some(value) if !keep(addr, value)) {
+                slot.value = None;
+                self.vacant.push(index);
+            }
+        }
+    }
+}
+
+impl<T> Default for Slots<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<Addr> for Slots<T> {
+    type Output = T;
+
+    fn index(&self, addr: Addr) -> &T {
+        self.get(addr).expect("store entry is removed")
+    }
+}
+
+impl<T> IndexMut<Addr> for Slots<T> {
+    fn index_mut(&mut self, addr: Addr) -> &mut T {
+        self.get_mut(addr).expect("store entry is removed")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reused_slot_invalidates_handle() {
+        let mut slots = Slots::new();
+        let first = slots.push("first");
+        let second = slots.push("second");
+
+        assert_eq!(slots.remove(first), Some("first"));
+        assert_eq!(slots.remove(first), None);
+
+        let third = slots.push("third");
+
+        assert_eq!(third.index, first.index);
+        assert_eq!(slots.get(first), None);
+        assert_eq!(slots[third], "third");
+
+        slots.retain(|addr, _| addr == third);
+
+        assert_eq!(slots.get(second), None);
+        assert_eq!(slots.len(), 1);
+    }
+}
@@ -38,6 +38,12 @@ pub struct Stack {
     stack: Vec<StackEntry>,
 }
 
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Stack {
     pub fn new() -> Self {
         Stack { stack: vec![] }
@@ -63,6 +69,10 @@ impl Stack {
         self.stack.push(StackEntry::Label(label));
     }
 
+    pub fn entries(&self) -> &[StackEntry] {
+        &self.stack
+    }
+
     pub fn last(&self) -> Option<&StackEntry> {
         self.stack.last()
     }
@@ -190,6 +200,24 @@ impl Stack {
         None
     }
 
+    // Drops every entry above the innermost label together with the label
+    pub fn unwind_label(&mut self) -> Option<LabelInst> {
+        loop {
+            if let StackEntry::Label(label) = self.stack.pop()? {
+                return Some(label);
+            }
+        }
+    }
+
+    // Drops every entry above the innermost frame together with the frame
+    pub fn unwind_frame(&mut self) -> Option<Frame> {
+        loop {
+            if let StackEntry::Frame(frame) = self.stack.pop()? {
+                return Some(frame);
+            }
+        }
+    }
+
     pub fn current_frame(&mut self) -> Option<&mut Frame> {
         self.stack.iter_mut().rev().find_map(|entry| match entry {
             StackEntry::Frame(frame) => Some(frame),
@@ -1,55 +1,83 @@
+use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 use super::data::DataInst;
 use super::elem::ElemInst;
+use super::exception::ExnInst;
 use super::export::{ExportInst, ExternVal};
-use super::func::{FuncInst, FuncInstLocal, HostCode, HostFunc};
+use super::func::{FuncCode, FuncInst, FuncInstLocal, HostCode, HostFunc};
+use super::gc::HostRoots;
 use super::global::GlobalInst;
+use super::limiter::ResourceLimiter;
 use super::memory::MemInst;
 use super::module::{ExternalDependency, ModuleInst};
 use super::ref_inst::RefInst;
+use super::slots::Slots;
 use super::table::TableInst;
+use super::tag::TagInst;
 use super::value::Val;
 use crate::entities::{
-    module::{ExportDescription, Module},
-    types::{Byte, Func, FuncType, GlobalType, MemType, RefType, TableType},
+    module::ExportDescription,
+    types::{Byte, FuncType, GlobalType, MemType, RefType, TableType},
+};
+use crate::validation::{
+    types_validation::{is_memory_type_valid, is_table_type_valid},
+    validated_module::ValidatedModule,
 };
-use crate::validation::types_validation::{is_memory_type_valid, is_table_type_valid};
 use crate::{
     address::*,
     result::{RResult, Trap},
 };
 
-// TODO: consider using HashMap instead of Vec,
-// encapsulate this detail to make store content not public
+// TODO: encapsulate entries to make store content not public
 // and provide only neccessary methods to work with the Store content
 
 // #[derive(Debug)]
 pub struct Store {
-    pub funcs: Vec<FuncInst>,
-    pub tables: Vec<TableInst>,
-    pub mems: Vec<MemInst>,
-    pub globals: Vec<GlobalInst>,
-    // TODO: rewrite to HashMap<usize, ElemInst> ?
-    pub elems: Vec<Option<ElemInst>>,
-    // TODO: rewrite to HashMap<usize, DataInst> ?
-    pub datas: Vec<Option<DataInst>>,
+    pub funcs: Slots<FuncInst>,
+    pub tables: Slots<TableInst>,
+    pub mems: Slots<MemInst>,
+    pub globals: Slots<GlobalInst>,
+    // dropped segments are kept as None
+    pub elems: Slots<Option<ElemInst>>,
+    pub datas: Slots<Option<DataInst>>,
+    pub tags: Slots<TagInst>,
+    pub exns: Slots<ExnInst>,
+    // host objects referenced by externref
+    pub externs: Slots<Rc<dyn Any>>,
+    pub host_roots: HostRoots,
+    pub limiter: Option<Box<dyn ResourceLimiter>>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Store {
     /// Create empy store
     pub fn new() -> Store {
         Store {
-            funcs: vec![],
-            tables: vec![],
-            mems: vec![],
-            globals: vec![],
-            elems: vec![],
-            datas: vec![],
+            funcs: Slots::new(),
+            tables: Slots::new(),
+            mems: Slots::new(),
+            globals: Slots::new(),
+            elems: Slots::new(),
+            datas: Slots::new(),
+            tags: Slots::new(),
+            exns: Slots::new(),
+            externs: Slots::new(),
+            host_roots: HostRoots::default(),
+            limiter: None,
         }
     }
 
+    pub fn set_limiter(&mut self, limiter: impl ResourceLimiter + 'static) {
+        self.limiter = Some(Box::new(limiter));
+    }
+
     pub fn drop_elem(&mut self, e: ElemAddr) -> RResult<()> {
         self.elems
             .get(e)
@@ -80,97 +108,149 @@ impl Store {
 
     pub fn allocate_local_func(
         &mut self,
-        func: Func,
+        func: FuncCode,
         module_inst: Rc<RefCell<ModuleInst>>,
     ) -> FuncAddr {
-        let func_type = module_inst.borrow().types[func.func_type.0 .0 as usize].clone();
+        let func_type = module_inst.borrow().types[func.type_idx().0 .0 as usize].clone();
         let func_inst = FuncInst::FuncInst(FuncInstLocal {
             func_type,
             module: module_inst.clone(),
             code: func,
         });
-        self.funcs.push(func_inst);
-
-        self.funcs.len() - 1 as FuncAddr
+        self.funcs.push(func_inst)
     }
 
-    #[allow(dead_code)]
+    /// Allocates a function of the host, it can be provided to modules as an import.
+    /// The function survives garbage collection until it is released.
     pub fn allocate_host_func(&mut self, func_type: FuncType, host_code: HostCode) -> FuncAddr {
         let func_inst = FuncInst::HostFunc(HostFunc {
             func_type,
             host_code,
         });
+        let func_addr = self.funcs.push(func_inst);
+        self.host_roots.funcs.insert(func_addr);
 
-        self.funcs.push(func_inst);
+        func_addr
+    }
 
-        self.funcs.len() - 1 as FuncAddr
+    /// The host function is collected once it isn't reachable from instances anymore
+    pub fn release_func(&mut self, func_addr: FuncAddr) {
+        self.host_roots.funcs.remove(&func_addr);
     }
 
-    pub fn allocate_table(&mut self, table_type: TableType, elem: RefInst) -> TableAddr {
-        let len = table_type.limits.min.0 as usize;
-        let table_inst = TableInst {
-            table_type,
-            elem: vec![elem; len],
-        };
-        self.tables.push(table_inst);
+    pub fn allocate_table(&mut self, table_type: TableType, elem: RefInst) -> RResult<TableAddr> {
+        Ok(self.tables.push(TableInst::new(table_type, elem)?))
+    }
+
+    /// Allocates a memory owned by the host, it can be provided to modules as an import.
+    /// The memory survives garbage collection until it is released.
+    pub fn allocate_mem(&mut self, mem_type: MemType) -> RResult<MemAddr> {
+        let mem_addr = self.mems.push(MemInst::new(mem_type)?);
+        self.host_roots.mems.insert(mem_addr);
 
-        self.tables.len() - 1 as TableAddr
+        Ok(mem_addr)
     }
 
-    pub fn allocate_mem(&mut self, mem_type: MemType) -> MemAddr {
-        let size = (mem_type.limits.min.0.clone() as usize) * 2usize.pow(16);
-        let mem_inst = MemInst {
-            mem_type,
-            data: vec![0x00; size],
-        };
+    // Adds a handle of a shared memory created by a store of another thread, rooted like allocate_mem
+    pub fn allocate_shared_mem(&mut self, mem_inst: MemInst) -> RResult<MemAddr> {
+        if !mem_inst.is_shared() {
+            return Err(Trap);
+        }
 
-        self.mems.push(mem_inst);
+        let mem_addr = self.mems.push(mem_inst);
+        self.host_roots.mems.insert(mem_addr);
 
-        self.mems.len() - 1 as MemAddr
+        Ok(mem_addr)
+    }
+
+    /// The memory is collected once it isn't reachable from instances anymore
+    pub fn release_mem(&mut self, mem_addr: MemAddr) {
+        self.host_roots.mems.remove(&mem_addr);
     }
 
     pub fn allocate_global(&mut self, global_type: GlobalType, value: Val) -> GlobalAddr {
         let global_inst = GlobalInst { global_type, value };
-        self.globals.push(global_inst);
+        self.globals.push(global_inst)
+    }
 
-        self.globals.len() - 1 as GlobalAddr
+    pub fn allocate_tag(&mut self, tag_type: FuncType) -> TagAddr {
+        self.tags.push(TagInst { tag_type })
+    }
+
+    pub fn allocate_exn(&mut self, tag: TagAddr, fields: Vec<Val>) -> ExnAddr {
+        self.exns.push(ExnInst { tag, fields })
+    }
+
+    /// Keeps the host object in the store, a reference to it can be passed to guests as externref.
+    /// The object survives garbage collection until it is released.
+    pub fn allocate_extern(&mut self, object: Rc<dyn Any>) -> ExternAddr {
+        let extern_addr = self.externs.push(object);
+        self.host_roots.externs.insert(extern_addr);
+
+        extern_addr
+    }
+
+    /// The host object is collected once guests don't reference it anymore
+    pub fn release_extern(&mut self, extern_addr: ExternAddr) {
+        self.host_roots.externs.remove(&extern_addr);
+    }
+
+    /// Host object referenced by externref, None if it has a different type or doesn't exist
+    pub fn get_extern<T: Any>(&self, extern_addr: ExternAddr) -> Option<Rc<T>> {
+        self.externs.get(extern_addr)?.clone().downcast::<T>().ok()
     }
 
     pub fn allocate_elem(&mut self, elem_type: RefType, elem: Vec<RefInst>) -> ElemAddr {
         let elem_inst = ElemInst { elem, elem_type };
-        self.elems.push(Some(elem_inst));
-
-        self.elems.len() - 1 as ElemAddr
+        self.elems.push(Some(elem_inst))
     }
 
     pub fn allocate_data(&mut self, data: Vec<Byte>) -> DataAddr {
         let data_inst = DataInst { data };
-        self.datas.push(Some(data_inst));
+        self.datas.push(Some(data_inst))
+    }
+
+    /// Creates the module instance with its functions, imported functions come first
+    /// in the function index space. Local functions are bound to the returned instance.
+    pub fn allocate_funcs(
+        &mut self,
+        module: &ValidatedModule,
+        extern_vals: &[ExternalDependency],
+    ) -> Rc<RefCell<ModuleInst>> {
+        let module_inst_rc = Rc::new(RefCell::new(ModuleInst {
+            types: module.types.clone(),
+            funcaddrs: extern_vals
+                .iter()
+                .filter_map(|v| match v {
+                    ExternalDependency::Func { func_addr, .. } => Some(*func_addr),
+                    _ => None,
+                })
+                .collect(),
+            start: module.start.clone(),
+            ..Default::default()
+        }));
 
-        self.datas.len() - 1 as DataAddr
+        for func in module.funcs() {
+            let func_addr = self.allocate_local_func(func.clone(), module_inst_rc.clone());
+            module_inst_rc.borrow_mut().funcaddrs.push(func_addr);
+        }
+
+        module_inst_rc
     }
 
     // TODO: implement resolve_imports to get extern_vals (implement module registry)
     // TODO: implement resolve_globals to get globals values (according to the spec init of a global must be a single const instruction, take value from there)
     // TODO: implement resolve_elems to get refs vector of module's element segments
+    /// Allocates the remaining entries of the instance created by `allocate_funcs`
     pub fn allocate_module(
         &mut self,
-        module: &Module,
-        extern_vals: &Vec<ExternalDependency>,
+        module_inst: &Rc<RefCell<ModuleInst>>,
+        module: &ValidatedModule,
+        extern_vals: &[ExternalDependency],
         mut globals: Vec<Val>,
         mut refs: Vec<Vec<RefInst>>,
-    ) -> RResult<Rc<RefCell<ModuleInst>>> {
-        let mut module_inst = ModuleInst {
-            types: module.types.clone(),
-            tableaddrs: vec![],
-            globaladdrs: vec![],
-            funcaddrs: vec![],
-            memaddrs: vec![],
-            elemaddrs: vec![],
-            dataaddrs: vec![],
-            exports: vec![],
-            start: module.start.clone(),
-        };
+    ) -> RResult<()> {
+        let mut module_inst = module_inst.borrow_mut();
 
         // table allocations
         for table_type in &module.tables {
@@ -178,18 +258,19 @@ impl Store {
                 return Err(Trap);
             }
         }
-        for table_type in &module.tables {
-            let elem = RefInst::Null(table_type.element_ref_type.clone());
-            module_inst
-                .tableaddrs
-                .push(self.allocate_table(table_type.clone(), elem));
-        }
+        // imported tables come first in the table index space
         module_inst
             .tableaddrs
             .extend(extern_vals.iter().filter_map(|v| match v {
                 ExternalDependency::Table { table_addr, .. } => Some(*table_addr),
                 _ => None,
             }));
+        for table_type in &module.tables {
+            let elem = RefInst::Null(table_type.element_ref_type.clone());
+            module_inst
+                .tableaddrs
+                .push(self.allocate_table(table_type.clone(), elem)?);
+        }
 
         // mem allocations
         for mem_type in &module.mems {
@@ -197,19 +278,26 @@ impl Store {
                 return Err(Trap);
             }
         }
+        // imported memories come first in the memory index space
+        module_inst
+            .memaddrs
+            .extend(extern_vals.iter().filter_map(|v| match v {
+                ExternalDependency::Mem { mem_addr, .. } => Some(*mem_addr),
+                _ => None,
+            }));
         for mem_type in &module.mems {
             module_inst
                 .memaddrs
-                .push(self.allocate_mem(mem_type.clone()));
+                .push(self.mems.push(MemInst::new(mem_type.clone())?));
         }
+
+        // global allocations, imported globals come first in the global index space
         module_inst
-            .memaddrs
+            .globaladdrs
             .extend(extern_vals.iter().filter_map(|v| match v {
-                ExternalDependency::Mem { mem_addr, .. } => Some(*mem_addr),
+                ExternalDependency::Global { global_addr, .. } => Some(*global_addr),
                 _ => None,
             }));
-
-        // global allocations
         for global in &module.globals {
             globals.rotate_left(1);
             let val = globals.pop().ok_or(Trap)?;
@@ -217,50 +305,48 @@ impl Store {
                 .globaladdrs
                 .push(self.allocate_global(global.global_type.clone(), val));
         }
+
+        // tag allocations, imported tags come first in the tag index space
         module_inst
-            .globaladdrs
+            .tagaddrs
             .extend(extern_vals.iter().filter_map(|v| match v {
-                ExternalDependency::Global { global_addr, .. } => Some(*global_addr),
+                ExternalDependency::Tag { tag_addr, .. } => Some(*tag_addr),
                 _ => None,
             }));
+        for tag_type in &module.tags {
+            let func_type = module
+                .types
+                .get(tag_type.type_idx.0 .0 as usize)
+                .ok_or(Trap)?;
+            module_inst
+                .tagaddrs
+                .push(self.allocate_tag(func_type.clone()));
+        }
 
         // elem allocation
         for element_segment in &module.elems {
             let elem_type = element_segment.get_type();
             refs.rotate_left(1);
             let elem = refs.pop().ok_or(Trap)?;
-            self.allocate_elem(elem_type, elem);
+            module_inst
+                .elemaddrs
+                .push(self.allocate_elem(elem_type, elem));
         }
 
         // data allocation
         for data in &module.datas {
-            self.allocate_data(data.clone_data());
+            module_inst
+                .dataaddrs
+                .push(self.allocate_data(data.clone_data()));
         }
 
-        let module_inst_rc = Rc::new(RefCell::new(module_inst));
-
-        // func allocations
-        let funcs = module.get_funcs().ok_or(Trap)?;
-        for func in funcs {
-            let func_addr = self.allocate_local_func(func, module_inst_rc.clone());
-            module_inst_rc.borrow_mut().funcaddrs.push(func_addr);
-        }
-        module_inst_rc
-            .borrow_mut()
-            .funcaddrs
-            .extend(extern_vals.iter().filter_map(|v| match v {
-                ExternalDependency::Func { func_addr, .. } => Some(*func_addr),
-                _ => None,
-            }));
-
         // exports instantiation
         for export_declaration in &module.exports {
             let export_inst = ExportInst {
                 name: export_declaration.name.clone(),
                 value: match &export_declaration.desc {
                     ExportDescription::Func(func_idx) => {
-                        let funcaddr = module_inst_rc
-                            .borrow()
+                        let funcaddr = module_inst
                             .funcaddrs
                             .get(func_idx.0 .0 as usize)
                             .ok_or(Trap)?
@@ -268,8 +354,7 @@ impl Store {
                         ExternVal::Func(funcaddr)
                     }
                     ExportDescription::Global(global_idx) => {
-                        let globaladdr = module_inst_rc
-                            .borrow()
+                        let globaladdr = module_inst
                             .globaladdrs
                             .get(global_idx.0 .0 as usize)
                             .ok_or(Trap)?
@@ -277,8 +362,7 @@ impl Store {
                         ExternVal::Global(globaladdr)
                     }
                     ExportDescription::Mem(mem_idx) => {
-                        let memaddr = module_inst_rc
-                            .borrow()
+                        let memaddr = module_inst
                             .memaddrs
                             .get(mem_idx.0 .0 as usize)
                             .ok_or(Trap)?
@@ -286,20 +370,26 @@ impl Store {
                         ExternVal::Mem(memaddr)
                     }
                     ExportDescription::Table(table_idx) => {
-                        let tableaddr = module_inst_rc
-                            .borrow()
+                        let tableaddr = module_inst
                             .tableaddrs
                             .get(table_idx.0 .0 as usize)
                             .ok_or(Trap)?
                             .clone();
                         ExternVal::Table(tableaddr)
                     }
+                    ExportDescription::Tag(tag_idx) => {
+                        let tagaddr = *module_inst
+                            .tagaddrs
+                            .get(tag_idx.0 .0 as usize)
+                            .ok_or(Trap)?;
+                        ExternVal::Tag(tagaddr)
+                    }
                 },
             };
-            module_inst_rc.borrow_mut().exports.push(export_inst);
+            module_inst.exports.push(export_inst);
         }
 
-        Ok(module_inst_rc)
+        Ok(())
     }
 }
 
@@ -307,15 +397,28 @@ impl Store {
 mod test {
     use std::{cell::RefCell, rc::Rc};
 
+    use crate::controller::run_func;
     use crate::entities::{
-        module::ExpressionType,
-        types::{Func, FuncType, TypeIdx, U32Type},
+        module::{
+            ExportDescription, ExpressionType, Global, ImportDescription, ImportType,
+            InstructionType, Module,
+        },
+        types::{
+            Func, FuncIdx, FuncType, GlobalIdx, GlobalType, I32Type, LocalIdx, MemIdx, MutType,
+            NameType, RefType, TableIdx, TypeIdx, U32Type, ValType,
+        },
     };
 
     use crate::instances::{
-        func::{FuncInst, FuncInstLocal, HostCode, HostFunc},
-        module::ModuleInst,
+        func::{FuncCode, FuncInst, FuncInstLocal, HostCode, HostFunc},
+        module::{ExternalDependency, ModuleInst},
+        ref_inst::RefInst,
+        stack::Stack,
+        value::Val,
     };
+    use crate::result::{RuntimeError, Trap};
+    use crate::test_utils::{code, export, i32_global, i32_type, import, mem_type, table_type};
+    use crate::validation::validated_module::ValidatedModule;
 
     use super::Store;
 
@@ -342,20 +445,21 @@ mod test {
 
         let module_func_type = module_inst.borrow().types[func.func_type.0 .0 as usize].clone();
         let expected_code = func.clone();
-        let func_addr = store.allocate_local_func(func, module_inst);
+        let func_addr = store.allocate_local_func(FuncCode::Decoded(func), module_inst);
 
-        assert_eq!(func_addr, 0);
+        assert_eq!(func_addr.index, 0);
 
         if let Some(FuncInst::FuncInst(FuncInstLocal {
             func_type, code, ..
-        })) = store.funcs.get(0)
+        })) = store.funcs.get(func_addr)
         {
             assert_eq!(
                 func_type, &module_func_type,
                 "allocated local function type should match"
             );
             assert_eq!(
-                code, &expected_code,
+                code.get().ok(),
+                Some(&expected_code),
                 "allocated local function code should match"
             );
         } else {
@@ -373,17 +477,17 @@ mod test {
         };
         let expected_func_type = func_type.clone();
 
-        let host_hode = HostCode;
+        let host_hode = HostCode::new(|_, _| Ok(vec![]));
         let expected_host_code = host_hode.clone();
 
         let func_addr = store.allocate_host_func(func_type, host_hode);
 
-        assert_eq!(func_addr, 0);
+        assert_eq!(func_addr.index, 0);
 
         if let Some(FuncInst::HostFunc(HostFunc {
             func_type,
             host_code,
-        })) = store.funcs.get(0)
+        })) = store.funcs.get(func_addr)
         {
             assert_eq!(
                 func_type, &expected_func_type,
@@ -400,4 +504,334 @@ mod test {
 
     #[test]
     fn allocate_module() {}
+
+    fn i32_result_type() -> FuncType {
+        FuncType {
+            parameters: vec![],
+            results: vec![i32_type()],
+        }
+    }
+
+    // Exports one extern of every kind, the function returns 10 and the global holds 20
+    fn lib_module() -> Module {
+        Module {
+            types: vec![i32_result_type()],
+            funcs: vec![TypeIdx(U32Type(0))],
+            tables: vec![table_type(RefType::FuncRef)],
+            mems: vec![mem_type(1)],
+            globals: vec![i32_global(
+                MutType::Const,
+                vec![InstructionType::I32Const(I32Type(20))],
+            )],
+            exports: vec![
+                export("func", ExportDescription::Func(FuncIdx(U32Type(0)))),
+                export("table", ExportDescription::Table(TableIdx(U32Type(0)))),
+                export("mem", ExportDescription::Mem(MemIdx(U32Type(0)))),
+                export("global", ExportDescription::Global(GlobalIdx(U32Type(0)))),
+            ],
+            code: vec![code(vec![InstructionType::I32Const(I32Type(10))])],
+            ..Default::default()
+        }
+    }
+
+    // Imports every extern of the lib module and defines one local extern of every kind
+    fn main_module() -> Module {
+        let global_type =
+            i32_global(MutType::Const, vec![InstructionType::I32Const(I32Type(0))]).global_type;
+
+        Module {
+            types: vec![i32_result_type()],
+            imports: vec![
+                import("lib", "func", ImportDescription::Func(TypeIdx(U32Type(0)))),
+                import(
+                    "lib",
+                    "table",
+                    ImportDescription::Table(table_type(RefType::FuncRef)),
+                ),
+                import("lib", "mem", ImportDescription::Mem(mem_type(1))),
+                import("lib", "global", ImportDescription::Global(global_type)),
+            ],
+            funcs: vec![TypeIdx(U32Type(0)), TypeIdx(U32Type(0))],
+            tables: vec![table_type(RefType::FuncRef)],
+            mems: vec![mem_type(1)],
+            globals: vec![i32_global(
+                MutType::Const,
+                vec![InstructionType::I32Const(I32Type(1))],
+            )],
+            exports: vec![
+                export(
+                    "call_imported",
+                    ExportDescription::Func(FuncIdx(U32Type(1))),
+                ),
+                export("get_imported", ExportDescription::Func(FuncIdx(U32Type(2)))),
+            ],
+            code: vec![
+                code(vec![InstructionType::Call(FuncIdx(U32Type(0)))]),
+                code(vec![InstructionType::GlobalGet(GlobalIdx(U32Type(0)))]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn instantiate_linked(
+        store: &mut Store,
+        stack: &mut Stack,
+    ) -> (Rc<RefCell<ModuleInst>>, Rc<RefCell<ModuleInst>>) {
+        let lib_inst = ModuleInst::instantiate(
+            store,
+            stack,
+            &ValidatedModule::new(lib_module()).unwrap(),
+            vec![],
+        )
+        .unwrap();
+
+        let externals = {
+            let lib = lib_inst.borrow();
+            vec![
+                ExternalDependency::Func {
+                    func_addr: lib.funcaddrs[0],
+                    func_type: i32_result_type(),
+                },
+                ExternalDependency::Table {
+                    table_addr: lib.tableaddrs[0],
+                    table_type: table_type(RefType::FuncRef),
+                },
+                ExternalDependency::Mem {
+                    mem_addr: lib.memaddrs[0],
+                    mem_type: mem_type(1),
+                },
+                ExternalDependency::Global {
+                    global_addr: lib.globaladdrs[0],
+                    global_type: i32_global(
+                        MutType::Const,
+                        vec![InstructionType::I32Const(I32Type(0))],
+                    )
+                    .global_type,
+                },
+            ]
+        };
+
+        let main_inst = ModuleInst::instantiate(
+            store,
+            stack,
+            &ValidatedModule::new(main_module()).unwrap(),
+            externals,
+        )
+        .unwrap();
+
+        (lib_inst, main_inst)
+    }
+
+    #[test]
+    fn allocate_module_with_imports_first() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let (lib_inst, main_inst) = instantiate_linked(&mut store, &mut stack);
+        let lib = lib_inst.borrow();
+        let main = main_inst.borrow();
+
+        assert_eq!(main.funcaddrs.len(), 3);
+        assert_eq!(main.funcaddrs[0], lib.funcaddrs[0]);
+        assert_eq!(main.tableaddrs.len(), 2);
+        assert_eq!(main.tableaddrs[0], lib.tableaddrs[0]);
+        assert_eq!(main.memaddrs.len(), 2);
+        assert_eq!(main.memaddrs[0], lib.memaddrs[0]);
+        assert_eq!(main.globaladdrs.len(), 2);
+        assert_eq!(main.globaladdrs[0], lib.globaladdrs[0]);
+    }
+
+    #[test]
+    fn run_linked_module() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let (_, main_inst) = instantiate_linked(&mut store, &mut stack);
+        let module = main_module();
+
+        let call_result = run_func(
+            main_inst.clone(),
+            &module,
+            "call_imported",
+            vec![],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+        let global_result = run_func(
+            main_inst,
+            &module,
+            "get_imported",
+            vec![],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+
+        assert_eq!(call_result, vec![Val::I32(10)]);
+        assert_eq!(global_result, vec![Val::I32(20)]);
+    }
+
+    // $keep stores an externref in a global, $call passes it to the imported host function
+    fn extern_module() -> Module {
+        let extern_type = ValType::RefType(RefType::ExternRef);
+
+        Module {
+            types: vec![
+                FuncType {
+                    parameters: vec![extern_type.clone()],
+                    results: vec![],
+                },
+                i32_result_type(),
+                FuncType {
+                    parameters: vec![extern_type.clone()],
+                    results: vec![i32_type()],
+                },
+            ],
+            imports: vec![ImportType {
+                module: NameType("env".into()),
+                name: NameType("describe".into()),
+                desc: ImportDescription::Func(TypeIdx(U32Type(2))),
+            }],
+            funcs: vec![TypeIdx(U32Type(0)), TypeIdx(U32Type(1))],
+            globals: vec![Global {
+                global_type: GlobalType {
+                    mut_type: MutType::Var,
+                    val_type: extern_type,
+                },
+                init: ExpressionType::new(vec![InstructionType::RefNull(RefType::ExternRef)]),
+            }],
+            exports: vec![
+                export("keep", ExportDescription::Func(FuncIdx(U32Type(1)))),
+                export("call", ExportDescription::Func(FuncIdx(U32Type(2)))),
+            ],
+            code: vec![
+                code(vec![
+                    InstructionType::LocalGet(LocalIdx(U32Type(0))),
+                    InstructionType::GlobalSet(GlobalIdx(U32Type(0))),
+                ]),
+                code(vec![
+                    InstructionType::GlobalGet(GlobalIdx(U32Type(0))),
+                    InstructionType::Call(FuncIdx(U32Type(0))),
+                ]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn host_objects_as_externref() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let module = ValidatedModule::new(extern_module()).unwrap();
+
+        let describe_type = module.types[2].clone();
+        let describe = store.allocate_host_func(
+            describe_type.clone(),
+            HostCode::new(|store, args| {
+                let extern_addr = match &args[0] {
+                    Val::Ref(reference) => reference.as_extern()?,
+                    _ => return Err(Trap),
+                };
+                let name = store.get_extern::<String>(extern_addr).ok_or(Trap)?;
+
+                Ok(vec![Val::I32(name.len() as u32)])
+            }),
+        );
+        let externals = vec![ExternalDependency::Func {
+            func_addr: describe,
+            func_type: describe_type,
+        }];
+        let module_inst =
+            ModuleInst::instantiate(&mut store, &mut stack, &module, externals).unwrap();
+
+        let object = store.allocate_extern(Rc::new(String::from("hello")));
+        let object_ref = Val::Ref(RefInst::Extern(object));
+        let unrelated = store.allocate_extern(Rc::new(42));
+        store.release_extern(object);
+        store.release_extern(unrelated);
+
+        assert!(store.get_extern::<u32>(object).is_none());
+
+        run_func(
+            module_inst.clone(),
+            &module,
+            "keep",
+            vec![object_ref],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+        store.collect_garbage(std::slice::from_ref(&module_inst), &[], &stack);
+
+        assert!(store.get_extern::<i32>(unrelated).is_none());
+
+        let result = run_func(module_inst, &module, "call", vec![], &mut stack, &mut store);
+        assert_eq!(result.unwrap(), vec![Val::I32(5)]);
+    }
+
+    #[test]
+    fn host_func_errors_trap() {
+        let module = ValidatedModule::new(extern_module()).unwrap();
+        let describe_type = module.types[2].clone();
+        let call_with = |describe: HostCode, object: Option<Rc<dyn std::any::Any>>| {
+            let mut store = Store::new();
+            let mut stack = Stack::new();
+            let func_addr = store.allocate_host_func(describe_type.clone(), describe);
+            let externals = vec![ExternalDependency::Func {
+                func_addr,
+                func_type: describe_type.clone(),
+            }];
+            let module_inst =
+                ModuleInst::instantiate(&mut store, &mut stack, &module, externals).unwrap();
+
+            if let Some(object) = object {
+                let object_ref = Val::Ref(RefInst::Extern(store.allocate_extern(object)));
+                run_func(
+                    module_inst.clone(),
+                    &module,
+                    "keep",
+                    vec![object_ref],
+                    &mut stack,
+                    &mut store,
+                )
+                .unwrap();
+            }
+
+            run_func(module_inst, &module, "call", vec![], &mut stack, &mut store)
+        };
+        let describe = || {
+            HostCode::new(|store, args| {
+                let extern_addr = match &args[0] {
+                    Val::Ref(reference) => reference.as_extern()?,
+                    _ => return Err(Trap),
+                };
+                let name = store.get_extern::<String>(extern_addr).ok_or(Trap)?;
+
+                Ok(vec![Val::I32(name.len() as u32)])
+            })
+        };
+
+        assert_eq!(
+            call_with(describe(), Some(Rc::new(String::from("hello")))).unwrap(),
+            vec![Val::I32(5)]
+        );
+        // the global still holds ref.null extern
+        assert!(matches!(
+            call_with(describe(), None),
+            Err(RuntimeError::Trap(_))
+        ));
+        // the object is not a String
+        assert!(matches!(
+            call_with(describe(), Some(Rc::new(5))),
+            Err(RuntimeError::Trap(_))
+        ));
+        // results must match the type of the host function
+        assert!(matches!(
+            call_with(HostCode::new(|_, _| Ok(vec![Val::I64(5)])), None),
+            Err(RuntimeError::Trap(_))
+        ));
+        assert!(matches!(
+            call_with(HostCode::new(|_, _| Ok(vec![])), None),
+            Err(RuntimeError::Trap(_))
+        ));
+    }
 }
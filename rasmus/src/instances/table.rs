@@ -1,8 +1,11 @@
+use super::limiter::ResourceLimiter;
+use super::module::ModuleInst;
 use super::ref_inst::RefInst;
-use super::value::Val;
-use crate::entities::types::{LimitsType, TableType, U32Type};
-use crate::result::{RResult, Trap};
-use crate::validation::types_validation::is_table_type_valid;
+use super::store::Store;
+use crate::address::TableAddr;
+use crate::entities::types::{TableType, U64Type, ValType};
+use crate::instances::export::ExternVal;
+use crate::result::{HandleError, RResult, Trap};
 
 pub struct TableInst {
     pub table_type: TableType,
@@ -10,42 +13,286 @@ pub struct TableInst {
 }
 
 impl TableInst {
-    pub const MAX_TABLE_SIZE: usize = 2usize.pow(32);
+    /// Implementation limit on the number of entries of a table
+    pub const MAX_ENTRIES: u32 = 10_000_000;
 
-    #[inline]
-    #[allow(dead_code)]
-    pub fn grow(&mut self, n_val: &Val, reference: RefInst) -> RResult<()> {
-        if let Val::I32(n) = n_val {
-            return self.grow_n(*n, reference);
+    /// Fails when the minimum size exceeds `MAX_ENTRIES` or can't be allocated
+    pub fn new(table_type: TableType, reference: RefInst) -> RResult<TableInst> {
+        let mut table = TableInst {
+            table_type,
+            elem: vec![],
+        };
+        let min = u32::try_from(table.table_type.limits.min.0).map_err(|_| Trap)?;
+        table.fill_to(min, reference).ok_or(Trap)?;
+
+        Ok(table)
+    }
+
+    // table indices are i32, so the size always fits into u32
+    pub fn size(&self) -> u32 {
+        self.elem.len() as u32
+    }
+
+    /// Grows the table by `n` entries initialized with `reference`.
+    /// Returns the previous size or None when the table can't grow, the table is not changed then.
+    pub fn grow_n(
+        &mut self,
+        n: u32,
+        reference: RefInst,
+        limiter: Option<&mut (dyn ResourceLimiter + 'static)>,
+    ) -> Option<u32> {
+        let size = self.size();
+        let len = size.checked_add(n)?;
+        let max = self.table_type.limits.max.as_ref().map(|max| max.0);
+
+        if max.is_some_and(|max| len as u64 > max) {
+            return None;
+        }
+
+        if let Some(limiter) = limiter {
+            if !limiter.table_growing(size, len, max) {
+                return None;
+            }
         }
 
-        Err(Trap)
+        self.fill_to(len, reference)?;
+        self.table_type.limits.min = U64Type(len as u64);
+
+        Some(size)
     }
 
-    #[inline]
-    pub fn grow_n(&mut self, n: u32, reference: RefInst) -> RResult<()> {
-        let num = n as usize;
-        let len = num + self.elem.len();
-        if len > Self::MAX_TABLE_SIZE {
-            return Err(Trap);
+    // Growing an unbounded table by up to 2^32 entries fails instead of aborting the host
+    fn fill_to(&mut self, len: u32, reference: RefInst) -> Option<()> {
+        if len > Self::MAX_ENTRIES {
+            return None;
         }
 
-        let new_limits = LimitsType {
-            min: U32Type(len as u32),
-            max: self.table_type.limits.max.clone(),
-        };
-        let new_table_type = TableType {
-            limits: new_limits,
-            element_ref_type: self.table_type.element_ref_type.clone(),
-        };
+        let additional = (len as usize).checked_sub(self.elem.len())?;
+        self.elem.try_reserve(additional).ok()?;
+        self.elem.resize(len as usize, reference);
+
+        Some(())
+    }
+}
+
+/// Handle of a table for embedders.
+/// References are checked against the element type of the table.
+/// The table is a root of garbage collection until the handle is released, copies share the root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Table {
+    addr: TableAddr,
+}
+
+impl Table {
+    pub fn new(store: &mut Store, addr: TableAddr) -> Self {
+        store.host_roots.tables.insert(addr);
+
+        Table { addr }
+    }
+
+    /// Table exported by the module instance with the name
+    pub fn from_export(store: &mut Store, module_inst: &ModuleInst, name: &str) -> Option<Self> {
+        module_inst
+            .exports
+            .iter()
+            .find(|export| export.name.0 == name)
+            .and_then(|export| match export.value {
+                ExternVal::Table(addr) => Some(Table::new(store, addr)),
+                _ => None,
+            })
+    }
+
+    /// The table is collected once it isn't reachable from instances anymore
+    pub fn release(self, store: &mut Store) {
+        store.host_roots.tables.remove(&self.addr);
+    }
+
+    pub fn addr(&self) -> TableAddr {
+        self.addr
+    }
+
+    pub fn size(&self, store: &Store) -> Result<u32, HandleError> {
+        Ok(self.table(store)?.size())
+    }
+
+    pub fn get(&self, store: &Store, index: u32) -> Result<RefInst, HandleError> {
+        let table = self.table(store)?;
+
+        table
+            .elem
+            .get(index as usize)
+            .cloned()
+            .ok_or(HandleError::OutOfBounds {
+                index,
+                size: table.size(),
+            })
+    }
 
-        if !is_table_type_valid(&new_table_type) {
-            return Err(Trap);
+    pub fn set(
+        &self,
+        store: &mut Store,
+        index: u32,
+        reference: RefInst,
+    ) -> Result<(), HandleError> {
+        let table = store
+            .tables
+            .get_mut(self.addr)
+            .ok_or(HandleError::UnknownEntry)?;
+        check_element_type(table, &reference)?;
+
+        let size = table.size();
+        *table
+            .elem
+            .get_mut(index as usize)
+            .ok_or(HandleError::OutOfBounds { index, size })? = reference;
+
+        Ok(())
+    }
+
+    /// Returns the previous size of the table
+    pub fn grow(&self, store: &mut Store, n: u32, init: RefInst) -> Result<u32, HandleError> {
+        let table = store
+            .tables
+            .get_mut(self.addr)
+            .ok_or(HandleError::UnknownEntry)?;
+        check_element_type(table, &init)?;
+
+        table
+            .grow_n(n, init, store.limiter.as_deref_mut())
+            .ok_or(HandleError::GrowthRefused)
+    }
+
+    fn table<'s>(&self, store: &'s Store) -> Result<&'s TableInst, HandleError> {
+        store.tables.get(self.addr).ok_or(HandleError::UnknownEntry)
+    }
+}
+
+fn check_element_type(table: &TableInst, reference: &RefInst) -> Result<(), HandleError> {
+    let expected = &table.table_type.element_ref_type;
+    let actual = reference.get_type();
+
+    if &actual != expected {
+        return Err(HandleError::TypeMismatch {
+            expected: ValType::RefType(expected.clone()),
+            actual: ValType::RefType(actual),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::types::{LimitsType, RefType};
+
+    struct MaxEntries(u32);
+
+    impl ResourceLimiter for MaxEntries {
+        fn table_growing(&mut self, _current: u32, desired: u32, _maximum: Option<u64>) -> bool {
+            desired <= self.0
         }
+    }
+
+    fn table(store: &mut Store, min: u64, max: Option<u64>) -> Table {
+        let table_type = TableType {
+            limits: LimitsType {
+                min: U64Type(min),
+                max: max.map(U64Type),
+            },
+            element_ref_type: RefType::FuncRef,
+        };
+
+        let addr = store
+            .allocate_table(table_type, RefInst::Null(RefType::FuncRef))
+            .unwrap();
+        Table::new(store, addr)
+    }
+
+    #[test]
+    fn test_grow_respects_limits() {
+        let mut store = Store::new();
+        let table = table(&mut store, 1, Some(3));
+        let null = RefInst::Null(RefType::FuncRef);
+
+        assert_eq!(table.grow(&mut store, 2, null.clone()).ok(), Some(1));
+        assert!(table.grow(&mut store, 1, null.clone()).is_err());
+        assert_eq!(table.size(&store).ok(), Some(3));
+
+        let unbounded_addr = store
+            .allocate_table(store.tables[table.addr()].table_type.clone(), null.clone())
+            .unwrap();
+        let unbounded = Table::new(&mut store, unbounded_addr);
+        store.tables[unbounded.addr()].table_type.limits.max = None;
+        store.set_limiter(MaxEntries(4));
+
+        assert_eq!(unbounded.grow(&mut store, 1, null.clone()).ok(), Some(3));
+        assert!(unbounded.grow(&mut store, 1, null.clone()).is_err());
+        assert_eq!(
+            store.tables[unbounded.addr()].grow_n(u32::MAX, null, None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_grow_empty_unbounded_table() {
+        let mut store = Store::new();
+        let table = table(&mut store, 0, None);
+        let null = RefInst::Null(RefType::FuncRef);
+
+        // table.grow (ref.null func) (i32.const -2)
+        assert_eq!(
+            store.tables[table.addr()].grow_n(-2i32 as u32, null.clone(), None),
+            None
+        );
+        assert_eq!(table.size(&store).ok(), Some(0));
+        assert!(store
+            .allocate_table(
+                TableType {
+                    limits: LimitsType {
+                        min: U64Type(u32::MAX as u64),
+                        max: None,
+                    },
+                    element_ref_type: RefType::FuncRef,
+                },
+                null,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_type_checked_access() {
+        let mut store = Store::new();
+        let table = table(&mut store, 2, None);
+        let func_addr = crate::address::Addr::default();
 
-        self.elem.append(&mut vec![reference; num]);
-        self.table_type = new_table_type;
+        assert_eq!(
+            table.set(&mut store, 0, RefInst::Null(RefType::ExternRef)),
+            Err(HandleError::TypeMismatch {
+                expected: ValType::RefType(RefType::FuncRef),
+                actual: ValType::RefType(RefType::ExternRef),
+            })
+        );
+        assert_eq!(
+            table.set(&mut store, 2, RefInst::Func(func_addr)),
+            Err(HandleError::OutOfBounds { index: 2, size: 2 })
+        );
+        assert!(table.set(&mut store, 1, RefInst::Func(func_addr)).is_ok());
+        assert_eq!(table.get(&store, 1), Ok(RefInst::Func(func_addr)));
+        assert_eq!(
+            table.get(&store, 2),
+            Err(HandleError::OutOfBounds { index: 2, size: 2 })
+        );
+        assert_eq!(
+            table.grow(&mut store, 1, RefInst::Null(RefType::ExternRef)),
+            Err(HandleError::TypeMismatch {
+                expected: ValType::RefType(RefType::FuncRef),
+                actual: ValType::RefType(RefType::ExternRef),
+            })
+        );
 
-        return Ok(());
+        table.release(&mut store);
+        store.collect_garbage(&[], &[], &crate::instances::stack::Stack::new());
+        assert_eq!(table.size(&store), Err(HandleError::UnknownEntry));
     }
 }
@@ -0,0 +1,5 @@
+use crate::entities::types::FuncType;
+
+pub struct TagInst {
+    pub tag_type: FuncType,
+}
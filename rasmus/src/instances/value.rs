@@ -1,4 +1,5 @@
 use super::ref_inst::RefInst;
+use crate::entities::types::{NumType, ValType, VecType};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Val {
@@ -11,6 +12,31 @@ pub enum Val {
     Ref(RefInst),
 }
 
+impl Val {
+    /// Zero or a null reference, the initial value of declared locals
+    pub fn default_of(val_type: &ValType) -> Self {
+        match val_type {
+            ValType::NumType(NumType::I32) => Val::I32(0),
+            ValType::NumType(NumType::I64) => Val::I64(0),
+            ValType::NumType(NumType::F32) => Val::F32(0.0),
+            ValType::NumType(NumType::F64) => Val::F64(0.0),
+            ValType::VecType(VecType::V128) => Val::Vec(0),
+            ValType::RefType(ref_type) => Val::Ref(RefInst::Null(ref_type.clone())),
+        }
+    }
+
+    pub fn get_type(&self) -> ValType {
+        match self {
+            Val::I32(_) => ValType::NumType(NumType::I32),
+            Val::I64(_) => ValType::NumType(NumType::I64),
+            Val::F32(_) => ValType::NumType(NumType::F32),
+            Val::F64(_) => ValType::NumType(NumType::F64),
+            Val::Vec(_) => ValType::VecType(VecType::V128),
+            Val::Ref(reference) => ValType::RefType(reference.get_type()),
+        }
+    }
+}
+
 impl From<u32> for Val {
     fn from(u: u32) -> Self {
         Val::I32(u)
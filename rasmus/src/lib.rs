@@ -0,0 +1,18 @@
+pub mod address;
+pub mod binary;
+pub mod controller;
+pub mod entities;
+pub mod execute;
+pub mod inspect;
+pub mod instances;
+pub mod module_registry;
+pub mod nesting;
+pub mod result;
+pub mod sign;
+pub mod validation;
+pub mod wat;
+
+#[cfg(test)]
+mod execute_test;
+#[cfg(test)]
+mod test_utils;
@@ -1,64 +1,98 @@
-use crate::{
+use std::fs::File;
+
+use rasmus::{
+    binary::module_parser::ModuleParser,
     controller::run_func,
+    inspect::ModuleSummary,
     instances::{stack::Stack, store::Store, value::Val},
-    module_registry::ModuleRegistry,
+    module_registry::{ModuleRegistry, SearchPathResolver},
+    result::RuntimeError,
+    wat::module_to_text,
 };
 
-mod address;
-mod binary;
 mod cli;
-mod controller;
-mod entities;
-mod execute;
-mod instances;
-mod module_registry;
-mod result;
-pub mod sign;
-mod validation;
-
-#[cfg(test)]
-mod execute_test;
-#[cfg(test)]
-mod test_utils;
 
 const MAIN_MODULE: &'static str = "$MAIN";
 
 fn main() {
-    let input = cli::UserInput::parse_args();
+    match cli::UserInput::parse_args() {
+        cli::UserInput::Run { source_file_path } => run(source_file_path),
+        cli::UserInput::Inspect {
+            source_file_path,
+            json,
+        } => inspect(source_file_path, json),
+        cli::UserInput::Wat2Text { source_file_path } => wat2text(source_file_path),
+    }
+}
+
+fn inspect(source_file_path: String, json: bool) {
+    let file = File::open(&source_file_path)
+        .unwrap_or_else(|error| panic!("Unable to read {source_file_path}: {error}"));
+    let module = ModuleParser::parse_reader(file)
+        .unwrap_or_else(|error| panic!("Unable to parse {source_file_path}: {error}"));
+    let summary = ModuleSummary::new(&module);
+
+    match json {
+        true => println!("{}", summary.to_json()),
+        false => print!("{summary}"),
+    }
+}
+
+fn wat2text(source_file_path: String) {
+    let file = File::open(&source_file_path)
+        .unwrap_or_else(|error| panic!("Unable to read {source_file_path}: {error}"));
+    let module = ModuleParser::parse_reader(file)
+        .unwrap_or_else(|error| panic!("Unable to parse {source_file_path}: {error}"));
 
+    print!("{}", module_to_text(&module));
+}
+
+fn run(source_file_path: String) {
     let mut store = Store::new();
     let mut stack = Stack::new();
 
     let mut module_registry = ModuleRegistry::new(&mut store, &mut stack);
     module_registry
-        .register_module(MAIN_MODULE.into(), input.source_file_path.into())
-        .expect("Unable register");
+        .register_module(MAIN_MODULE.into(), source_file_path.clone())
+        .unwrap_or_else(|error| panic!("Unable register: {error}"));
 
-    module_registry
-        .register_module(
-            "factorial-lib".into(),
-            "./rasmus/tests/files/factorial-lib.wasm".into(),
-        )
-        .expect("Unable register");
+    // dependencies are looked up next to the main module first, then in RASMUS_PATH
+    let mut resolver = SearchPathResolver::new(vec![]);
+    if let Some(dir) = std::path::Path::new(&source_file_path).parent() {
+        resolver.add_path(dir);
+    }
+    for path in SearchPathResolver::from_env().paths() {
+        resolver.add_path(path);
+    }
+    module_registry.add_resolver(resolver);
 
     let module_inst = module_registry
         .get_instance(&MAIN_MODULE.into())
-        .expect("Unable resolve")
+        .unwrap_or_else(|error| panic!("Unable resolve: {error}"))
         .clone();
     let module = module_registry
         .get_module(&MAIN_MODULE.into())
         .expect("Unable to get module")
         .clone();
 
-    let result = run_func(
+    let result = match run_func(
         module_inst.clone(),
         &module,
         "factorial",
         vec![Val::I32(10)],
         &mut stack,
         &mut store,
-    )
-    .expect("finish without errors");
+    ) {
+        Ok(result) => result,
+        Err(RuntimeError::UncaughtException { tag, values }) => {
+            panic!(
+                "uncaught exception with tag {} and values {:?}",
+                tag, values
+            )
+        }
+        Err(RuntimeError::Trap(trap)) => panic!("finish without errors: {:?}", trap),
+        Err(RuntimeError::InvalidFunction(error)) => panic!("{error}"),
+    };
 
     println!("result >>> {:?}", result);
 }
@@ -0,0 +1,147 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::entities::module::Module;
+use crate::module_registry::ModuleRegistryError;
+
+use super::module_registry::ModuleName;
+
+/// Modules each registered module imports from, in the order of their first import.
+/// Modules which are not registered are left out, their imports stay unresolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyGraph {
+    dependencies: BTreeMap<ModuleName, Vec<ModuleName>>,
+}
+
+impl DependencyGraph {
+    pub fn new<M: Borrow<Module>>(modules: &HashMap<ModuleName, M>) -> Self {
+        let dependencies = modules
+            .iter()
+            .map(|(name, module)| {
+                let module: &Module = module.borrow();
+                let mut module_dependencies: Vec<ModuleName> = vec![];
+
+                for import in &module.imports {
+                    let dependency = &import.module.0;
+                    if modules.contains_key(dependency) && !module_dependencies.contains(dependency)
+                    {
+                        module_dependencies.push(dependency.clone());
+                    }
+                }
+
+                (name.clone(), module_dependencies)
+            })
+            .collect();
+
+        DependencyGraph { dependencies }
+    }
+
+    pub fn dependencies(&self, name: &ModuleName) -> &[ModuleName] {
+        self.dependencies
+            .get(name)
+            .map(|dependencies| dependencies.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The module and everything it depends on, every module comes after its dependencies
+    pub fn instantiation_order(
+        &self,
+        name: &ModuleName,
+    ) -> Result<Vec<ModuleName>, ModuleRegistryError> {
+        let mut order = vec![];
+        let mut path = vec![];
+
+        self.visit(name, &mut path, &mut order)?;
+
+        Ok(order)
+    }
+
+    // Depth-first traversal, `path` holds the modules whose dependencies are being visited
+    fn visit(
+        &self,
+        name: &ModuleName,
+        path: &mut Vec<ModuleName>,
+        order: &mut Vec<ModuleName>,
+    ) -> Result<(), ModuleRegistryError> {
+        if order.contains(name) {
+            return Ok(());
+        }
+
+        if let Some(start) = path.iter().position(|visited| visited == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.clone());
+
+            return Err(ModuleRegistryError::CircularDependency { cycle });
+        }
+
+        path.push(name.clone());
+        for dependency in self.dependencies(name) {
+            self.visit(dependency, path, order)?;
+        }
+        path.pop();
+
+        order.push(name.clone());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::entities::{
+        module::{ImportDescription, ImportType},
+        types::{NameType, TypeIdx, U32Type},
+    };
+
+    use super::*;
+
+    fn module(imports_from: &[&str]) -> Module {
+        Module {
+            imports: imports_from
+                .iter()
+                .map(|module_name| ImportType {
+                    module: NameType(module_name.to_string()),
+                    name: NameType("f".into()),
+                    desc: ImportDescription::Func(TypeIdx(U32Type(0))),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn graph(modules: &[(&str, &[&str])]) -> DependencyGraph {
+        DependencyGraph::new(
+            &modules
+                .iter()
+                .map(|(name, imports_from)| (name.to_string(), module(imports_from)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_instantiation_order() {
+        let graph = graph(&[
+            ("main", &["utils", "lib", "utils", "env"]),
+            ("lib", &["utils"]),
+            ("utils", &[]),
+        ]);
+
+        assert_eq!(graph.dependencies(&"main".into()), ["utils", "lib"]);
+        assert_eq!(
+            graph.instantiation_order(&"main".into()).unwrap(),
+            vec!["utils", "lib", "main"]
+        );
+    }
+
+    #[test]
+    fn test_circular_dependency() {
+        let graph = graph(&[("main", &["a"]), ("a", &["b"]), ("b", &["a"])]);
+
+        match graph.instantiation_order(&"main".into()) {
+            Err(ModuleRegistryError::CircularDependency { cycle }) => {
+                assert_eq!(cycle, vec!["a", "b", "a"]);
+            }
+            result => panic!("cycle should be detected, got {:?}", result),
+        }
+    }
+}
@@ -1,5 +1,11 @@
+mod dependency_graph;
 mod module_registry;
 mod module_registry_error;
+mod module_resolver;
+mod search_path_resolver;
 
-pub use module_registry::ModuleRegistry;
+pub use dependency_graph::DependencyGraph;
+pub use module_registry::{InstanceName, ModuleName, ModuleRegistry};
 pub use module_registry_error::ModuleRegistryError;
+pub use module_resolver::ModuleResolver;
+pub use search_path_resolver::SearchPathResolver;
@@ -1,25 +1,38 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::fs::read;
+use std::fs::File;
 use std::rc::Rc;
 
 use crate::binary::module_parser::ModuleParser;
 use crate::binary::parse_trait::ParseBin;
+use crate::binary::streaming_parser::{PayloadReader, StreamError};
 use crate::entities::module::Module;
+use crate::entities::types::NameType;
 use crate::instances::export::ExternVal;
 use crate::instances::module::{ExternalDependency, ModuleInst};
 use crate::instances::stack::Stack;
 use crate::instances::store::Store;
-use crate::module_registry::ModuleRegistryError;
-use crate::result::Trap;
+use crate::instances::value::Val;
+use crate::module_registry::{DependencyGraph, ModuleRegistryError, ModuleResolver};
+use crate::validation::validated_module::ValidatedModule;
 
-type ModuleName = String;
+pub type ModuleName = String;
+
+/// Name of a module instance, imports are resolved against instance names.
+/// The default instance of a module is named after the module.
+pub type InstanceName = String;
 
 type ModulePath = String;
 
 pub struct ModuleRegistry<'a> {
-    modules: HashMap<ModuleName, Module>,
-    instances: RefCell<HashMap<ModuleName, Rc<RefCell<ModuleInst>>>>,
+    // modules are validated when they are registered
+    modules: HashMap<ModuleName, ValidatedModule>,
+    instances: RefCell<HashMap<InstanceName, Rc<RefCell<ModuleInst>>>>,
+    // modules of the instances created by the registry
+    instance_modules: HashMap<InstanceName, ModuleName>,
+    resolvers: Vec<Box<dyn ModuleResolver>>,
+    // function bodies of registered modules are decoded on their first call
+    lazy_function_bodies: bool,
     store: &'a mut Store,
     stack: &'a mut Stack,
 }
@@ -29,23 +42,102 @@ impl<'a> ModuleRegistry<'a> {
         ModuleRegistry {
             modules: HashMap::new(),
             instances: RefCell::new(HashMap::new()),
+            instance_modules: HashMap::new(),
+            resolvers: vec![],
+            lazy_function_bodies: false,
             store,
             stack,
         }
     }
 
-    pub fn register_module(&mut self, name: ModuleName, path: ModulePath) -> Result<(), Trap> {
+    /// Modules registered afterwards keep their function bodies undecoded,
+    /// a body is decoded and validated when its function is called first
+    pub fn set_lazy_function_bodies(&mut self, lazy: bool) {
+        self.lazy_function_bodies = lazy;
+    }
+
+    pub fn register_module(
+        &mut self,
+        name: ModuleName,
+        path: ModulePath,
+    ) -> Result<(), ModuleRegistryError> {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(_) => return Err(ModuleRegistryError::UnableToReadModule { path }),
+        };
+        let mut payloads = PayloadReader::new(file);
+        if self.lazy_function_bodies {
+            payloads = payloads.lazy_function_bodies();
+        }
+        let module = match ModuleParser::parse_payloads(payloads) {
+            Ok(module) => module,
+            Err(StreamError::Io(_)) => {
+                return Err(ModuleRegistryError::UnableToReadModule { path })
+            }
+            Err(StreamError::Syntax(error)) => {
+                return Err(ModuleRegistryError::InvalidModule { name, error })
+            }
+        };
+
+        self.register_parsed(name, module)
+    }
+
+    pub fn register_bytes(
+        &mut self,
+        name: ModuleName,
+        bytes: &[u8],
+    ) -> Result<(), ModuleRegistryError> {
+        let module = match self.lazy_function_bodies {
+            true => ModuleParser::parse_lazy(bytes).map_err(|error| {
+                ModuleRegistryError::InvalidModule {
+                    name: name.clone(),
+                    error,
+                }
+            })?,
+            false => parse_module_bytes(&name, bytes)?,
+        };
+
+        self.register_parsed(name, module)
+    }
+
+    pub fn register_parsed(
+        &mut self,
+        name: ModuleName,
+        module: Module,
+    ) -> Result<(), ModuleRegistryError> {
+        if self.module_exists(&name) {
+            return Err(ModuleRegistryError::ModuleAlreadyRegistered { name });
+        }
+
+        let module = validate_module(&name, module)?;
+        self.modules.insert(name, module);
+
+        Ok(())
+    }
+
+    /// Registers an instance created outside of the registry, e.g. by another registry sharing the store
+    pub fn register_instance(
+        &mut self,
+        name: ModuleName,
+        instance: Rc<RefCell<ModuleInst>>,
+    ) -> Result<(), ModuleRegistryError> {
         if self.module_exists(&name) {
-            // TODO: ModuleRegistryError::ModuleAlreadyRegistered { name }
-            return Err(Trap);
+            return Err(ModuleRegistryError::ModuleAlreadyRegistered { name });
         }
 
-        self.modules.insert(name, parse_module(&path)?);
+        self.instances.borrow_mut().insert(name, instance);
 
         Ok(())
     }
 
-    pub fn get_instance(&mut self, name: &ModuleName) -> Result<Rc<RefCell<ModuleInst>>, Trap> {
+    pub fn add_resolver(&mut self, resolver: impl ModuleResolver + 'static) {
+        self.resolvers.push(Box::new(resolver));
+    }
+
+    pub fn get_instance(
+        &mut self,
+        name: &ModuleName,
+    ) -> Result<Rc<RefCell<ModuleInst>>, ModuleRegistryError> {
         if self.instances.borrow().get(name).is_none() {
             self.instantiate_module(name)?;
         }
@@ -53,110 +145,825 @@ impl<'a> ModuleRegistry<'a> {
         Ok(self.instances.borrow().get(name).unwrap().clone())
     }
 
-    pub fn get_module(&self, name: &ModuleName) -> Option<&Module> {
-        self.modules.get(name)
-    }
+    /// Creates a new instance of the registered module with its own memories, tables and globals.
+    /// Dependencies of the module are linked to their default instances.
+    pub fn instantiate(
+        &mut self,
+        module_name: &ModuleName,
+        instance_name: InstanceName,
+    ) -> Result<Rc<RefCell<ModuleInst>>, ModuleRegistryError> {
+        if !self.modules.contains_key(module_name) {
+            return Err(ModuleRegistryError::ModuleNotRegistered {
+                name: module_name.clone(),
+            });
+        }
+        if self.module_exists(&instance_name) {
+            return Err(ModuleRegistryError::ModuleAlreadyRegistered {
+                name: instance_name,
+            });
+        }
 
-    fn module_exists(&self, name: &String) -> bool {
-        self.modules.get(name).is_some()
+        self.resolve_missing_modules(module_name)?;
+
+        for dependency_name in self.dependency_graph().instantiation_order(module_name)? {
+            if &dependency_name != module_name
+                && self.instances.borrow().get(&dependency_name).is_none()
+            {
+                self.link_module(&dependency_name, dependency_name.clone())?;
+            }
+        }
+
+        self.link_module(module_name, instance_name.clone())?;
+
+        Ok(self.instances.borrow().get(&instance_name).unwrap().clone())
     }
 
-    fn instantiate_module(&mut self, name: &ModuleName) -> Result<(), Trap> {
-        if self.instances.borrow().get(name).is_some() {
-            return Ok(());
+    /// Unregisters the instance. Its store entries are reclaimed by the next `collect_garbage`
+    /// once neither instances, host handles nor references in tables refer to them anymore.
+    pub fn drop_instance(&mut self, name: &InstanceName) -> Result<(), ModuleRegistryError> {
+        if self.instances.borrow().get(name).is_none() {
+            return Err(ModuleRegistryError::ModuleNotRegistered { name: name.clone() });
         }
 
-        let dependency_names: Vec<String> = self
-            .modules
-            .get(name)
-            .ok_or_else(|| {
-                Trap::from(ModuleRegistryError::ModuleNotRegistered { name: name.clone() })
-            })?
-            .imports
+        let dependent = self
+            .instance_modules
             .iter()
-            .map(|import| import.module.0.clone())
-            .collect();
-
-        for dep_name in &dependency_names {
-            // iteratively instantiate module until either:
-            // 1. a module has no dependencies
-            // 2. its dependencies have been already instantiated
-            // TODO: detect circular dependencies
-            self.instantiate_module(dep_name)?;
+            .filter(|(instance_name, _)| *instance_name != name)
+            .find(|(_, module_name)| {
+                self.modules[*module_name]
+                    .imports
+                    .iter()
+                    .any(|import| &import.module.0 == name)
+            });
+        if let Some((dependent, _)) = dependent {
+            return Err(ModuleRegistryError::InstanceInUse {
+                name: name.clone(),
+                dependent: dependent.clone(),
+            });
         }
 
-        let module = self.modules.get(name).ok_or_else(|| {
-            Trap::from(ModuleRegistryError::ModuleNotRegistered { name: name.clone() })
-        })?;
+        self.instances.borrow_mut().remove(name);
+        self.instance_modules.remove(name);
 
-        let resolved_imports: Vec<Option<ExternalDependency>> = module
-            .imports
-            .iter()
-            .map(|import| {
-                let module_name = &import.module.0;
-                let val_name = &import.name;
-
-                self.instances
-                    .borrow()
-                    .get(module_name)
-                    .and_then(|module_ref| {
-                        let module = module_ref.clone();
-                        let exports = &module.borrow().exports;
-
-                        exports
-                            .iter()
-                            .cloned()
-                            .find(|export| &export.name == val_name)
-                            .map(|export_inst| match export_inst.value {
-                                ExternVal::Func(func_addr) => ExternalDependency::Func {
-                                    func_addr,
-                                    func_type: self.store.funcs[func_addr].get_type().clone(),
-                                },
-                                ExternVal::Table(table_addr) => ExternalDependency::Table {
-                                    table_addr,
-                                    table_type: self.store.tables[table_addr].table_type.clone(),
-                                },
-                                ExternVal::Mem(mem_addr) => ExternalDependency::Mem {
-                                    mem_addr,
-                                    mem_type: self.store.mems[mem_addr].mem_type.clone(),
-                                },
-                                ExternVal::Global(global_addr) => ExternalDependency::Global {
-                                    global_addr,
-                                    global_type: self.store.globals[global_addr]
-                                        .global_type
-                                        .clone(),
-                                },
-                            })
-                    })
-            })
-            .collect();
+        Ok(())
+    }
 
-        let mut externals: Vec<ExternalDependency> = Vec::with_capacity(resolved_imports.len());
+    /// Reclaims store entries unreachable from the registered instances and the stack.
+    /// Instances and references kept by the host outside of the registry must be passed as roots.
+    pub fn collect_garbage(
+        &mut self,
+        instances: &[Rc<RefCell<ModuleInst>>],
+        values: &[Val],
+    ) -> usize {
+        let mut roots: Vec<_> = self.instances.borrow().values().cloned().collect();
+        roots.extend(instances.iter().cloned());
 
-        for resolved_import in resolved_imports {
-            match resolved_import {
-                Some(external) => {
-                    externals.push(external);
+        self.store.collect_garbage(&roots, values, self.stack)
+    }
+
+    pub fn get_module(&self, name: &ModuleName) -> Option<&Module> {
+        self.modules.get(name).map(ValidatedModule::module)
+    }
+
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph::new(&self.modules)
+    }
+
+    fn module_exists(&self, name: &String) -> bool {
+        self.modules.get(name).is_some() || self.instances.borrow().get(name).is_some()
+    }
+
+    // Asks resolvers for every module the named module transitively imports from
+    // and which is neither registered nor instantiated yet
+    fn resolve_missing_modules(&mut self, name: &ModuleName) -> Result<(), ModuleRegistryError> {
+        let mut pending = vec![name.clone()];
+
+        while let Some(module_name) = pending.pop() {
+            let import_module_names: Vec<ModuleName> = match self.modules.get(&module_name) {
+                Some(module) => module
+                    .imports
+                    .iter()
+                    .map(|import| import.module.0.clone())
+                    .collect(),
+                None => continue,
+            };
+
+            for import_module_name in import_module_names {
+                if self.module_exists(&import_module_name) {
+                    continue;
                 }
-                None => {
-                    // TODO: iterate through extern_vals and throw error if any is none
-                    // add import module and name to error
+
+                for resolver in &self.resolvers {
+                    if let Some(module) = resolver.resolve(&import_module_name)? {
+                        let module = validate_module(&import_module_name, module)?;
+                        self.modules.insert(import_module_name.clone(), module);
+                        pending.push(import_module_name);
+                        break;
+                    }
                 }
             }
         }
 
+        Ok(())
+    }
+
+    fn instantiate_module(&mut self, name: &ModuleName) -> Result<(), ModuleRegistryError> {
+        if !self.module_exists(name) {
+            return Err(ModuleRegistryError::ModuleNotRegistered { name: name.clone() });
+        }
+
+        self.resolve_missing_modules(name)?;
+
+        // dependencies are instantiated before the modules importing from them
+        for module_name in self.dependency_graph().instantiation_order(name)? {
+            if self.instances.borrow().get(&module_name).is_none() {
+                self.link_module(&module_name, module_name.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn link_module(
+        &mut self,
+        name: &ModuleName,
+        instance_name: InstanceName,
+    ) -> Result<(), ModuleRegistryError> {
+        let module = self
+            .modules
+            .get(name)
+            .ok_or_else(|| ModuleRegistryError::ModuleNotRegistered { name: name.clone() })?;
+
+        let mut externals: Vec<ExternalDependency> = Vec::with_capacity(module.imports.len());
+
+        for (import, import_type) in module.imports.iter().zip(module.import_types()) {
+            let external = self
+                .resolve_import(&import.module.0, &import.name)
+                .ok_or_else(|| ModuleRegistryError::UnresolvedImport {
+                    module: import.module.0.clone(),
+                    name: import.name.0.clone(),
+                    kind: import_type.kind(),
+                })?;
+
+            externals.push(external);
+        }
+
         let inst = ModuleInst::instantiate(self.store, self.stack, module, externals)?;
-        self.instances.borrow_mut().insert(name.clone(), inst);
+        self.instances
+            .borrow_mut()
+            .insert(instance_name.clone(), inst);
+        self.instance_modules.insert(instance_name, name.clone());
 
         Ok(())
     }
-}
 
-fn parse_module(path: &String) -> Result<Module, Trap> {
-    let file_content = read(path)
-        .map_err(|_| Trap::from(ModuleRegistryError::UnableToReadModule { path: path.clone() }))?;
+    // Exports whose store entry was removed, e.g. by garbage collection, are not resolved
+    fn resolve_import(
+        &self,
+        module_name: &ModuleName,
+        name: &NameType,
+    ) -> Option<ExternalDependency> {
+        let instances = self.instances.borrow();
+        let module = instances.get(module_name)?.borrow();
+        let export_inst = module.exports.iter().find(|export| &export.name == name)?;
 
-    ModuleParser::parse(&file_content)
-        .map_err(Into::<Trap>::into)
+        Some(match export_inst.value {
+            ExternVal::Func(func_addr) => ExternalDependency::Func {
+                func_addr,
+                func_type: self.store.funcs.get(func_addr)?.get_type().clone(),
+            },
+            ExternVal::Table(table_addr) => ExternalDependency::Table {
+                table_addr,
+                table_type: self.store.tables.get(table_addr)?.table_type.clone(),
+            },
+            ExternVal::Mem(mem_addr) => ExternalDependency::Mem {
+                mem_addr,
+                mem_type: self.store.mems.get(mem_addr)?.mem_type(),
+            },
+            ExternVal::Global(global_addr) => ExternalDependency::Global {
+                global_addr,
+                global_type: self.store.globals.get(global_addr)?.global_type.clone(),
+            },
+            ExternVal::Tag(tag_addr) => ExternalDependency::Tag {
+                tag_addr,
+                tag_type: self.store.tags.get(tag_addr)?.tag_type.clone(),
+            },
+        })
+    }
+}
+
+pub(super) fn parse_module_bytes(
+    name: &ModuleName,
+    bytes: &[u8],
+) -> Result<Module, ModuleRegistryError> {
+    ModuleParser::parse(bytes)
+        .map_err(|error| ModuleRegistryError::InvalidModule {
+            name: name.clone(),
+            error,
+        })
         .map(|res| res.1)
 }
+
+fn validate_module(
+    name: &ModuleName,
+    module: Module,
+) -> Result<ValidatedModule, ModuleRegistryError> {
+    ValidatedModule::new(module).map_err(|error| ModuleRegistryError::ValidationFailed {
+        name: name.clone(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        address::Addr,
+        controller::run_func,
+        entities::types::ExternKind,
+        instances::{stack::Stack, store::Store, value::Val},
+        module_registry::ModuleRegistryError,
+        result::RuntimeError,
+        validation::validated_module::ValidatedModule,
+    };
+
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use crate::instances::{
+        func::{FuncCode, FuncInst, FuncInstLocal, LazyFuncError},
+        module::{ExternalDependency, InstantiationError, ModuleInst},
+    };
+
+    use crate::entities::{
+        module::{
+            CodeType, ExportDescription, ExportType, ExpressionType, FuncCodeType, Global,
+            ImportDescription, ImportType, InstructionType, Module,
+        },
+        types::{
+            FuncIdx, FuncType, GlobalIdx, GlobalType, I32Type, IndexType, LimitsType, MemType,
+            MutType, NameType, NumType, TypeIdx, U32Type, U64Type, ValType,
+        },
+    };
+
+    use super::{parse_module_bytes, ModuleRegistry};
+
+    fn file_path(name: &str) -> String {
+        format!(
+            "{}/tests/files/{}",
+            std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+            name
+        )
+    }
+
+    #[test]
+    fn test_imported_function_call() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+
+        registry
+            .register_module("main".into(), file_path("factorial-main.wasm"))
+            .unwrap();
+        registry
+            .register_module("factorial-lib".into(), file_path("factorial-lib.wasm"))
+            .unwrap();
+
+        let module_inst = registry.get_instance(&"main".into()).unwrap();
+        let module = registry.get_module(&"main".into()).unwrap().clone();
+
+        let result = run_func(
+            module_inst,
+            &module,
+            "factorial",
+            vec![Val::I32(5)],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+
+        assert_eq!(result, vec![Val::I32(120)]);
+    }
+
+    #[test]
+    fn test_unresolved_import() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+
+        registry
+            .register_module("main".into(), file_path("factorial-main.wasm"))
+            .unwrap();
+
+        match registry.get_instance(&"main".into()) {
+            Err(ModuleRegistryError::UnresolvedImport { module, name, kind }) => {
+                assert_eq!(module, "factorial-lib");
+                assert_eq!(name, "factorial");
+                assert_eq!(kind, ExternKind::Func);
+            }
+            result => panic!("import should not be resolved, got {:?}", result.err()),
+        }
+    }
+
+    #[test]
+    fn test_resolve_imports_from_bundle() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+        let bundle = HashMap::from([(
+            "factorial-lib".to_string(),
+            std::fs::read(file_path("factorial-lib.wasm")).unwrap(),
+        )]);
+
+        registry
+            .register_bytes(
+                "main".into(),
+                &std::fs::read(file_path("factorial-main.wasm")).unwrap(),
+            )
+            .unwrap();
+        registry.add_resolver(bundle);
+
+        let module_inst = registry.get_instance(&"main".into()).unwrap();
+        let module = registry.get_module(&"main".into()).unwrap().clone();
+
+        assert!(registry.get_module(&"factorial-lib".into()).is_some());
+
+        let result = run_func(
+            module_inst,
+            &module,
+            "factorial",
+            vec![Val::I32(4)],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+
+        assert_eq!(result, vec![Val::I32(24)]);
+    }
+
+    #[test]
+    fn test_link_registered_instance() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let lib_module = ValidatedModule::new(
+            parse_module_bytes(
+                &"lib".into(),
+                &std::fs::read(file_path("factorial-lib.wasm")).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let lib_inst =
+            ModuleInst::instantiate(&mut store, &mut stack, &lib_module, vec![]).unwrap();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+
+        registry
+            .register_instance("factorial-lib".into(), lib_inst.clone())
+            .unwrap();
+        registry
+            .register_module("main".into(), file_path("factorial-main.wasm"))
+            .unwrap();
+
+        assert!(matches!(
+            registry.register_instance("main".into(), lib_inst),
+            Err(ModuleRegistryError::ModuleAlreadyRegistered { .. })
+        ));
+
+        let module_inst = registry.get_instance(&"main".into()).unwrap();
+        let module = registry.get_module(&"main".into()).unwrap().clone();
+        let result = run_func(
+            module_inst,
+            &module,
+            "factorial",
+            vec![Val::I32(3)],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+
+        assert_eq!(result, vec![Val::I32(6)]);
+    }
+
+    #[test]
+    fn test_import_of_collected_instance() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let lib_module = ValidatedModule::new(
+            parse_module_bytes(
+                &"lib".into(),
+                &std::fs::read(file_path("factorial-lib.wasm")).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let lib_inst =
+            ModuleInst::instantiate(&mut store, &mut stack, &lib_module, vec![]).unwrap();
+        // the instance is not passed as a root, so its function is removed
+        store.collect_garbage(&[], &[], &stack);
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+
+        registry
+            .register_instance("factorial-lib".into(), lib_inst)
+            .unwrap();
+        registry
+            .register_module("main".into(), file_path("factorial-main.wasm"))
+            .unwrap();
+
+        assert!(matches!(
+            registry.get_instance(&"main".into()),
+            Err(ModuleRegistryError::UnresolvedImport { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validation_error_location() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let wasm = [
+            b"\0asm\x01\0\0\0".as_slice(),
+            // type section, [] -> [i32]
+            &[0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7F],
+            // function section
+            &[0x03, 0x02, 0x01, 0x00],
+            // export section, "f" func 0
+            &[0x07, 0x05, 0x01, 0x01, b'f', 0x00, 0x00],
+            // code section, i32.const 1 block (result i32) i64.const 1 i32.eqz end
+            &[
+                0x0A, 0x0C, 0x01, 0x0A, 0x00, 0x41, 0x01, 0x02, 0x7F, 0x42, 0x01, 0x45, 0x0B, 0x0B,
+            ],
+        ]
+        .concat();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+
+        let error = match registry.register_bytes("main".into(), &wasm) {
+            Err(ModuleRegistryError::ValidationFailed { name, error }) => {
+                assert_eq!(name, "main");
+                error
+            }
+            result => panic!("module should be invalid, got {:?}", result.err()),
+        };
+        assert!(registry.get_module(&"main".into()).is_none());
+        let func = error.func.as_ref().unwrap();
+
+        assert_eq!(func.func_idx, 0);
+        assert_eq!(func.name.as_deref(), Some("f"));
+        assert_eq!(func.path, vec![1, 1]);
+        assert_eq!(func.instruction, Some(InstructionType::I32Eqz));
+        assert_eq!(
+            error.to_string(),
+            "func #0 \"f\", instruction 1.1 (i32.eqz): \
+            type mismatch, expected i32, got i64, operands [i32] []"
+        );
+
+        // a lazy body is reported with the same location on the first call
+        registry.set_lazy_function_bodies(true);
+        registry.register_bytes("lazy".into(), &wasm).unwrap();
+        let module_inst = registry.get_instance(&"lazy".into()).unwrap();
+        let module = registry.get_module(&"lazy".into()).unwrap().clone();
+
+        match run_func(module_inst, &module, "f", vec![], &mut stack, &mut store) {
+            Err(RuntimeError::InvalidFunction(LazyFuncError::Invalid(lazy_error))) => {
+                assert_eq!(lazy_error, error)
+            }
+            result => panic!("lazy body should be invalid, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_lazy_function_bodies() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let wasm = [
+            b"\0asm\x01\0\0\0".as_slice(),
+            // type section, [] -> [i32]
+            &[0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7F],
+            // function section
+            &[0x03, 0x03, 0x02, 0x00, 0x00],
+            // export section, "ok" func 0 and "bad" func 1
+            &[0x07, 0x0C, 0x02, 0x02, b'o', b'k', 0x00, 0x00],
+            &[0x03, b'b', b'a', b'd', 0x00, 0x01],
+            // code section, the second body has an unknown opcode
+            &[
+                0x0A, 0x0A, 0x02, 0x04, 0x00, 0x41, 0x07, 0x0B, 0x03, 0x00, 0xFF, 0x0B,
+            ],
+        ]
+        .concat();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+
+        assert!(matches!(
+            registry.register_bytes("eager".into(), &wasm),
+            Err(ModuleRegistryError::InvalidModule { .. })
+        ));
+
+        registry.set_lazy_function_bodies(true);
+        registry.register_bytes("lazy".into(), &wasm).unwrap();
+
+        let module_inst = registry.get_instance(&"lazy".into()).unwrap();
+        let second_inst = registry
+            .instantiate(&"lazy".into(), "second".into())
+            .unwrap();
+        let module = registry.get_module(&"lazy".into()).unwrap().clone();
+        let lazy_func =
+            |store: &Store, module_inst: &Rc<RefCell<ModuleInst>>, idx: usize| match &store.funcs
+                [module_inst.borrow().funcaddrs[idx]]
+            {
+                FuncInst::FuncInst(FuncInstLocal {
+                    code: FuncCode::Lazy(lazy_func),
+                    ..
+                }) => lazy_func.clone(),
+                _ => panic!("function body should be lazy"),
+            };
+
+        let result = run_func(
+            module_inst.clone(),
+            &module,
+            "ok",
+            vec![],
+            &mut stack,
+            &mut store,
+        )
+        .unwrap();
+
+        assert_eq!(result, vec![Val::I32(7)]);
+        assert!(lazy_func(&store, &module_inst, 0).is_decoded());
+        assert!(!lazy_func(&store, &module_inst, 1).is_decoded());
+        // instances of the module share the bodies, so they are decoded only once
+        assert!(Rc::ptr_eq(
+            &lazy_func(&store, &module_inst, 0),
+            &lazy_func(&store, &second_inst, 0)
+        ));
+
+        // the error of the body is kept and returned by every call
+        for _ in 0..2 {
+            let mut stack = Stack::new();
+            match run_func(
+                module_inst.clone(),
+                &module,
+                "bad",
+                vec![],
+                &mut stack,
+                &mut store,
+            ) {
+                Err(RuntimeError::InvalidFunction(LazyFuncError::Malformed(error))) => assert_eq!(
+                    error.to_string(),
+                    "error at 0x2c in code section, func #1: unknown opcode 0xff"
+                ),
+                result => panic!("malformed body should be reported, got {result:?}"),
+            }
+        }
+    }
+
+    // $inc increments the mutable global and returns its new value
+    fn counter_module() -> Module {
+        let i32_type = ValType::NumType(NumType::I32);
+
+        Module {
+            types: vec![FuncType {
+                parameters: vec![],
+                results: vec![i32_type.clone()],
+            }],
+            funcs: vec![TypeIdx(U32Type(0))],
+            mems: vec![MemType {
+                limits: LimitsType {
+                    min: U64Type(1),
+                    max: None,
+                },
+                index_type: IndexType::I32,
+                shared: false,
+            }],
+            globals: vec![Global {
+                global_type: GlobalType {
+                    mut_type: MutType::Var,
+                    val_type: i32_type,
+                },
+                init: ExpressionType::new(vec![InstructionType::I32Const(I32Type(0))]),
+            }],
+            exports: vec![ExportType {
+                name: NameType("inc".into()),
+                desc: ExportDescription::Func(FuncIdx(U32Type(0))),
+            }],
+            code: vec![CodeType {
+                size: U32Type(0),
+                code: FuncCodeType {
+                    locals: vec![],
+                    expression: ExpressionType::new(vec![
+                        InstructionType::GlobalGet(GlobalIdx(U32Type(0))),
+                        InstructionType::I32Const(I32Type(1)),
+                        InstructionType::I32Add,
+                        InstructionType::GlobalSet(GlobalIdx(U32Type(0))),
+                        InstructionType::GlobalGet(GlobalIdx(U32Type(0))),
+                    ]),
+                }
+                .into(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_isolated_instances() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+        let module = counter_module();
+
+        registry
+            .register_parsed("counter".into(), module.clone())
+            .unwrap();
+        let first = registry
+            .instantiate(&"counter".into(), "first".into())
+            .unwrap();
+        let second = registry
+            .instantiate(&"counter".into(), "second".into())
+            .unwrap();
+
+        assert!(matches!(
+            registry.instantiate(&"counter".into(), "first".into()),
+            Err(ModuleRegistryError::ModuleAlreadyRegistered { .. })
+        ));
+        assert_ne!(first.borrow().memaddrs, second.borrow().memaddrs);
+
+        let first_mem = first.borrow().memaddrs[0];
+        let second_global = second.borrow().globaladdrs[0];
+        registry.drop_instance(&"first".into()).unwrap();
+
+        // entries of the dropped instance stay while the host still refers to it
+        assert_eq!(
+            registry.collect_garbage(std::slice::from_ref(&first), &[]),
+            0
+        );
+        assert_eq!(registry.collect_garbage(&[], &[]), 3);
+        drop(registry);
+
+        assert!(store.mems.get(first_mem).is_none());
+        assert!(store.globals.get(second_global).is_some());
+
+        for expected in 1..=2 {
+            let result = run_func(
+                second.clone(),
+                &module,
+                "inc",
+                vec![],
+                &mut stack,
+                &mut store,
+            )
+            .unwrap();
+            assert_eq!(result, vec![Val::I32(expected)]);
+        }
+    }
+
+    #[test]
+    fn test_import_types_checked_at_instantiation() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let counter = ValidatedModule::new(counter_module()).unwrap();
+        let counter_inst =
+            ModuleInst::instantiate(&mut store, &mut stack, &counter, vec![]).unwrap();
+        let inc = || ExternalDependency::Func {
+            func_addr: counter_inst.borrow().funcaddrs[0],
+            func_type: counter_module().types[0].clone(),
+        };
+        let import = |results: Vec<ValType>| {
+            ValidatedModule::new(Module {
+                types: vec![FuncType {
+                    parameters: vec![],
+                    results,
+                }],
+                imports: vec![ImportType {
+                    module: NameType("counter".into()),
+                    name: NameType("inc".into()),
+                    desc: ImportDescription::Func(TypeIdx(U32Type(0))),
+                }],
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let expecting_i32 = import(vec![ValType::NumType(NumType::I32)]);
+        let expecting_i64 = import(vec![ValType::NumType(NumType::I64)]);
+
+        assert!(matches!(
+            ModuleInst::instantiate(&mut store, &mut stack, &expecting_i32, vec![]),
+            Err(InstantiationError::ImportCountMismatch {
+                expected: 1,
+                actual: 0
+            })
+        ));
+        assert!(matches!(
+            ModuleInst::instantiate(&mut store, &mut stack, &expecting_i64, vec![inc()]),
+            Err(InstantiationError::IncompatibleImportType { .. })
+        ));
+
+        // the type of the store entry is checked, not the one given with the external
+        let claimed_i64 = ExternalDependency::Func {
+            func_addr: counter_inst.borrow().funcaddrs[0],
+            func_type: expecting_i64.types[0].clone(),
+        };
+        assert!(matches!(
+            ModuleInst::instantiate(&mut store, &mut stack, &expecting_i64, vec![claimed_i64]),
+            Err(InstantiationError::IncompatibleImportType { .. })
+        ));
+        // an address of another generation refers to an entry removed from the slot
+        let inc_addr = counter_inst.borrow().funcaddrs[0];
+        let removed = ExternalDependency::Func {
+            func_addr: Addr {
+                generation: inc_addr.generation + 1,
+                ..inc_addr
+            },
+            func_type: counter_module().types[0].clone(),
+        };
+        assert!(matches!(
+            ModuleInst::instantiate(&mut store, &mut stack, &expecting_i32, vec![removed]),
+            Err(InstantiationError::UnknownExternal { index: 0 })
+        ));
+        for _ in 0..2 {
+            assert!(
+                ModuleInst::instantiate(&mut store, &mut stack, &expecting_i32, vec![inc()])
+                    .is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_incompatible_export_at_link_time() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+        let global_type = GlobalType {
+            mut_type: MutType::Const,
+            val_type: ValType::NumType(NumType::I32),
+        };
+        // "inc" of the counter module is a function, not a global
+        let main = Module {
+            imports: vec![ImportType {
+                module: NameType("counter".into()),
+                name: NameType("inc".into()),
+                desc: ImportDescription::Global(global_type),
+            }],
+            ..Default::default()
+        };
+
+        registry
+            .register_parsed("counter".into(), counter_module())
+            .unwrap();
+        registry.register_parsed("main".into(), main).unwrap();
+
+        assert!(matches!(
+            registry.get_instance(&"main".into()),
+            Err(ModuleRegistryError::IncompatibleImportType { .. })
+        ));
+        // the validated module is kept, the dependency was instantiated before the mismatch
+        assert!(registry.get_module(&"main".into()).is_some());
+        assert!(registry.get_instance(&"counter".into()).is_ok());
+    }
+
+    #[test]
+    fn test_drop_imported_instance() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+
+        registry
+            .register_module("factorial-lib".into(), file_path("factorial-lib.wasm"))
+            .unwrap();
+        registry
+            .register_module("main".into(), file_path("factorial-main.wasm"))
+            .unwrap();
+        registry
+            .instantiate(&"main".into(), "request".into())
+            .unwrap();
+
+        assert!(matches!(
+            registry.drop_instance(&"factorial-lib".into()),
+            Err(ModuleRegistryError::InstanceInUse { .. })
+        ));
+
+        registry.drop_instance(&"request".into()).unwrap();
+        registry.drop_instance(&"factorial-lib".into()).unwrap();
+    }
+
+    #[test]
+    fn test_collect_garbage_keeps_registered_instances() {
+        let mut store = Store::new();
+        let mut stack = Stack::new();
+        let mut registry = ModuleRegistry::new(&mut store, &mut stack);
+        let module = counter_module();
+
+        registry
+            .register_parsed("counter".into(), module.clone())
+            .unwrap();
+        let kept = registry.get_instance(&"counter".into()).unwrap();
+        let host_owned = registry
+            .instantiate(&"counter".into(), "host".into())
+            .unwrap();
+        registry.instances.borrow_mut().remove("host");
+
+        assert_eq!(
+            registry.collect_garbage(std::slice::from_ref(&host_owned), &[]),
+            0
+        );
+        assert_eq!(registry.collect_garbage(&[], &[]), 3);
+        drop(registry);
+
+        assert!(store.mems.get(host_owned.borrow().memaddrs[0]).is_none());
+        let result = run_func(kept, &module, "inc", vec![], &mut stack, &mut store).unwrap();
+        assert_eq!(result, vec![Val::I32(1)]);
+    }
+}
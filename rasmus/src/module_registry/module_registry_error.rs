@@ -1,4 +1,147 @@
+use crate::{
+    binary::syntax_error::SyntaxError,
+    entities::types::{ExternKind, ExternType},
+    instances::{func::LazyFuncError, module::InstantiationError},
+    result::Trap,
+    validation::validation_error::ModuleValidationError,
+};
+
+#[derive(Debug)]
 pub enum ModuleRegistryError {
-    UnableToReadModule { path: String },
-    ModuleNotRegistered { name: String },
+    UnableToReadModule {
+        path: String,
+    },
+    // Modules in the text format can be printed but not parsed
+    TextFormatNotSupported {
+        path: String,
+    },
+    ModuleNotRegistered {
+        name: String,
+    },
+    ModuleAlreadyRegistered {
+        name: String,
+    },
+    // Another live instance imports from the instance
+    InstanceInUse {
+        name: String,
+        dependent: String,
+    },
+    InvalidModule {
+        name: String,
+        error: SyntaxError,
+    },
+    // The module is well-formed but fails validation
+    ValidationFailed {
+        name: String,
+        error: ModuleValidationError,
+    },
+    // Neither a registered module nor its export with the name exists
+    UnresolvedImport {
+        module: String,
+        name: String,
+        kind: ExternKind,
+    },
+    // The type of the export does not match the type declared by the import
+    IncompatibleImportType {
+        expected: ExternType,
+        actual: ExternType,
+    },
+    // Number of externals given for the imports of the module differs from the number of imports
+    ImportCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    // The store entry of the external given for the import at the index was removed
+    UnknownExternal {
+        index: usize,
+    },
+    // Modules of the cycle, the first one is repeated at the end
+    CircularDependency {
+        cycle: Vec<String>,
+    },
+    // The start function called a malformed or invalid lazy function body
+    InvalidFunction(LazyFuncError),
+    Trap(Trap),
+}
+
+impl From<Trap> for ModuleRegistryError {
+    fn from(trap: Trap) -> Self {
+        ModuleRegistryError::Trap(trap)
+    }
+}
+
+impl From<InstantiationError> for ModuleRegistryError {
+    fn from(error: InstantiationError) -> Self {
+        match error {
+            InstantiationError::ImportCountMismatch { expected, actual } => {
+                ModuleRegistryError::ImportCountMismatch { expected, actual }
+            }
+            InstantiationError::IncompatibleImportType { expected, actual } => {
+                ModuleRegistryError::IncompatibleImportType { expected, actual }
+            }
+            InstantiationError::UnknownExternal { index } => {
+                ModuleRegistryError::UnknownExternal { index }
+            }
+            InstantiationError::Trap(trap) => ModuleRegistryError::Trap(trap),
+            InstantiationError::InvalidFunction(error) => {
+                ModuleRegistryError::InvalidFunction(error)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ModuleRegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModuleRegistryError::UnableToReadModule { path } => {
+                write!(f, "unable to read module at {path}")
+            }
+            ModuleRegistryError::TextFormatNotSupported { path } => {
+                write!(
+                    f,
+                    "module at {path} is in the text format, only .wasm binaries are supported"
+                )
+            }
+            ModuleRegistryError::ModuleNotRegistered { name } => {
+                write!(f, "module {name} is not registered")
+            }
+            ModuleRegistryError::ModuleAlreadyRegistered { name } => {
+                write!(f, "module {name} is already registered")
+            }
+            ModuleRegistryError::InstanceInUse { name, dependent } => {
+                write!(f, "instance {name} is imported by instance {dependent}")
+            }
+            ModuleRegistryError::InvalidModule { name, error } => {
+                write!(f, "module {name} is malformed, {error}")
+            }
+            ModuleRegistryError::ValidationFailed { name, error } => {
+                write!(f, "module {name} is invalid, {error}")
+            }
+            ModuleRegistryError::UnresolvedImport { module, name, kind } => {
+                write!(f, "unresolved import {module}.{name} of kind {kind:?}")
+            }
+            ModuleRegistryError::IncompatibleImportType { expected, actual } => {
+                write!(
+                    f,
+                    "incompatible import type, expected {expected:?}, got {actual:?}"
+                )
+            }
+            ModuleRegistryError::ImportCountMismatch { expected, actual } => {
+                write!(f, "{actual} externals given for {expected} imports")
+            }
+            ModuleRegistryError::UnknownExternal { index } => {
+                write!(
+                    f,
+                    "external given for import #{index} was removed from the store"
+                )
+            }
+            ModuleRegistryError::CircularDependency { cycle } => {
+                write!(f, "circular dependency {}", cycle.join(" -> "))
+            }
+            ModuleRegistryError::InvalidFunction(error) => {
+                write!(f, "instantiation failed, {error}")
+            }
+            ModuleRegistryError::Trap(trap) => write!(f, "instantiation trapped: {trap:?}"),
+        }
+    }
 }
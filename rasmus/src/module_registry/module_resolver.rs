@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::entities::module::Module;
+
+use super::module_registry::{parse_module_bytes, ModuleName};
+use super::ModuleRegistryError;
+
+/// Source of modules which are imported but have not been registered.
+/// Resolvers are asked in the order they were added to the registry.
+pub trait ModuleResolver {
+    /// Returns `Ok(None)` when the module is unknown to the resolver
+    fn resolve(&self, name: &ModuleName) -> Result<Option<Module>, ModuleRegistryError>;
+}
+
+// In-memory bundle of module binaries keyed by module name
+impl ModuleResolver for HashMap<ModuleName, Vec<u8>> {
+    fn resolve(&self, name: &ModuleName) -> Result<Option<Module>, ModuleRegistryError> {
+        self.get(name)
+            .map(|bytes| parse_module_bytes(name, bytes))
+            .transpose()
+    }
+}
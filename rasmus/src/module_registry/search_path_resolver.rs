@@ -0,0 +1,120 @@
+use std::env;
+use std::fs::read;
+use std::path::{Path, PathBuf};
+
+use crate::entities::module::Module;
+
+use super::module_registry::{parse_module_bytes, ModuleName};
+use super::{ModuleRegistryError, ModuleResolver};
+
+pub const SEARCH_PATH_ENV: &str = "RASMUS_PATH";
+
+/// Resolves an import module name `<name>` to the file `<dir>/<name>.wasm`,
+/// directories are searched in order and the first match wins.
+/// Only binary modules are supported, a `<dir>/<name>.wat` found first fails the resolution
+/// with `TextFormatNotSupported` instead of being skipped.
+#[derive(Debug, Default)]
+pub struct SearchPathResolver {
+    paths: Vec<PathBuf>,
+}
+
+impl SearchPathResolver {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        SearchPathResolver { paths }
+    }
+
+    /// Directories listed in `RASMUS_PATH`, separated the same way as in `PATH`
+    pub fn from_env() -> Self {
+        let paths = env::var_os(SEARCH_PATH_ENV)
+            .map(|value| env::split_paths(&value).collect())
+            .unwrap_or_default();
+
+        SearchPathResolver { paths }
+    }
+
+    pub fn add_path(&mut self, path: impl Into<PathBuf>) {
+        self.paths.push(path.into());
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    // Module names containing path components would escape the search directories
+    fn is_plain_name(name: &ModuleName) -> bool {
+        !name.is_empty()
+            && name != "."
+            && name != ".."
+            && !name.contains(['/', '\\'])
+            && Path::new(name).components().count() == 1
+    }
+}
+
+impl ModuleResolver for SearchPathResolver {
+    fn resolve(&self, name: &ModuleName) -> Result<Option<Module>, ModuleRegistryError> {
+        if !Self::is_plain_name(name) {
+            return Ok(None);
+        }
+
+        for dir in &self.paths {
+            let binary_path = dir.join(format!("{name}.wasm"));
+            if binary_path.is_file() {
+                let bytes =
+                    read(&binary_path).map_err(|_| ModuleRegistryError::UnableToReadModule {
+                        path: binary_path.display().to_string(),
+                    })?;
+
+                return parse_module_bytes(name, &bytes).map(Some);
+            }
+
+            let text_path = dir.join(format!("{name}.wat"));
+            if text_path.is_file() {
+                return Err(ModuleRegistryError::TextFormatNotSupported {
+                    path: text_path.display().to_string(),
+                });
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn files_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/files")
+    }
+
+    #[test]
+    fn test_resolve_from_search_paths() {
+        let resolver = SearchPathResolver::new(vec![
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            files_dir(),
+        ]);
+
+        assert!(resolver.resolve(&"factorial-lib".into()).unwrap().is_some());
+        assert!(resolver.resolve(&"missing".into()).unwrap().is_none());
+        assert!(resolver
+            .resolve(&"../files/factorial-lib".into())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_text_format_not_supported() {
+        let dir = env::temp_dir().join(format!("rasmus-search-path-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("text-lib.wat"), "(module)").unwrap();
+        let resolver = SearchPathResolver::new(vec![dir.clone(), files_dir()]);
+
+        let result = resolver.resolve(&"text-lib".into());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(ModuleRegistryError::TextFormatNotSupported { .. })
+        ));
+    }
+}
@@ -0,0 +1,42 @@
+use std::cell::Cell;
+
+/// Default maximum nesting of blocks and calls on a thread.
+/// Parsing, validation and execution recurse on the native stack for every nested block,
+/// execution also for every call. Deeper nesting is rejected instead of exhausting the stack,
+/// the limit fits into the 2 MiB stack of a spawned thread even for debug builds.
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+thread_local! {
+    static NESTING_DEPTH: Cell<usize> = const { Cell::new(0) };
+    static NESTING_LIMIT: Cell<usize> = const { Cell::new(MAX_NESTING_DEPTH) };
+}
+
+/// Changes the maximum nesting of blocks and calls on the current thread,
+/// e.g. for compiled switches nesting a block per case or for deep recursion.
+/// A debug build needs a few KiB of stack per level, release builds far less.
+pub fn set_max_nesting_depth(depth: usize) {
+    NESTING_LIMIT.with(|limit| limit.set(depth));
+}
+
+/// One level of nesting, the level is left when the guard is dropped
+pub(crate) struct NestingGuard {
+    depth: usize,
+}
+
+/// Enters one more level of nesting, None when the limit of the thread is reached
+pub(crate) fn enter_nesting() -> Option<NestingGuard> {
+    let depth = NESTING_DEPTH.with(|depth| depth.get());
+    if depth >= NESTING_LIMIT.with(|limit| limit.get()) {
+        return None;
+    }
+
+    NESTING_DEPTH.with(|d| d.set(depth + 1));
+
+    Some(NestingGuard { depth })
+}
+
+impl Drop for NestingGuard {
+    fn drop(&mut self) {
+        NESTING_DEPTH.with(|d| d.set(self.depth));
+    }
+}
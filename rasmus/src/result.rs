@@ -1,9 +1,38 @@
-use crate::{binary::syntax_error::SyntaxError, module_registry::ModuleRegistryError};
+use crate::{
+    address::TagAddr,
+    binary::syntax_error::SyntaxError,
+    entities::types::ValType,
+    instances::{func::LazyFuncError, value::Val},
+    module_registry::ModuleRegistryError,
+};
 
 pub type RResult<T> = Result<T, Trap>;
 
+/// Abrupt termination of the execution. A call to a malformed or invalid lazy function body
+/// keeps the error of the body, other traps have no cause.
 #[derive(Debug)]
-pub struct Trap;
+pub struct Trap {
+    invalid_function: Option<Box<LazyFuncError>>,
+}
+
+/// Trap without a cause
+#[allow(non_upper_case_globals)]
+pub const Trap: Trap = Trap {
+    invalid_function: None,
+};
+
+impl Trap {
+    pub fn invalid_function(error: LazyFuncError) -> Self {
+        Trap {
+            invalid_function: Some(Box::new(error)),
+        }
+    }
+
+    /// Error of the lazy function body which caused the trap
+    pub fn take_invalid_function(&mut self) -> Option<LazyFuncError> {
+        self.invalid_function.take().map(|error| *error)
+    }
+}
 
 impl From<ModuleRegistryError> for Trap {
     fn from(_registry_error: ModuleRegistryError) -> Self {
@@ -16,3 +45,35 @@ impl From<SyntaxError> for Trap {
         Trap
     }
 }
+
+/// Reason of an abrupt termination of an invocation made by the embedder
+#[derive(Debug)]
+pub enum RuntimeError {
+    Trap(Trap),
+    // An exception has left the invoked function without reaching any try_table handler
+    UncaughtException { tag: TagAddr, values: Vec<Val> },
+    // A lazy function body called by the invocation is malformed or invalid
+    InvalidFunction(LazyFuncError),
+}
+
+impl From<Trap> for RuntimeError {
+    fn from(mut trap: Trap) -> Self {
+        match trap.take_invalid_function() {
+            Some(error) => RuntimeError::InvalidFunction(error),
+            None => RuntimeError::Trap(trap),
+        }
+    }
+}
+
+/// Misuse of a table or global handle by the embedder, the table or global is left unchanged
+#[derive(Debug, PartialEq)]
+pub enum HandleError {
+    // The entry of the handle was removed from the store
+    UnknownEntry,
+    // The value doesn't have the type of the global or the reference the element type of the table
+    TypeMismatch { expected: ValType, actual: ValType },
+    ImmutableGlobal,
+    OutOfBounds { index: u32, size: u32 },
+    // The maximum of the table or the resource limiter doesn't allow the table to grow
+    GrowthRefused,
+}
@@ -1,4 +1,12 @@
-use crate::entities::module::InstructionType;
+use crate::entities::instructions::{BlockInstructionType, BlockType};
+use crate::entities::module::{
+    CodeType, ExportDescription, ExportType, ExpressionType, FuncCodeType, Global,
+    ImportDescription, ImportType, InstructionType, MemArgType, Module,
+};
+use crate::entities::types::{
+    FuncIdx, FuncType, GlobalType, IndexType, LimitsType, MemIdx, MemType, MutType, NameType,
+    NumType, RefType, TableType, TypeIdx, U32Type, U64Type, ValType,
+};
 
 use crate::instances::{stack::Stack, store::Store, value::Val};
 
@@ -41,3 +49,111 @@ pub fn test_instruction_with_stack_and_store(
         assert!(false, "stack should contain value");
     }
 }
+
+pub fn i32_type() -> ValType {
+    ValType::NumType(NumType::I32)
+}
+
+/// Table of one entry without a maximum
+pub fn table_type(element_ref_type: RefType) -> TableType {
+    TableType {
+        limits: LimitsType {
+            min: U64Type(1),
+            max: None,
+        },
+        element_ref_type,
+    }
+}
+
+/// Unshared 32-bit memory without a maximum
+pub fn mem_type(min: u64) -> MemType {
+    MemType {
+        limits: LimitsType {
+            min: U64Type(min),
+            max: None,
+        },
+        index_type: IndexType::I32,
+        shared: false,
+    }
+}
+
+pub fn memarg(align: u32, memory: u32) -> MemArgType {
+    MemArgType {
+        align: U32Type(align),
+        offset: U64Type(0),
+        memory: MemIdx(U32Type(memory)),
+    }
+}
+
+pub fn i32_global(mut_type: MutType, init: Vec<InstructionType>) -> Global {
+    Global {
+        global_type: GlobalType {
+            mut_type,
+            val_type: i32_type(),
+        },
+        init: ExpressionType::new(init),
+    }
+}
+
+/// Empty blocks nested `depth` times
+pub fn nested_blocks(depth: usize) -> Vec<InstructionType> {
+    let mut instructions = vec![];
+    for _ in 0..depth {
+        instructions = vec![InstructionType::Block(BlockInstructionType {
+            blocktype: BlockType::Empty,
+            instructions,
+        })];
+    }
+    instructions
+}
+
+/// Body of a function without locals
+pub fn code(instructions: Vec<InstructionType>) -> CodeType {
+    CodeType {
+        size: U32Type(0),
+        code: FuncCodeType {
+            locals: vec![],
+            expression: ExpressionType::new(instructions),
+        }
+        .into(),
+    }
+}
+
+pub fn import(module: &str, name: &str, desc: ImportDescription) -> ImportType {
+    ImportType {
+        module: NameType(module.into()),
+        name: NameType(name.into()),
+        desc,
+    }
+}
+
+pub fn export(name: &str, desc: ExportDescription) -> ExportType {
+    ExportType {
+        name: NameType(name.into()),
+        desc,
+    }
+}
+
+pub fn func_export(name: &str, func_idx: u32) -> ExportType {
+    export(name, ExportDescription::Func(FuncIdx(U32Type(func_idx))))
+}
+
+/// Module with a single function of type [] -> [i32] running `instructions`, exported as "run"
+pub fn module_with_run_func(
+    imports: Vec<ImportType>,
+    mems: Vec<MemType>,
+    instructions: Vec<InstructionType>,
+) -> Module {
+    Module {
+        types: vec![FuncType {
+            parameters: vec![],
+            results: vec![ValType::NumType(NumType::I32)],
+        }],
+        imports,
+        funcs: vec![TypeIdx(U32Type(0))],
+        mems,
+        exports: vec![func_export("run", 0)],
+        code: vec![code(instructions)],
+        ..Default::default()
+    }
+}
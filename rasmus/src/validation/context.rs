@@ -1,6 +1,9 @@
-use crate::entities::{
-    module::{DataType, Module},
-    types::*,
+use crate::{
+    entities::{
+        module::{DataType, Module},
+        types::*,
+    },
+    validation::module::create_context,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -10,8 +13,11 @@ pub struct ValidationContext {
     pub tables: Vec<TableType>,
     pub mems: Vec<MemType>,
     pub globals: Vec<GlobalType>,
+    pub tags: Vec<FuncType>,
     pub elems: Vec<RefType>,
     pub datas: Vec<DataType>,
+    // memory.init and data.drop require the data count section
+    pub data_count: Option<u32>,
     pub locals: Vec<ValType>,
     pub labels: Vec<ResultType>,
     pub maybe_return: Option<ResultType>,
@@ -20,26 +26,6 @@ pub struct ValidationContext {
 
 impl From<&Module> for ValidationContext {
     fn from(module: &Module) -> Self {
-        ValidationContext {
-            types: module.types.clone(),
-            funcs: module
-                .funcs
-                .iter()
-                .map(|idx| module.types[idx.0 .0 as usize].clone())
-                .collect(),
-            tables: module.tables.clone(),
-            mems: module.mems.clone(),
-            globals: module
-                .globals
-                .iter()
-                .map(|g| g.global_type.clone())
-                .collect(),
-            elems: module.elems.iter().map(|e| e.get_type()).collect(),
-            datas: module.datas.clone(),
-            locals: vec![],
-            labels: Vec::new(),
-            maybe_return: None,
-            refs: vec![],
-        }
+        create_context(module)
     }
 }
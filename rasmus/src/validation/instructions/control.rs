@@ -1,10 +1,13 @@
 use crate::{
     entities::{
         instructions::{
-            BlockInstructionType, BlockType, IfElseInstructionType, InstructionType,
-            LoopInstructionType,
+            BlockInstructionType, BlockType, CatchType, IfElseInstructionType, InstructionType,
+            LoopInstructionType, TryTableInstructionType,
+        },
+        types::{
+            FuncIdx, FuncType, LabelIdx, RefType, ResultType, TableIdx, TagIdx, TypeIdx, U32Type,
+            ValType,
         },
-        types::{FuncIdx, LabelIdx, RefType, ResultType, TableIdx, TypeIdx, U32Type, ValType},
     },
     validation::{
         context::ValidationContext,
@@ -41,16 +44,13 @@ pub fn block(
     let mut block_ctx = ctx.clone();
     block_ctx.labels.insert(0, ResultType(output_types.clone()));
 
-    for instruction in &block_instruction_type.instructions {
+    for (i, instruction) in block_instruction_type.instructions.iter().enumerate() {
+        val_stack.set_position(i);
         validate_instruction(&instruction, &mut block_ctx, val_stack)?;
     }
+    val_stack.set_position(block_instruction_type.instructions.len());
 
-    let return_types = val_stack.pop_vals(&output_types.iter().map(Into::into).collect())?;
-
-    end(val_stack)?;
-
-    val_stack.push_vals_2(return_types);
-    Ok(())
+    end(val_stack)
 }
 
 pub fn loop_instr(
@@ -69,24 +69,21 @@ pub fn loop_instr(
     val_stack.pop_vals(&input_types.iter().map(Into::into).collect())?;
     val_stack.push_ctrl(
         InstructionType::Loop(loop_instruction_type.clone()),
-        input_types,
+        input_types.clone(),
         output_types.clone(),
         false,
     );
 
     let mut loop_ctx = ctx.clone();
-    loop_ctx.labels.insert(0, ResultType(output_types.clone()));
+    loop_ctx.labels.insert(0, ResultType(input_types));
 
-    for instruction in &loop_instruction_type.instructions {
+    for (i, instruction) in loop_instruction_type.instructions.iter().enumerate() {
+        val_stack.set_position(i);
         validate_instruction(&instruction, &mut loop_ctx, val_stack)?;
     }
+    val_stack.set_position(loop_instruction_type.instructions.len());
 
-    let return_types = val_stack.pop_vals(&output_types.iter().map(Into::into).collect())?;
-
-    end(val_stack)?;
-
-    val_stack.push_vals_2(return_types);
-    Ok(())
+    end(val_stack)
 }
 
 pub fn if_else(
@@ -119,11 +116,12 @@ pub fn if_else(
         .labels
         .insert(0, ResultType(output_types.clone()));
 
-    for instruction in &ifelse_instruction_type.if_instructions {
+    let if_len = ifelse_instruction_type.if_instructions.len();
+    for (i, instruction) in ifelse_instruction_type.if_instructions.iter().enumerate() {
+        val_stack.set_position(i);
         validate_instruction(&instruction, &mut ifelse_ctx, val_stack)?;
     }
-
-    val_stack.pop_vals(&output_types.iter().map(Into::into).collect())?;
+    val_stack.set_position(if_len);
 
     let ctrl = val_stack.pop_ctrl()?;
 
@@ -142,11 +140,13 @@ pub fn if_else(
         false,
     );
 
-    for instruction in &ifelse_instruction_type.else_instructions {
+    // the else branch is numbered after the if branch
+    let else_instructions = &ifelse_instruction_type.else_instructions;
+    for (i, instruction) in else_instructions.iter().enumerate() {
+        val_stack.set_position(if_len + i);
         validate_instruction(&instruction, &mut ifelse_ctx, val_stack)?;
     }
-
-    let return_types = val_stack.pop_vals(&output_types.iter().map(Into::into).collect())?;
+    val_stack.set_position(if_len + else_instructions.len());
 
     let ctrl = val_stack.pop_ctrl()?;
 
@@ -157,7 +157,7 @@ pub fn if_else(
         }
     }
 
-    val_stack.push_vals_2(return_types);
+    val_stack.push_vals_2(ctrl.end_types);
     Ok(())
 }
 
@@ -165,74 +165,67 @@ pub fn br(
     val_stack: &mut ValidationStack,
     &LabelIdx(U32Type(label_idx)): &LabelIdx,
 ) -> ValidationResult<()> {
-    let ctrl_frame = val_stack
-        .get_ctrl(label_idx as usize)
-        .ok_or_else(|| ValidationError::InsufficientOperandStackForInstruction)?;
-
-    let vals = val_stack.pop_vals(&label_types(ctrl_frame).clone())?;
+    let types = label_types(
+        val_stack
+            .get_label(label_idx as usize)
+            .ok_or(ValidationError::LabelNotFound)?,
+    )
+    .clone();
 
-    val_stack.unreachable()?;
-    end(val_stack)?;
-    val_stack.push_vals_2(vals);
-
-    Ok(())
+    val_stack.pop_vals(&types)?;
+    val_stack.unreachable()
 }
 
 pub fn br_if(
     val_stack: &mut ValidationStack,
     &LabelIdx(U32Type(label_idx)): &LabelIdx,
 ) -> ValidationResult<()> {
-    let ctrl_frame = val_stack
-        .get_ctrl(label_idx as usize)
-        .ok_or_else(|| ValidationError::InsufficientOperandStackForInstruction)?;
+    let types = label_types(
+        val_stack
+            .get_label(label_idx as usize)
+            .ok_or(ValidationError::LabelNotFound)?,
+    )
+    .clone();
 
-    let types = label_types(ctrl_frame).clone();
     val_stack.pop_val_expect(ValidationType::i32())?;
-    val_stack.pop_vals(&types)?;
-    // FIXME: re-check if it should be removed
-    // val_stack.push_vals_2(types);
-    val_stack.unreachable()?;
-    end(val_stack)?;
-    val_stack.push_vals_2(types);
+    // the operands stay on the stack when the branch is not taken
+    let vals = val_stack.pop_vals(&types)?;
+    val_stack.push_vals_2(vals);
 
     Ok(())
 }
 
 pub fn br_table(
     val_stack: &mut ValidationStack,
-    (frames, LabelIdx(U32Type(m))): &(Vec<LabelIdx>, LabelIdx),
+    (labels, LabelIdx(U32Type(default))): &(Vec<LabelIdx>, LabelIdx),
 ) -> ValidationResult<()> {
     val_stack.pop_val_expect(ValidationType::i32())?;
 
-    if val_stack.ctrl_len() < *m as usize {
-        return Err(ValidationError::InsufficientOperandStackForInstruction);
-    }
-
-    let ctrl_frame = val_stack
-        .get_ctrl(*m as usize)
-        .ok_or_else(|| ValidationError::InsufficientOperandStackForInstruction)?;
-
-    let types = label_types(ctrl_frame).clone();
-    let arity = types.len();
-
-    for ref frame in frames {
-        let ctrl_frame = val_stack
-            .get_ctrl(frame.0 .0 as usize)
-            .ok_or_else(|| ValidationError::InsufficientOperandStackForInstruction)?;
-        let label_types_val = label_types(ctrl_frame).clone();
-        if label_types_val.len() != arity {
+    let types = label_types(
+        val_stack
+            .get_label(*default as usize)
+            .ok_or(ValidationError::LabelNotFound)?,
+    )
+    .clone();
+
+    for LabelIdx(U32Type(label_idx)) in labels {
+        let label_types = label_types(
+            val_stack
+                .get_label(*label_idx as usize)
+                .ok_or(ValidationError::LabelNotFound)?,
+        )
+        .clone();
+
+        if label_types.len() != types.len() {
             return Err(ValidationError::NotConsistentArity);
         }
-        val_stack.push_vals_2(label_types_val);
-    }
 
-    let vals = val_stack.pop_vals(&types)?;
-    val_stack.unreachable()?;
-
-    end(val_stack)?;
-    val_stack.push_vals_2(vals);
+        let vals = val_stack.pop_vals(&label_types)?;
+        val_stack.push_vals_2(vals);
+    }
 
-    Ok(())
+    val_stack.pop_vals(&types)?;
+    val_stack.unreachable()
 }
 
 pub fn return_instr(
@@ -307,6 +300,173 @@ pub fn call_indirect(
     Ok(())
 }
 
+pub fn return_call(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    FuncIdx(U32Type(func_idx)): &FuncIdx,
+) -> ValidationResult<()> {
+    let idx_usize = *func_idx as usize;
+    let func_type = ctx
+        .funcs
+        .get(idx_usize)
+        .ok_or(ValidationError::FuncTypeNotFound {
+            func_idx: idx_usize,
+        })?;
+
+    check_tail_call_results(ctx, func_type)?;
+
+    val_stack.pop_vals(&func_type.parameters.iter().map(Into::into).collect())?;
+    val_stack.unreachable()?;
+
+    Ok(())
+}
+
+pub fn return_call_indirect(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    (TableIdx(U32Type(table_idx)), TypeIdx(U32Type(type_idx))): &(TableIdx, TypeIdx),
+) -> ValidationResult<()> {
+    let table_idx_usize = *table_idx as usize;
+    let type_idx_usize = *type_idx as usize;
+
+    let table_type = &ctx
+        .tables
+        .get(table_idx_usize)
+        .ok_or(ValidationError::TableNotFound)?
+        .element_ref_type;
+
+    if table_type != &RefType::FuncRef {
+        return Err(ValidationError::UnexpectedRefType {
+            actual: table_type.clone(),
+            expected: RefType::FuncRef,
+        });
+    }
+
+    let func_type = ctx
+        .types
+        .get(type_idx_usize)
+        .ok_or(ValidationError::FuncTypeNotFound {
+            func_idx: type_idx_usize,
+        })?;
+
+    check_tail_call_results(ctx, func_type)?;
+
+    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_vals(&func_type.parameters.iter().map(Into::into).collect())?;
+    val_stack.unreachable()?;
+
+    Ok(())
+}
+
+pub fn throw(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    tag_idx: &TagIdx,
+) -> ValidationResult<()> {
+    let tag_type = get_tag_type(ctx, tag_idx)?;
+
+    val_stack.pop_vals(&tag_type.parameters.iter().map(Into::into).collect())?;
+    val_stack.unreachable()?;
+
+    Ok(())
+}
+
+pub fn throw_ref(val_stack: &mut ValidationStack) -> ValidationResult<()> {
+    val_stack.pop_val_expect(ValidationType::reference())?;
+    val_stack.unreachable()?;
+
+    Ok(())
+}
+
+pub fn try_table(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    try_table_instruction_type: &TryTableInstructionType,
+    validate_instruction: impl Fn(
+        &InstructionType,
+        &ValidationContext,
+        &mut ValidationStack,
+    ) -> ValidationResult<()>,
+) -> ValidationResult<()> {
+    // catch labels are resolved before the label of the try_table is added
+    for catch in &try_table_instruction_type.catches {
+        validate_catch(ctx, catch)?;
+    }
+
+    let (input_types, output_types): (Vec<ValType>, Vec<ValType>) =
+        get_input_output_types(&try_table_instruction_type.blocktype, ctx)?;
+
+    val_stack.pop_vals(&input_types.iter().map(Into::into).collect())?;
+    val_stack.push_ctrl(
+        InstructionType::TryTable(try_table_instruction_type.clone()),
+        input_types,
+        output_types.clone(),
+        false,
+    );
+
+    let mut try_table_ctx = ctx.clone();
+    try_table_ctx
+        .labels
+        .insert(0, ResultType(output_types.clone()));
+
+    for (i, instruction) in try_table_instruction_type.instructions.iter().enumerate() {
+        val_stack.set_position(i);
+        validate_instruction(instruction, &try_table_ctx, val_stack)?;
+    }
+    val_stack.set_position(try_table_instruction_type.instructions.len());
+
+    end(val_stack)
+}
+
+fn validate_catch(ctx: &ValidationContext, catch: &CatchType) -> ValidationResult<()> {
+    let exnref = ValType::RefType(RefType::ExnRef);
+    let (label_idx, expected_types) = match catch {
+        CatchType::Catch((tag_idx, label_idx)) => {
+            (label_idx, get_tag_type(ctx, tag_idx)?.parameters.clone())
+        }
+        CatchType::CatchRef((tag_idx, label_idx)) => {
+            let mut types = get_tag_type(ctx, tag_idx)?.parameters.clone();
+            types.push(exnref);
+            (label_idx, types)
+        }
+        CatchType::CatchAll(label_idx) => (label_idx, vec![]),
+        CatchType::CatchAllRef(label_idx) => (label_idx, vec![exnref]),
+    };
+
+    let label_types = ctx
+        .labels
+        .get(label_idx.0 .0 as usize)
+        .ok_or(ValidationError::LabelNotFound)?;
+
+    if label_types.0 != expected_types {
+        return Err(ValidationError::InvalidCatchLabelType);
+    }
+
+    Ok(())
+}
+
+fn get_tag_type<'a>(
+    ctx: &'a ValidationContext,
+    TagIdx(U32Type(tag_idx)): &TagIdx,
+) -> ValidationResult<&'a FuncType> {
+    ctx.tags
+        .get(*tag_idx as usize)
+        .ok_or(ValidationError::TagNotFound)
+}
+
+fn check_tail_call_results(ctx: &ValidationContext, func_type: &FuncType) -> ValidationResult<()> {
+    let return_type = ctx
+        .maybe_return
+        .as_ref()
+        .ok_or(ValidationError::ReturnNotFoundInContext)?;
+
+    if return_type.0 != func_type.results {
+        return Err(ValidationError::InvalidTailCallResultType);
+    }
+
+    Ok(())
+}
+
 pub fn end(val_stack: &mut ValidationStack) -> ValidationResult<()> {
     let frame = val_stack.pop_ctrl()?;
     val_stack.push_vals_2(frame.end_types);
@@ -1,5 +1,8 @@
 use crate::{
-    entities::types::{DataIdx, LaneIdx, U32Type},
+    entities::{
+        instructions::MemArgType,
+        types::{DataIdx, IndexType, LaneIdx, MemIdx, MemType},
+    },
     validation::{
         context::ValidationContext,
         validation_error::{ValidationError, ValidationResult},
@@ -10,11 +13,11 @@ use crate::{
 pub fn load_i32(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, 32, memarg)?;
+    let address_type = check_memarg(ctx, 32, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::i32());
 
     Ok(())
@@ -23,11 +26,11 @@ pub fn load_i32(
 pub fn load_i64(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, 64, memarg)?;
+    let address_type = check_memarg(ctx, 64, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::i64());
 
     Ok(())
@@ -36,11 +39,11 @@ pub fn load_i64(
 pub fn load_f32(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, 32, memarg)?;
+    let address_type = check_memarg(ctx, 32, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::f32());
 
     Ok(())
@@ -49,11 +52,11 @@ pub fn load_f32(
 pub fn load_f64(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, 64, memarg)?;
+    let address_type = check_memarg(ctx, 64, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::f64());
 
     Ok(())
@@ -62,11 +65,11 @@ pub fn load_f64(
 pub fn load_v128(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, 128, memarg)?;
+    let address_type = check_memarg(ctx, 128, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::v128());
 
     Ok(())
@@ -75,12 +78,12 @@ pub fn load_v128(
 pub fn load_i32_t(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
     t: u8,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, t, memarg)?;
+    let address_type = check_memarg(ctx, t, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::i32());
 
     Ok(())
@@ -89,42 +92,28 @@ pub fn load_i32_t(
 pub fn load_i64_t(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
     t: u8,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, t, memarg)?;
+    let address_type = check_memarg(ctx, t, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::i64());
 
     Ok(())
 }
 
-pub fn store_value(
-    val_stack: &mut ValidationStack,
-    ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
-    value_type: ValidationType,
-) -> ValidationResult<()> {
-    check_memarg(ctx, 32, memarg)?;
-
-    val_stack.pop_val_expect(ValidationType::i32())?;
-    val_stack.pop_val_expect(value_type)?;
-
-    Ok(())
-}
-
 pub fn store_value_t(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
     value_type: ValidationType,
     t: u8,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, t, memarg)?;
+    let address_type = check_memarg(ctx, t, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
     val_stack.pop_val_expect(value_type)?;
+    val_stack.pop_val_expect(address_type)?;
 
     Ok(())
 }
@@ -132,18 +121,12 @@ pub fn store_value_t(
 pub fn load_vec_nm(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
     (n, m): (u8, u8),
 ) -> ValidationResult<()> {
-    if ctx.mems.get(0).is_none() {
-        return Err(ValidationError::MemNotFound);
-    }
+    let address_type = check_memarg_with_max_align(ctx, n / 8 * m, memarg)?;
 
-    if 2u8.pow(memarg.0 .0) > n / 8 * m {
-        return Err(ValidationError::MemargAlignTooBig);
-    }
-
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::v128());
 
     Ok(())
@@ -152,12 +135,12 @@ pub fn load_vec_nm(
 pub fn load_vec_splat(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
     t: u8,
 ) -> ValidationResult<()> {
-    check_memarg(ctx, t, memarg)?;
+    let address_type = check_memarg(ctx, t, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::v128());
 
     Ok(())
@@ -166,7 +149,7 @@ pub fn load_vec_splat(
 pub fn load_vec_lane(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
     lane_idx: &LaneIdx,
     n: u8,
 ) -> ValidationResult<()> {
@@ -174,10 +157,10 @@ pub fn load_vec_lane(
         return Err(ValidationError::LaneIdxTooBix);
     }
 
-    check_memarg(ctx, n, memarg)?;
+    let address_type = check_memarg(ctx, n, memarg)?;
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
     val_stack.pop_val_expect(ValidationType::v128())?;
+    val_stack.pop_val_expect(address_type)?;
     val_stack.push_val(ValidationType::v128());
 
     Ok(())
@@ -186,7 +169,7 @@ pub fn load_vec_lane(
 pub fn store_vec_lane(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    memarg: &(U32Type, U32Type),
+    memarg: &MemArgType,
     lane_idx: &LaneIdx,
     n: u8,
 ) -> ValidationResult<()> {
@@ -196,12 +179,11 @@ pub fn store_vec_lane(
 pub fn memory_size(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
+    mem_idx: &MemIdx,
 ) -> ValidationResult<()> {
-    if ctx.mems.get(0).is_none() {
-        return Err(ValidationError::MemNotFound);
-    }
+    let address_type = address_type(check_mem(ctx, mem_idx)?);
 
-    val_stack.push_val(ValidationType::i32());
+    val_stack.push_val(address_type);
 
     Ok(())
 }
@@ -209,13 +191,12 @@ pub fn memory_size(
 pub fn memory_grow(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
+    mem_idx: &MemIdx,
 ) -> ValidationResult<()> {
-    if ctx.mems.get(0).is_none() {
-        return Err(ValidationError::MemNotFound);
-    }
+    let address_type = address_type(check_mem(ctx, mem_idx)?);
 
-    val_stack.pop_val_expect(ValidationType::i32())?;
-    val_stack.push_val(ValidationType::i32());
+    val_stack.pop_val_expect(address_type.clone())?;
+    val_stack.push_val(address_type);
 
     Ok(())
 }
@@ -223,14 +204,33 @@ pub fn memory_grow(
 pub fn memory_fill(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
+    mem_idx: &MemIdx,
 ) -> ValidationResult<()> {
-    if ctx.mems.get(0).is_none() {
-        return Err(ValidationError::MemNotFound);
-    }
+    let address_type = address_type(check_mem(ctx, mem_idx)?);
 
+    val_stack.pop_val_expect(address_type.clone())?;
     val_stack.pop_val_expect(ValidationType::i32())?;
-    val_stack.pop_val_expect(ValidationType::i32())?;
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
+
+    Ok(())
+}
+
+pub fn memory_copy(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    (dst_mem_idx, src_mem_idx): &(MemIdx, MemIdx),
+) -> ValidationResult<()> {
+    let dst_mem_type = check_mem(ctx, dst_mem_idx)?;
+    let src_mem_type = check_mem(ctx, src_mem_idx)?;
+    // length is i64 only when both memories are 64-bit
+    let n_type = match (&dst_mem_type.index_type, &src_mem_type.index_type) {
+        (IndexType::I64, IndexType::I64) => ValidationType::i64(),
+        _ => ValidationType::i32(),
+    };
+
+    val_stack.pop_val_expect(n_type)?;
+    val_stack.pop_val_expect(address_type(src_mem_type))?;
+    val_stack.pop_val_expect(address_type(dst_mem_type))?;
 
     Ok(())
 }
@@ -238,19 +238,20 @@ pub fn memory_fill(
 pub fn memory_init(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
-    data_idx: &DataIdx,
+    (data_idx, mem_idx): &(DataIdx, MemIdx),
 ) -> ValidationResult<()> {
-    if ctx.mems.get(0).is_none() {
-        return Err(ValidationError::MemNotFound);
-    }
+    let address_type = address_type(check_mem(ctx, mem_idx)?);
 
+    if ctx.data_count.is_none() {
+        return Err(ValidationError::DataCountRequired);
+    }
     if ctx.datas.get(data_idx.0 .0 as usize).is_none() {
         return Err(ValidationError::DataNotFound);
     }
 
     val_stack.pop_val_expect(ValidationType::i32())?;
     val_stack.pop_val_expect(ValidationType::i32())?;
-    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
 
     Ok(())
 }
@@ -260,6 +261,9 @@ pub fn data_drop(
     ctx: &ValidationContext,
     data_idx: &DataIdx,
 ) -> ValidationResult<()> {
+    if ctx.data_count.is_none() {
+        return Err(ValidationError::DataCountRequired);
+    }
     if ctx.datas.get(data_idx.0 .0 as usize).is_none() {
         return Err(ValidationError::DataNotFound);
     }
@@ -267,18 +271,158 @@ pub fn data_drop(
     Ok(())
 }
 
-fn check_memarg(
+pub fn atomic_load(
+    val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
+    memarg: &MemArgType,
+    value_type: ValidationType,
     t: u8,
-    memarg: &(U32Type, U32Type),
 ) -> ValidationResult<()> {
-    if ctx.mems.get(0).is_none() {
-        return Err(ValidationError::MemNotFound);
-    }
+    let address_type = check_atomic_memarg(ctx, t, memarg)?;
+
+    val_stack.pop_val_expect(address_type)?;
+    val_stack.push_val(value_type);
+
+    Ok(())
+}
+
+pub fn atomic_store(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    memarg: &MemArgType,
+    value_type: ValidationType,
+    t: u8,
+) -> ValidationResult<()> {
+    let address_type = check_atomic_memarg(ctx, t, memarg)?;
+
+    val_stack.pop_val_expect(value_type)?;
+    val_stack.pop_val_expect(address_type)?;
+
+    Ok(())
+}
 
-    if 2u8.pow(memarg.0 .0) > t / 8 {
+pub fn atomic_rmw(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    memarg: &MemArgType,
+    value_type: ValidationType,
+    t: u8,
+) -> ValidationResult<()> {
+    let address_type = check_atomic_memarg(ctx, t, memarg)?;
+
+    val_stack.pop_val_expect(value_type.clone())?;
+    val_stack.pop_val_expect(address_type)?;
+    val_stack.push_val(value_type);
+
+    Ok(())
+}
+
+pub fn atomic_cmpxchg(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    memarg: &MemArgType,
+    value_type: ValidationType,
+    t: u8,
+) -> ValidationResult<()> {
+    let address_type = check_atomic_memarg(ctx, t, memarg)?;
+
+    val_stack.pop_val_expect(value_type.clone())?;
+    val_stack.pop_val_expect(value_type.clone())?;
+    val_stack.pop_val_expect(address_type)?;
+    val_stack.push_val(value_type);
+
+    Ok(())
+}
+
+pub fn memory_atomic_notify(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    memarg: &MemArgType,
+) -> ValidationResult<()> {
+    let address_type = check_atomic_memarg(ctx, 32, memarg)?;
+
+    val_stack.pop_val_expect(ValidationType::i32())?;
+    val_stack.pop_val_expect(address_type)?;
+    val_stack.push_val(ValidationType::i32());
+
+    Ok(())
+}
+
+pub fn memory_atomic_wait(
+    val_stack: &mut ValidationStack,
+    ctx: &ValidationContext,
+    memarg: &MemArgType,
+    value_type: ValidationType,
+    t: u8,
+) -> ValidationResult<()> {
+    let address_type = check_atomic_memarg(ctx, t, memarg)?;
+
+    val_stack.pop_val_expect(ValidationType::i64())?;
+    val_stack.pop_val_expect(value_type)?;
+    val_stack.pop_val_expect(address_type)?;
+    val_stack.push_val(ValidationType::i32());
+
+    Ok(())
+}
+
+fn check_memarg(
+    ctx: &ValidationContext,
+    t: u8,
+    memarg: &MemArgType,
+) -> ValidationResult<ValidationType> {
+    check_memarg_with_max_align(ctx, t / 8, memarg)
+}
+
+fn check_memarg_with_max_align(
+    ctx: &ValidationContext,
+    max_bytes: u8,
+    memarg: &MemArgType,
+) -> ValidationResult<ValidationType> {
+    let mem_type = check_mem(ctx, &memarg.memory)?;
+
+    if !is_align_valid(memarg, max_bytes) {
         return Err(ValidationError::MemargAlignTooBig);
     }
 
-    Ok(())
+    if mem_type.index_type == IndexType::I32 && memarg.offset.0 > u32::MAX as u64 {
+        return Err(ValidationError::MemargOffsetTooBig);
+    }
+
+    Ok(address_type(mem_type))
+}
+
+fn check_atomic_memarg(
+    ctx: &ValidationContext,
+    t: u8,
+    memarg: &MemArgType,
+) -> ValidationResult<ValidationType> {
+    let address_type = check_memarg(ctx, t, memarg)?;
+
+    if 2u32.pow(memarg.align.0) != (t / 8) as u32 {
+        return Err(ValidationError::MemargAlignNotNatural);
+    }
+
+    Ok(address_type)
+}
+
+pub fn check_mem<'a>(
+    ctx: &'a ValidationContext,
+    mem_idx: &MemIdx,
+) -> ValidationResult<&'a MemType> {
+    ctx.mems
+        .get(mem_idx.0 .0 as usize)
+        .ok_or(ValidationError::MemNotFound)
+}
+
+pub fn address_type(mem_type: &MemType) -> ValidationType {
+    match mem_type.index_type {
+        IndexType::I32 => ValidationType::i32(),
+        IndexType::I64 => ValidationType::i64(),
+    }
+}
+
+fn is_align_valid(memarg: &MemArgType, max_bytes: u8) -> bool {
+    2u32.checked_pow(memarg.align.0)
+        .map(|align_bytes| align_bytes <= max_bytes as u32)
+        .unwrap_or(false)
 }
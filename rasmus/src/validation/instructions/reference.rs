@@ -25,7 +25,8 @@ pub fn ref_func(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
 ) -> ValidationResult<()> {
-    if ctx.funcs.get(func_idx as usize).is_none() || ctx.refs.get(func_idx as usize).is_none() {
+    if ctx.funcs.get(func_idx as usize).is_none() || !ctx.refs.contains(&FuncIdx(U32Type(func_idx)))
+    {
         return Err(ValidationError::CannotFindRefFuncInValidationContext);
     }
 
@@ -1,9 +1,10 @@
-mod context;
+pub mod context;
 
 mod instructions;
 pub mod module;
 pub mod types_validation;
 pub mod validate_instruction;
-mod validation_error;
+pub mod validated_module;
+pub mod validation_error;
 mod validation_macros;
-mod validation_stack;
+pub mod validation_stack;
@@ -1,17 +1,24 @@
+use std::collections::{HashMap, HashSet};
+
 use crate::{
     entities::{
         instructions::{BlockInstructionType, BlockType, ExpressionType, InstructionType},
         module::{
             Active0DataType, Active0ExprElementSegmentType, Active0FunctionsElementSegmentType,
-            ActiveDataType, ActiveRefElementSegmentType, CodeType, DataModeActive, DataModeActive0,
+            ActiveDataType, ActiveRefElementSegmentType, CodeBody, DataModeActive, DataModeActive0,
             DataType, ElemKindActiveFunctionsElementSegmentType, ElemModeActive, ElemModeActive0,
-            ElementSegmentType, ExportDescription, ExportType, Global, ImportDescription,
-            ImportType, Module, StartType,
+            ElementSegmentType, ExportDescription, ExportType, FuncCodeType, Global,
+            ImportDescription, ImportType, Module, StartType,
+        },
+        types::{
+            FuncIdx, FuncType, GlobalType, IndexType, MemIdx, MemType, MutType, NumType,
+            ResultType, TableIdx, TableType, TagType, TypeIdx, U32Type, ValType,
         },
-        types::{FuncIdx, FuncType, MemType, MutType, NumType, ResultType, TableType, ValType},
     },
-    instances::module::ExternalDependency,
-    validation::{validation_error::ValidationError, validation_stack::ValidationStack},
+    validation::{
+        validation_error::{FuncLocation, ModuleValidationError, ValidationError},
+        validation_stack::ValidationStack,
+    },
 };
 
 use super::{
@@ -24,21 +31,33 @@ use super::{
     validation_error::ValidationResult,
 };
 
-pub fn validate(module: &Module, externals: &Vec<ExternalDependency>) -> ValidationResult<()> {
-    let ctx = create_context(module, externals);
-    let sub_ctx = create_sub_context(module, externals);
-    let mut val_stack = ValidationStack::new();
+/// Validates the module against the types its imports declare
+pub fn validate(module: &Module) -> Result<(), ModuleValidationError> {
+    let ctx = create_context(module);
+    let sub_ctx = create_sub_context(module);
+    let imported_funcs = ctx.funcs.len() - module.funcs.len();
 
-    for func_idx in &module.funcs {
-        let idx = func_idx.0 .0 as usize;
-        let func_code = module.code.get(idx).ok_or(ValidationError::CodeNotFound)?;
+    for (i, type_idx) in module.funcs.iter().enumerate() {
+        let func_code = module.code.get(i).ok_or(ValidationError::CodeNotFound)?;
         let func_type = module
-            .funcs
-            .get(idx)
-            .and_then(|func_idx| module.types.get(func_idx.0 .0 as usize))
+            .types
+            .get(type_idx.0 .0 as usize)
             .ok_or(ValidationError::TypeNotFound)?;
 
-        validate_func(&mut val_stack, &ctx, func_type, func_code)?;
+        match func_code.code {
+            CodeBody::Decoded(ref code) => {
+                validate_func_code(&ctx, func_type, code, imported_funcs + i).map_err(
+                    |mut error| {
+                        if let Some(ref mut func) = error.func {
+                            func.name = export_name(module, func.func_idx);
+                        }
+                        error
+                    },
+                )?
+            }
+            // validated on the first call
+            CodeBody::Raw(_) => {}
+        }
     }
 
     if let Some(ref start_type) = module.start {
@@ -49,7 +68,14 @@ pub fn validate(module: &Module, externals: &Vec<ExternalDependency>) -> Validat
         validate_import(&ctx, import_type)?;
     }
 
+    let mut export_names = HashSet::with_capacity(module.exports.len());
     for export_type in &module.exports {
+        if !export_names.insert(&export_type.name.0) {
+            return Err(ValidationError::DuplicateExportName {
+                name: export_type.name.0.clone(),
+            }
+            .into());
+        }
         validate_export(&ctx, export_type)?;
     }
 
@@ -61,6 +87,10 @@ pub fn validate(module: &Module, externals: &Vec<ExternalDependency>) -> Validat
         validate_memory(memory_type)?;
     }
 
+    for tag_type in &module.tags {
+        validate_tag(&ctx, tag_type)?;
+    }
+
     for global in &module.globals {
         validate_global(global, &sub_ctx)?;
     }
@@ -76,16 +106,39 @@ pub fn validate(module: &Module, externals: &Vec<ExternalDependency>) -> Validat
     Ok(())
 }
 
+/// Validates a function body, the error tells where in the body validation stopped
+pub fn validate_func_code(
+    ctx: &ValidationContext,
+    func_type: &FuncType,
+    code: &FuncCodeType,
+    func_idx: usize,
+) -> Result<(), ModuleValidationError> {
+    let mut val_stack = ValidationStack::new();
+
+    validate_func(&mut val_stack, ctx, func_type, code).map_err(|cause| {
+        let path = val_stack.path();
+        ModuleValidationError {
+            func: Some(Box::new(FuncLocation {
+                func_idx,
+                name: None,
+                instruction: instruction_at(&code.expression.instructions, &path).cloned(),
+                path,
+                stack: val_stack.snapshot(),
+            })),
+            cause,
+        }
+    })
+}
+
 fn validate_func(
     val_stack: &mut ValidationStack,
     ctx: &ValidationContext,
     func_type: &FuncType,
-    code: &CodeType,
+    code: &FuncCodeType,
 ) -> ValidationResult<()> {
     let mut func_ctx = ctx.clone();
-    for param in &func_type.parameters {
-        func_ctx.locals.insert(0, param.clone());
-    }
+    func_ctx.locals = func_type.parameters.clone();
+    func_ctx.locals.extend(code.local_types());
     func_ctx.labels = vec![ResultType(func_type.results.clone())];
     func_ctx.maybe_return = Some(ResultType(func_type.results.clone()));
 
@@ -93,20 +146,75 @@ fn validate_func(
         // should be administrative instruction frame
         InstructionType::Block(BlockInstructionType {
             blocktype: BlockType::Empty,
-            instructions: code.code.expression.instructions.clone(),
+            instructions: code.expression.instructions.clone(),
         }),
-        func_type.parameters.clone(),
+        // parameters are locals, the body starts with an empty operand stack
+        vec![],
         func_type.results.clone(),
         false,
     );
 
-    for instruction in &code.code.expression.instructions {
+    for (i, instruction) in code.expression.instructions.iter().enumerate() {
+        val_stack.set_position(i);
         validate_instruction(instruction, &func_ctx, val_stack)?;
     }
+    val_stack.set_position(code.expression.instructions.len());
+
+    // the results of the function are left on the stack by the end of the body
+    val_stack.pop_ctrl()?;
 
     Ok(())
 }
 
+// Follows the positions of a validation stack path, None for the end of a block
+fn instruction_at<'a>(
+    instructions: &'a [InstructionType],
+    path: &[usize],
+) -> Option<&'a InstructionType> {
+    let (&position, rest) = path.split_first()?;
+    let instruction = instructions.get(position)?;
+    let Some(&inner) = rest.first() else {
+        return Some(instruction);
+    };
+
+    match instruction {
+        InstructionType::Block(block) => instruction_at(&block.instructions, rest),
+        InstructionType::Loop(block) => instruction_at(&block.instructions, rest),
+        InstructionType::TryTable(block) => instruction_at(&block.instructions, rest),
+        InstructionType::IfElse(block) => match inner.checked_sub(block.if_instructions.len()) {
+            None => instruction_at(&block.if_instructions, rest),
+            Some(else_position) => {
+                let rest = [&[else_position], &rest[1..]].concat();
+                instruction_at(&block.else_instructions, &rest)
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Names of the first export of every exported function, by function index
+pub fn func_export_names(module: &Module) -> HashMap<usize, String> {
+    let mut names = HashMap::new();
+    for export in &module.exports {
+        if let ExportDescription::Func(ref idx) = export.desc {
+            names
+                .entry(idx.0 .0 as usize)
+                .or_insert_with(|| export.name.0.clone());
+        }
+    }
+
+    names
+}
+
+fn export_name(module: &Module, func_idx: usize) -> Option<String> {
+    module.exports.iter().find_map(|export| match export.desc {
+        ExportDescription::Func(ref idx) if idx.0 .0 as usize == func_idx => {
+            Some(export.name.0.clone())
+        }
+        _ => None,
+    })
+}
+
 fn validate_table(table_type: &TableType) -> ValidationResult<()> {
     if !is_table_type_valid(table_type) {
         return Err(ValidationError::InvalidTableType {
@@ -127,6 +235,19 @@ fn validate_memory(mem_type: &MemType) -> ValidationResult<()> {
     Ok(())
 }
 
+fn validate_tag(ctx: &ValidationContext, tag_type: &TagType) -> ValidationResult<()> {
+    let func_type = ctx
+        .types
+        .get(tag_type.type_idx.0 .0 as usize)
+        .ok_or(ValidationError::TypeNotFound)?;
+
+    if !func_type.results.is_empty() {
+        return Err(ValidationError::InvalidTagType);
+    }
+
+    Ok(())
+}
+
 fn validate_global(global: &Global, ctx: &ValidationContext) -> ValidationResult<()> {
     let global_type = &global.global_type;
     let init = &global.init;
@@ -172,24 +293,22 @@ fn validate_instruction_constant(
         | InstructionType::V128Const(_)
         | InstructionType::RefNull(_)
         | InstructionType::RefFunc(_) => Ok(()),
-        InstructionType::GlobalGet(global_idx) => {
-            // TODO: or of the form global.get x, in which case C.globals[x] must be a global type of the form const t.
-            // shall we recursively validate global init expression to be constant?
-            ctx.globals
-                .get(global_idx.0 .0 as usize)
-                .ok_or_else(|| ValidationError::NonConstantInstruction {
-                    instruction: instr.clone(),
-                })
-                .and_then(|global_type| {
-                    if global_type.mut_type == MutType::Const {
-                        Ok(())
-                    } else {
-                        Err(ValidationError::NonConstantInstruction {
-                            instruction: instr.clone(),
-                        })
-                    }
-                })
-        }
+        // the context of constant expressions only has the imported globals
+        InstructionType::GlobalGet(global_idx) => ctx
+            .globals
+            .get(global_idx.0 .0 as usize)
+            .ok_or_else(|| ValidationError::NonConstantInstruction {
+                instruction: instr.clone(),
+            })
+            .and_then(|global_type| {
+                if global_type.mut_type == MutType::Const {
+                    Ok(())
+                } else {
+                    Err(ValidationError::NonConstantInstruction {
+                        instruction: instr.clone(),
+                    })
+                }
+            }),
         _ => Err(ValidationError::NonConstantInstruction {
             instruction: instr.clone(),
         }),
@@ -219,6 +338,7 @@ fn validate_import(ctx: &ValidationContext, import_type: &ImportType) -> Validat
         ImportDescription::Global(ref global_type) => validate_global_type(global_type),
         ImportDescription::Mem(ref mem_type) => validate_memory(mem_type),
         ImportDescription::Table(ref table_type) => validate_table(table_type),
+        ImportDescription::Tag(ref tag_type) => validate_tag(ctx, tag_type),
     }
 }
 
@@ -242,6 +362,11 @@ fn validate_export(ctx: &ValidationContext, export_type: &ExportType) -> Validat
             .get(table_idx.0 .0 as usize)
             .ok_or_else(|| ValidationError::TableNotFound)
             .and_then(validate_table),
+        ExportDescription::Tag(ref tag_idx) => ctx
+            .tags
+            .get(tag_idx.0 .0 as usize)
+            .map(|_| ())
+            .ok_or(ValidationError::TagNotFound),
     }
 }
 
@@ -249,124 +374,804 @@ fn validate_elem(
     el_segment_type: &ElementSegmentType,
     ctx: &ValidationContext,
 ) -> ValidationResult<()> {
-    let init = el_segment_type.get_init();
-    let result_type = ResultType(vec![ValType::RefType(el_segment_type.get_type())]);
+    let elem_type = el_segment_type.get_type();
+    let result_type = ResultType(vec![ValType::RefType(elem_type.clone())]);
 
-    for expression in init {
+    for expression in el_segment_type.get_init() {
         validate_constant_expression(&expression, &result_type, ctx)?;
     }
 
-    match el_segment_type {
+    let (table_idx, offset) = match el_segment_type {
         ElementSegmentType::Active0Functions(Active0FunctionsElementSegmentType {
             mode: ElemModeActive0 { offset },
             ..
         })
-        | ElementSegmentType::ElemKindActiveFunctions(
+        | ElementSegmentType::Active0Expr(Active0ExprElementSegmentType {
+            mode: ElemModeActive0 { offset },
+            ..
+        }) => (TableIdx(U32Type(0)), offset),
+        ElementSegmentType::ElemKindActiveFunctions(
             ElemKindActiveFunctionsElementSegmentType {
-                mode: ElemModeActive { offset, .. },
+                mode: ElemModeActive { table_idx, offset },
                 ..
             },
         )
-        | ElementSegmentType::Active0Expr(Active0ExprElementSegmentType {
-            mode: ElemModeActive0 { offset },
-            ..
-        })
         | ElementSegmentType::ActiveRef(ActiveRefElementSegmentType {
-            mode: ElemModeActive { offset, .. },
+            mode: ElemModeActive { table_idx, offset },
             ..
-        }) => validate_constant_expression(
-            &offset,
-            &ResultType(vec![ValType::NumType(NumType::I32)]),
-            ctx,
-        ),
+        }) => (table_idx.clone(), offset),
         // Valid with any reftype
-        ElementSegmentType::ElemKindPassiveFunctions(_) => Ok(()),
+        ElementSegmentType::ElemKindPassiveFunctions(_) => return Ok(()),
         // Valid with any reftype
-        ElementSegmentType::ElemKindDeclarativeFunctions(_) => Ok(()),
+        ElementSegmentType::ElemKindDeclarativeFunctions(_) => return Ok(()),
         // Valid with any reftype
-        ElementSegmentType::PassiveRef(_) => Ok(()),
+        ElementSegmentType::PassiveRef(_) => return Ok(()),
         // Valid with any reftype
-        ElementSegmentType::DeclarativeRef(_) => Ok(()),
+        ElementSegmentType::DeclarativeRef(_) => return Ok(()),
+    };
+
+    // active segments are copied into a table of the same reference type
+    let table_type = ctx
+        .tables
+        .get(table_idx.0 .0 as usize)
+        .ok_or(ValidationError::TableNotFound)?;
+    if table_type.element_ref_type != elem_type {
+        return Err(ValidationError::WrongElemType);
     }
+
+    validate_constant_expression(
+        offset,
+        &ResultType(vec![ValType::NumType(NumType::I32)]),
+        ctx,
+    )
 }
 
 fn validate_data(data_type: &DataType, ctx: &ValidationContext) -> ValidationResult<()> {
-    match data_type {
-        DataType::Passive(_) => Ok(()),
+    let (memory, offset) = match data_type {
+        DataType::Passive(_) => return Ok(()),
         DataType::Active(ActiveDataType {
-            mode: DataModeActive { offset, .. },
+            mode: DataModeActive { memory, offset },
             ..
-        })
-        | DataType::Active0(Active0DataType {
+        }) => (memory.clone(), offset),
+        DataType::Active0(Active0DataType {
             mode: DataModeActive0 { offset },
             ..
-        }) => validate_constant_expression(
-            &offset,
-            &ResultType(vec![ValType::NumType(NumType::I32)]),
-            ctx,
-        ),
-    }
+        }) => (MemIdx(U32Type(0)), offset),
+    };
+
+    let offset_type = match ctx
+        .mems
+        .get(memory.0 .0 as usize)
+        .ok_or(ValidationError::MemNotFound)?
+        .index_type
+    {
+        IndexType::I32 => NumType::I32,
+        IndexType::I64 => NumType::I64,
+    };
+
+    validate_constant_expression(
+        offset,
+        &ResultType(vec![ValType::NumType(offset_type)]),
+        ctx,
+    )
 }
 
-fn create_context(module_src: &Module, externals: &Vec<ExternalDependency>) -> ValidationContext {
+pub fn create_context(module_src: &Module) -> ValidationContext {
     ValidationContext {
         types: module_src.types.clone(),
-        funcs: get_func_types(module_src, externals),
-        // TODO: add imported external types to tables
-        tables: module_src.tables.clone(),
-        // TODO: add imported external types to mems
-        mems: module_src.mems.clone(),
-        // TODO: add imported external types to globals
-        globals: module_src
-            .globals
-            .iter()
-            .map(|g| g.global_type.clone())
-            .collect(),
+        funcs: get_func_types(module_src),
+        tables: get_table_types(module_src),
+        mems: get_mem_types(module_src),
+        tags: get_tag_types(module_src),
+        globals: get_global_types(module_src),
         elems: module_src.elems.iter().map(|e| e.get_type()).collect(),
         datas: module_src.datas.clone(),
+        data_count: module_src.data_count,
         locals: vec![],
         labels: vec![],
         maybe_return: None,
-        // TODO: collect function indexes wherever they occur in the module, but skip its own functions and a start function
-        refs: vec![],
+        refs: get_refs(module_src),
     }
 }
 
-fn get_func_types(module_src: &Module, externals: &Vec<ExternalDependency>) -> Vec<FuncType> {
-    let mut external_func_types: Vec<FuncType> = externals
+// Function types are resolved from the type section, missing ones keep the index space intact
+fn get_func_types(module_src: &Module) -> Vec<FuncType> {
+    let func_type = |type_idx: &TypeIdx| {
+        module_src
+            .types
+            .get(type_idx.0 .0 as usize)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let mut func_types: Vec<FuncType> = module_src
+        .imports
         .iter()
-        .filter_map(|external| {
-            if let ExternalDependency::Func { func_type, .. } = external {
-                Some(func_type.clone())
-            } else {
-                None
-            }
+        .filter_map(|import| match import.desc {
+            ImportDescription::Func(ref type_idx) => Some(func_type(type_idx)),
+            _ => None,
+        })
+        .collect();
+
+    func_types.extend(module_src.funcs.iter().map(func_type));
+
+    func_types
+}
+
+fn get_table_types(module_src: &Module) -> Vec<TableType> {
+    let mut table_types: Vec<TableType> = module_src
+        .imports
+        .iter()
+        .filter_map(|import| match import.desc {
+            ImportDescription::Table(ref table_type) => Some(table_type.clone()),
+            _ => None,
+        })
+        .collect();
+
+    table_types.extend_from_slice(&module_src.tables);
+
+    table_types
+}
+
+fn get_imported_global_types(module_src: &Module) -> Vec<GlobalType> {
+    module_src
+        .imports
+        .iter()
+        .filter_map(|import| match import.desc {
+            ImportDescription::Global(ref global_type) => Some(global_type.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn get_global_types(module_src: &Module) -> Vec<GlobalType> {
+    let mut global_types = get_imported_global_types(module_src);
+
+    global_types.extend(module_src.globals.iter().map(|g| g.global_type.clone()));
+
+    global_types
+}
+
+fn get_mem_types(module_src: &Module) -> Vec<MemType> {
+    let mut mem_types: Vec<MemType> = module_src
+        .imports
+        .iter()
+        .filter_map(|import| match import.desc {
+            ImportDescription::Mem(ref mem_type) => Some(mem_type.clone()),
+            _ => None,
         })
         .collect();
-    let module_func_types: Vec<FuncType> = module_src
-        .funcs
+
+    mem_types.extend_from_slice(&module_src.mems);
+
+    mem_types
+}
+
+fn get_tag_types(module_src: &Module) -> Vec<FuncType> {
+    module_src
+        .imports
         .iter()
-        .map(|idx| module_src.types[idx.0 .0 as usize].clone())
+        .filter_map(|import| match import.desc {
+            ImportDescription::Tag(ref tag_type) => Some(tag_type),
+            _ => None,
+        })
+        .chain(&module_src.tags)
+        .filter_map(|tag| module_src.types.get(tag.type_idx.0 .0 as usize).cloned())
+        .collect()
+}
+
+// Functions which ref.func may refer to: the ones referenced outside of function bodies
+fn get_refs(module_src: &Module) -> Vec<FuncIdx> {
+    let mut refs: Vec<FuncIdx> = module_src
+        .exports
+        .iter()
+        .filter_map(|export| match export.desc {
+            ExportDescription::Func(ref func_idx) => Some(func_idx.clone()),
+            _ => None,
+        })
         .collect();
 
-    external_func_types.extend_from_slice(&module_func_types);
+    let expressions = module_src
+        .globals
+        .iter()
+        .map(|global| global.init.clone())
+        .chain(module_src.elems.iter().flat_map(|elem| elem.get_init()));
+    for expression in expressions {
+        for instruction in expression.instructions {
+            if let InstructionType::RefFunc(func_idx) = instruction {
+                refs.push(func_idx);
+            }
+        }
+    }
 
-    external_func_types
+    refs
 }
 
-fn create_sub_context(
-    module_src: &Module,
-    externals: &Vec<ExternalDependency>,
-) -> ValidationContext {
+fn create_sub_context(module_src: &Module) -> ValidationContext {
     ValidationContext {
-        globals: module_src
-            .globals
-            .iter()
-            .map(|g| g.global_type.clone())
-            .collect(),
-        funcs: get_func_types(module_src, externals),
-        // TODO: collect function indexes wherever they occur in the module, but skip its own functions and a start function
-        refs: vec![],
+        // constant expressions can only refer to imported globals
+        globals: get_imported_global_types(module_src),
+        funcs: get_func_types(module_src),
+        tables: get_table_types(module_src),
+        mems: get_mem_types(module_src),
+        refs: get_refs(module_src),
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::{
+        instructions::{IfElseInstructionType, LoopInstructionType},
+        module::{
+            CodeType, DataModePassive, ElemKind, ElemKindDeclarativeFunctionsElementSegmentType,
+            ElemModeDeclarative, LocalsType, PassiveDataType,
+        },
+        types::{DataIdx, GlobalIdx, I32Type, I64Type, LabelIdx, LocalIdx, RefType, TableIdx},
+    };
+    use crate::nesting::MAX_NESTING_DEPTH;
+    use crate::test_utils::{
+        export, i32_global, i32_type, import, mem_type, memarg, nested_blocks, table_type,
+    };
+    use crate::validation::validation_stack::ValidationType;
+
+    // Module with a single function of the type and the body
+    fn func_module(
+        func_type: FuncType,
+        locals: Vec<LocalsType>,
+        instructions: Vec<InstructionType>,
+    ) -> Module {
+        Module {
+            types: vec![func_type],
+            funcs: vec![TypeIdx(U32Type(0))],
+            code: vec![CodeType {
+                size: U32Type(0),
+                code: FuncCodeType {
+                    locals,
+                    expression: ExpressionType::new(instructions),
+                }
+                .into(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn void_func_module(instructions: Vec<InstructionType>) -> Module {
+        func_module(FuncType::default(), vec![], instructions)
+    }
+
+    fn assert_cases<const N: usize>(cases: [(Module, Option<ValidationError>); N]) {
+        for (i, (module, expected)) in cases.into_iter().enumerate() {
+            let result = validate(&module);
+
+            assert_eq!(result.err().map(|error| error.cause), expected, "case {i}");
+        }
+    }
+
+    #[test]
+    fn test_func_locals_and_results() {
+        let local_get = |idx| InstructionType::LocalGet(LocalIdx(U32Type(idx)));
+
+        assert_cases([
+            // parameters come first in the declared order
+            (
+                func_module(
+                    FuncType {
+                        parameters: vec![i32_type(), ValType::NumType(NumType::I64)],
+                        results: vec![ValType::NumType(NumType::I64)],
+                    },
+                    vec![],
+                    vec![local_get(1)],
+                ),
+                None,
+            ),
+            // declared locals follow the parameters
+            (
+                func_module(
+                    FuncType {
+                        parameters: vec![i32_type()],
+                        results: vec![ValType::NumType(NumType::F32)],
+                    },
+                    vec![LocalsType {
+                        n: U32Type(2),
+                        val_type: ValType::NumType(NumType::F32),
+                    }],
+                    vec![local_get(2)],
+                ),
+                None,
+            ),
+            (
+                void_func_module(vec![local_get(0), InstructionType::Drop]),
+                Some(ValidationError::LocalNotFound),
+            ),
+            // the body must leave exactly the results
+            (
+                func_module(
+                    FuncType {
+                        parameters: vec![],
+                        results: vec![i32_type()],
+                    },
+                    vec![],
+                    vec![],
+                ),
+                Some(ValidationError::InsufficientOperandStackForInstruction),
+            ),
+            (
+                void_func_module(vec![InstructionType::I32Const(I32Type(1))]),
+                Some(ValidationError::InsufficientOperandStackForInstruction),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        assert_cases([
+            (void_func_module(nested_blocks(MAX_NESTING_DEPTH)), None),
+            (
+                void_func_module(nested_blocks(MAX_NESTING_DEPTH + 1)),
+                Some(ValidationError::NestingTooDeep),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_branch_labels() {
+        let br = |idx| InstructionType::Br(LabelIdx(U32Type(idx)));
+        let block = |blocktype, instructions| {
+            InstructionType::Block(BlockInstructionType {
+                blocktype,
+                instructions,
+            })
+        };
+        let i32_block = || BlockType::ValType(i32_type());
+
+        assert_cases([
+            // labels are counted from the innermost block
+            (
+                void_func_module(vec![
+                    block(
+                        i32_block(),
+                        vec![
+                            block(BlockType::Empty, vec![br(0)]),
+                            InstructionType::I32Const(I32Type(1)),
+                        ],
+                    ),
+                    InstructionType::Drop,
+                ]),
+                None,
+            ),
+            (
+                void_func_module(vec![
+                    block(i32_block(), vec![block(BlockType::Empty, vec![br(1)])]),
+                    InstructionType::Drop,
+                ]),
+                Some(ValidationError::InsufficientOperandStackForInstruction),
+            ),
+            // the function body is the outermost label
+            (
+                void_func_module(vec![block(BlockType::Empty, vec![br(1)])]),
+                None,
+            ),
+            (
+                void_func_module(vec![block(BlockType::Empty, vec![br(2)])]),
+                Some(ValidationError::LabelNotFound),
+            ),
+            // a branch to a loop takes the parameters of the loop
+            (
+                void_func_module(vec![
+                    InstructionType::Loop(LoopInstructionType {
+                        blocktype: i32_block(),
+                        instructions: vec![br(0)],
+                    }),
+                    InstructionType::Drop,
+                ]),
+                None,
+            ),
+            // br_if leaves the operands of the label when not taken
+            (
+                void_func_module(vec![
+                    block(
+                        i32_block(),
+                        vec![
+                            InstructionType::I32Const(I32Type(1)),
+                            InstructionType::I32Const(I32Type(0)),
+                            InstructionType::BrIf(LabelIdx(U32Type(0))),
+                        ],
+                    ),
+                    InstructionType::Drop,
+                ]),
+                None,
+            ),
+            (
+                void_func_module(vec![
+                    block(
+                        i32_block(),
+                        vec![
+                            InstructionType::I32Const(I32Type(0)),
+                            InstructionType::I32Const(I32Type(0)),
+                            InstructionType::BrTable((
+                                vec![LabelIdx(U32Type(1))],
+                                LabelIdx(U32Type(0)),
+                            )),
+                        ],
+                    ),
+                    InstructionType::Drop,
+                ]),
+                Some(ValidationError::NotConsistentArity),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_error_location_in_nested_blocks() {
+        let block = |instructions| {
+            InstructionType::Block(BlockInstructionType {
+                blocktype: BlockType::Empty,
+                instructions,
+            })
+        };
+        let in_else_branch = void_func_module(vec![
+            InstructionType::I32Const(I32Type(1)),
+            InstructionType::IfElse(IfElseInstructionType {
+                blocktype: BlockType::Empty,
+                if_instructions: vec![InstructionType::Nop],
+                else_instructions: vec![block(vec![InstructionType::Loop(LoopInstructionType {
+                    blocktype: BlockType::Empty,
+                    instructions: vec![
+                        InstructionType::I64Const(I64Type(1)),
+                        InstructionType::I32Eqz,
+                    ],
+                })])],
+            }),
+        ]);
+
+        let error = validate(&in_else_branch).unwrap_err();
+        let func = error.func.as_ref().unwrap();
+
+        assert_eq!(func.path, vec![1, 1, 0, 1]);
+        assert_eq!(func.instruction, Some(InstructionType::I32Eqz));
+        assert_eq!(func.stack.frame_height, 0);
+        assert_eq!(
+            error.to_string(),
+            "func #0, instruction 1.1.0.1 (i32.eqz): \
+            type mismatch, expected i32, got i64, operands [] []"
+        );
+
+        // a value left in an inner block is reported at the end of that block
+        let left_in_block = void_func_module(vec![block(vec![
+            InstructionType::Nop,
+            block(vec![InstructionType::I32Const(I32Type(1))]),
+        ])]);
+
+        let error = validate(&left_in_block).unwrap_err();
+        let func = error.func.as_ref().unwrap();
+
+        assert_eq!(func.path, vec![0, 1, 1]);
+        assert_eq!(func.instruction, None);
+        assert_eq!(func.stack.operands, vec![ValidationType::i32()]);
+        assert!(error
+            .to_string()
+            .starts_with("func #0, instruction 0.1.1 (end): "));
+    }
+
+    #[test]
+    fn test_ref_func_declarations() {
+        let ref_func = vec![
+            InstructionType::RefFunc(FuncIdx(U32Type(0))),
+            InstructionType::Drop,
+        ];
+        let with = |update: fn(&mut Module)| {
+            let mut module = void_func_module(ref_func.clone());
+            update(&mut module);
+            module
+        };
+
+        assert_cases([
+            (
+                void_func_module(ref_func.clone()),
+                Some(ValidationError::CannotFindRefFuncInValidationContext),
+            ),
+            (
+                with(|module| {
+                    module.exports = vec![export("f", ExportDescription::Func(FuncIdx(U32Type(0))))]
+                }),
+                None,
+            ),
+            (
+                with(|module| {
+                    module.elems = vec![ElementSegmentType::ElemKindDeclarativeFunctions(
+                        ElemKindDeclarativeFunctionsElementSegmentType {
+                            elem_kind: ElemKind::FuncRef,
+                            init: vec![FuncIdx(U32Type(0))],
+                            mode: ElemModeDeclarative,
+                        },
+                    )]
+                }),
+                None,
+            ),
+            (
+                with(|module| {
+                    module.globals = vec![Global {
+                        global_type: GlobalType {
+                            mut_type: MutType::Const,
+                            val_type: ValType::RefType(RefType::FuncRef),
+                        },
+                        init: ExpressionType::new(vec![InstructionType::RefFunc(FuncIdx(
+                            U32Type(0),
+                        ))]),
+                    }]
+                }),
+                None,
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_imported_items() {
+        let with_import = |desc: ImportDescription, instructions: Vec<InstructionType>| {
+            let mut module = void_func_module(instructions);
+            module.imports = vec![import("env", "item", desc)];
+            module
+        };
+        let load = vec![
+            InstructionType::I32Const(I32Type(0)),
+            InstructionType::I32Load(memarg(2, 0)),
+            InstructionType::Drop,
+        ];
+        let table_size = vec![
+            InstructionType::TableSize(TableIdx(U32Type(0))),
+            InstructionType::Drop,
+        ];
+        let global_get = vec![
+            InstructionType::GlobalGet(GlobalIdx(U32Type(0))),
+            InstructionType::Drop,
+        ];
+
+        assert_cases([
+            (
+                with_import(ImportDescription::Mem(mem_type(1)), load.clone()),
+                None,
+            ),
+            (void_func_module(load), Some(ValidationError::MemNotFound)),
+            (
+                with_import(
+                    ImportDescription::Table(table_type(RefType::FuncRef)),
+                    table_size.clone(),
+                ),
+                None,
+            ),
+            (
+                void_func_module(table_size),
+                Some(ValidationError::TableNotFound),
+            ),
+            (
+                with_import(
+                    ImportDescription::Global(GlobalType {
+                        mut_type: MutType::Var,
+                        val_type: i32_type(),
+                    }),
+                    global_get.clone(),
+                ),
+                None,
+            ),
+            (
+                void_func_module(global_get),
+                Some(ValidationError::GlobalNotFound),
+            ),
+            (
+                with_import(ImportDescription::Func(TypeIdx(U32Type(1))), vec![]),
+                Some(ValidationError::FuncTypeNotFound { func_idx: 1 }),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_constant_expressions() {
+        let global_module = |imported: Option<MutType>, globals: Vec<Global>| Module {
+            imports: imported
+                .map(|mut_type| {
+                    vec![import(
+                        "env",
+                        "item",
+                        ImportDescription::Global(GlobalType {
+                            mut_type,
+                            val_type: i32_type(),
+                        }),
+                    )]
+                })
+                .unwrap_or_default(),
+            globals,
+            ..Default::default()
+        };
+        let global_get = |idx| InstructionType::GlobalGet(GlobalIdx(U32Type(idx)));
+        let i32_const = InstructionType::I32Const(I32Type(1));
+
+        assert_cases([
+            (
+                global_module(
+                    Some(MutType::Const),
+                    vec![i32_global(MutType::Var, vec![global_get(0)])],
+                ),
+                None,
+            ),
+            (
+                global_module(
+                    Some(MutType::Var),
+                    vec![i32_global(MutType::Var, vec![global_get(0)])],
+                ),
+                Some(ValidationError::NonConstantInstruction {
+                    instruction: global_get(0),
+                }),
+            ),
+            // globals of the module itself can't be referred to
+            (
+                global_module(
+                    None,
+                    vec![
+                        i32_global(MutType::Const, vec![i32_const.clone()]),
+                        i32_global(MutType::Const, vec![global_get(0)]),
+                    ],
+                ),
+                Some(ValidationError::NonConstantInstruction {
+                    instruction: global_get(0),
+                }),
+            ),
+            (
+                global_module(
+                    None,
+                    vec![i32_global(
+                        MutType::Const,
+                        vec![i32_const.clone(), InstructionType::I32Eqz],
+                    )],
+                ),
+                Some(ValidationError::NonConstantInstruction {
+                    instruction: InstructionType::I32Eqz,
+                }),
+            ),
+            (
+                global_module(
+                    None,
+                    vec![Global {
+                        global_type: GlobalType {
+                            mut_type: MutType::Const,
+                            val_type: ValType::NumType(NumType::I64),
+                        },
+                        init: ExpressionType::new(vec![i32_const]),
+                    }],
+                ),
+                Some(ValidationError::UnexpectedType {
+                    actual: ValidationType::i32(),
+                    expected: ValidationType::i64(),
+                }),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_exports() {
+        let with_exports = |exports| {
+            let mut module = void_func_module(vec![]);
+            module.exports = exports;
+            module
+        };
+        let func = |idx| ExportDescription::Func(FuncIdx(U32Type(idx)));
+
+        assert_cases([
+            (
+                with_exports(vec![export("a", func(0)), export("b", func(0))]),
+                None,
+            ),
+            (
+                with_exports(vec![export("a", func(0)), export("a", func(0))]),
+                Some(ValidationError::DuplicateExportName { name: "a".into() }),
+            ),
+            (
+                with_exports(vec![export("a", func(1))]),
+                Some(ValidationError::FuncTypeNotFound { func_idx: 1 }),
+            ),
+            (
+                with_exports(vec![export(
+                    "g",
+                    ExportDescription::Global(GlobalIdx(U32Type(0))),
+                )]),
+                Some(ValidationError::GlobalNotFound),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_start_function() {
+        let with_start = |func_type: FuncType, func_idx| {
+            let mut module = func_module(func_type, vec![], vec![InstructionType::Unreachable]);
+            module.start = Some(StartType {
+                func: FuncIdx(U32Type(func_idx)),
+            });
+            module
+        };
+
+        assert_cases([
+            (with_start(FuncType::default(), 0), None),
+            (
+                with_start(FuncType::default(), 1),
+                Some(ValidationError::FuncTypeNotFound { func_idx: 1 }),
+            ),
+            (
+                with_start(
+                    FuncType {
+                        parameters: vec![i32_type()],
+                        results: vec![],
+                    },
+                    0,
+                ),
+                Some(ValidationError::InvalidStartFunctionType),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_data_count() {
+        let with_data = |data_count, instructions| {
+            let mut module = void_func_module(instructions);
+            module.mems = vec![mem_type(1)];
+            module.datas = vec![DataType::Passive(PassiveDataType {
+                mode: DataModePassive,
+                init: vec![1, 2],
+            })];
+            module.data_count = data_count;
+            module
+        };
+        let memory_init = vec![
+            InstructionType::I32Const(I32Type(0)),
+            InstructionType::I32Const(I32Type(0)),
+            InstructionType::I32Const(I32Type(2)),
+            InstructionType::MemoryInit((DataIdx(U32Type(0)), MemIdx(U32Type(0)))),
+        ];
+        let data_drop = vec![InstructionType::DataDrop(DataIdx(U32Type(0)))];
+
+        assert_cases([
+            (with_data(Some(1), memory_init.clone()), None),
+            (
+                with_data(None, memory_init),
+                Some(ValidationError::DataCountRequired),
+            ),
+            (with_data(Some(1), data_drop.clone()), None),
+            (
+                with_data(None, data_drop),
+                Some(ValidationError::DataCountRequired),
+            ),
+            (
+                with_data(
+                    Some(1),
+                    vec![InstructionType::DataDrop(DataIdx(U32Type(1)))],
+                ),
+                Some(ValidationError::DataNotFound),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_active_elements() {
+        let with_table = |tables: Vec<RefType>| {
+            let mut module = void_func_module(vec![]);
+            module.tables = tables.into_iter().map(table_type).collect();
+            module.elems = vec![ElementSegmentType::Active0Functions(
+                Active0FunctionsElementSegmentType {
+                    mode: ElemModeActive0 {
+                        offset: ExpressionType::new(vec![InstructionType::I32Const(I32Type(0))]),
+                    },
+                    init: vec![FuncIdx(U32Type(0))],
+                },
+            )];
+            module
+        };
+
+        assert_cases([
+            (with_table(vec![RefType::FuncRef]), None),
+            (with_table(vec![]), Some(ValidationError::TableNotFound)),
+            (
+                with_table(vec![RefType::ExternRef]),
+                Some(ValidationError::WrongElemType),
+            ),
+        ]);
+    }
+}
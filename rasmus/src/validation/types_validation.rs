@@ -2,7 +2,7 @@ use super::context::ValidationContext;
 use super::validation_error::{ValidationError, ValidationResult};
 use crate::entities::types::*;
 
-pub fn is_limit_type_valid(limit: &LimitsType, range: U32Type) -> bool {
+pub fn is_limit_type_valid(limit: &LimitsType, range: U64Type) -> bool {
     limit.min <= range
         && limit
             .max
@@ -12,11 +12,21 @@ pub fn is_limit_type_valid(limit: &LimitsType, range: U32Type) -> bool {
 }
 
 pub fn is_table_type_valid(table_type: &TableType) -> bool {
-    is_limit_type_valid(&table_type.limits, U32Type(u32::MAX))
+    is_limit_type_valid(&table_type.limits, U64Type(u32::MAX as u64))
 }
 
 pub fn is_memory_type_valid(memory_type: &MemType) -> bool {
-    is_limit_type_valid(&memory_type.limits, U32Type(2u32.pow(16)))
+    let range = match memory_type.index_type {
+        IndexType::I32 => 2u64.pow(16),
+        IndexType::I64 => 2u64.pow(48),
+    };
+
+    // shared memories must declare a maximum size
+    if memory_type.shared && memory_type.limits.max.is_none() {
+        return false;
+    }
+
+    is_limit_type_valid(&memory_type.limits, U64Type(range))
 }
 
 pub fn validate_func_type(ctx: &ValidationContext, func_type: &TypeIdx) -> ValidationResult<()> {
@@ -42,3 +52,90 @@ pub fn validate_global_type(_global_type: &GlobalType) -> ValidationResult<()> {
     // global type is always valid
     Ok(())
 }
+
+// Checks that an extern of the actual type can be provided for an import of the expected type
+pub fn is_extern_type_matching(actual: &ExternType, expected: &ExternType) -> bool {
+    match (actual, expected) {
+        (ExternType::Func(actual), ExternType::Func(expected))
+        | (ExternType::Tag(actual), ExternType::Tag(expected)) => actual == expected,
+        (ExternType::Table(actual), ExternType::Table(expected)) => {
+            actual.element_ref_type == expected.element_ref_type
+                && is_limits_matching(&actual.limits, &expected.limits)
+        }
+        (ExternType::Mem(actual), ExternType::Mem(expected)) => {
+            actual.index_type == expected.index_type
+                && actual.shared == expected.shared
+                && is_limits_matching(&actual.limits, &expected.limits)
+        }
+        (ExternType::Global(actual), ExternType::Global(expected)) => actual == expected,
+        _ => false,
+    }
+}
+
+// Actual limits must be at least as narrow as the expected ones
+fn is_limits_matching(actual: &LimitsType, expected: &LimitsType) -> bool {
+    if actual.min < expected.min {
+        return false;
+    }
+
+    match (&actual.max, &expected.max) {
+        (_, None) => true,
+        (Some(actual_max), Some(expected_max)) => actual_max <= expected_max,
+        (None, Some(_)) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn limits(min: u64, max: Option<u64>) -> LimitsType {
+        LimitsType {
+            min: U64Type(min),
+            max: max.map(U64Type),
+        }
+    }
+
+    fn mem(min: u64, max: Option<u64>) -> ExternType {
+        ExternType::Mem(MemType {
+            limits: limits(min, max),
+            index_type: IndexType::I32,
+            shared: false,
+        })
+    }
+
+    fn global(mut_type: MutType) -> ExternType {
+        ExternType::Global(GlobalType {
+            mut_type,
+            val_type: ValType::NumType(NumType::I32),
+        })
+    }
+
+    #[test]
+    fn test_limits_subtyping() {
+        assert!(is_extern_type_matching(&mem(2, Some(3)), &mem(1, None)));
+        assert!(is_extern_type_matching(&mem(2, Some(3)), &mem(2, Some(4))));
+        assert!(!is_extern_type_matching(&mem(1, None), &mem(2, None)));
+        assert!(!is_extern_type_matching(&mem(2, None), &mem(1, Some(4))));
+        assert!(!is_extern_type_matching(&mem(2, Some(5)), &mem(1, Some(4))));
+    }
+
+    #[test]
+    fn test_extern_kinds_and_global_mutability() {
+        let func = ExternType::Func(FuncType {
+            parameters: vec![],
+            results: vec![],
+        });
+
+        assert!(is_extern_type_matching(&func, &func));
+        assert!(!is_extern_type_matching(&func, &mem(0, None)));
+        assert!(is_extern_type_matching(
+            &global(MutType::Var),
+            &global(MutType::Var)
+        ));
+        assert!(!is_extern_type_matching(
+            &global(MutType::Var),
+            &global(MutType::Const)
+        ));
+    }
+}
@@ -1,32 +1,68 @@
 use crate::entities::instructions::{InstructionType, InstructionType as I};
+use crate::nesting::enter_nesting;
 
 use super::{
     context::ValidationContext,
     instructions::{
-        block, br, br_if, br_table, call, call_indirect, data_drop, drop_val, elem_drop,
-        extract_line_f, extract_line_i, f32_binop, f32_const, f32_relop, f32_to_f64_cvtop,
-        f32_to_i32_cvtop, f32_to_i64_cvtop, f32_unop, f32x4_splat, f64_binop, f64_const, f64_relop,
-        f64_to_f32_cvtop, f64_to_i32_cvtop, f64_to_i64_cvtop, f64_unop, f64x2_splat, global_get,
-        global_set, i32_binop, i32_const, i32_relop, i32_testop, i32_to_f32_cvtop,
-        i32_to_f64_cvtop, i32_to_i32_cvtop, i32_to_i64_cvtop, i32_unop, i64_binop, i64_const,
-        i64_relop, i64_testop, i64_to_f32_cvtop, i64_to_f64_cvtop, i64_to_i64_cvtop, i64_unop,
-        i64x2_splat, i8x16_shuffle, i8x16_splat, i8x16_swizzle, if_else, ishape_bitmask, load_f32,
-        load_f64, load_i32, load_i32_t, load_i64, load_i64_t, load_v128, load_vec_lane,
-        load_vec_nm, load_vec_splat, local_get, local_set, local_tee, loop_instr, memory_fill,
+        atomic_cmpxchg, atomic_load, atomic_rmw, atomic_store, block, br, br_if, br_table, call,
+        call_indirect, data_drop, drop_val, elem_drop, extract_line_f, extract_line_i, f32_binop,
+        f32_const, f32_relop, f32_to_f64_cvtop, f32_to_i32_cvtop, f32_to_i64_cvtop, f32_unop,
+        f32x4_splat, f64_binop, f64_const, f64_relop, f64_to_f32_cvtop, f64_to_i32_cvtop,
+        f64_to_i64_cvtop, f64_unop, f64x2_splat, global_get, global_set, i32_binop, i32_const,
+        i32_relop, i32_testop, i32_to_f32_cvtop, i32_to_f64_cvtop, i32_to_i32_cvtop,
+        i32_to_i64_cvtop, i32_unop, i64_binop, i64_const, i64_relop, i64_testop, i64_to_f32_cvtop,
+        i64_to_f64_cvtop, i64_to_i64_cvtop, i64_unop, i64x2_splat, i8x16_shuffle, i8x16_splat,
+        i8x16_swizzle, if_else, ishape_bitmask, load_f32, load_f64, load_i32, load_i32_t, load_i64,
+        load_i64_t, load_v128, load_vec_lane, load_vec_nm, load_vec_splat, local_get, local_set,
+        local_tee, loop_instr, memory_atomic_notify, memory_atomic_wait, memory_copy, memory_fill,
         memory_grow, memory_init, memory_size, ref_func, ref_is_null, ref_null, replace_line_f,
-        replace_line_i, return_instr, select, select_vec, shape_bitop, store_value, store_value_t,
-        store_vec_lane, table_copy, table_fill, table_get, table_grow, table_init, table_set,
-        table_size, unreachable_instr, v128_binop, v128_const, v128_relop, v128_ternop,
-        v128_testop, v128_unop,
+        replace_line_i, return_call, return_call_indirect, return_instr, select, select_vec,
+        shape_bitop, store_value_t, store_vec_lane, table_copy, table_fill, table_get, table_grow,
+        table_init, table_set, table_size, throw, throw_ref, try_table, unreachable_instr,
+        v128_binop, v128_const, v128_relop, v128_ternop, v128_testop, v128_unop,
     },
-    validation_error::ValidationResult,
+    validation_error::{ValidationError, ValidationResult},
     validation_stack::{ValidationStack, ValidationType},
 };
 
+/// Validates the instruction, instructions nested in blocks are validated recursively.
+/// Blocks take one level of the nesting budget of the thread.
 pub fn validate_instruction(
     instruction: &InstructionType,
     ctx: &ValidationContext,
     val_stack: &mut ValidationStack,
+) -> ValidationResult<()> {
+    // the match on every instruction takes a large frame in debug builds,
+    // it is kept out of the recursion of nested blocks
+    let _nesting = match instruction {
+        I::Block(_) | I::Loop(_) | I::IfElse(_) | I::TryTable(_) => {
+            enter_nesting().ok_or(ValidationError::NestingTooDeep)?
+        }
+        _ => return validate_flat_instruction(instruction, ctx, val_stack),
+    };
+
+    match instruction {
+        I::Block(block_instruction) => {
+            block(val_stack, ctx, block_instruction, validate_instruction)
+        }
+        I::Loop(loop_instruction) => {
+            loop_instr(val_stack, ctx, loop_instruction, validate_instruction)
+        }
+        I::IfElse(ifelse_instruction) => {
+            if_else(val_stack, ctx, ifelse_instruction, validate_instruction)
+        }
+        I::TryTable(try_table_instruction) => {
+            try_table(val_stack, ctx, try_table_instruction, validate_instruction)
+        }
+        _ => validate_flat_instruction(instruction, ctx, val_stack),
+    }
+}
+
+// Validates an instruction without nested instructions
+fn validate_flat_instruction(
+    instruction: &InstructionType,
+    ctx: &ValidationContext,
+    val_stack: &mut ValidationStack,
 ) -> ValidationResult<()> {
     match instruction {
         // t.const
@@ -435,11 +471,11 @@ pub fn validate_instruction(
         I::I64Load8S(memarg) | I::I64Load8U(memarg) => load_i64_t(val_stack, ctx, memarg, 8)?,
         I::I64Load16S(memarg) | I::I64Load16U(memarg) => load_i64_t(val_stack, ctx, memarg, 16)?,
         I::I64Load32S(memarg) | I::I64Load32U(memarg) => load_i64_t(val_stack, ctx, memarg, 32)?,
-        I::I32Store(memarg) => store_value(val_stack, ctx, memarg, ValidationType::i32())?,
-        I::I64Store(memarg) => store_value(val_stack, ctx, memarg, ValidationType::i64())?,
-        I::F32Store(memarg) => store_value(val_stack, ctx, memarg, ValidationType::f32())?,
-        I::F64Store(memarg) => store_value(val_stack, ctx, memarg, ValidationType::f64())?,
-        I::V128Store(memarg) => store_value(val_stack, ctx, memarg, ValidationType::v128())?,
+        I::I32Store(memarg) => store_value_t(val_stack, ctx, memarg, ValidationType::i32(), 32)?,
+        I::I64Store(memarg) => store_value_t(val_stack, ctx, memarg, ValidationType::i64(), 64)?,
+        I::F32Store(memarg) => store_value_t(val_stack, ctx, memarg, ValidationType::f32(), 32)?,
+        I::F64Store(memarg) => store_value_t(val_stack, ctx, memarg, ValidationType::f64(), 64)?,
+        I::V128Store(memarg) => store_value_t(val_stack, ctx, memarg, ValidationType::v128(), 128)?,
         I::I32Store8(memarg) => store_value_t(val_stack, ctx, memarg, ValidationType::i32(), 8)?,
         I::I32Store16(memarg) => store_value_t(val_stack, ctx, memarg, ValidationType::i32(), 16)?,
         I::I64Store8(memarg) => store_value_t(val_stack, ctx, memarg, ValidationType::i64(), 8)?,
@@ -484,25 +520,211 @@ pub fn validate_instruction(
         I::V128Store64Lane((memarg, lane_idx)) => {
             store_vec_lane(val_stack, ctx, memarg, lane_idx, 64)?
         }
-        I::MemorySize => memory_size(val_stack, ctx)?,
-        I::MemoryGrow => memory_grow(val_stack, ctx)?,
-        I::MemoryFill | I::MemoryCopy => memory_fill(val_stack, ctx)?,
-        I::MemoryInit(data_idx) => memory_init(val_stack, ctx, data_idx)?,
+        I::MemorySize(mem_idx) => memory_size(val_stack, ctx, mem_idx)?,
+        I::MemoryGrow(mem_idx) => memory_grow(val_stack, ctx, mem_idx)?,
+        I::MemoryFill(mem_idx) => memory_fill(val_stack, ctx, mem_idx)?,
+        I::MemoryCopy(mem_idxes) => memory_copy(val_stack, ctx, mem_idxes)?,
+        I::MemoryInit(idxes) => memory_init(val_stack, ctx, idxes)?,
         I::DataDrop(data_idx) => data_drop(val_stack, ctx, data_idx)?,
 
+        // Atomic memory instructions
+        I::MemoryAtomicNotify(memarg) => memory_atomic_notify(val_stack, ctx, memarg)?,
+        I::MemoryAtomicWait32(memarg) => {
+            memory_atomic_wait(val_stack, ctx, memarg, ValidationType::i32(), 32)?
+        }
+        I::MemoryAtomicWait64(memarg) => {
+            memory_atomic_wait(val_stack, ctx, memarg, ValidationType::i64(), 64)?
+        }
+        I::AtomicFence => {}
+        I::I32AtomicLoad(memarg) => atomic_load(val_stack, ctx, memarg, ValidationType::i32(), 32)?,
+        I::I64AtomicLoad(memarg) => atomic_load(val_stack, ctx, memarg, ValidationType::i64(), 64)?,
+        I::I32AtomicLoad8U(memarg) => {
+            atomic_load(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicLoad16U(memarg) => {
+            atomic_load(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicLoad8U(memarg) => {
+            atomic_load(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicLoad16U(memarg) => {
+            atomic_load(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicLoad32U(memarg) => {
+            atomic_load(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+        I::I32AtomicStore(memarg) => {
+            atomic_store(val_stack, ctx, memarg, ValidationType::i32(), 32)?
+        }
+        I::I64AtomicStore(memarg) => {
+            atomic_store(val_stack, ctx, memarg, ValidationType::i64(), 64)?
+        }
+        I::I32AtomicStore8(memarg) => {
+            atomic_store(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicStore16(memarg) => {
+            atomic_store(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicStore8(memarg) => {
+            atomic_store(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicStore16(memarg) => {
+            atomic_store(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicStore32(memarg) => {
+            atomic_store(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+        I::I32AtomicRmwAdd(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 32)?
+        }
+        I::I64AtomicRmwAdd(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 64)?
+        }
+        I::I32AtomicRmw8AddU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicRmw16AddU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicRmw8AddU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicRmw16AddU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicRmw32AddU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+        I::I32AtomicRmwSub(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 32)?
+        }
+        I::I64AtomicRmwSub(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 64)?
+        }
+        I::I32AtomicRmw8SubU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicRmw16SubU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicRmw8SubU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicRmw16SubU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicRmw32SubU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+        I::I32AtomicRmwAnd(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 32)?
+        }
+        I::I64AtomicRmwAnd(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 64)?
+        }
+        I::I32AtomicRmw8AndU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicRmw16AndU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicRmw8AndU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicRmw16AndU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicRmw32AndU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+        I::I32AtomicRmwOr(memarg) => atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 32)?,
+        I::I64AtomicRmwOr(memarg) => atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 64)?,
+        I::I32AtomicRmw8OrU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicRmw16OrU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicRmw8OrU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicRmw16OrU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicRmw32OrU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+        I::I32AtomicRmwXor(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 32)?
+        }
+        I::I64AtomicRmwXor(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 64)?
+        }
+        I::I32AtomicRmw8XorU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicRmw16XorU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicRmw8XorU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicRmw16XorU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicRmw32XorU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+        I::I32AtomicRmwXchg(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 32)?
+        }
+        I::I64AtomicRmwXchg(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 64)?
+        }
+        I::I32AtomicRmw8XchgU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicRmw16XchgU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicRmw8XchgU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicRmw16XchgU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicRmw32XchgU(memarg) => {
+            atomic_rmw(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+        I::I32AtomicRmwCmpxchg(memarg) => {
+            atomic_cmpxchg(val_stack, ctx, memarg, ValidationType::i32(), 32)?
+        }
+        I::I64AtomicRmwCmpxchg(memarg) => {
+            atomic_cmpxchg(val_stack, ctx, memarg, ValidationType::i64(), 64)?
+        }
+        I::I32AtomicRmw8CmpxchgU(memarg) => {
+            atomic_cmpxchg(val_stack, ctx, memarg, ValidationType::i32(), 8)?
+        }
+        I::I32AtomicRmw16CmpxchgU(memarg) => {
+            atomic_cmpxchg(val_stack, ctx, memarg, ValidationType::i32(), 16)?
+        }
+        I::I64AtomicRmw8CmpxchgU(memarg) => {
+            atomic_cmpxchg(val_stack, ctx, memarg, ValidationType::i64(), 8)?
+        }
+        I::I64AtomicRmw16CmpxchgU(memarg) => {
+            atomic_cmpxchg(val_stack, ctx, memarg, ValidationType::i64(), 16)?
+        }
+        I::I64AtomicRmw32CmpxchgU(memarg) => {
+            atomic_cmpxchg(val_stack, ctx, memarg, ValidationType::i64(), 32)?
+        }
+
         // Control instructions
         I::Nop => {
             // always valid
         }
         I::Unreachable => unreachable_instr(val_stack)?,
-        I::Block(block_instruction) => {
-            block(val_stack, ctx, block_instruction, validate_instruction)?
-        }
-        I::Loop(loop_instruction) => {
-            loop_instr(val_stack, ctx, loop_instruction, validate_instruction)?
-        }
-        I::IfElse(ifelse_instruction) => {
-            if_else(val_stack, ctx, ifelse_instruction, validate_instruction)?
+        I::Block(_) | I::Loop(_) | I::IfElse(_) | I::TryTable(_) => {
+            // validated by validate_instruction
         }
         I::Br(label_idx) => br(val_stack, label_idx)?,
         I::BrIf(label_idx) => br_if(val_stack, label_idx)?,
@@ -510,6 +732,12 @@ pub fn validate_instruction(
         I::Return => return_instr(val_stack, ctx)?,
         I::Call(func_idx) => call(val_stack, ctx, func_idx)?,
         I::CallIndirect(call_indirect_arg) => call_indirect(val_stack, ctx, call_indirect_arg)?,
+        I::ReturnCall(func_idx) => return_call(val_stack, ctx, func_idx)?,
+        I::ReturnCallIndirect(call_indirect_arg) => {
+            return_call_indirect(val_stack, ctx, call_indirect_arg)?
+        }
+        I::Throw(tag_idx) => throw(val_stack, ctx, tag_idx)?,
+        I::ThrowRef => throw_ref(val_stack)?,
     }
 
     Ok(())
@@ -0,0 +1,69 @@
+use std::{borrow::Borrow, ops::Deref};
+
+use crate::{
+    entities::{module::Module, types::ExternType},
+    instances::func::FuncCode,
+};
+
+use super::{
+    module::validate,
+    validation_error::{ModuleValidationError, ValidationError},
+};
+
+/// Module validated against the types its imports declare.
+/// It can be instantiated any number of times, only the types of the externals are checked then.
+/// Lazy function bodies are validated on their first call, every instance shares the result.
+#[derive(Debug, Clone)]
+pub struct ValidatedModule {
+    module: Module,
+    import_types: Vec<ExternType>,
+    funcs: Vec<FuncCode>,
+}
+
+impl ValidatedModule {
+    pub fn new(module: Module) -> Result<Self, ModuleValidationError> {
+        validate(&module)?;
+
+        let import_types = module
+            .imports
+            .iter()
+            .map(|import| module.get_import_type(import))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(ValidationError::TypeNotFound)?;
+        let funcs = module.get_funcs().ok_or(ValidationError::CodeNotFound)?;
+
+        Ok(ValidatedModule {
+            module,
+            import_types,
+            funcs,
+        })
+    }
+
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Declared types of the imports, in the order of the imports
+    pub fn import_types(&self) -> &[ExternType] {
+        &self.import_types
+    }
+
+    /// Code of the functions defined by the module, allocated for every instance
+    pub fn funcs(&self) -> &[FuncCode] {
+        &self.funcs
+    }
+}
+
+impl Deref for ValidatedModule {
+    type Target = Module;
+
+    fn deref(&self) -> &Module {
+        &self.module
+    }
+}
+
+impl Borrow<Module> for ValidatedModule {
+    fn borrow(&self) -> &Module {
+        &self.module
+    }
+}
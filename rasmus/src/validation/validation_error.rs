@@ -1,12 +1,17 @@
-use crate::entities::{
-    instructions::InstructionType,
-    types::{MemType, RefType, TableType},
+use std::fmt;
+
+use crate::{
+    entities::{
+        instructions::InstructionType,
+        types::{LimitsType, MemType, RefType, TableType},
+    },
+    wat::instruction_text,
 };
 
 use super::validation_stack::ValidationType;
 
 // TODO: try to add more debugging information to each option
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ValidationError {
     InsufficientOperandStackForInstruction,
     CannotFindRefFuncInValidationContext,
@@ -29,9 +34,15 @@ pub enum ValidationError {
     WrongElemType,
     // When load a value memarg align should not be bigger than th bit width divided by 8
     MemargAlignTooBig,
+    // Offset of a memarg must fit into 32 bits when the memory is not 64-bit
+    MemargOffsetTooBig,
+    // Atomic memory instructions require memarg align to be exactly the natural alignment
+    MemargAlignNotNatural,
     MemNotFound,
     LaneIdxTooBix,
     DataNotFound,
+    // memory.init and data.drop are used without the data count section
+    DataCountRequired,
     TypeNotFound,
     ControlFrameNotFound,
     FrameNotFound,
@@ -45,6 +56,14 @@ pub enum ValidationError {
     },
     NotConsistentArity,
     ReturnNotFoundInContext,
+    // Results of a function called by return_call must match results of the caller
+    InvalidTailCallResultType,
+    TagNotFound,
+    LabelNotFound,
+    // Tag types must refer to a function type without results
+    InvalidTagType,
+    // Values passed by a catch clause must match the types of its target label
+    InvalidCatchLabelType,
     FuncTypeNotFound {
         func_idx: usize,
     },
@@ -57,9 +76,192 @@ pub enum ValidationError {
         memory_type: MemType,
     },
     InvalidStartFunctionType,
+    DuplicateExportName {
+        name: String,
+    },
     NonConstantInstruction {
         instruction: InstructionType,
     },
+    // Blocks are nested deeper than the nesting limit of the thread
+    NestingTooDeep,
 }
 
 pub type ValidationResult<T> = Result<T, ValidationError>;
+
+// Messages follow the ones of the spec test suite where it has one
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InsufficientOperandStackForInstruction => {
+                write!(f, "type mismatch, operand stack is too short")
+            }
+            ValidationError::CannotFindRefFuncInValidationContext => {
+                write!(f, "undeclared function reference")
+            }
+            ValidationError::LaneIndexIsOutOfRange { value, max_allowed } => {
+                write!(f, "invalid lane index {value}, at most {max_allowed}")
+            }
+            ValidationError::InvalidSelectVecOperandSequence => {
+                write!(f, "invalid result arity of select")
+            }
+            ValidationError::InvalidSelectBranchTypes => {
+                write!(
+                    f,
+                    "type mismatch, select operands are not both numbers or vectors"
+                )
+            }
+            ValidationError::LocalNotFound => write!(f, "unknown local"),
+            ValidationError::GlobalNotFound => write!(f, "unknown global"),
+            ValidationError::UnableToSetToConstGlobal => write!(f, "global is immutable"),
+            ValidationError::TableNotFound => write!(f, "unknown table"),
+            ValidationError::UnableToCopyIncosistentTableTypes => {
+                write!(f, "type mismatch, tables of table.copy differ in type")
+            }
+            ValidationError::ElemNotFound => write!(f, "unknown elem segment"),
+            ValidationError::WrongElemType => {
+                write!(f, "type mismatch, elem segment and table differ in type")
+            }
+            ValidationError::MemargAlignTooBig => {
+                write!(f, "alignment must not be larger than natural")
+            }
+            ValidationError::MemargOffsetTooBig => write!(f, "offset out of range"),
+            ValidationError::MemargAlignNotNatural => write!(f, "alignment must be natural"),
+            ValidationError::MemNotFound => write!(f, "unknown memory"),
+            ValidationError::LaneIdxTooBix => write!(f, "invalid lane index"),
+            ValidationError::DataNotFound => write!(f, "unknown data segment"),
+            ValidationError::DataCountRequired => write!(f, "data count section required"),
+            ValidationError::TypeNotFound => write!(f, "unknown type"),
+            ValidationError::ControlFrameNotFound => write!(f, "unexpected end of block"),
+            ValidationError::FrameNotFound => write!(f, "unknown frame"),
+            ValidationError::UnexpectedType { actual, expected } => {
+                write!(f, "type mismatch, expected {expected}, got {actual}")
+            }
+            ValidationError::UnexpectedRefType { actual, expected } => {
+                write!(f, "type mismatch, expected {expected}, got {actual}")
+            }
+            ValidationError::NotConsistentArity => {
+                write!(f, "type mismatch, branch targets differ in arity")
+            }
+            ValidationError::ReturnNotFoundInContext => write!(f, "return outside of a function"),
+            ValidationError::InvalidTailCallResultType => {
+                write!(f, "type mismatch, tail call results differ from the caller")
+            }
+            ValidationError::TagNotFound => write!(f, "unknown tag"),
+            ValidationError::LabelNotFound => write!(f, "unknown label"),
+            ValidationError::InvalidTagType => write!(f, "non-empty tag result type"),
+            ValidationError::InvalidCatchLabelType => {
+                write!(f, "type mismatch, catch values differ from the label")
+            }
+            ValidationError::FuncTypeNotFound { func_idx } => {
+                write!(f, "unknown type of function {func_idx}")
+            }
+            ValidationError::CodeNotFound => {
+                write!(f, "function and code section have inconsistent lengths")
+            }
+            ValidationError::IfControlFrameIsExpected => write!(f, "else outside of an if block"),
+            ValidationError::InvalidTableType { table_type } => {
+                write!(f, "invalid table type, {}", limits_text(&table_type.limits))
+            }
+            ValidationError::InvalidMemoryType { memory_type } => {
+                write!(
+                    f,
+                    "invalid memory type, {}",
+                    limits_text(&memory_type.limits)
+                )
+            }
+            ValidationError::InvalidStartFunctionType => write!(f, "start function"),
+            ValidationError::DuplicateExportName { name } => {
+                write!(f, "duplicate export name \"{name}\"")
+            }
+            ValidationError::NonConstantInstruction { instruction } => write!(
+                f,
+                "constant expression required, got {}",
+                instruction_text(instruction)
+            ),
+            ValidationError::NestingTooDeep => write!(f, "blocks nested too deep"),
+        }
+    }
+}
+
+fn limits_text(limits: &LimitsType) -> String {
+    match limits.max {
+        Some(ref max) => format!("limits {} {}", limits.min.0, max.0),
+        None => format!("limits {}", limits.min.0),
+    }
+}
+
+/// Invalid module with the function and the instruction where validation stopped
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleValidationError {
+    /// None when the error is outside of function bodies, e.g. in an import or a global
+    pub func: Option<Box<FuncLocation>>,
+    pub cause: ValidationError,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuncLocation {
+    /// Index in the function index space, after the imported functions
+    pub func_idx: usize,
+    /// Name of the first export of the function
+    pub name: Option<String>,
+    /// Index of the instruction in the body and in every enclosing block.
+    /// The instructions of an else branch are numbered after the ones of its if branch.
+    pub path: Vec<usize>,
+    /// None when validation stopped at the end of a block
+    pub instruction: Option<InstructionType>,
+    pub stack: StackSnapshot,
+}
+
+/// Operand types on the validation stack when validation stopped
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackSnapshot {
+    pub operands: Vec<ValidationType>,
+    /// Number of operands below the innermost block
+    pub frame_height: usize,
+    pub unreachable: bool,
+}
+
+impl From<ValidationError> for ModuleValidationError {
+    fn from(cause: ValidationError) -> Self {
+        ModuleValidationError { func: None, cause }
+    }
+}
+
+impl fmt::Display for ModuleValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(ref func) = self.func else {
+            return write!(f, "{}", self.cause);
+        };
+
+        write!(f, "func #{}", func.func_idx)?;
+        if let Some(ref name) = func.name {
+            write!(f, " \"{name}\"")?;
+        }
+        let path: Vec<String> = func.path.iter().map(usize::to_string).collect();
+        let instruction = match func.instruction {
+            Some(ref instruction) => instruction_text(instruction),
+            None => "end".into(),
+        };
+        write!(
+            f,
+            ", instruction {} ({instruction}): {}",
+            path.join("."),
+            self.cause
+        )?;
+
+        let (outer, block) = func.stack.operands.split_at(func.stack.frame_height);
+        let types = |operands: &[ValidationType]| {
+            operands
+                .iter()
+                .map(ValidationType::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        write!(f, ", operands [{}] [{}]", types(outer), types(block))?;
+        if func.stack.unreachable {
+            write!(f, " unreachable")?;
+        }
+
+        Ok(())
+    }
+}
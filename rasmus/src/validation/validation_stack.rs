@@ -1,10 +1,13 @@
+use std::fmt;
+
 use crate::entities::{
     instructions::InstructionType,
     types::{NumType, ValType},
 };
 
-use super::validation_error::{ValidationError, ValidationResult};
+use super::validation_error::{StackSnapshot, ValidationError, ValidationResult};
 
+#[derive(Default)]
 pub struct ValidationStack {
     vals: Vec<ValidationType>,
     ctrls: Vec<CtrlFrame>,
@@ -25,7 +28,7 @@ impl ValidationStack {
     pub fn pop_val(&mut self) -> ValidationResult<ValidationType> {
         let frame = self
             .ctrls
-            .get(0)
+            .last()
             .ok_or_else(|| ValidationError::ControlFrameNotFound)?;
 
         if self.vals.len() == frame.height && frame.unreachable {
@@ -94,6 +97,7 @@ impl ValidationStack {
             end_types: end_types.iter().map(From::from).collect(),
             height: self.vals.len(),
             unreachable,
+            position: 0,
         });
 
         self.push_vals(start_types.iter().map(From::from).collect());
@@ -106,7 +110,7 @@ impl ValidationStack {
 
         let frame = self
             .ctrls
-            .get(0)
+            .last()
             .expect("Should return CtrlFrame due to the previous check");
 
         let end_types = frame.end_types.clone();
@@ -127,13 +131,13 @@ impl ValidationStack {
     pub fn unreachable(&mut self) -> ValidationResult<()> {
         let height = self
             .ctrls
-            .get(0)
+            .last()
             .map(|f| f.height)
             .ok_or(ValidationError::FrameNotFound)?;
 
         self.vals.truncate(height);
 
-        match self.ctrls.get_mut(0) {
+        match self.ctrls.last_mut() {
             Some(ref mut frame) => {
                 frame.unreachable = true;
             }
@@ -147,8 +151,31 @@ impl ValidationStack {
         self.ctrls.len()
     }
 
-    pub fn get_ctrl(&self, i: usize) -> Option<&CtrlFrame> {
-        self.ctrls.get(i)
+    /// Frame targeted by a branch to `label_idx`, counted from the innermost frame
+    pub fn get_label(&self, label_idx: usize) -> Option<&CtrlFrame> {
+        self.ctrls.iter().rev().nth(label_idx)
+    }
+
+    /// Records the index of the instruction validated next in the innermost frame
+    pub fn set_position(&mut self, position: usize) {
+        if let Some(frame) = self.ctrls.last_mut() {
+            frame.position = position;
+        }
+    }
+
+    /// Positions of the validated instruction in every frame, starting from the outermost
+    pub fn path(&self) -> Vec<usize> {
+        self.ctrls.iter().map(|frame| frame.position).collect()
+    }
+
+    pub fn snapshot(&self) -> StackSnapshot {
+        let frame = self.ctrls.last();
+
+        StackSnapshot {
+            operands: self.vals.clone(),
+            frame_height: frame.map(|frame| frame.height).unwrap_or(0),
+            unreachable: frame.map(|frame| frame.unreachable).unwrap_or(false),
+        }
     }
 }
 
@@ -220,6 +247,20 @@ impl ValidationType {
     }
 }
 
+impl fmt::Display for ValidationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationType::Known(VType::I32) => write!(f, "i32"),
+            ValidationType::Known(VType::I64) => write!(f, "i64"),
+            ValidationType::Known(VType::F32) => write!(f, "f32"),
+            ValidationType::Known(VType::F64) => write!(f, "f64"),
+            ValidationType::Known(VType::V128) => write!(f, "v128"),
+            ValidationType::Known(VType::Ref) => write!(f, "ref"),
+            ValidationType::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum VType {
     I32,
@@ -269,4 +310,6 @@ pub struct CtrlFrame {
     pub end_types: Vec<ValidationType>,
     pub height: usize,
     pub unreachable: bool,
+    // index of the instruction validated in the frame, for error locations
+    pub position: usize,
 }
@@ -0,0 +1,859 @@
+use crate::entities::{
+    instructions::{BlockType, CatchType, InstructionType, MemArgType},
+    types::{RefType, ValType},
+};
+
+const INDENT: &str = "  ";
+
+/// Writes the instructions one per line, the bodies of blocks are indented one level deeper
+pub fn write_instructions(out: &mut String, instructions: &[InstructionType], depth: usize) {
+    for instruction in instructions {
+        match instruction {
+            InstructionType::Block(block) => {
+                write_line(out, depth, &instruction_text(instruction));
+                write_instructions(out, &block.instructions, depth + 1);
+                write_line(out, depth, "end");
+            }
+            InstructionType::Loop(block) => {
+                write_line(out, depth, &instruction_text(instruction));
+                write_instructions(out, &block.instructions, depth + 1);
+                write_line(out, depth, "end");
+            }
+            InstructionType::IfElse(block) => {
+                write_line(out, depth, &instruction_text(instruction));
+                write_instructions(out, &block.if_instructions, depth + 1);
+                if !block.else_instructions.is_empty() {
+                    write_line(out, depth, "else");
+                    write_instructions(out, &block.else_instructions, depth + 1);
+                }
+                write_line(out, depth, "end");
+            }
+            InstructionType::TryTable(block) => {
+                write_line(out, depth, &instruction_text(instruction));
+                write_instructions(out, &block.instructions, depth + 1);
+                write_line(out, depth, "end");
+            }
+            instruction => write_line(out, depth, &instruction_text(instruction)),
+        }
+    }
+}
+
+pub(super) fn write_line(out: &mut String, depth: usize, line: &str) {
+    out.push_str(&INDENT.repeat(depth));
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// Text of a single instruction with its immediates, without the body of blocks
+pub fn instruction_text(instruction: &InstructionType) -> String {
+    let mnemonic = mnemonic(instruction);
+    let immediates = match instruction {
+        InstructionType::Block(block) => blocktype(&block.blocktype),
+        InstructionType::Loop(block) => blocktype(&block.blocktype),
+        InstructionType::IfElse(block) => blocktype(&block.blocktype),
+        InstructionType::TryTable(block) => {
+            let catches: String = block.catches.iter().map(catch).collect();
+            format!("{}{catches}", blocktype(&block.blocktype))
+        }
+        InstructionType::Br(label) | InstructionType::BrIf(label) => format!(" {}", label.0 .0),
+        InstructionType::BrTable((labels, default)) => labels
+            .iter()
+            .chain([default])
+            .map(|label| format!(" {}", label.0 .0))
+            .collect(),
+        InstructionType::Call(func)
+        | InstructionType::ReturnCall(func)
+        | InstructionType::RefFunc(func) => format!(" {}", func.0 .0),
+        InstructionType::CallIndirect((table, type_idx))
+        | InstructionType::ReturnCallIndirect((table, type_idx)) => {
+            format!(" {} (type {})", table.0 .0, type_idx.0 .0)
+        }
+        InstructionType::Throw(tag) => format!(" {}", tag.0 .0),
+        InstructionType::RefNull(ref_type) => format!(" {}", heap_type(ref_type)),
+        InstructionType::SelectVec(val_types) => format!(" {}", result(val_types)),
+        InstructionType::LocalGet(local)
+        | InstructionType::LocalSet(local)
+        | InstructionType::LocalTee(local) => format!(" {}", local.0 .0),
+        InstructionType::GlobalGet(global) | InstructionType::GlobalSet(global) => {
+            format!(" {}", global.0 .0)
+        }
+        InstructionType::TableGet(table)
+        | InstructionType::TableSet(table)
+        | InstructionType::TableGrow(table)
+        | InstructionType::TableSize(table)
+        | InstructionType::TableFill(table) => format!(" {}", table.0 .0),
+        InstructionType::TableInit((table, elem)) => format!(" {} {}", table.0 .0, elem.0 .0),
+        InstructionType::ElemDrop(elem) => format!(" {}", elem.0 .0),
+        InstructionType::TableCopy((dst, src)) => format!(" {} {}", dst.0 .0, src.0 .0),
+        InstructionType::MemorySize(mem)
+        | InstructionType::MemoryGrow(mem)
+        | InstructionType::MemoryFill(mem) => memory(mem.0 .0),
+        InstructionType::MemoryInit((data, mem)) => format!("{} {}", memory(mem.0 .0), data.0 .0),
+        InstructionType::DataDrop(data) => format!(" {}", data.0 .0),
+        InstructionType::MemoryCopy((dst, src)) => match dst.0 .0 == 0 && src.0 .0 == 0 {
+            true => String::new(),
+            false => format!(" {} {}", dst.0 .0, src.0 .0),
+        },
+        InstructionType::I32Const(value) => format!(" {}", value.0 as i32),
+        InstructionType::I64Const(value) => format!(" {}", value.0 as i64),
+        InstructionType::F32Const(value) => format!(" {}", f32_text(value.0)),
+        InstructionType::F64Const(value) => format!(" {}", f64_text(value.0)),
+        InstructionType::V128Const(bytes) => {
+            let bytes: String = bytes.iter().map(|byte| format!(" {byte}")).collect();
+            format!(" i8x16{bytes}")
+        }
+        InstructionType::I8x16Shuffle(lanes) => {
+            lanes.iter().map(|lane| format!(" {}", lane.0)).collect()
+        }
+        InstructionType::V128Load8Lane((memarg, lane))
+        | InstructionType::V128Load16Lane((memarg, lane))
+        | InstructionType::V128Load32Lane((memarg, lane))
+        | InstructionType::V128Load64Lane((memarg, lane))
+        | InstructionType::V128Store8Lane((memarg, lane))
+        | InstructionType::V128Store16Lane((memarg, lane))
+        | InstructionType::V128Store32Lane((memarg, lane))
+        | InstructionType::V128Store64Lane((memarg, lane)) => {
+            format!("{} {}", memarg_text(mnemonic, memarg), lane.0)
+        }
+        InstructionType::I8x16ExtractLaneS(lane)
+        | InstructionType::I8x16ExtractLaneU(lane)
+        | InstructionType::I8x16ReplaceLane(lane)
+        | InstructionType::I16x8ExtractLaneS(lane)
+        | InstructionType::I16x8ExtractLaneU(lane)
+        | InstructionType::I16x8ReplaceLane(lane)
+        | InstructionType::I32x4ExtractLane(lane)
+        | InstructionType::I32x4ReplaceLane(lane)
+        | InstructionType::I64x2ExtractLane(lane)
+        | InstructionType::I64x2ReplaceLane(lane)
+        | InstructionType::F32x4ExtractLane(lane)
+        | InstructionType::F32x4ReplaceLane(lane)
+        | InstructionType::F64x2ExtractLane(lane)
+        | InstructionType::F64x2ReplaceLane(lane) => format!(" {}", lane.0),
+        InstructionType::I32Load(memarg)
+        | InstructionType::I64Load(memarg)
+        | InstructionType::F32Load(memarg)
+        | InstructionType::F64Load(memarg)
+        | InstructionType::I32Load8S(memarg)
+        | InstructionType::I32Load8U(memarg)
+        | InstructionType::I32Load16S(memarg)
+        | InstructionType::I32Load16U(memarg)
+        | InstructionType::I64Load8S(memarg)
+        | InstructionType::I64Load8U(memarg)
+        | InstructionType::I64Load16S(memarg)
+        | InstructionType::I64Load16U(memarg)
+        | InstructionType::I64Load32S(memarg)
+        | InstructionType::I64Load32U(memarg)
+        | InstructionType::I32Store(memarg)
+        | InstructionType::I64Store(memarg)
+        | InstructionType::F32Store(memarg)
+        | InstructionType::F64Store(memarg)
+        | InstructionType::I32Store8(memarg)
+        | InstructionType::I32Store16(memarg)
+        | InstructionType::I64Store8(memarg)
+        | InstructionType::I64Store16(memarg)
+        | InstructionType::I64Store32(memarg)
+        | InstructionType::MemoryAtomicNotify(memarg)
+        | InstructionType::MemoryAtomicWait32(memarg)
+        | InstructionType::MemoryAtomicWait64(memarg)
+        | InstructionType::I32AtomicLoad(memarg)
+        | InstructionType::I64AtomicLoad(memarg)
+        | InstructionType::I32AtomicLoad8U(memarg)
+        | InstructionType::I32AtomicLoad16U(memarg)
+        | InstructionType::I64AtomicLoad8U(memarg)
+        | InstructionType::I64AtomicLoad16U(memarg)
+        | InstructionType::I64AtomicLoad32U(memarg)
+        | InstructionType::I32AtomicStore(memarg)
+        | InstructionType::I64AtomicStore(memarg)
+        | InstructionType::I32AtomicStore8(memarg)
+        | InstructionType::I32AtomicStore16(memarg)
+        | InstructionType::I64AtomicStore8(memarg)
+        | InstructionType::I64AtomicStore16(memarg)
+        | InstructionType::I64AtomicStore32(memarg)
+        | InstructionType::I32AtomicRmwAdd(memarg)
+        | InstructionType::I64AtomicRmwAdd(memarg)
+        | InstructionType::I32AtomicRmw8AddU(memarg)
+        | InstructionType::I32AtomicRmw16AddU(memarg)
+        | InstructionType::I64AtomicRmw8AddU(memarg)
+        | InstructionType::I64AtomicRmw16AddU(memarg)
+        | InstructionType::I64AtomicRmw32AddU(memarg)
+        | InstructionType::I32AtomicRmwSub(memarg)
+        | InstructionType::I64AtomicRmwSub(memarg)
+        | InstructionType::I32AtomicRmw8SubU(memarg)
+        | InstructionType::I32AtomicRmw16SubU(memarg)
+        | InstructionType::I64AtomicRmw8SubU(memarg)
+        | InstructionType::I64AtomicRmw16SubU(memarg)
+        | InstructionType::I64AtomicRmw32SubU(memarg)
+        | InstructionType::I32AtomicRmwAnd(memarg)
+        | InstructionType::I64AtomicRmwAnd(memarg)
+        | InstructionType::I32AtomicRmw8AndU(memarg)
+        | InstructionType::I32AtomicRmw16AndU(memarg)
+        | InstructionType::I64AtomicRmw8AndU(memarg)
+        | InstructionType::I64AtomicRmw16AndU(memarg)
+        | InstructionType::I64AtomicRmw32AndU(memarg)
+        | InstructionType::I32AtomicRmwOr(memarg)
+        | InstructionType::I64AtomicRmwOr(memarg)
+        | InstructionType::I32AtomicRmw8OrU(memarg)
+        | InstructionType::I32AtomicRmw16OrU(memarg)
+        | InstructionType::I64AtomicRmw8OrU(memarg)
+        | InstructionType::I64AtomicRmw16OrU(memarg)
+        | InstructionType::I64AtomicRmw32OrU(memarg)
+        | InstructionType::I32AtomicRmwXor(memarg)
+        | InstructionType::I64AtomicRmwXor(memarg)
+        | InstructionType::I32AtomicRmw8XorU(memarg)
+        | InstructionType::I32AtomicRmw16XorU(memarg)
+        | InstructionType::I64AtomicRmw8XorU(memarg)
+        | InstructionType::I64AtomicRmw16XorU(memarg)
+        | InstructionType::I64AtomicRmw32XorU(memarg)
+        | InstructionType::I32AtomicRmwXchg(memarg)
+        | InstructionType::I64AtomicRmwXchg(memarg)
+        | InstructionType::I32AtomicRmw8XchgU(memarg)
+        | InstructionType::I32AtomicRmw16XchgU(memarg)
+        | InstructionType::I64AtomicRmw8XchgU(memarg)
+        | InstructionType::I64AtomicRmw16XchgU(memarg)
+        | InstructionType::I64AtomicRmw32XchgU(memarg)
+        | InstructionType::I32AtomicRmwCmpxchg(memarg)
+        | InstructionType::I64AtomicRmwCmpxchg(memarg)
+        | InstructionType::I32AtomicRmw8CmpxchgU(memarg)
+        | InstructionType::I32AtomicRmw16CmpxchgU(memarg)
+        | InstructionType::I64AtomicRmw8CmpxchgU(memarg)
+        | InstructionType::I64AtomicRmw16CmpxchgU(memarg)
+        | InstructionType::I64AtomicRmw32CmpxchgU(memarg)
+        | InstructionType::V128Load(memarg)
+        | InstructionType::V128Load8x8S(memarg)
+        | InstructionType::V128Load8x8U(memarg)
+        | InstructionType::V128Load16x4S(memarg)
+        | InstructionType::V128Load16x4U(memarg)
+        | InstructionType::V128Load32x2S(memarg)
+        | InstructionType::V128Load32x2U(memarg)
+        | InstructionType::V128Load8Splat(memarg)
+        | InstructionType::V128Load16Splat(memarg)
+        | InstructionType::V128Load32Splat(memarg)
+        | InstructionType::V128Load64Splat(memarg)
+        | InstructionType::V128Load32Zero(memarg)
+        | InstructionType::V128Load64Zero(memarg)
+        | InstructionType::V128Store(memarg) => memarg_text(mnemonic, memarg),
+        _ => String::new(),
+    };
+
+    format!("{mnemonic}{immediates}")
+}
+
+fn blocktype(blocktype: &BlockType) -> String {
+    match blocktype {
+        BlockType::Empty => String::new(),
+        BlockType::ValType(val_type) => format!(" (result {val_type})"),
+        BlockType::TypeIndex(type_idx) => format!(" (type {})", type_idx.0),
+    }
+}
+
+fn catch(catch: &CatchType) -> String {
+    match catch {
+        CatchType::Catch((tag, label)) => format!(" (catch {} {})", tag.0 .0, label.0 .0),
+        CatchType::CatchRef((tag, label)) => format!(" (catch_ref {} {})", tag.0 .0, label.0 .0),
+        CatchType::CatchAll(label) => format!(" (catch_all {})", label.0 .0),
+        CatchType::CatchAllRef(label) => format!(" (catch_all_ref {})", label.0 .0),
+    }
+}
+
+fn heap_type(ref_type: &RefType) -> &'static str {
+    match ref_type {
+        RefType::FuncRef => "func",
+        RefType::ExternRef => "extern",
+        RefType::ExnRef => "exn",
+    }
+}
+
+pub(super) fn result(val_types: &[ValType]) -> String {
+    let val_types: String = val_types
+        .iter()
+        .map(|val_type| format!(" {val_type}"))
+        .collect();
+    format!("(result{val_types})")
+}
+
+// The memory index is omitted for the default memory
+fn memory(memory: u32) -> String {
+    match memory {
+        0 => String::new(),
+        memory => format!(" {memory}"),
+    }
+}
+
+// The offset and the alignment are omitted when they are the defaults
+fn memarg_text(mnemonic: &str, memarg: &MemArgType) -> String {
+    let mut text = memory(memarg.memory.0 .0);
+    if memarg.offset.0 != 0 {
+        text.push_str(&format!(" offset={}", memarg.offset.0));
+    }
+    let align = 1u64.checked_shl(memarg.align.0).unwrap_or(0);
+    if align != natural_alignment(mnemonic) {
+        text.push_str(&format!(" align={align}"));
+    }
+    text
+}
+
+// Number of bytes accessed, e.g. 2 for i32.load16_s, 8 for v128.load8x8_s
+fn natural_alignment(mnemonic: &str) -> u64 {
+    let (prefix, op) = mnemonic.split_once('.').unwrap_or(("", mnemonic));
+    if prefix == "v128" && op.contains('x') {
+        return 8;
+    }
+    let bits: String = op
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    match bits.parse::<u64>() {
+        Ok(bits) => bits / 8,
+        Err(_) => match prefix {
+            "i64" | "f64" => 8,
+            "v128" => 16,
+            _ => 4,
+        },
+    }
+}
+
+pub(super) fn f32_text(value: f32) -> String {
+    match value.is_nan() {
+        true => {
+            let bits = value.to_bits();
+            nan_text(bits >> 31 == 1, (bits & 0x7F_FFFF) as u64, 0x40_0000)
+        }
+        false => format!("{value}"),
+    }
+}
+
+pub(super) fn f64_text(value: f64) -> String {
+    match value.is_nan() {
+        true => {
+            let bits = value.to_bits();
+            nan_text(
+                bits >> 63 == 1,
+                bits & 0xF_FFFF_FFFF_FFFF,
+                0x8_0000_0000_0000,
+            )
+        }
+        false => format!("{value}"),
+    }
+}
+
+// The payload is printed unless it is the canonical one
+fn nan_text(negative: bool, payload: u64, canonical: u64) -> String {
+    let sign = if negative { "-" } else { "" };
+    match payload == canonical {
+        true => format!("{sign}nan"),
+        false => format!("{sign}nan:{payload:#x}"),
+    }
+}
+
+pub fn mnemonic(instruction: &InstructionType) -> &'static str {
+    match instruction {
+        InstructionType::Unreachable => "unreachable",
+        InstructionType::Nop => "nop",
+        InstructionType::Block(_) => "block",
+        InstructionType::Loop(_) => "loop",
+        InstructionType::IfElse(_) => "if",
+        InstructionType::Br(_) => "br",
+        InstructionType::BrIf(_) => "br_if",
+        InstructionType::BrTable(_) => "br_table",
+        InstructionType::Return => "return",
+        InstructionType::Call(_) => "call",
+        InstructionType::CallIndirect(_) => "call_indirect",
+        InstructionType::ReturnCall(_) => "return_call",
+        InstructionType::ReturnCallIndirect(_) => "return_call_indirect",
+        InstructionType::Throw(_) => "throw",
+        InstructionType::ThrowRef => "throw_ref",
+        InstructionType::TryTable(_) => "try_table",
+        InstructionType::RefNull(_) => "ref.null",
+        InstructionType::RefIsNull => "ref.is_null",
+        InstructionType::RefFunc(_) => "ref.func",
+        InstructionType::Drop => "drop",
+        InstructionType::Select => "select",
+        InstructionType::SelectVec(_) => "select",
+        InstructionType::LocalGet(_) => "local.get",
+        InstructionType::LocalSet(_) => "local.set",
+        InstructionType::LocalTee(_) => "local.tee",
+        InstructionType::GlobalGet(_) => "global.get",
+        InstructionType::GlobalSet(_) => "global.set",
+        InstructionType::TableGet(_) => "table.get",
+        InstructionType::TableSet(_) => "table.set",
+        InstructionType::TableInit(_) => "table.init",
+        InstructionType::ElemDrop(_) => "elem.drop",
+        InstructionType::TableCopy(_) => "table.copy",
+        InstructionType::TableGrow(_) => "table.grow",
+        InstructionType::TableSize(_) => "table.size",
+        InstructionType::TableFill(_) => "table.fill",
+        InstructionType::I32Load(_) => "i32.load",
+        InstructionType::I64Load(_) => "i64.load",
+        InstructionType::F32Load(_) => "f32.load",
+        InstructionType::F64Load(_) => "f64.load",
+        InstructionType::I32Load8S(_) => "i32.load8_s",
+        InstructionType::I32Load8U(_) => "i32.load8_u",
+        InstructionType::I32Load16S(_) => "i32.load16_s",
+        InstructionType::I32Load16U(_) => "i32.load16_u",
+        InstructionType::I64Load8S(_) => "i64.load8_s",
+        InstructionType::I64Load8U(_) => "i64.load8_u",
+        InstructionType::I64Load16S(_) => "i64.load16_s",
+        InstructionType::I64Load16U(_) => "i64.load16_u",
+        InstructionType::I64Load32S(_) => "i64.load32_s",
+        InstructionType::I64Load32U(_) => "i64.load32_u",
+        InstructionType::I32Store(_) => "i32.store",
+        InstructionType::I64Store(_) => "i64.store",
+        InstructionType::F32Store(_) => "f32.store",
+        InstructionType::F64Store(_) => "f64.store",
+        InstructionType::I32Store8(_) => "i32.store8",
+        InstructionType::I32Store16(_) => "i32.store16",
+        InstructionType::I64Store8(_) => "i64.store8",
+        InstructionType::I64Store16(_) => "i64.store16",
+        InstructionType::I64Store32(_) => "i64.store32",
+        InstructionType::MemorySize(_) => "memory.size",
+        InstructionType::MemoryGrow(_) => "memory.grow",
+        InstructionType::MemoryInit(_) => "memory.init",
+        InstructionType::DataDrop(_) => "data.drop",
+        InstructionType::MemoryCopy(_) => "memory.copy",
+        InstructionType::MemoryFill(_) => "memory.fill",
+        InstructionType::MemoryAtomicNotify(_) => "memory.atomic.notify",
+        InstructionType::MemoryAtomicWait32(_) => "memory.atomic.wait32",
+        InstructionType::MemoryAtomicWait64(_) => "memory.atomic.wait64",
+        InstructionType::AtomicFence => "atomic.fence",
+        InstructionType::I32AtomicLoad(_) => "i32.atomic.load",
+        InstructionType::I64AtomicLoad(_) => "i64.atomic.load",
+        InstructionType::I32AtomicLoad8U(_) => "i32.atomic.load8_u",
+        InstructionType::I32AtomicLoad16U(_) => "i32.atomic.load16_u",
+        InstructionType::I64AtomicLoad8U(_) => "i64.atomic.load8_u",
+        InstructionType::I64AtomicLoad16U(_) => "i64.atomic.load16_u",
+        InstructionType::I64AtomicLoad32U(_) => "i64.atomic.load32_u",
+        InstructionType::I32AtomicStore(_) => "i32.atomic.store",
+        InstructionType::I64AtomicStore(_) => "i64.atomic.store",
+        InstructionType::I32AtomicStore8(_) => "i32.atomic.store8",
+        InstructionType::I32AtomicStore16(_) => "i32.atomic.store16",
+        InstructionType::I64AtomicStore8(_) => "i64.atomic.store8",
+        InstructionType::I64AtomicStore16(_) => "i64.atomic.store16",
+        InstructionType::I64AtomicStore32(_) => "i64.atomic.store32",
+        InstructionType::I32AtomicRmwAdd(_) => "i32.atomic.rmw.add",
+        InstructionType::I64AtomicRmwAdd(_) => "i64.atomic.rmw.add",
+        InstructionType::I32AtomicRmw8AddU(_) => "i32.atomic.rmw8.add_u",
+        InstructionType::I32AtomicRmw16AddU(_) => "i32.atomic.rmw16.add_u",
+        InstructionType::I64AtomicRmw8AddU(_) => "i64.atomic.rmw8.add_u",
+        InstructionType::I64AtomicRmw16AddU(_) => "i64.atomic.rmw16.add_u",
+        InstructionType::I64AtomicRmw32AddU(_) => "i64.atomic.rmw32.add_u",
+        InstructionType::I32AtomicRmwSub(_) => "i32.atomic.rmw.sub",
+        InstructionType::I64AtomicRmwSub(_) => "i64.atomic.rmw.sub",
+        InstructionType::I32AtomicRmw8SubU(_) => "i32.atomic.rmw8.sub_u",
+        InstructionType::I32AtomicRmw16SubU(_) => "i32.atomic.rmw16.sub_u",
+        InstructionType::I64AtomicRmw8SubU(_) => "i64.atomic.rmw8.sub_u",
+        InstructionType::I64AtomicRmw16SubU(_) => "i64.atomic.rmw16.sub_u",
+        InstructionType::I64AtomicRmw32SubU(_) => "i64.atomic.rmw32.sub_u",
+        InstructionType::I32AtomicRmwAnd(_) => "i32.atomic.rmw.and",
+        InstructionType::I64AtomicRmwAnd(_) => "i64.atomic.rmw.and",
+        InstructionType::I32AtomicRmw8AndU(_) => "i32.atomic.rmw8.and_u",
+        InstructionType::I32AtomicRmw16AndU(_) => "i32.atomic.rmw16.and_u",
+        InstructionType::I64AtomicRmw8AndU(_) => "i64.atomic.rmw8.and_u",
+        InstructionType::I64AtomicRmw16AndU(_) => "i64.atomic.rmw16.and_u",
+        InstructionType::I64AtomicRmw32AndU(_) => "i64.atomic.rmw32.and_u",
+        InstructionType::I32AtomicRmwOr(_) => "i32.atomic.rmw.or",
+        InstructionType::I64AtomicRmwOr(_) => "i64.atomic.rmw.or",
+        InstructionType::I32AtomicRmw8OrU(_) => "i32.atomic.rmw8.or_u",
+        InstructionType::I32AtomicRmw16OrU(_) => "i32.atomic.rmw16.or_u",
+        InstructionType::I64AtomicRmw8OrU(_) => "i64.atomic.rmw8.or_u",
+        InstructionType::I64AtomicRmw16OrU(_) => "i64.atomic.rmw16.or_u",
+        InstructionType::I64AtomicRmw32OrU(_) => "i64.atomic.rmw32.or_u",
+        InstructionType::I32AtomicRmwXor(_) => "i32.atomic.rmw.xor",
+        InstructionType::I64AtomicRmwXor(_) => "i64.atomic.rmw.xor",
+        InstructionType::I32AtomicRmw8XorU(_) => "i32.atomic.rmw8.xor_u",
+        InstructionType::I32AtomicRmw16XorU(_) => "i32.atomic.rmw16.xor_u",
+        InstructionType::I64AtomicRmw8XorU(_) => "i64.atomic.rmw8.xor_u",
+        InstructionType::I64AtomicRmw16XorU(_) => "i64.atomic.rmw16.xor_u",
+        InstructionType::I64AtomicRmw32XorU(_) => "i64.atomic.rmw32.xor_u",
+        InstructionType::I32AtomicRmwXchg(_) => "i32.atomic.rmw.xchg",
+        InstructionType::I64AtomicRmwXchg(_) => "i64.atomic.rmw.xchg",
+        InstructionType::I32AtomicRmw8XchgU(_) => "i32.atomic.rmw8.xchg_u",
+        InstructionType::I32AtomicRmw16XchgU(_) => "i32.atomic.rmw16.xchg_u",
+        InstructionType::I64AtomicRmw8XchgU(_) => "i64.atomic.rmw8.xchg_u",
+        InstructionType::I64AtomicRmw16XchgU(_) => "i64.atomic.rmw16.xchg_u",
+        InstructionType::I64AtomicRmw32XchgU(_) => "i64.atomic.rmw32.xchg_u",
+        InstructionType::I32AtomicRmwCmpxchg(_) => "i32.atomic.rmw.cmpxchg",
+        InstructionType::I64AtomicRmwCmpxchg(_) => "i64.atomic.rmw.cmpxchg",
+        InstructionType::I32AtomicRmw8CmpxchgU(_) => "i32.atomic.rmw8.cmpxchg_u",
+        InstructionType::I32AtomicRmw16CmpxchgU(_) => "i32.atomic.rmw16.cmpxchg_u",
+        InstructionType::I64AtomicRmw8CmpxchgU(_) => "i64.atomic.rmw8.cmpxchg_u",
+        InstructionType::I64AtomicRmw16CmpxchgU(_) => "i64.atomic.rmw16.cmpxchg_u",
+        InstructionType::I64AtomicRmw32CmpxchgU(_) => "i64.atomic.rmw32.cmpxchg_u",
+        InstructionType::I32Const(_) => "i32.const",
+        InstructionType::I64Const(_) => "i64.const",
+        InstructionType::F32Const(_) => "f32.const",
+        InstructionType::F64Const(_) => "f64.const",
+        InstructionType::I32Eqz => "i32.eqz",
+        InstructionType::I32Eq => "i32.eq",
+        InstructionType::I32Ne => "i32.ne",
+        InstructionType::I32LtS => "i32.lt_s",
+        InstructionType::I32LtU => "i32.lt_u",
+        InstructionType::I32GtS => "i32.gt_s",
+        InstructionType::I32GtU => "i32.gt_u",
+        InstructionType::I32LeS => "i32.le_s",
+        InstructionType::I32LeU => "i32.le_u",
+        InstructionType::I32GeS => "i32.ge_s",
+        InstructionType::I32GeU => "i32.ge_u",
+        InstructionType::I64Eqz => "i64.eqz",
+        InstructionType::I64Eq => "i64.eq",
+        InstructionType::I64Ne => "i64.ne",
+        InstructionType::I64LtS => "i64.lt_s",
+        InstructionType::I64LtU => "i64.lt_u",
+        InstructionType::I64GtS => "i64.gt_s",
+        InstructionType::I64GtU => "i64.gt_u",
+        InstructionType::I64LeS => "i64.le_s",
+        InstructionType::I64LeU => "i64.le_u",
+        InstructionType::I64GeS => "i64.ge_s",
+        InstructionType::I64GeU => "i64.ge_u",
+        InstructionType::F32Eq => "f32.eq",
+        InstructionType::F32Ne => "f32.ne",
+        InstructionType::F32Lt => "f32.lt",
+        InstructionType::F32Gt => "f32.gt",
+        InstructionType::F32Le => "f32.le",
+        InstructionType::F32Ge => "f32.ge",
+        InstructionType::F64Eq => "f64.eq",
+        InstructionType::F64Ne => "f64.ne",
+        InstructionType::F64Lt => "f64.lt",
+        InstructionType::F64Gt => "f64.gt",
+        InstructionType::F64Le => "f64.le",
+        InstructionType::F64Ge => "f64.ge",
+        InstructionType::I32Clz => "i32.clz",
+        InstructionType::I32Ctz => "i32.ctz",
+        InstructionType::I32Popcnt => "i32.popcnt",
+        InstructionType::I32Add => "i32.add",
+        InstructionType::I32Sub => "i32.sub",
+        InstructionType::I32Mul => "i32.mul",
+        InstructionType::I32DivS => "i32.div_s",
+        InstructionType::I32DivU => "i32.div_u",
+        InstructionType::I32RemS => "i32.rem_s",
+        InstructionType::I32RemU => "i32.rem_u",
+        InstructionType::I32And => "i32.and",
+        InstructionType::I32Or => "i32.or",
+        InstructionType::I32Xor => "i32.xor",
+        InstructionType::I32Shl => "i32.shl",
+        InstructionType::I32ShrS => "i32.shr_s",
+        InstructionType::I32ShrU => "i32.shr_u",
+        InstructionType::I32Rotl => "i32.rotl",
+        InstructionType::I32Rotr => "i32.rotr",
+        InstructionType::I64Clz => "i64.clz",
+        InstructionType::I64Ctz => "i64.ctz",
+        InstructionType::I64Popcnt => "i64.popcnt",
+        InstructionType::I64Add => "i64.add",
+        InstructionType::I64Sub => "i64.sub",
+        InstructionType::I64Mul => "i64.mul",
+        InstructionType::I64DivS => "i64.div_s",
+        InstructionType::I64DivU => "i64.div_u",
+        InstructionType::I64RemS => "i64.rem_s",
+        InstructionType::I64RemU => "i64.rem_u",
+        InstructionType::I64And => "i64.and",
+        InstructionType::I64Or => "i64.or",
+        InstructionType::I64Xor => "i64.xor",
+        InstructionType::I64Shl => "i64.shl",
+        InstructionType::I64ShrS => "i64.shr_s",
+        InstructionType::I64ShrU => "i64.shr_u",
+        InstructionType::I64Rotl => "i64.rotl",
+        InstructionType::I64Rotr => "i64.rotr",
+        InstructionType::F32Abs => "f32.abs",
+        InstructionType::F32Neg => "f32.neg",
+        InstructionType::F32Ceil => "f32.ceil",
+        InstructionType::F32Floor => "f32.floor",
+        InstructionType::F32Trunc => "f32.trunc",
+        InstructionType::F32Nearest => "f32.nearest",
+        InstructionType::F32Sqrt => "f32.sqrt",
+        InstructionType::F32Add => "f32.add",
+        InstructionType::F32Sub => "f32.sub",
+        InstructionType::F32Mul => "f32.mul",
+        InstructionType::F32Div => "f32.div",
+        InstructionType::F32Min => "f32.min",
+        InstructionType::F32Max => "f32.max",
+        InstructionType::F32Copysign => "f32.copysign",
+        InstructionType::F64Abs => "f64.abs",
+        InstructionType::F64Neg => "f64.neg",
+        InstructionType::F64Ceil => "f64.ceil",
+        InstructionType::F64Floor => "f64.floor",
+        InstructionType::F64Trunc => "f64.trunc",
+        InstructionType::F64Nearest => "f64.nearest",
+        InstructionType::F64Sqrt => "f64.sqrt",
+        InstructionType::F64Add => "f64.add",
+        InstructionType::F64Sub => "f64.sub",
+        InstructionType::F64Mul => "f64.mul",
+        InstructionType::F64Div => "f64.div",
+        InstructionType::F64Min => "f64.min",
+        InstructionType::F64Max => "f64.max",
+        InstructionType::F64Copysign => "f64.copysign",
+        InstructionType::I32WrapI64 => "i32.wrap_i64",
+        InstructionType::I32TruncF32S => "i32.trunc_f32_s",
+        InstructionType::I32TruncF32U => "i32.trunc_f32_u",
+        InstructionType::I32TruncF64S => "i32.trunc_f64_s",
+        InstructionType::I32TruncF64U => "i32.trunc_f64_u",
+        InstructionType::I64ExtendI32S => "i64.extend_i32_s",
+        InstructionType::I64ExtendI32U => "i64.extend_i32_u",
+        InstructionType::I64TruncF32S => "i64.trunc_f32_s",
+        InstructionType::I64TruncF32U => "i64.trunc_f32_u",
+        InstructionType::I64TruncF64S => "i64.trunc_f64_s",
+        InstructionType::I64TruncF64U => "i64.trunc_f64_u",
+        InstructionType::F32ConvertI32S => "f32.convert_i32_s",
+        InstructionType::F32ConvertI32U => "f32.convert_i32_u",
+        InstructionType::F32ConvertI64S => "f32.convert_i64_s",
+        InstructionType::F32ConvertI64U => "f32.convert_i64_u",
+        InstructionType::F32DemoteF64 => "f32.demote_f64",
+        InstructionType::F64ConvertI32S => "f64.convert_i32_s",
+        InstructionType::F64ConvertI32U => "f64.convert_i32_u",
+        InstructionType::F64ConvertI64S => "f64.convert_i64_s",
+        InstructionType::F64ConvertI64U => "f64.convert_i64_u",
+        InstructionType::F64PromoteF32 => "f64.promote_f32",
+        InstructionType::I32ReinterpretF32 => "i32.reinterpret_f32",
+        InstructionType::I64ReinterpretF64 => "i64.reinterpret_f64",
+        InstructionType::F32ReinterpretI32 => "f32.reinterpret_i32",
+        InstructionType::F64ReinterpretI64 => "f64.reinterpret_i64",
+        InstructionType::I32Extend8S => "i32.extend8_s",
+        InstructionType::I32Extend16S => "i32.extend16_s",
+        InstructionType::I64Extend8S => "i64.extend8_s",
+        InstructionType::I64Extend16S => "i64.extend16_s",
+        InstructionType::I64Extend32S => "i64.extend32_s",
+        InstructionType::I32TruncSatF32S => "i32.trunc_sat_f32_s",
+        InstructionType::I32TruncSatF32U => "i32.trunc_sat_f32_u",
+        InstructionType::I32TruncSatF64S => "i32.trunc_sat_f64_s",
+        InstructionType::I32TruncSatF64U => "i32.trunc_sat_f64_u",
+        InstructionType::I64TruncSatF32S => "i64.trunc_sat_f32_s",
+        InstructionType::I64TruncSatF32U => "i64.trunc_sat_f32_u",
+        InstructionType::I64TruncSatF64S => "i64.trunc_sat_f64_s",
+        InstructionType::I64TruncSatF64U => "i64.trunc_sat_f64_u",
+        InstructionType::V128Load(_) => "v128.load",
+        InstructionType::V128Load8x8S(_) => "v128.load8x8_s",
+        InstructionType::V128Load8x8U(_) => "v128.load8x8_u",
+        InstructionType::V128Load16x4S(_) => "v128.load16x4_s",
+        InstructionType::V128Load16x4U(_) => "v128.load16x4_u",
+        InstructionType::V128Load32x2S(_) => "v128.load32x2_s",
+        InstructionType::V128Load32x2U(_) => "v128.load32x2_u",
+        InstructionType::V128Load8Splat(_) => "v128.load8_splat",
+        InstructionType::V128Load16Splat(_) => "v128.load16_splat",
+        InstructionType::V128Load32Splat(_) => "v128.load32_splat",
+        InstructionType::V128Load64Splat(_) => "v128.load64_splat",
+        InstructionType::V128Load32Zero(_) => "v128.load32_zero",
+        InstructionType::V128Load64Zero(_) => "v128.load64_zero",
+        InstructionType::V128Store(_) => "v128.store",
+        InstructionType::V128Load8Lane(_) => "v128.load8_lane",
+        InstructionType::V128Load16Lane(_) => "v128.load16_lane",
+        InstructionType::V128Load32Lane(_) => "v128.load32_lane",
+        InstructionType::V128Load64Lane(_) => "v128.load64_lane",
+        InstructionType::V128Store8Lane(_) => "v128.store8_lane",
+        InstructionType::V128Store16Lane(_) => "v128.store16_lane",
+        InstructionType::V128Store32Lane(_) => "v128.store32_lane",
+        InstructionType::V128Store64Lane(_) => "v128.store64_lane",
+        InstructionType::V128Const(_) => "v128.const",
+        InstructionType::I8x16Shuffle(_) => "i8x16.shuffle",
+        InstructionType::I8x16ExtractLaneS(_) => "i8x16.extract_lane_s",
+        InstructionType::I8x16ExtractLaneU(_) => "i8x16.extract_lane_u",
+        InstructionType::I8x16ReplaceLane(_) => "i8x16.replace_lane",
+        InstructionType::I16x8ExtractLaneS(_) => "i16x8.extract_lane_s",
+        InstructionType::I16x8ExtractLaneU(_) => "i16x8.extract_lane_u",
+        InstructionType::I16x8ReplaceLane(_) => "i16x8.replace_lane",
+        InstructionType::I32x4ExtractLane(_) => "i32x4.extract_lane",
+        InstructionType::I32x4ReplaceLane(_) => "i32x4.replace_lane",
+        InstructionType::I64x2ExtractLane(_) => "i64x2.extract_lane",
+        InstructionType::I64x2ReplaceLane(_) => "i64x2.replace_lane",
+        InstructionType::F32x4ExtractLane(_) => "f32x4.extract_lane",
+        InstructionType::F32x4ReplaceLane(_) => "f32x4.replace_lane",
+        InstructionType::F64x2ExtractLane(_) => "f64x2.extract_lane",
+        InstructionType::F64x2ReplaceLane(_) => "f64x2.replace_lane",
+        InstructionType::I8x16Swizzle => "i8x16.swizzle",
+        InstructionType::I8x16Splat => "i8x16.splat",
+        InstructionType::I16x8Splat => "i16x8.splat",
+        InstructionType::I32x4Splat => "i32x4.splat",
+        InstructionType::I64x2Splat => "i64x2.splat",
+        InstructionType::F32x4Splat => "f32x4.splat",
+        InstructionType::F64x2Splat => "f64x2.splat",
+        InstructionType::I8x16Eq => "i8x16.eq",
+        InstructionType::I8x16Ne => "i8x16.ne",
+        InstructionType::I8x16LtS => "i8x16.lt_s",
+        InstructionType::I8x16LtU => "i8x16.lt_u",
+        InstructionType::I8x16GtS => "i8x16.gt_s",
+        InstructionType::I8x16GtU => "i8x16.gt_u",
+        InstructionType::I8x16LeS => "i8x16.le_s",
+        InstructionType::I8x16LeU => "i8x16.le_u",
+        InstructionType::I8x16GeS => "i8x16.ge_s",
+        InstructionType::I8x16GeU => "i8x16.ge_u",
+        InstructionType::I16x8Eq => "i16x8.eq",
+        InstructionType::I16x8Ne => "i16x8.ne",
+        InstructionType::I16x8LtS => "i16x8.lt_s",
+        InstructionType::I16x8LtU => "i16x8.lt_u",
+        InstructionType::I16x8GtS => "i16x8.gt_s",
+        InstructionType::I16x8GtU => "i16x8.gt_u",
+        InstructionType::I16x8LeS => "i16x8.le_s",
+        InstructionType::I16x8LeU => "i16x8.le_u",
+        InstructionType::I16x8GeS => "i16x8.ge_s",
+        InstructionType::I16x8GeU => "i16x8.ge_u",
+        InstructionType::I32x4Eq => "i32x4.eq",
+        InstructionType::I32x4Ne => "i32x4.ne",
+        InstructionType::I32x4LtS => "i32x4.lt_s",
+        InstructionType::I32x4LtU => "i32x4.lt_u",
+        InstructionType::I32x4GtS => "i32x4.gt_s",
+        InstructionType::I32x4GtU => "i32x4.gt_u",
+        InstructionType::I32x4LeS => "i32x4.le_s",
+        InstructionType::I32x4LeU => "i32x4.le_u",
+        InstructionType::I32x4GeS => "i32x4.ge_s",
+        InstructionType::I32x4GeU => "i32x4.ge_u",
+        InstructionType::I64x2Eq => "i64x2.eq",
+        InstructionType::I64x2Ne => "i64x2.ne",
+        InstructionType::I64x2LtS => "i64x2.lt_s",
+        InstructionType::I64x2GtS => "i64x2.gt_s",
+        InstructionType::I64x2LeS => "i64x2.le_s",
+        InstructionType::I64x2GeS => "i64x2.ge_s",
+        InstructionType::F32x4Eq => "f32x4.eq",
+        InstructionType::F32x4Ne => "f32x4.ne",
+        InstructionType::F32x4Lt => "f32x4.lt",
+        InstructionType::F32x4Gt => "f32x4.gt",
+        InstructionType::F32x4Le => "f32x4.le",
+        InstructionType::F32x4Ge => "f32x4.ge",
+        InstructionType::F64x2Eq => "f64x2.eq",
+        InstructionType::F64x2Ne => "f64x2.ne",
+        InstructionType::F64x2Lt => "f64x2.lt",
+        InstructionType::F64x2Gt => "f64x2.gt",
+        InstructionType::F64x2Le => "f64x2.le",
+        InstructionType::F64x2Ge => "f64x2.ge",
+        InstructionType::V128Not => "v128.not",
+        InstructionType::V128And => "v128.and",
+        InstructionType::V128AndNot => "v128.andnot",
+        InstructionType::V128Or => "v128.or",
+        InstructionType::V128Xor => "v128.xor",
+        InstructionType::V128Bitselect => "v128.bitselect",
+        InstructionType::V128AnyTrue => "v128.any_true",
+        InstructionType::I8x16Abs => "i8x16.abs",
+        InstructionType::I8x16Neg => "i8x16.neg",
+        InstructionType::I8x16Popcnt => "i8x16.popcnt",
+        InstructionType::I8x16AllTrue => "i8x16.all_true",
+        InstructionType::I8x16Bitmask => "i8x16.bitmask",
+        InstructionType::I8x16NarrowI16x8S => "i8x16.narrow_i16x8_s",
+        InstructionType::I8x16NarrowI16x8U => "i8x16.narrow_i16x8_u",
+        InstructionType::I8x16Shl => "i8x16.shl",
+        InstructionType::I8x16ShrS => "i8x16.shr_s",
+        InstructionType::I8x16ShrU => "i8x16.shr_u",
+        InstructionType::I8x16Add => "i8x16.add",
+        InstructionType::I8x16AddSatS => "i8x16.add_sat_s",
+        InstructionType::I8x16AddSatU => "i8x16.add_sat_u",
+        InstructionType::I8x16Sub => "i8x16.sub",
+        InstructionType::I8x16SubSatS => "i8x16.sub_sat_s",
+        InstructionType::I8x16SubSatU => "i8x16.sub_sat_u",
+        InstructionType::I8x16MinS => "i8x16.min_s",
+        InstructionType::I8x16MinU => "i8x16.min_u",
+        InstructionType::I8x16MaxS => "i8x16.max_s",
+        InstructionType::I8x16MaxU => "i8x16.max_u",
+        InstructionType::I8x16AvgrU => "i8x16.avgr_u",
+        InstructionType::I16x8ExtaddPairwiseI8x16S => "i16x8.extadd_pairwise_i8x16_s",
+        InstructionType::I16x8ExtaddPairwiseI8x16U => "i16x8.extadd_pairwise_i8x16_u",
+        InstructionType::I16x8Abs => "i16x8.abs",
+        InstructionType::I16x8Neg => "i16x8.neg",
+        InstructionType::I16x8Q15MulrSatS => "i16x8.q15mulr_sat_s",
+        InstructionType::I16x8AllTrue => "i16x8.all_true",
+        InstructionType::I16x8Bitmask => "i16x8.bitmask",
+        InstructionType::I16x8NarrowI32x4S => "i16x8.narrow_i32x4_s",
+        InstructionType::I16x8NarrowI32x4U => "i16x8.narrow_i32x4_u",
+        InstructionType::I16x8ExtendLowI8x16S => "i16x8.extend_low_i8x16_s",
+        InstructionType::I16x8ExtendHighI8x16S => "i16x8.extend_high_i8x16_s",
+        InstructionType::I16x8ExtendLowI8x16U => "i16x8.extend_low_i8x16_u",
+        InstructionType::I16x8ExtendHighI8x16U => "i16x8.extend_high_i8x16_u",
+        InstructionType::I16x8Shl => "i16x8.shl",
+        InstructionType::I16x8ShrS => "i16x8.shr_s",
+        InstructionType::I16x8ShrU => "i16x8.shr_u",
+        InstructionType::I16x8Add => "i16x8.add",
+        InstructionType::I16x8AddSatS => "i16x8.add_sat_s",
+        InstructionType::I16x8AddSatU => "i16x8.add_sat_u",
+        InstructionType::I16x8Sub => "i16x8.sub",
+        InstructionType::I16x8SubSatS => "i16x8.sub_sat_s",
+        InstructionType::I16x8SubSatU => "i16x8.sub_sat_u",
+        InstructionType::I16x8Mul => "i16x8.mul",
+        InstructionType::I16x8MinS => "i16x8.min_s",
+        InstructionType::I16x8MinU => "i16x8.min_u",
+        InstructionType::I16x8MaxS => "i16x8.max_s",
+        InstructionType::I16x8MaxU => "i16x8.max_u",
+        InstructionType::I16x8AvgrU => "i16x8.avgr_u",
+        InstructionType::I16x8ExtmulLowI8x16S => "i16x8.extmul_low_i8x16_s",
+        InstructionType::I16x8ExtmulHighI8x16S => "i16x8.extmul_high_i8x16_s",
+        InstructionType::I16x8ExtmulLowI8x16U => "i16x8.extmul_low_i8x16_u",
+        InstructionType::I16x8ExtmulHighI8x16U => "i16x8.extmul_high_i8x16_u",
+        InstructionType::I32x4ExtaddPairwiseI16x8S => "i32x4.extadd_pairwise_i16x8_s",
+        InstructionType::I32x4ExtaddPairwiseI16x8U => "i32x4.extadd_pairwise_i16x8_u",
+        InstructionType::I32x4Abs => "i32x4.abs",
+        InstructionType::I32x4Neg => "i32x4.neg",
+        InstructionType::I32x4AllTrue => "i32x4.all_true",
+        InstructionType::I32x4Bitmask => "i32x4.bitmask",
+        InstructionType::I32x4ExtendLowI16x8S => "i32x4.extend_low_i16x8_s",
+        InstructionType::I32x4ExtendHighI16x8S => "i32x4.extend_high_i16x8_s",
+        InstructionType::I32x4ExtendLowI16x8U => "i32x4.extend_low_i16x8_u",
+        InstructionType::I32x4ExtendHighI16x8U => "i32x4.extend_high_i16x8_u",
+        InstructionType::I32x4Shl => "i32x4.shl",
+        InstructionType::I32x4ShrS => "i32x4.shr_s",
+        InstructionType::I32x4ShrU => "i32x4.shr_u",
+        InstructionType::I32x4Add => "i32x4.add",
+        InstructionType::I32x4Sub => "i32x4.sub",
+        InstructionType::I32x4Mul => "i32x4.mul",
+        InstructionType::I32x4MinS => "i32x4.min_s",
+        InstructionType::I32x4MinU => "i32x4.min_u",
+        InstructionType::I32x4MaxS => "i32x4.max_s",
+        InstructionType::I32x4MaxU => "i32x4.max_u",
+        InstructionType::I32x4DotI16x8S => "i32x4.dot_i16x8_s",
+        InstructionType::I32x4ExtmulLowI16x8S => "i32x4.extmul_low_i16x8_s",
+        InstructionType::I32x4ExtmulHighI16x8S => "i32x4.extmul_high_i16x8_s",
+        InstructionType::I32x4ExtmulLowI16x8U => "i32x4.extmul_low_i16x8_u",
+        InstructionType::I32x4ExtmulHighI16x8U => "i32x4.extmul_high_i16x8_u",
+        InstructionType::I64x2Abs => "i64x2.abs",
+        InstructionType::I64x2Neg => "i64x2.neg",
+        InstructionType::I64x2AllTrue => "i64x2.all_true",
+        InstructionType::I64x2Bitmask => "i64x2.bitmask",
+        InstructionType::I64x2ExtendLowI32x4S => "i64x2.extend_low_i32x4_s",
+        InstructionType::I64x2ExtendHighI32x4S => "i64x2.extend_high_i32x4_s",
+        InstructionType::I64x2ExtendLowI32x4U => "i64x2.extend_low_i32x4_u",
+        InstructionType::I64x2ExtendHighI32x4U => "i64x2.extend_high_i32x4_u",
+        InstructionType::I64x2Shl => "i64x2.shl",
+        InstructionType::I64x2ShrS => "i64x2.shr_s",
+        InstructionType::I64x2ShrU => "i64x2.shr_u",
+        InstructionType::I64x2Add => "i64x2.add",
+        InstructionType::I64x2Sub => "i64x2.sub",
+        InstructionType::I64x2Mul => "i64x2.mul",
+        InstructionType::I64x2ExtmulLowI32x4S => "i64x2.extmul_low_i32x4_s",
+        InstructionType::I64x2ExtmulHighI32x4S => "i64x2.extmul_high_i32x4_s",
+        InstructionType::I64x2ExtmulLowI32x4U => "i64x2.extmul_low_i32x4_u",
+        InstructionType::I64x2ExtmulHighI32x4U => "i64x2.extmul_high_i32x4_u",
+        InstructionType::F32x4Ceil => "f32x4.ceil",
+        InstructionType::F32x4Floor => "f32x4.floor",
+        InstructionType::F32x4Trunc => "f32x4.trunc",
+        InstructionType::F32x4Nearest => "f32x4.nearest",
+        InstructionType::F32x4Abs => "f32x4.abs",
+        InstructionType::F32x4Neg => "f32x4.neg",
+        InstructionType::F32x4Sqrt => "f32x4.sqrt",
+        InstructionType::F32x4Add => "f32x4.add",
+        InstructionType::F32x4Sub => "f32x4.sub",
+        InstructionType::F32x4Mul => "f32x4.mul",
+        InstructionType::F32x4Div => "f32x4.div",
+        InstructionType::F32x4Min => "f32x4.min",
+        InstructionType::F32x4Max => "f32x4.max",
+        InstructionType::F32x4Pmin => "f32x4.pmin",
+        InstructionType::F32x4Pmax => "f32x4.pmax",
+        InstructionType::F64x2Ceil => "f64x2.ceil",
+        InstructionType::F64x2Floor => "f64x2.floor",
+        InstructionType::F64x2Trunc => "f64x2.trunc",
+        InstructionType::F64x2Nearest => "f64x2.nearest",
+        InstructionType::F64x2Abs => "f64x2.abs",
+        InstructionType::F64x2Neg => "f64x2.neg",
+        InstructionType::F64x2Sqrt => "f64x2.sqrt",
+        InstructionType::F64x2Add => "f64x2.add",
+        InstructionType::F64x2Sub => "f64x2.sub",
+        InstructionType::F64x2Mul => "f64x2.mul",
+        InstructionType::F64x2Div => "f64x2.div",
+        InstructionType::F64x2Min => "f64x2.min",
+        InstructionType::F64x2Max => "f64x2.max",
+        InstructionType::F64x2Pmin => "f64x2.pmin",
+        InstructionType::F64x2Pmax => "f64x2.pmax",
+        InstructionType::I32x4TruncSatF32x4S => "i32x4.trunc_sat_f32x4_s",
+        InstructionType::I32x4TruncSatF32x4U => "i32x4.trunc_sat_f32x4_u",
+        InstructionType::F32x4ConvertI32x4S => "f32x4.convert_i32x4_s",
+        InstructionType::F32x4ConvertI32x4U => "f32x4.convert_i32x4_u",
+        InstructionType::I32x4TruncSatF64x2SZero => "i32x4.trunc_sat_f64x2_s_zero",
+        InstructionType::I32x4TruncSatF64x2UZero => "i32x4.trunc_sat_f64x2_u_zero",
+        InstructionType::F64x2ConvertLowI32x4S => "f64x2.convert_low_i32x4_s",
+        InstructionType::F64x2ConvertLowI32x4U => "f64x2.convert_low_i32x4_u",
+        InstructionType::F32x4DemoteF64x2Zero => "f32x4.demote_f64x2_zero",
+        InstructionType::F64x2PromoteLowF32x4 => "f64x2.promote_low_f32x4",
+    }
+}
@@ -0,0 +1,474 @@
+use crate::entities::{
+    instructions::{ExpressionType, InstructionType},
+    module::{
+        CodeBody, DataType, ElementSegmentType, ExportDescription, FuncCodeType, ImportDescription,
+        Module,
+    },
+    types::{
+        FuncIdx, FuncType, GlobalType, IndexType, LimitsType, MemType, MutType, TableType, TypeIdx,
+    },
+};
+pub use instructions::{instruction_text, mnemonic, write_instructions};
+use instructions::{result, write_line};
+
+mod instructions;
+
+/// Prints the module in the WebAssembly text format, items are referred to by index
+pub fn module_to_text(module: &Module) -> String {
+    let mut out = String::from("(module\n");
+
+    for (i, func_type) in module.types.iter().enumerate() {
+        let func_type = func_signature(func_type);
+        write_line(&mut out, 1, &format!("(type (;{i};) (func{func_type}))"));
+    }
+
+    let mut imported = ImportedItems::default();
+    for import in &module.imports {
+        let desc = match import.desc {
+            ImportDescription::Func(ref type_idx) => {
+                let i = next_index(&mut imported.funcs);
+                format!("(func (;{i};) {})", type_use(module, type_idx))
+            }
+            ImportDescription::Table(ref table_type) => {
+                let i = next_index(&mut imported.tables);
+                format!("(table (;{i};) {})", table_type_text(table_type))
+            }
+            ImportDescription::Mem(ref mem_type) => {
+                let i = next_index(&mut imported.mems);
+                format!("(memory (;{i};) {})", mem_type_text(mem_type))
+            }
+            ImportDescription::Global(ref global_type) => {
+                let i = next_index(&mut imported.globals);
+                format!("(global (;{i};) {})", global_type_text(global_type))
+            }
+            ImportDescription::Tag(ref tag_type) => {
+                let i = next_index(&mut imported.tags);
+                format!("(tag (;{i};) {})", type_use(module, &tag_type.type_idx))
+            }
+        };
+        write_line(
+            &mut out,
+            1,
+            &format!(
+                "(import {} {} {desc})",
+                string(import.module.0.as_bytes()),
+                string(import.name.0.as_bytes())
+            ),
+        );
+    }
+
+    for (i, type_idx) in module.funcs.iter().enumerate() {
+        let index = imported.funcs + i;
+        write_line(
+            &mut out,
+            1,
+            &format!("(func (;{index};) {}", type_use(module, type_idx)),
+        );
+        match module.code.get(i).map(|code| &code.code) {
+            Some(CodeBody::Decoded(code)) => write_func_code(&mut out, code),
+            Some(CodeBody::Raw(raw_code)) => match raw_code.decode() {
+                Ok(code) => write_func_code(&mut out, &code),
+                Err(error) => write_line(&mut out, 2, &format!(";; {error}")),
+            },
+            None => write_line(&mut out, 2, ";; missing body"),
+        }
+        close(&mut out);
+    }
+
+    for (i, table_type) in module.tables.iter().enumerate() {
+        let index = imported.tables + i;
+        let table_type = table_type_text(table_type);
+        write_line(&mut out, 1, &format!("(table (;{index};) {table_type})"));
+    }
+
+    for (i, mem_type) in module.mems.iter().enumerate() {
+        let index = imported.mems + i;
+        let mem_type = mem_type_text(mem_type);
+        write_line(&mut out, 1, &format!("(memory (;{index};) {mem_type})"));
+    }
+
+    for (i, tag_type) in module.tags.iter().enumerate() {
+        let index = imported.tags + i;
+        let type_use = type_use(module, &tag_type.type_idx);
+        write_line(&mut out, 1, &format!("(tag (;{index};) {type_use})"));
+    }
+
+    for (i, global) in module.globals.iter().enumerate() {
+        let index = imported.globals + i;
+        let global_type = global_type_text(&global.global_type);
+        let init = expression(&global.init.instructions, None);
+        write_line(
+            &mut out,
+            1,
+            &format!("(global (;{index};) {global_type} {init})"),
+        );
+    }
+
+    for export in &module.exports {
+        let (kind, index) = match export.desc {
+            ExportDescription::Func(ref idx) => ("func", idx.0 .0),
+            ExportDescription::Table(ref idx) => ("table", idx.0 .0),
+            ExportDescription::Mem(ref idx) => ("memory", idx.0 .0),
+            ExportDescription::Global(ref idx) => ("global", idx.0 .0),
+            ExportDescription::Tag(ref idx) => ("tag", idx.0 .0),
+        };
+        let name = string(export.name.0.as_bytes());
+        write_line(&mut out, 1, &format!("(export {name} ({kind} {index}))"));
+    }
+
+    if let Some(ref start) = module.start {
+        write_line(&mut out, 1, &format!("(start {})", start.func.0 .0));
+    }
+
+    for (i, elem) in module.elems.iter().enumerate() {
+        write_line(&mut out, 1, &format!("(elem (;{i};) {})", elem_text(elem)));
+    }
+
+    for (i, data) in module.datas.iter().enumerate() {
+        let (mode, init) = match data {
+            DataType::Active0(data) => {
+                let offset = expression(&data.mode.offset.instructions, Some("offset"));
+                (format!("{offset} "), &data.init)
+            }
+            DataType::Active(data) => {
+                let offset = expression(&data.mode.offset.instructions, Some("offset"));
+                let memory = data.mode.memory.0 .0;
+                (format!("(memory {memory}) {offset} "), &data.init)
+            }
+            DataType::Passive(data) => (String::new(), &data.init),
+        };
+        write_line(
+            &mut out,
+            1,
+            &format!("(data (;{i};) {mode}{})", string(init)),
+        );
+    }
+
+    close(&mut out);
+    out
+}
+
+// Number of imports of every kind, the module's own items are indexed after them
+#[derive(Default)]
+struct ImportedItems {
+    funcs: usize,
+    tables: usize,
+    mems: usize,
+    globals: usize,
+    tags: usize,
+}
+
+fn next_index(count: &mut usize) -> usize {
+    *count += 1;
+    *count - 1
+}
+
+fn write_func_code(out: &mut String, code: &FuncCodeType) {
+    let locals: String = code
+        .locals
+        .iter()
+        .flat_map(|locals| std::iter::repeat_n(&locals.val_type, locals.n.0 as usize))
+        .map(|val_type| format!(" {val_type}"))
+        .collect();
+    if !locals.is_empty() {
+        write_line(out, 2, &format!("(local{locals})"));
+    }
+    write_instructions(out, &code.expression.instructions, 2);
+}
+
+// Puts the closing parenthesis at the end of the last line, like the folded output of wasm2wat
+fn close(out: &mut String) {
+    if out.ends_with('\n') {
+        out.pop();
+    }
+    out.push_str(")\n");
+}
+
+fn type_use(module: &Module, type_idx: &TypeIdx) -> String {
+    let signature = module
+        .types
+        .get(type_idx.0 .0 as usize)
+        .map(func_signature)
+        .unwrap_or_default();
+    format!("(type {}){signature}", type_idx.0 .0)
+}
+
+fn func_signature(func_type: &FuncType) -> String {
+    let mut text = String::new();
+    if !func_type.parameters.is_empty() {
+        let params: String = func_type
+            .parameters
+            .iter()
+            .map(|val_type| format!(" {val_type}"))
+            .collect();
+        text.push_str(&format!(" (param{params})"));
+    }
+    if !func_type.results.is_empty() {
+        text.push_str(&format!(" {}", result(&func_type.results)));
+    }
+    text
+}
+
+fn limits_text(limits: &LimitsType) -> String {
+    match limits.max {
+        Some(ref max) => format!("{} {}", limits.min.0, max.0),
+        None => format!("{}", limits.min.0),
+    }
+}
+
+fn table_type_text(table_type: &TableType) -> String {
+    let limits = limits_text(&table_type.limits);
+    format!("{limits} {}", table_type.element_ref_type)
+}
+
+fn mem_type_text(mem_type: &MemType) -> String {
+    let index_type = match mem_type.index_type {
+        IndexType::I32 => "",
+        IndexType::I64 => "i64 ",
+    };
+    let shared = if mem_type.shared { " shared" } else { "" };
+    format!("{index_type}{}{shared}", limits_text(&mem_type.limits))
+}
+
+fn global_type_text(global_type: &GlobalType) -> String {
+    match global_type.mut_type {
+        MutType::Const => format!("{}", global_type.val_type),
+        MutType::Var => format!("(mut {})", global_type.val_type),
+    }
+}
+
+// A single instruction is folded, longer constant expressions are wrapped in the keyword
+fn expression(instructions: &[InstructionType], keyword: Option<&str>) -> String {
+    let instructions: Vec<String> = instructions.iter().map(instruction_text).collect();
+    match (instructions.as_slice(), keyword) {
+        ([instruction], _) => format!("({instruction})"),
+        (instructions, Some(keyword)) => format!("({keyword} {})", instructions.join(" ")),
+        (instructions, None) => instructions.join(" "),
+    }
+}
+
+fn elem_text(elem: &ElementSegmentType) -> String {
+    let offset = |offset: &ExpressionType| expression(&offset.instructions, Some("offset"));
+    let funcs = |funcs: &[FuncIdx]| -> String {
+        funcs.iter().map(|func| format!(" {}", func.0 .0)).collect()
+    };
+    let items = |items: &[ExpressionType]| -> String {
+        items
+            .iter()
+            .map(|item| format!(" {}", expression(&item.instructions, Some("item"))))
+            .collect()
+    };
+
+    match elem {
+        ElementSegmentType::Active0Functions(t) => {
+            format!("{} func{}", offset(&t.mode.offset), funcs(&t.init))
+        }
+        ElementSegmentType::ElemKindPassiveFunctions(t) => format!("func{}", funcs(&t.init)),
+        ElementSegmentType::ElemKindActiveFunctions(t) => format!(
+            "(table {}) {} func{}",
+            t.mode.table_idx.0 .0,
+            offset(&t.mode.offset),
+            funcs(&t.init)
+        ),
+        ElementSegmentType::ElemKindDeclarativeFunctions(t) => {
+            format!("declare func{}", funcs(&t.init))
+        }
+        ElementSegmentType::Active0Expr(t) => {
+            format!("{} funcref{}", offset(&t.mode.offset), items(&t.init))
+        }
+        ElementSegmentType::PassiveRef(t) => format!("{}{}", t.ref_type, items(&t.init)),
+        ElementSegmentType::ActiveRef(t) => format!(
+            "(table {}) {} {}{}",
+            t.mode.table_idx.0 .0,
+            offset(&t.mode.offset),
+            t.ref_type,
+            items(&t.init)
+        ),
+        ElementSegmentType::DeclarativeRef(t) => {
+            format!("declare {}{}", t.ref_type, items(&t.init))
+        }
+    }
+}
+
+// Printable ASCII is kept, other bytes are escaped as two hex digits
+fn string(bytes: &[u8]) -> String {
+    let escaped: String = bytes
+        .iter()
+        .map(|&byte| match byte {
+            b'"' | b'\\' => format!("\\{}", byte as char),
+            0x20..=0x7E => (byte as char).to_string(),
+            byte => format!("\\{byte:02x}"),
+        })
+        .collect();
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::entities::{
+        instructions::{BlockInstructionType, BlockType, MemArgType},
+        module::{
+            Active0DataType, CodeType, DataModeActive0, ExportType, Global, ImportType, LocalsType,
+        },
+        types::*,
+    };
+
+    #[test]
+    fn test_module_to_text() {
+        let i32_type = ValType::NumType(NumType::I32);
+        let module = Module {
+            types: vec![FuncType {
+                parameters: vec![i32_type.clone()],
+                results: vec![i32_type.clone()],
+            }],
+            imports: vec![ImportType {
+                module: NameType("env".into()),
+                name: NameType("mem".into()),
+                desc: ImportDescription::Mem(MemType {
+                    limits: LimitsType {
+                        min: U64Type(1),
+                        max: None,
+                    },
+                    index_type: IndexType::I32,
+                    shared: false,
+                }),
+            }],
+            funcs: vec![TypeIdx(U32Type(0))],
+            globals: vec![Global {
+                global_type: GlobalType {
+                    mut_type: MutType::Var,
+                    val_type: i32_type.clone(),
+                },
+                init: ExpressionType::new(vec![InstructionType::I32Const(I32Type(-1i32 as u32))]),
+            }],
+            exports: vec![ExportType {
+                name: NameType("load".into()),
+                desc: ExportDescription::Func(FuncIdx(U32Type(0))),
+            }],
+            code: vec![CodeType {
+                size: U32Type(0),
+                code: FuncCodeType {
+                    locals: vec![LocalsType {
+                        n: U32Type(2),
+                        val_type: ValType::NumType(NumType::F64),
+                    }],
+                    expression: ExpressionType::new(vec![InstructionType::Block(
+                        BlockInstructionType {
+                            blocktype: BlockType::ValType(i32_type.clone()),
+                            instructions: vec![
+                                InstructionType::LocalGet(LocalIdx(U32Type(0))),
+                                InstructionType::I32Load16S(MemArgType {
+                                    align: U32Type(0),
+                                    offset: U64Type(4),
+                                    memory: MemIdx(U32Type(0)),
+                                }),
+                                InstructionType::I32Load(MemArgType {
+                                    align: U32Type(2),
+                                    offset: U64Type(0),
+                                    memory: MemIdx(U32Type(0)),
+                                }),
+                                InstructionType::F64Const(F64Type(f64::NAN)),
+                                InstructionType::Drop,
+                            ],
+                        },
+                    )]),
+                }
+                .into(),
+            }],
+            datas: vec![DataType::Active0(Active0DataType {
+                mode: DataModeActive0 {
+                    offset: ExpressionType::new(vec![InstructionType::I32Const(I32Type(8))]),
+                },
+                init: b"hi\"\n".to_vec(),
+            })],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            module_to_text(&module),
+            [
+                "(module",
+                "  (type (;0;) (func (param i32) (result i32)))",
+                "  (import \"env\" \"mem\" (memory (;0;) 1))",
+                "  (func (;0;) (type 0) (param i32) (result i32)",
+                "    (local f64 f64)",
+                "    block (result i32)",
+                "      local.get 0",
+                "      i32.load16_s offset=4 align=1",
+                "      i32.load",
+                "      f64.const nan",
+                "      drop",
+                "    end)",
+                "  (global (;0;) (mut i32) (i32.const -1))",
+                "  (export \"load\" (func 0))",
+                "  (data (;0;) (i32.const 8) \"hi\\\"\\0a\"))",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_memarg_alignment() {
+        let memarg = |align, offset, memory| MemArgType {
+            align: U32Type(align),
+            offset: U64Type(offset),
+            memory: MemIdx(U32Type(memory)),
+        };
+
+        let cases = [
+            (InstructionType::I64Load(memarg(3, 0, 0)), "i64.load"),
+            (
+                InstructionType::I64Load(memarg(2, 0, 0)),
+                "i64.load align=4",
+            ),
+            (InstructionType::I64Load8U(memarg(0, 0, 0)), "i64.load8_u"),
+            (
+                InstructionType::I32Store8(memarg(1, 0, 0)),
+                "i32.store8 align=2",
+            ),
+            (
+                InstructionType::F32Store(memarg(2, 8, 1)),
+                "f32.store 1 offset=8",
+            ),
+            (InstructionType::V128Load(memarg(4, 0, 0)), "v128.load"),
+            (
+                InstructionType::V128Load(memarg(0, 0, 0)),
+                "v128.load align=1",
+            ),
+            (
+                InstructionType::V128Load8x8S(memarg(3, 0, 0)),
+                "v128.load8x8_s",
+            ),
+            (
+                InstructionType::V128Load32Zero(memarg(2, 0, 0)),
+                "v128.load32_zero",
+            ),
+        ];
+
+        for (instruction, text) in cases {
+            assert_eq!(instruction_text(&instruction), text);
+        }
+    }
+
+    #[test]
+    fn test_float_text() {
+        let f32_const =
+            |bits| instruction_text(&InstructionType::F32Const(F32Type(f32::from_bits(bits))));
+        let f64_const =
+            |bits| instruction_text(&InstructionType::F64Const(F64Type(f64::from_bits(bits))));
+
+        assert_eq!(f32_const(0x7FC0_0000), "f32.const nan");
+        assert_eq!(f32_const(0xFFC0_0000), "f32.const -nan");
+        assert_eq!(f32_const(0x7F80_0001), "f32.const nan:0x1");
+        assert_eq!(f32_const(0x7F80_0000), "f32.const inf");
+        assert_eq!(f32_const(0x8000_0000), "f32.const -0");
+        assert_eq!(f64_const(0x7FF8_0000_0000_0000), "f64.const nan");
+        assert_eq!(
+            f64_const(0xFFF4_0000_0000_0000),
+            "f64.const -nan:0x4000000000000"
+        );
+        assert_eq!(f64_const(1.5f64.to_bits()), "f64.const 1.5");
+    }
+}